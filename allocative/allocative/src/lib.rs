@@ -52,6 +52,7 @@ mod impls;
 mod key;
 mod rc_str;
 mod size_of;
+mod snapshot;
 mod test_derive;
 mod visitor;
 
@@ -64,6 +65,10 @@ pub use crate::global_root::register_root;
 pub use crate::key::Key;
 pub use crate::size_of::size_of_unique;
 pub use crate::size_of::size_of_unique_allocated_data;
+pub use crate::snapshot::SizeChange;
+pub use crate::snapshot::Snapshot;
+pub use crate::snapshot::SnapshotDiff;
+pub use crate::snapshot::SnapshotParseError;
 pub use crate::visitor::Visitor;
 
 #[doc(hidden)]
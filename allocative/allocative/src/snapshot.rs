@@ -0,0 +1,282 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write as _;
+
+/// Memory usage by key path, as written to flamegraph source
+/// by [`FlameGraphBuilder`](crate::FlameGraphBuilder).
+///
+/// Snapshots are stored as flamegraph source, so a previously written
+/// flamegraph can be parsed back and compared to the current one.
+#[derive(Default, Eq, PartialEq, Clone, Debug)]
+pub struct Snapshot {
+    /// Size excluding children, keyed by `;`-separated key path.
+    sizes: BTreeMap<String, u64>,
+}
+
+/// Error parsing flamegraph source into a [`Snapshot`].
+#[derive(Debug, Eq, PartialEq)]
+pub struct SnapshotParseError {
+    line: usize,
+    message: &'static str,
+}
+
+impl fmt::Display for SnapshotParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid flamegraph source at line {}: {}",
+            self.line, self.message
+        )
+    }
+}
+
+impl std::error::Error for SnapshotParseError {}
+
+impl Snapshot {
+    /// Parse flamegraph source: lines of `key;path size`.
+    pub fn parse_flamegraph(src: &str) -> Result<Snapshot, SnapshotParseError> {
+        let mut sizes = BTreeMap::new();
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message| SnapshotParseError {
+                line: i + 1,
+                message,
+            };
+            // Keys may contain spaces (e.g. `Box<dyn Any>`), the size is after the last one.
+            let (path, size) = line
+                .rsplit_once(' ')
+                .ok_or_else(|| error("expecting `path size`"))?;
+            if path.is_empty() {
+                return Err(error("empty path"));
+            }
+            let size: u64 = size.parse().map_err(|_| error("invalid size"))?;
+            *sizes.entry(path.to_owned()).or_insert(0) += size;
+        }
+        Ok(Snapshot { sizes })
+    }
+
+    /// Total size of all the nodes.
+    pub fn total(&self) -> u64 {
+        self.sizes.values().sum()
+    }
+
+    /// Sizes including children for each key path and all its prefixes.
+    fn inclusive_sizes(&self) -> BTreeMap<&str, u64> {
+        let mut sizes = BTreeMap::new();
+        for (path, size) in &self.sizes {
+            for (i, c) in path.char_indices() {
+                if c == ';' {
+                    *sizes.entry(&path[..i]).or_insert(0) += size;
+                }
+            }
+            *sizes.entry(path.as_str()).or_insert(0) += size;
+        }
+        sizes
+    }
+
+    /// Compare this (older) snapshot with a newer one.
+    pub fn diff(&self, after: &Snapshot) -> SnapshotDiff {
+        let before_sizes = self.inclusive_sizes();
+        let after_sizes = after.inclusive_sizes();
+        let mut changes: Vec<SizeChange> = Vec::new();
+        for (path, before) in &before_sizes {
+            let after = after_sizes.get(path).copied().unwrap_or(0);
+            if *before != after {
+                changes.push(SizeChange {
+                    path: (*path).to_owned(),
+                    before: *before,
+                    after,
+                });
+            }
+        }
+        for (path, after) in &after_sizes {
+            if !before_sizes.contains_key(path) {
+                changes.push(SizeChange {
+                    path: (*path).to_owned(),
+                    before: 0,
+                    after: *after,
+                });
+            }
+        }
+        changes.sort_by_key(|c| (Reverse(c.delta().unsigned_abs()), c.path.clone()));
+        SnapshotDiff {
+            before: self.clone(),
+            after: after.clone(),
+            changes,
+        }
+    }
+}
+
+/// Change of size (including children) of a key path between two snapshots.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SizeChange {
+    /// `;`-separated key path.
+    pub path: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl SizeChange {
+    /// Positive if the node grew.
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+/// Difference between two [`Snapshot`]s.
+#[derive(Clone, Debug)]
+pub struct SnapshotDiff {
+    before: Snapshot,
+    after: Snapshot,
+    /// Sorted by absolute delta, largest first.
+    changes: Vec<SizeChange>,
+}
+
+impl SnapshotDiff {
+    /// Changed key paths, sorted by absolute delta, largest first.
+    ///
+    /// Sizes include children, so growth of `a;b` is also reported for `a`.
+    pub fn changes(&self) -> &[SizeChange] {
+        &self.changes
+    }
+
+    /// Differential flamegraph source: lines of `key;path before after`,
+    /// can be fed to `inferno-flamegraph` or `flamegraph.pl`.
+    pub fn differential_flamegraph(&self) -> String {
+        let mut paths: Vec<&String> = self
+            .before
+            .sizes
+            .keys()
+            .chain(self.after.sizes.keys())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        let mut w = String::new();
+        for path in paths {
+            let before = self.before.sizes.get(path).copied().unwrap_or(0);
+            let after = self.after.sizes.get(path).copied().unwrap_or(0);
+            writeln!(w, "{} {} {}", path, before, after).unwrap();
+        }
+        w
+    }
+
+    /// Flamegraph source of memory growth only (excluding children).
+    pub fn grown_flamegraph(&self) -> String {
+        Self::delta_flamegraph(&self.before, &self.after)
+    }
+
+    /// Flamegraph source of memory shrinkage only (excluding children).
+    pub fn shrunk_flamegraph(&self) -> String {
+        Self::delta_flamegraph(&self.after, &self.before)
+    }
+
+    fn delta_flamegraph(before: &Snapshot, after: &Snapshot) -> String {
+        let mut w = String::new();
+        for (path, after) in &after.sizes {
+            let before = before.sizes.get(path).copied().unwrap_or(0);
+            if *after > before {
+                writeln!(w, "{} {}", path, after - before).unwrap();
+            }
+        }
+        w
+    }
+
+    /// Human readable report of at most `limit` largest changes.
+    pub fn report(&self, limit: usize) -> String {
+        let mut w = String::new();
+        let before = self.before.total();
+        let after = self.after.total();
+        writeln!(
+            w,
+            "Total: {} -> {} ({:+})",
+            before,
+            after,
+            after as i64 - before as i64
+        )
+        .unwrap();
+        for change in self.changes.iter().take(limit) {
+            writeln!(
+                w,
+                "{:>+14} {} ({} -> {})",
+                change.delta(),
+                change.path,
+                change.before,
+                change.after
+            )
+            .unwrap();
+        }
+        if self.changes.len() > limit {
+            writeln!(w, "... and {} more", self.changes.len() - limit).unwrap();
+        }
+        w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::SizeChange;
+    use crate::snapshot::Snapshot;
+    use crate::snapshot::SnapshotParseError;
+
+    #[test]
+    fn test_parse() {
+        let snapshot = Snapshot::parse_flamegraph("a;b 10\na;Box<dyn Any> 5\n\na 1\n").unwrap();
+        assert_eq!(16, snapshot.total());
+        assert_eq!(
+            Err(SnapshotParseError {
+                line: 2,
+                message: "invalid size"
+            }),
+            Snapshot::parse_flamegraph("a 1\na x\n")
+        );
+        assert!(Snapshot::parse_flamegraph("a\n").is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = Snapshot::parse_flamegraph("a;b 10\na;c 5\nd 3\n").unwrap();
+        let after = Snapshot::parse_flamegraph("a;b 100\na;c 2\ne 4\n").unwrap();
+        let diff = before.diff(&after);
+
+        let change = |path: &str, before, after| SizeChange {
+            path: path.to_owned(),
+            before,
+            after,
+        };
+        assert_eq!(
+            &[
+                change("a;b", 10, 100),
+                change("a", 15, 102),
+                change("e", 0, 4),
+                change("a;c", 5, 2),
+                change("d", 3, 0),
+            ],
+            diff.changes()
+        );
+
+        assert_eq!(
+            "a;b 10 100\na;c 5 2\nd 3 0\ne 0 4\n",
+            diff.differential_flamegraph()
+        );
+        assert_eq!("a;b 90\ne 4\n", diff.grown_flamegraph());
+        assert_eq!("a;c 3\nd 3\n", diff.shrunk_flamegraph());
+
+        let report = diff.report(2);
+        assert!(report.starts_with("Total: 18 -> 106 (+88)\n"));
+        assert!(report.contains("+90 a;b (10 -> 100)"));
+        assert!(report.ends_with("... and 3 more\n"));
+    }
+}
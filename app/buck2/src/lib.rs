@@ -31,6 +31,8 @@ use buck2_client::commands::kill::KillCommand;
 use buck2_client::commands::killall::KillallCommand;
 use buck2_client::commands::log::LogCommand;
 use buck2_client::commands::lsp::LspCommand;
+use buck2_client::commands::materialize::MaterializeCommand;
+use buck2_client::commands::offline_archive::OfflineArchiveCommand;
 use buck2_client::commands::profile::ProfileCommand;
 use buck2_client::commands::query::aquery::AqueryCommand;
 use buck2_client::commands::query::cquery::CqueryCommand;
@@ -259,6 +261,7 @@ pub(crate) enum CommandKind {
     Install(InstallCommand),
     Kill(KillCommand),
     Killall(KillallCommand),
+    Materialize(MaterializeCommand),
     Root(RootCommand),
     /// Alias for `uquery`.
     Query(UqueryCommand),
@@ -281,6 +284,8 @@ pub(crate) enum CommandKind {
     Log(LogCommand),
     Lsp(LspCommand),
     Subscribe(SubscribeCommand),
+    #[clap(subcommand)]
+    OfflineArchive(OfflineArchiveCommand),
 }
 
 impl CommandKind {
@@ -395,6 +400,7 @@ impl CommandKind {
             CommandKind::Test(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Cquery(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Kill(cmd) => cmd.exec(matches, command_ctx).into(),
+            CommandKind::Materialize(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Killall(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Clean(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Root(cmd) => cmd.exec(matches, command_ctx).into(),
@@ -421,6 +427,7 @@ impl CommandKind {
             CommandKind::Log(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Lsp(cmd) => cmd.exec(matches, command_ctx),
             CommandKind::Subscribe(cmd) => cmd.exec(matches, command_ctx),
+            CommandKind::OfflineArchive(cmd) => cmd.exec(matches, command_ctx),
         }
     }
 }
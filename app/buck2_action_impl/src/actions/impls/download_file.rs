@@ -24,6 +24,7 @@ use buck2_build_api::actions::IncrementalActionExecutable;
 use buck2_build_api::actions::UnregisteredAction;
 use buck2_build_api::artifact_groups::ArtifactGroup;
 use buck2_common::cas_digest::RawDigest;
+use buck2_common::executor_config::RemoteExecutorUseCase;
 use buck2_common::file_ops::FileDigest;
 use buck2_common::file_ops::FileMetadata;
 use buck2_common::file_ops::TrackedFileDigest;
@@ -31,12 +32,14 @@ use buck2_common::http::HttpClient;
 use buck2_common::io::trace::TracingIoProvider;
 use buck2_core::category::Category;
 use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::digest::CasDigestFromReExt;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::execute::command_executor::ActionExecutionTimingData;
 use buck2_execute::materialize::http::http_download;
 use buck2_execute::materialize::http::http_head;
 use buck2_execute::materialize::http::Checksum;
 use buck2_execute::materialize::http::HttpError;
+use buck2_execute::materialize::materializer::CasDownloadInfo;
 use buck2_execute::materialize::materializer::HttpDownloadInfo;
 use dupe::Dupe;
 use indexmap::IndexSet;
@@ -195,6 +198,80 @@ impl DownloadFileAction {
         }
     }
 
+    /// Try to have the RE backend fetch the file into the CAS via the Remote Asset API. If that
+    /// works, we can declare the output without downloading it: the materializer will only
+    /// download it from the CAS if something needs it locally. Returns `None` if the Remote Asset
+    /// API is unavailable or failed, in which case we fall back to downloading it ourselves.
+    async fn remote_asset_metadata(
+        &self,
+        ctx: &mut dyn ActionExecutionCtx,
+    ) -> anyhow::Result<Option<FileMetadata>> {
+        if !self.inner.is_deferrable {
+            return Ok(None);
+        }
+
+        // The CAS is addressed by the RE digest, so we need the checksum we were given to be one
+        // the CAS will use, otherwise we can't check that the server fetched the right file.
+        let digest_config = ctx.digest_config();
+        if !digest_config.cas_digest_config().allows_sha256() {
+            return Ok(None);
+        }
+
+        let expected = match self
+            .inner
+            .checksum
+            .sha256()
+            .and_then(|sha256| RawDigest::parse_sha256(sha256.as_bytes()).ok())
+        {
+            Some(expected) => expected,
+            None => return Ok(None),
+        };
+
+        let checksum_sri = match self.inner.checksum.to_sri()? {
+            Some(checksum_sri) => checksum_sri,
+            None => return Ok(None),
+        };
+
+        let digest = match ctx
+            .re_client()
+            .fetch_blob(
+                &self.inner.url,
+                &checksum_sri,
+                RemoteExecutorUseCase::buck2_default(),
+            )
+            .await
+        {
+            Ok(Some(digest)) => digest,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                tracing::warn!(
+                    "Remote Asset API failed to fetch `{}`, downloading locally instead: {:#}",
+                    self.inner.url,
+                    e
+                );
+                return Ok(None);
+            }
+        };
+
+        let digest = FileDigest::from_re(&digest, digest_config)
+            .with_context(|| format!("Remote Asset API returned an invalid digest: {}", digest))?;
+
+        if digest.raw_digest() != &expected {
+            tracing::warn!(
+                "Remote Asset API returned digest `{}` for `{}`, expected sha256 `{}`, downloading locally instead",
+                digest,
+                self.inner.url,
+                expected,
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(FileMetadata {
+            digest: TrackedFileDigest::new(digest, digest_config.cas_digest_config()),
+            is_executable: self.inner.is_executable,
+        }))
+    }
+
     /// Execute this action for offline builds (e.g. no network).
     async fn execute_for_offline(
         &self,
@@ -260,7 +337,27 @@ impl IncrementalActionExecutable for DownloadFileAction {
             return self.execute_for_offline(ctx).await;
         }
 
-        let (value, execution_kind) = {
+        let (value, execution_kind) = if let Some(metadata) =
+            self.remote_asset_metadata(ctx).await?
+        {
+            let artifact_fs = ctx.fs();
+            let rel_path = artifact_fs.resolve_build(self.output().get_path());
+
+            // Fastest path: the file is already in the CAS, download later via the materializer
+            // (if at all).
+            let value = ArtifactValue::file(metadata);
+            ctx.materializer()
+                .declare_cas_many(
+                    Arc::new(CasDownloadInfo::new_declared(
+                        RemoteExecutorUseCase::buck2_default(),
+                    )),
+                    vec![(rel_path, value.dupe())],
+                    ctx.cancellation_context(),
+                )
+                .await?;
+
+            (value, ActionExecutionKind::Deferred)
+        } else {
             match self
                 .declared_metadata(&*ctx.http_client(), ctx.digest_config())
                 .await?
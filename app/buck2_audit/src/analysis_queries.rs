@@ -39,4 +39,8 @@ impl AuditSubcommand for AuditAnalysisQueriesCommand {
     fn common_opts(&self) -> &CommonCommandOptions {
         &self.common_opts
     }
+
+    fn target_patterns_mut(&mut self) -> Vec<&mut String> {
+        self.patterns.iter_mut().collect()
+    }
 }
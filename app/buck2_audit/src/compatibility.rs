@@ -0,0 +1,37 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::CommonCommandOptions;
+
+use crate::AuditSubcommand;
+
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(
+    name = "audit-compatibility",
+    about = "list targets incompatible with the target platform, grouped by reason"
+)]
+pub struct AuditCompatibilityCommand {
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+
+    #[clap(name = "TARGET_PATTERNS", help = "Target patterns to check")]
+    pub patterns: Vec<String>,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditCompatibilityCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+
+    fn target_patterns_mut(&mut self) -> Vec<&mut String> {
+        self.patterns.iter_mut().collect()
+    }
+}
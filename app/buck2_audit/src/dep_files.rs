@@ -36,4 +36,8 @@ impl AuditSubcommand for AuditDepFilesCommand {
     fn common_opts(&self) -> &CommonCommandOptions {
         &self.common_opts
     }
+
+    fn target_patterns_mut(&mut self) -> Vec<&mut String> {
+        vec![&mut self.pattern]
+    }
 }
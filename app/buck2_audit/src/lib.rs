@@ -22,10 +22,12 @@ use buck2_client_ctx::daemon::client::BuckdClientConnector;
 use buck2_client_ctx::daemon::client::StdoutPartialResultHandler;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::configuration::modifiers::strip_patterns_modifiers;
 use classpath::AuditClasspathCommand;
 
 use crate::analysis_queries::AuditAnalysisQueriesCommand;
 use crate::cell::AuditCellCommand;
+use crate::compatibility::AuditCompatibilityCommand;
 use crate::config::AuditConfigCommand;
 use crate::configurations::AuditConfigurationsCommand;
 use crate::deferred_materializer::DeferredMaterializerCommand;
@@ -36,11 +38,13 @@ use crate::output::command::AuditOutputCommand;
 use crate::prelude::AuditPreludeCommand;
 use crate::providers::AuditProvidersCommand;
 use crate::starlark::StarlarkCommand;
+use crate::transitions::AuditTransitionsCommand;
 use crate::visibility::AuditVisibilityCommand;
 
 pub mod analysis_queries;
 pub mod cell;
 pub mod classpath;
+pub mod compatibility;
 pub mod config;
 pub mod configurations;
 pub mod deferred_materializer;
@@ -51,6 +55,7 @@ pub mod output;
 pub mod prelude;
 pub mod providers;
 pub mod starlark;
+pub mod transitions;
 pub mod visibility;
 
 #[derive(Debug, clap::Subcommand, serde::Serialize, serde::Deserialize)]
@@ -66,6 +71,8 @@ pub enum AuditCommand {
     AnalysisQueries(AuditAnalysisQueriesCommand),
     ExecutionPlatformResolution(AuditExecutionPlatformResolutionCommand),
     Visibility(AuditVisibilityCommand),
+    Transitions(AuditTransitionsCommand),
+    Compatibility(AuditCompatibilityCommand),
     #[clap(subcommand)]
     Starlark(StarlarkCommand),
     DepFiles(AuditDepFilesCommand),
@@ -84,6 +91,12 @@ pub enum AuditCommand {
 #[async_trait]
 pub trait AuditSubcommand: Send + Sync + 'static {
     fn common_opts(&self) -> &CommonCommandOptions;
+
+    /// Target pattern arguments, from which configuration modifiers (`//foo:bar?opt`) are
+    /// stripped on the client.
+    fn target_patterns_mut(&mut self) -> Vec<&mut String> {
+        Vec::new()
+    }
 }
 
 impl AuditCommand {
@@ -102,6 +115,29 @@ impl AuditCommand {
             AuditCommand::DepFiles(cmd) => cmd,
             AuditCommand::DeferredMaterializer(cmd) => cmd,
             AuditCommand::Visibility(cmd) => cmd,
+            AuditCommand::Transitions(cmd) => cmd,
+            AuditCommand::Compatibility(cmd) => cmd,
+            AuditCommand::Output(cmd) => cmd,
+        }
+    }
+
+    fn as_subcommand_mut(&mut self) -> &mut dyn AuditSubcommand {
+        match self {
+            AuditCommand::Cell(cmd) => cmd,
+            AuditCommand::Classpath(cmd) => cmd,
+            AuditCommand::Config(cmd) => cmd,
+            AuditCommand::Configurations(cmd) => cmd,
+            AuditCommand::Includes(cmd) => cmd,
+            AuditCommand::Prelude(cmd) => cmd,
+            AuditCommand::Providers(cmd) => cmd,
+            AuditCommand::AnalysisQueries(cmd) => cmd,
+            AuditCommand::ExecutionPlatformResolution(cmd) => cmd,
+            AuditCommand::Starlark(cmd) => cmd,
+            AuditCommand::DepFiles(cmd) => cmd,
+            AuditCommand::DeferredMaterializer(cmd) => cmd,
+            AuditCommand::Visibility(cmd) => cmd,
+            AuditCommand::Transitions(cmd) => cmd,
+            AuditCommand::Compatibility(cmd) => cmd,
             AuditCommand::Output(cmd) => cmd,
         }
     }
@@ -113,11 +149,12 @@ impl StreamingCommand for AuditCommand {
 
     /// Audit subcommands are all implemented as a generic request to the buckd server that will deserialize the command object.
     async fn exec_impl(
-        self,
+        mut self,
         buckd: &mut BuckdClientConnector,
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
+        let modifiers = strip_patterns_modifiers(self.as_subcommand_mut().target_patterns_mut())?;
         let serialized = serde_json::to_string(&self)?;
 
         let config_opts = &self.as_subcommand().common_opts().config_opts;
@@ -127,7 +164,8 @@ impl StreamingCommand for AuditCommand {
             None => panic!("Parsed a subcommand but couldn't extract subcommand argument matches"),
         };
 
-        let context = ctx.client_context(config_opts, submatches, self.sanitized_argv())?;
+        let mut context = ctx.client_context(config_opts, submatches, self.sanitized_argv())?;
+        context.modifiers.extend(modifiers);
 
         buckd
             .with_flushing()
@@ -47,4 +47,8 @@ impl AuditSubcommand for AuditProvidersCommand {
     fn common_opts(&self) -> &CommonCommandOptions {
         &self.common_opts
     }
+
+    fn target_patterns_mut(&mut self) -> Vec<&mut String> {
+        self.patterns.iter_mut().collect()
+    }
 }
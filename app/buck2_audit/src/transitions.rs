@@ -0,0 +1,52 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_client_ctx::common::CommonCommandOptions;
+
+use crate::AuditSubcommand;
+
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(
+    name = "audit-transitions",
+    about = "explain why targets are built in multiple configurations"
+)]
+pub struct AuditTransitionsCommand {
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+
+    #[clap(
+        name = "TARGET_PATTERNS",
+        help = "Top-level targets to start configured graph traversal from"
+    )]
+    pub patterns: Vec<String>,
+
+    #[clap(
+        long = "target",
+        number_of_values = 1,
+        value_name = "TARGET",
+        help = "Targets to explain configurations of. \
+            If none provided, explain all targets reachable in more than one configuration."
+    )]
+    pub targets: Vec<String>,
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditTransitionsCommand {
+    fn common_opts(&self) -> &CommonCommandOptions {
+        &self.common_opts
+    }
+
+    fn target_patterns_mut(&mut self) -> Vec<&mut String> {
+        self.patterns
+            .iter_mut()
+            .chain(self.targets.iter_mut())
+            .collect()
+    }
+}
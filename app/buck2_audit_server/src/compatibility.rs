@@ -0,0 +1,177 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use buck2_audit::compatibility::AuditCompatibilityCommand;
+use buck2_build_api::calculation::load_patterns;
+use buck2_build_api::calculation::Calculation;
+use buck2_build_api::calculation::MissingTargetBehavior;
+use buck2_build_api::nodes::calculation::NodeCalculation;
+use buck2_cli_proto::ClientContext;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_query::query::compatibility::IncompatiblePlatformReason;
+use buck2_query::query::compatibility::MaybeCompatible;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::target_platform_from_client_context;
+use dupe::Dupe;
+use futures::future::try_join_all;
+use indent_write::io::IndentWriter;
+use indexmap::IndexMap;
+
+use crate::AuditSubcommand;
+
+/// Write incompatible targets grouped by the target which is incompatible by itself.
+fn write_grouped_by_root_cause(
+    incompatible: &[Arc<IncompatiblePlatformReason>],
+    stdout: &mut (dyn Write + Send),
+) -> anyhow::Result<()> {
+    let mut groups: IndexMap<&IncompatiblePlatformReason, Vec<&IncompatiblePlatformReason>> =
+        IndexMap::new();
+    for reason in incompatible {
+        groups.entry(reason.root_cause()).or_default().push(reason);
+    }
+    groups.sort_by(|a, _, b, _| a.target.cmp(&b.target));
+
+    for (root_cause, mut reasons) in groups {
+        reasons.sort_by(|a, b| a.target.cmp(&b.target));
+        writeln!(stdout, "{}", root_cause)?;
+        writeln!(stdout, "  {} incompatible target(s):", reasons.len())?;
+        for reason in reasons {
+            let mut stdout = IndentWriter::new("    ", &mut *stdout);
+            writeln!(stdout, "{:#}", reason)?;
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditCompatibilityCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx(async move |server_ctx, ctx| {
+                let target_platform =
+                    target_platform_from_client_context(&client_ctx, server_ctx, &ctx).await?;
+
+                let parsed_patterns = parse_patterns_from_cli_args::<TargetPatternExtra>(
+                    &ctx,
+                    &self
+                        .patterns
+                        .iter()
+                        .map(|value| buck2_data::TargetPattern {
+                            value: value.clone(),
+                        })
+                        .collect::<Vec<_>>(),
+                    server_ctx.working_dir(),
+                )
+                .await?;
+                let loaded_patterns =
+                    load_patterns(&ctx, parsed_patterns, MissingTargetBehavior::Fail).await?;
+
+                let mut labels = Vec::new();
+                for (_, targets) in loaded_patterns.into_iter() {
+                    for (_, node) in targets? {
+                        labels.push(node.label().dupe());
+                    }
+                }
+
+                let ctx = &ctx;
+                let target_platform = &target_platform;
+                let incompatible: Vec<_> = try_join_all(labels.iter().map(|label| async move {
+                    let label = ctx
+                        .get_configured_target(label, target_platform.as_ref())
+                        .await?;
+                    anyhow::Ok(match ctx.get_configured_target_node(&label).await? {
+                        MaybeCompatible::Compatible(_) => None,
+                        MaybeCompatible::Incompatible(reason) => Some(reason),
+                    })
+                }))
+                .await?
+                .into_iter()
+                .flatten()
+                .collect();
+
+                let mut stdout = stdout.as_writer();
+                if incompatible.is_empty() {
+                    writeln!(stdout, "All targets are compatible")?;
+                } else {
+                    write_grouped_by_root_cause(&incompatible, &mut stdout)?;
+                }
+
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::target::label::ConfiguredTargetLabel;
+    use buck2_core::target::label::TargetLabel;
+    use buck2_query::query::compatibility::IncompatiblePlatformReason;
+    use buck2_query::query::compatibility::IncompatiblePlatformReasonCause;
+
+    use crate::compatibility::write_grouped_by_root_cause;
+
+    #[test]
+    fn test_write_grouped_by_root_cause() -> anyhow::Result<()> {
+        let reason = |target: &str, cause| {
+            Arc::new(IncompatiblePlatformReason {
+                target: ConfiguredTargetLabel::testing_parse(
+                    target,
+                    ConfigurationData::testing_new(),
+                ),
+                cause,
+            })
+        };
+        let unsatisfied = || {
+            IncompatiblePlatformReasonCause::UnsatisfiedConfig(TargetLabel::testing_parse(
+                "root//:linux",
+            ))
+        };
+        let c = reason("root//:c", unsatisfied());
+        let a = reason(
+            "root//:a",
+            IncompatiblePlatformReasonCause::Dependency(c.clone()),
+        );
+        let d = reason("root//:d", unsatisfied());
+
+        let mut out = Vec::new();
+        write_grouped_by_root_cause(&[d.clone(), a.clone(), c.clone()], &mut out)?;
+        assert_eq!(
+            format!(
+                "\
+                {c}\n\
+                \x20 2 incompatible target(s):\n\
+                \x20   {a_target}\n\
+                \x20   └─ {c}\n\
+                \x20   {c}\n\
+                {d}\n\
+                \x20 1 incompatible target(s):\n\
+                \x20   {d}\n",
+                a_target = a.target,
+            ),
+            String::from_utf8(out)?
+        );
+        Ok(())
+    }
+}
@@ -22,6 +22,7 @@ use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 mod analysis_queries;
 mod cell;
 mod classpath;
+mod compatibility;
 mod config;
 mod configurations;
 pub mod deferred_materializer;
@@ -33,6 +34,7 @@ mod prelude;
 mod providers;
 pub mod server;
 mod starlark;
+mod transitions;
 mod visibility;
 
 /// `buck2 audit` subcommands have a somewhat unique approach to make it really easy to
@@ -91,6 +93,8 @@ impl AuditCommandExt for AuditCommand {
             AuditCommand::DepFiles(cmd) => cmd,
             AuditCommand::DeferredMaterializer(cmd) => cmd,
             AuditCommand::Visibility(cmd) => cmd,
+            AuditCommand::Transitions(cmd) => cmd,
+            AuditCommand::Compatibility(cmd) => cmd,
             AuditCommand::Output(cmd) => cmd,
         }
     }
@@ -0,0 +1,418 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::io::Write;
+
+use async_trait::async_trait;
+use buck2_audit::transitions::AuditTransitionsCommand;
+use buck2_build_api::calculation::load_patterns;
+use buck2_build_api::calculation::Calculation;
+use buck2_build_api::calculation::MissingTargetBehavior;
+use buck2_build_api::nodes::calculation::NodeCalculation;
+use buck2_cli_proto::ClientContext;
+use buck2_core::configuration::cfg_diff::cfg_diff;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_core::target::label::ConfiguredTargetLabel;
+use buck2_core::target::label::TargetLabel;
+use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_node::nodes::frontend::TargetGraphCalculation;
+use buck2_query::query::compatibility::MaybeCompatible;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::target_platform_from_client_context;
+use buck2_server_ctx::pattern::PatternParser;
+use dice::DiceComputations;
+use dupe::Dupe;
+use indent_write::io::IndentWriter;
+use indexmap::IndexMap;
+
+use crate::AuditSubcommand;
+
+/// How a configured node was reached from its parent in the configured graph.
+enum EdgeKind {
+    Dep,
+    ExecDep,
+    ToolchainDep,
+    /// Dep attribute with `cfg` transition or `split_transition_dep`.
+    AttrTransition,
+    /// Forward node to the node configured with rule `cfg` transition.
+    RuleTransition,
+}
+
+impl EdgeKind {
+    /// Description of the edge, unless it depends on the transition used.
+    fn description(&self) -> Option<&'static str> {
+        match self {
+            EdgeKind::Dep => Some("dep"),
+            EdgeKind::ExecDep => Some("exec dep"),
+            EdgeKind::ToolchainDep => Some("toolchain dep"),
+            EdgeKind::AttrTransition | EdgeKind::RuleTransition => None,
+        }
+    }
+}
+
+/// Configured graph reachable from top-level targets,
+/// with the first discovered (i.e. shortest path) parent of each node.
+struct ConfiguredGraphPaths {
+    parents: HashMap<ConfiguredTargetLabel, (ConfiguredTargetNode, EdgeKind)>,
+    /// All the configurations of each target in the order of discovery.
+    configurations: IndexMap<TargetLabel, Vec<ConfiguredTargetNode>>,
+}
+
+impl ConfiguredGraphPaths {
+    fn compute(roots: Vec<ConfiguredTargetNode>) -> ConfiguredGraphPaths {
+        let mut parents = HashMap::new();
+        let mut configurations: IndexMap<TargetLabel, Vec<ConfiguredTargetNode>> = IndexMap::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        for root in roots {
+            if seen.insert(root.label().dupe()) {
+                queue.push_back(root);
+            }
+        }
+
+        while let Some(node) = queue.pop_front() {
+            configurations
+                .entry(node.label().unconfigured().dupe())
+                .or_default()
+                .push(node.dupe());
+
+            let forward = node.forward_target();
+            for dep in node.deps() {
+                if !seen.insert(dep.label().dupe()) {
+                    continue;
+                }
+                let kind = if forward == Some(dep) {
+                    EdgeKind::RuleTransition
+                } else if node.exec_deps().any(|d| d == dep) {
+                    EdgeKind::ExecDep
+                } else if node.toolchain_deps().any(|d| d == dep) {
+                    EdgeKind::ToolchainDep
+                } else if dep.label().cfg() != node.label().cfg() {
+                    EdgeKind::AttrTransition
+                } else {
+                    EdgeKind::Dep
+                };
+                parents.insert(dep.label().dupe(), (node.dupe(), kind));
+                queue.push_back(dep.dupe());
+            }
+        }
+
+        ConfiguredGraphPaths {
+            parents,
+            configurations,
+        }
+    }
+
+    /// Path from a top-level target to the given node, top-level target first.
+    fn path_to<'a>(&'a self, node: &'a ConfiguredTargetNode) -> Vec<&'a ConfiguredTargetNode> {
+        let mut path = vec![node];
+        let mut current = node;
+        while let Some((parent, _)) = self.parents.get(current.label()) {
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Describe the edge to `node` from its parent.
+async fn describe_edge(
+    ctx: &DiceComputations,
+    parent: &ConfiguredTargetNode,
+    node: &ConfiguredTargetNode,
+    kind: &EdgeKind,
+) -> anyhow::Result<String> {
+    if let Some(description) = kind.description() {
+        return Ok(description.to_owned());
+    }
+    Ok(match kind {
+        EdgeKind::Dep | EdgeKind::ExecDep | EdgeKind::ToolchainDep => {
+            unreachable!("described above")
+        }
+        EdgeKind::AttrTransition => {
+            let parent_node = ctx.get_target_node(parent.label().unconfigured()).await?;
+            let transitions: Vec<String> = parent_node
+                .transition_deps()
+                .filter(|(dep, _)| *dep == node.label().unconfigured())
+                .map(|(_, tr)| tr.to_string())
+                .collect();
+            if transitions.is_empty() {
+                "dep".to_owned()
+            } else {
+                format!("transition {}", transitions.join(", "))
+            }
+        }
+        EdgeKind::RuleTransition => {
+            let target_node = ctx.get_target_node(node.label().unconfigured()).await?;
+            match &target_node.0.rule.cfg {
+                Some(tr) => format!("rule transition {}", tr),
+                None => "rule transition".to_owned(),
+            }
+        }
+    })
+}
+
+async fn explain_configurations(
+    ctx: &DiceComputations,
+    graph: &ConfiguredGraphPaths,
+    target: &TargetLabel,
+    nodes: &[ConfiguredTargetNode],
+    stdout: &mut (dyn Write + Send),
+) -> anyhow::Result<()> {
+    let mut edges = HashMap::new();
+    for node in nodes {
+        for step in graph.path_to(node) {
+            if let Some((parent, kind)) = graph.parents.get(step.label()) {
+                if !edges.contains_key(step.label()) {
+                    let edge = describe_edge(ctx, parent, step, kind).await?;
+                    edges.insert(step.label().dupe(), edge);
+                }
+            }
+        }
+    }
+    write_configurations(graph, target, nodes, &edges, stdout)
+}
+
+/// Write how each of the configurations of `target` is reached, given the description of the edge
+/// to each node on the way.
+fn write_configurations(
+    graph: &ConfiguredGraphPaths,
+    target: &TargetLabel,
+    nodes: &[ConfiguredTargetNode],
+    edges: &HashMap<ConfiguredTargetLabel, String>,
+    stdout: &mut (dyn Write + Send),
+) -> anyhow::Result<()> {
+    writeln!(
+        stdout,
+        "{} is configured in {} configuration(s):",
+        target,
+        nodes.len()
+    )?;
+    for node in nodes {
+        let mut stdout = IndentWriter::new("  ", &mut *stdout);
+        writeln!(stdout, "{}", node.label().cfg())?;
+        let mut stdout = IndentWriter::new("  ", &mut stdout);
+        let mut prev: Option<&ConfiguredTargetNode> = None;
+        for step in graph.path_to(node) {
+            match prev {
+                None => writeln!(stdout, "{} (top-level)", step.label())?,
+                Some(parent) => {
+                    let edge = edges
+                        .get(step.label())
+                        .expect("edges are described for the whole path");
+                    writeln!(stdout, "-> {} ({})", step.label(), edge)?;
+                    if let Err(diff) = cfg_diff(parent.label().cfg(), step.label().cfg()) {
+                        write!(IndentWriter::new("     ", &mut stdout), "{}", diff)?;
+                    }
+                }
+            }
+            prev = Some(step);
+        }
+    }
+
+    if let Some((first, rest)) = nodes.split_first() {
+        for node in rest {
+            writeln!(
+                stdout,
+                "  Difference between {} and {}:",
+                first.label().cfg(),
+                node.label().cfg()
+            )?;
+            if let Err(diff) = cfg_diff(first.label().cfg(), node.label().cfg()) {
+                write!(IndentWriter::new("    ", &mut *stdout), "{}", diff)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl AuditSubcommand for AuditTransitionsCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx(async move |server_ctx, ctx| {
+                let target_platform =
+                    target_platform_from_client_context(&client_ctx, server_ctx, &ctx).await?;
+
+                let parsed_patterns = parse_patterns_from_cli_args::<TargetPatternExtra>(
+                    &ctx,
+                    &self
+                        .patterns
+                        .iter()
+                        .map(|value| buck2_data::TargetPattern {
+                            value: value.clone(),
+                        })
+                        .collect::<Vec<_>>(),
+                    server_ctx.working_dir(),
+                )
+                .await?;
+                let loaded_patterns =
+                    load_patterns(&ctx, parsed_patterns, MissingTargetBehavior::Fail).await?;
+
+                let mut roots = Vec::new();
+                for (_, targets) in loaded_patterns.into_iter() {
+                    for (_, node) in targets? {
+                        let label = ctx
+                            .get_configured_target(node.label(), target_platform.as_ref())
+                            .await?;
+                        match ctx.get_configured_target_node(&label).await? {
+                            MaybeCompatible::Compatible(node) => roots.push(node),
+                            MaybeCompatible::Incompatible(_) => {}
+                        }
+                    }
+                }
+
+                let pattern_parser = PatternParser::new(&ctx, server_ctx.working_dir()).await?;
+                let targets = self
+                    .targets
+                    .iter()
+                    .map(|t| {
+                        pattern_parser
+                            .parse_pattern::<TargetPatternExtra>(t)?
+                            .as_target_label(t)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                let graph = ConfiguredGraphPaths::compute(roots);
+
+                let mut stdout = stdout.as_writer();
+                if targets.is_empty() {
+                    for (target, nodes) in &graph.configurations {
+                        if nodes.len() > 1 {
+                            explain_configurations(&ctx, &graph, target, nodes, &mut stdout)
+                                .await?;
+                        }
+                    }
+                } else {
+                    for target in &targets {
+                        match graph.configurations.get(target) {
+                            Some(nodes) => {
+                                explain_configurations(&ctx, &graph, target, nodes, &mut stdout)
+                                    .await?
+                            }
+                            None => writeln!(
+                                stdout,
+                                "{} is not reachable from the given targets",
+                                target
+                            )?,
+                        }
+                    }
+                }
+
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::collections::HashMap;
+
+    use buck2_core::configuration::constraints::ConstraintKey;
+    use buck2_core::configuration::constraints::ConstraintValue;
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::configuration::data::ConfigurationDataData;
+    use buck2_core::target::label::ConfiguredTargetLabel;
+    use buck2_core::target::label::TargetLabel;
+    use buck2_node::nodes::configured::ConfiguredTargetNode;
+    use dupe::Dupe;
+
+    use crate::transitions::write_configurations;
+    use crate::transitions::ConfiguredGraphPaths;
+
+    #[test]
+    fn test_write_configurations() -> anyhow::Result<()> {
+        let target_cfg = ConfigurationData::from_platform(
+            "cfg//:target".to_owned(),
+            ConfigurationDataData::empty(),
+        )?;
+        let exec_cfg = ConfigurationData::from_platform(
+            "cfg//:exec".to_owned(),
+            ConfigurationDataData::new(BTreeMap::from_iter([(
+                ConstraintKey(TargetLabel::testing_parse("cfg//os:os")),
+                ConstraintValue(TargetLabel::testing_parse("cfg//os:linux")),
+            )])),
+        )?;
+        let node = |label: &str, cfg: &ConfigurationData, deps, exec_deps| {
+            ConfiguredTargetNode::testing_new_with_deps(
+                ConfiguredTargetLabel::testing_parse(label, cfg.dupe()),
+                "rule",
+                deps,
+                exec_deps,
+            )
+        };
+
+        // `gen` is a dep of `app` and an exec dep of `lib`, so it is configured twice.
+        let gen_target = node("root//:gen", &target_cfg, Vec::new(), Vec::new());
+        let gen_exec = node("root//:gen", &exec_cfg, Vec::new(), Vec::new());
+        let lib = node("root//:lib", &target_cfg, Vec::new(), vec![gen_exec.dupe()]);
+        let app = node(
+            "root//:app",
+            &target_cfg,
+            vec![lib.dupe(), gen_target.dupe()],
+            Vec::new(),
+        );
+
+        let graph = ConfiguredGraphPaths::compute(vec![app.dupe()]);
+        let gen = TargetLabel::testing_parse("root//:gen");
+        let nodes = &graph.configurations[&gen];
+        assert_eq!(
+            vec![gen_target.label(), gen_exec.label()],
+            nodes.iter().map(|n| n.label()).collect::<Vec<_>>()
+        );
+
+        let edges = graph
+            .parents
+            .iter()
+            .map(|(label, (_, kind))| (label.dupe(), kind.description().unwrap().to_owned()))
+            .collect::<HashMap<_, _>>();
+        let mut out = Vec::new();
+        write_configurations(&graph, &gen, nodes, &edges, &mut out)?;
+        assert_eq!(
+            format!(
+                "\
+                root//:gen is configured in 2 configuration(s):\n\
+                \x20 {target_cfg}\n\
+                \x20   {app} (top-level)\n\
+                \x20   -> {gen_target} (dep)\n\
+                \x20 {exec_cfg}\n\
+                \x20   {app} (top-level)\n\
+                \x20   -> {lib} (dep)\n\
+                \x20   -> {gen_exec} (exec dep)\n\
+                \x20        - label: cfg//:target\n\
+                \x20        + label: cfg//:exec\n\
+                \x20        + constraint: cfg//os:os -> cfg//os:linux\n\
+                \x20 Difference between {target_cfg} and {exec_cfg}:\n\
+                \x20   - label: cfg//:target\n\
+                \x20   + label: cfg//:exec\n\
+                \x20   + constraint: cfg//os:os -> cfg//os:linux\n",
+                app = app.label(),
+                lib = lib.label(),
+                gen_target = gen_target.label(),
+                gen_exec = gen_exec.label(),
+            ),
+            String::from_utf8(out)?
+        );
+        Ok(())
+    }
+}
@@ -41,6 +41,7 @@ use ref_cast::RefCast;
 use smallvec::SmallVec;
 use tracing::debug;
 
+use crate::actions::diagnostics::HasActionDiagnostics;
 use crate::actions::execute::action_executor::ActionOutputs;
 use crate::actions::execute::action_executor::HasActionExecutor;
 use crate::actions::key::ActionKeyExt;
@@ -120,6 +121,15 @@ async fn build_action_no_redirect(
             .execute(materialized_inputs, &action, cancellation)
             .await;
 
+        if let Some(diagnostics) = ctx.per_transaction_data().get_action_diagnostics() {
+            diagnostics.record(
+                action.owner(),
+                command_reports
+                    .iter()
+                    .flat_map(|r| r.diagnostics.iter().cloned()),
+            );
+        }
+
         let allow_omit_details = execute_result.is_ok();
 
         let commands = future::join_all(
@@ -419,5 +429,6 @@ pub async fn command_details(
         command: command_data,
         signed_exit_code,
         execution_stats: command.timing.execution_stats,
+        diagnostics: command.diagnostics.clone(),
     }
 }
@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+
+use buck2_core::base_deferred_key::BaseDeferredKey;
+use dice::UserComputationData;
+use parking_lot::Mutex;
+
+/// Diagnostics reported via the downward api by actions executed in the current command, so
+/// that they can be included in the build report.
+#[derive(Default)]
+pub struct ActionDiagnostics {
+    diagnostics: Mutex<Vec<(BaseDeferredKey, buck2_data::ActionDiagnostic)>>,
+}
+
+impl ActionDiagnostics {
+    pub fn record(
+        &self,
+        owner: &BaseDeferredKey,
+        diagnostics: impl IntoIterator<Item = buck2_data::ActionDiagnostic>,
+    ) {
+        let mut all = self.diagnostics.lock();
+        all.extend(diagnostics.into_iter().map(|d| (owner.clone(), d)));
+    }
+
+    pub fn take(&self) -> Vec<(BaseDeferredKey, buck2_data::ActionDiagnostic)> {
+        std::mem::take(&mut *self.diagnostics.lock())
+    }
+}
+
+pub trait SetActionDiagnostics {
+    fn set_action_diagnostics(&mut self, diagnostics: Arc<ActionDiagnostics>);
+}
+
+impl SetActionDiagnostics for UserComputationData {
+    fn set_action_diagnostics(&mut self, diagnostics: Arc<ActionDiagnostics>) {
+        self.data.set(diagnostics);
+    }
+}
+
+pub trait HasActionDiagnostics {
+    fn get_action_diagnostics(&self) -> Option<&ActionDiagnostics>;
+}
+
+impl HasActionDiagnostics for UserComputationData {
+    fn get_action_diagnostics(&self) -> Option<&ActionDiagnostics> {
+        self.data
+            .get::<Arc<ActionDiagnostics>>()
+            .ok()
+            .map(|diagnostics| diagnostics.as_ref())
+    }
+}
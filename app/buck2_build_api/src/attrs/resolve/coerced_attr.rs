@@ -11,6 +11,7 @@ use buck2_node::attrs::coerced_attr::CoercedAttr;
 use buck2_node::attrs::display::AttrDisplayWithContextExt;
 use starlark::values::dict::Dict;
 use starlark::values::list::AllocList;
+use starlark::values::structs::AllocStruct;
 use starlark::values::tuple::AllocTuple;
 use starlark::values::Heap;
 use starlark::values::Value;
@@ -59,6 +60,13 @@ impl CoercedAttrResolveExt for CoercedAttr {
                 }
                 Ok(heap.alloc(AllocTuple(v)))
             }
+            CoercedAttr::Record(r) => {
+                let mut v = Vec::with_capacity(r.len());
+                for (k, e) in r.iter() {
+                    v.push((k.as_str(), e.to_value(heap)?));
+                }
+                Ok(heap.alloc(AllocStruct(v)))
+            }
             CoercedAttr::Dict(d) => {
                 let mut m = SmallMap::with_capacity(d.len());
                 for (k, v) in d.iter() {
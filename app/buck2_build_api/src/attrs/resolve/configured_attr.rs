@@ -26,6 +26,7 @@ use starlark::values::dict::Dict;
 use starlark::values::list::AllocList;
 use starlark::values::list::ListRef;
 use starlark::values::none::NoneType;
+use starlark::values::structs::AllocStruct;
 use starlark::values::tuple::AllocTuple;
 use starlark::values::FrozenValue;
 use starlark::values::Heap;
@@ -44,6 +45,9 @@ use crate::attrs::resolve::ctx::AttrResolutionContext;
 use crate::interpreter::rule_defs::artifact::StarlarkArtifact;
 use crate::interpreter::rule_defs::provider::dependency::DependencyGen;
 
+/// Type of `struct()` values, `attrs.record()` attributes resolve to structs.
+const STRUCT_TYPE: &str = "struct";
+
 pub trait ConfiguredAttrExt {
     fn resolve<'v>(
         &self,
@@ -108,6 +112,13 @@ impl ConfiguredAttrExt for ConfiguredAttr {
                 }
                 Ok(ctx.heap().alloc(AllocTuple(values)))
             }
+            ConfiguredAttr::Record(record) => {
+                let mut fields = Vec::with_capacity(record.len());
+                for (k, v) in record.iter() {
+                    fields.push((k.as_str(), v.resolve_single(pkg.dupe(), ctx)?));
+                }
+                Ok(ctx.heap().alloc(AllocStruct(fields)))
+            }
             ConfiguredAttr::Dict(dict) => {
                 let mut res = SmallMap::with_capacity(dict.len());
                 for (k, v) in dict.iter() {
@@ -157,6 +168,7 @@ impl ConfiguredAttrExt for ConfiguredAttr {
             }
             ConfiguredAttr::List(_) => Ok(starlark::values::list::ListRef::TYPE),
             ConfiguredAttr::Tuple(_) => Ok(starlark::values::tuple::TupleRef::TYPE),
+            ConfiguredAttr::Record(_) => Ok(STRUCT_TYPE),
             ConfiguredAttr::Dict(_) => Ok(Dict::TYPE),
             ConfiguredAttr::None => Ok(NoneType::TYPE),
             ConfiguredAttr::OneOf(box l, _) => l.starlark_type(),
@@ -187,6 +199,11 @@ impl ConfiguredAttrExt for ConfiguredAttr {
             ConfiguredAttr::Tuple(v) => {
                 heap.alloc(AllocTuple(v.try_map(|v| v.to_value(pkg.dupe(), heap))?))
             }
+            ConfiguredAttr::Record(r) => {
+                heap.alloc(AllocStruct(r.try_map(|(k, v)| {
+                    anyhow::Ok((k.as_str(), v.to_value(pkg.dupe(), heap)?))
+                })?))
+            }
             ConfiguredAttr::Dict(map) => {
                 let mut res = SmallMap::with_capacity(map.len());
 
@@ -22,7 +22,9 @@ use buck2_common::executor_config::PathSeparatorKind;
 use buck2_common::result::SharedResult;
 use buck2_common::result::ToSharedResultExt;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
+use buck2_data::ToProtoMessage;
 use buck2_events::dispatch::console_message;
+use buck2_events::dispatch::get_dispatcher;
 use buck2_execute::artifact::fs::ExecutorFs;
 use buck2_query::query::compatibility::MaybeCompatible;
 use dashmap::mapref::entry::Entry;
@@ -281,6 +283,19 @@ pub async fn build_configured_label(
         ));
     }
 
+    // Report the target as built once the last of its outputs is done, so that subscribers can
+    // follow per-target progress.
+    let dispatcher = get_dispatcher();
+    let target_label = providers_label.target().as_proto();
+    let mut remaining = outputs.len();
+    let mut failed = false;
+    if remaining == 0 {
+        dispatcher.instant_event(buck2_data::TargetBuilt {
+            target_label: Some(target_label.clone()),
+            failed,
+        });
+    }
+
     let outputs = outputs
         .into_iter()
         .enumerate()
@@ -308,9 +323,19 @@ pub async fn build_configured_label(
         .collect::<FuturesUnordered<_>>()
         .map({
             let providers_label = providers_label.dupe();
-            move |(index, output)| BuildEvent {
-                label: providers_label.dupe(),
-                variant: BuildEventVariant::Output { index, output },
+            move |(index, output)| {
+                remaining -= 1;
+                failed |= output.is_err();
+                if remaining == 0 {
+                    dispatcher.instant_event(buck2_data::TargetBuilt {
+                        target_label: Some(target_label.clone()),
+                        failed,
+                    });
+                }
+                BuildEvent {
+                    label: providers_label.dupe(),
+                    variant: BuildEventVariant::Output { index, output },
+                }
             }
         });
 
@@ -55,6 +55,7 @@ use thiserror::Error;
 use crate::actions::calculation as action_calculation;
 use crate::actions::execute::action_executor::ActionOutputs;
 use crate::configuration::calculation::ConfigurationCalculation;
+use crate::configuration::modifiers::apply_cfg_modifiers;
 use crate::context::HasBuildContextData;
 use crate::nodes::calculation::get_execution_platform_toolchain_dep;
 use crate::nodes::calculation::ConfiguredTargetNodeKey;
@@ -177,8 +178,8 @@ impl<'c> Calculation<'c> for DiceComputations {
     ) -> anyhow::Result<T::Configured> {
         let node = self.get_target_node(target.target()).await?;
 
-        let get_platform_configuration = async || -> SharedResult<ConfigurationData> {
-            Ok(match global_target_platform {
+        let get_platform_configuration = async || -> anyhow::Result<ConfigurationData> {
+            let cfg = match global_target_platform {
                 Some(global_target_platform) => {
                     self.get_platform_configuration(global_target_platform)
                         .await?
@@ -187,7 +188,8 @@ impl<'c> Calculation<'c> for DiceComputations {
                     Some(target) => self.get_platform_configuration(target.target()).await?,
                     None => self.get_default_platform(target.target()).await?,
                 },
-            })
+            };
+            apply_cfg_modifiers(self, cfg, node.package_cfg_modifiers()).await
         };
 
         match node.rule_kind() {
@@ -14,6 +14,7 @@ use buck2_node::configuration::execution::ExecutionPlatform;
 use buck2_node::configuration::resolved::ResolvedConfiguration;
 
 pub mod calculation;
+pub mod modifiers;
 
 pub type ExecutionPlatforms = Arc<ExecutionPlatformsData>;
 
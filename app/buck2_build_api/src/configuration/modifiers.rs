@@ -0,0 +1,226 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Configuration modifiers from the command line and `PACKAGE` files.
+
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::result::SharedResult;
+use buck2_common::result::ToSharedResultExt;
+use buck2_common::result::ToUnsharedResultExt;
+use buck2_core::configuration::data::ConfigurationData;
+use buck2_core::configuration::modifiers::apply_modifiers;
+use buck2_core::configuration::modifiers::ResolvedModifier;
+use buck2_core::target::label::TargetLabel;
+use buck2_node::configuration::modifiers::PackageCfgModifiers;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::DiceTransactionUpdater;
+use dice::InjectedKey;
+use dice::Key;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+use crate::analysis::calculation::RuleAnalysisCalculation;
+use crate::configuration::calculation::ConfigurationError;
+use crate::interpreter::rule_defs::provider::builtin::configuration_info::FrozenConfigurationInfo;
+
+/// Buckconfig section mapping modifier aliases to `constraint_value` or `config_setting` targets.
+const MODIFIER_ALIASES_SECTION: &str = "modifier_aliases";
+
+#[derive(Debug, thiserror::Error)]
+enum ModifiersError {
+    #[error(
+        "Unknown modifier alias `{0}`, aliases are defined in `[{}]` section of the root buckconfig",
+        MODIFIER_ALIASES_SECTION
+    )]
+    UnknownAlias(String),
+    #[error(
+        "Modifier `{0}` is a `config_setting` with buckconfig values, modifiers may only set constraints"
+    )]
+    BuckconfigsNotAllowed(TargetLabel),
+}
+
+/// Modifiers as specified on the command line.
+#[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct CliModifiersKey;
+
+impl InjectedKey for CliModifiersKey {
+    type Value = Arc<Vec<String>>;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+pub trait SetCliModifiers {
+    fn set_cli_modifiers(&mut self, modifiers: Vec<String>) -> anyhow::Result<()>;
+}
+
+impl SetCliModifiers for DiceTransactionUpdater {
+    fn set_cli_modifiers(&mut self, modifiers: Vec<String>) -> anyhow::Result<()> {
+        Ok(self.changed_to(vec![(CliModifiersKey, Arc::new(modifiers))])?)
+    }
+}
+
+/// A modifier target with the name used in the label of the modified configuration.
+#[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(fmt = "{}", target)]
+struct NamedModifier {
+    name: Arc<str>,
+    target: TargetLabel,
+}
+
+impl NamedModifier {
+    fn from_target(target: TargetLabel) -> NamedModifier {
+        NamedModifier {
+            name: Arc::from(target.name().as_str()),
+            target,
+        }
+    }
+}
+
+/// Resolve command line modifiers: either aliases or target labels relative to the root cell.
+async fn get_cli_modifiers(ctx: &DiceComputations) -> SharedResult<Arc<Vec<NamedModifier>>> {
+    #[derive(Clone, Dupe, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+    #[display(fmt = "{:?}", self)]
+    struct ResolvedCliModifiersKey;
+
+    #[async_trait]
+    impl Key for ResolvedCliModifiersKey {
+        type Value = SharedResult<Arc<Vec<NamedModifier>>>;
+
+        async fn compute(
+            &self,
+            ctx: &DiceComputations,
+            _cancellation: &CancellationContext,
+        ) -> Self::Value {
+            let modifiers = ctx.compute(&CliModifiersKey).await?;
+            if modifiers.is_empty() {
+                return Ok(Arc::new(Vec::new()));
+            }
+            let resolver = ctx.get_cell_resolver().await?;
+            let root_cell = resolver.root_cell();
+            let mut resolved = Vec::with_capacity(modifiers.len());
+            for modifier in modifiers.iter() {
+                if modifier.contains(':') || modifier.contains('/') {
+                    resolved.push(NamedModifier::from_target(TargetLabel::parse(
+                        modifier, root_cell, &resolver,
+                    )?));
+                } else {
+                    let target = ctx
+                        .get_legacy_config_property(root_cell, MODIFIER_ALIASES_SECTION, modifier)
+                        .await?
+                        .ok_or_else(|| ModifiersError::UnknownAlias(modifier.clone()))?;
+                    resolved.push(NamedModifier {
+                        name: Arc::from(modifier.as_str()),
+                        target: TargetLabel::parse(&target, root_cell, &resolver)?,
+                    });
+                }
+            }
+            Ok(Arc::new(resolved))
+        }
+
+        fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+            match (x, y) {
+                (Ok(x), Ok(y)) => x == y,
+                _ => false,
+            }
+        }
+    }
+
+    ctx.compute(&ResolvedCliModifiersKey).await?
+}
+
+async fn resolve_modifier(
+    ctx: &DiceComputations,
+    modifier: &NamedModifier,
+) -> anyhow::Result<ResolvedModifier> {
+    let result = ctx
+        .get_configuration_analysis_result(&modifier.target)
+        .await?;
+    let setting = FrozenConfigurationInfo::from_providers(result.providers().provider_collection())
+        .ok_or_else(|| {
+            ConfigurationError::MissingConfigurationInfoProvider(modifier.target.dupe())
+        })?
+        .to_config_setting_data();
+    if !setting.buckconfigs.is_empty() {
+        return Err(ModifiersError::BuckconfigsNotAllowed(modifier.target.dupe()).into());
+    }
+    Ok(ResolvedModifier {
+        name: modifier.name.to_string(),
+        constraints: setting.constraints.into_iter().collect(),
+    })
+}
+
+/// Apply `PACKAGE` file modifiers and then command line modifiers
+/// to the target platform configuration of a top-level target.
+pub(crate) async fn apply_cfg_modifiers(
+    ctx: &DiceComputations,
+    cfg: ConfigurationData,
+    package_modifiers: &PackageCfgModifiers,
+) -> anyhow::Result<ConfigurationData> {
+    #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+    #[display(
+        fmt = "ModifiedConfigurationKey({}, {} modifiers)",
+        base,
+        "modifiers.len()"
+    )]
+    struct ModifiedConfigurationKey {
+        base: ConfigurationData,
+        modifiers: Vec<NamedModifier>,
+    }
+
+    #[async_trait]
+    impl Key for ModifiedConfigurationKey {
+        type Value = SharedResult<ConfigurationData>;
+
+        async fn compute(
+            &self,
+            ctx: &DiceComputations,
+            _cancellation: &CancellationContext,
+        ) -> Self::Value {
+            let modifiers = futures::future::try_join_all(
+                self.modifiers.iter().map(|m| resolve_modifier(ctx, m)),
+            )
+            .await?;
+            apply_modifiers(&self.base, &modifiers).shared_error()
+        }
+
+        fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+            match (x, y) {
+                (Ok(x), Ok(y)) => x == y,
+                _ => false,
+            }
+        }
+    }
+
+    let cli_modifiers = get_cli_modifiers(ctx).await?;
+    if package_modifiers.is_empty() && cli_modifiers.is_empty() {
+        return Ok(cfg);
+    }
+
+    let modifiers: Vec<NamedModifier> = package_modifiers
+        .iter()
+        .map(|m| NamedModifier::from_target(m.dupe()))
+        .chain(cli_modifiers.iter().map(|m| m.dupe()))
+        .collect();
+
+    ctx.compute(&ModifiedConfigurationKey {
+        base: cfg,
+        modifiers,
+    })
+    .await?
+    .unshared_error()
+}
@@ -11,6 +11,10 @@ use std::sync::Arc;
 
 use buck2_common::dice::cells::SetCellResolver;
 use buck2_common::dice::data::SetIoProvider;
+use buck2_common::dice::file_ops::FileInvalidationStats;
+use buck2_common::dice::file_ops::SetFileInvalidationStats;
+use buck2_common::external_cells::ExternalCells;
+use buck2_common::external_cells::SetExternalCells;
 use buck2_common::io::IoProvider;
 use buck2_common::legacy_configs::dice::SetLegacyConfigs;
 use buck2_common::legacy_configs::LegacyBuckConfig;
@@ -26,6 +30,7 @@ use dice::WhichSpawner;
 pub async fn configure_dice_for_buck(
     io: Arc<dyn IoProvider>,
     digest_config: DigestConfig,
+    file_invalidation_stats: Arc<FileInvalidationStats>,
     root_config: Option<&LegacyBuckConfig>,
     detect_cycles: Option<DetectCycles>,
     which_dice: Option<WhichDice>,
@@ -60,7 +65,9 @@ pub async fn configure_dice_for_buck(
         WhichDice::Modern => Dice::modern(),
     };
     dice.set_io_provider(io);
+    dice.set_external_cells(Arc::new(ExternalCells::new()));
     dice.set_digest_config(digest_config);
+    dice.set_file_invalidation_stats(file_invalidation_stats);
 
     let dice = dice.build_with_which_spawner(detect_cycles, which_spawner);
     let mut dice_ctx = dice.updater();
@@ -53,6 +53,7 @@ use buck2_node::visibility::VisibilityError;
 use buck2_query::query::compatibility::IncompatiblePlatformReason;
 use buck2_query::query::compatibility::IncompatiblePlatformReasonCause;
 use buck2_query::query::compatibility::MaybeCompatible;
+use buck2_query::query::compatibility::UnsatisfiedCompatibility;
 use derive_more::Display;
 use dice::DiceComputations;
 use dice::Key;
@@ -510,9 +511,7 @@ fn check_compatible(
         Ok((left, right))
     };
 
-    // We only record the first incompatibility, for either ANY or ALL.
-    // TODO(cjhopman): Should we report _all_ the things that are incompatible?
-    let incompatible_target = match compatibility_constraints {
+    let unsatisfied = match compatibility_constraints {
         CompatibilityConstraints::Any(attr) => {
             let (compatible, incompatible) = check_compatibility(attr).with_context(|| {
                 format!(
@@ -520,30 +519,33 @@ fn check_compatible(
                     LEGACY_TARGET_COMPATIBLE_WITH_ATTRIBUTE_FIELD
                 )
             })?;
-            let incompatible = incompatible.into_iter().next();
-            match (compatible.is_empty(), incompatible.into_iter().next()) {
-                (false, _) | (true, None) => {
-                    return Ok(MaybeCompatible::Compatible(()));
-                }
-                (true, Some(v)) => v,
+            if !compatible.is_empty() || incompatible.is_empty() {
+                return Ok(MaybeCompatible::Compatible(()));
+            }
+            UnsatisfiedCompatibility {
+                attr: LEGACY_TARGET_COMPATIBLE_WITH_ATTRIBUTE_FIELD.to_owned(),
+                require_all: false,
+                unsatisfied: incompatible,
             }
         }
         CompatibilityConstraints::All(attr) => {
             let (_compatible, incompatible) = check_compatibility(attr).with_context(|| {
                 format!("attribute `{}`", TARGET_COMPATIBLE_WITH_ATTRIBUTE_FIELD)
             })?;
-            match incompatible.into_iter().next() {
-                Some(label) => label,
-                None => {
-                    return Ok(MaybeCompatible::Compatible(()));
-                }
+            if incompatible.is_empty() {
+                return Ok(MaybeCompatible::Compatible(()));
+            }
+            UnsatisfiedCompatibility {
+                attr: TARGET_COMPATIBLE_WITH_ATTRIBUTE_FIELD.to_owned(),
+                require_all: true,
+                unsatisfied: incompatible,
             }
         }
     };
     Ok(MaybeCompatible::Incompatible(Arc::new(
         IncompatiblePlatformReason {
             target: target_label.dupe(),
-            cause: IncompatiblePlatformReasonCause::UnsatisfiedConfig(incompatible_target),
+            cause: IncompatiblePlatformReasonCause::UnsatisfiedCompatibility(Arc::new(unsatisfied)),
         },
     )))
 }
@@ -35,6 +35,7 @@ use buck2_client_ctx::exit_result::FailureExitCode;
 use buck2_client_ctx::final_console::FinalConsole;
 use buck2_client_ctx::output_destination_arg::OutputDestinationArg;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::configuration::modifiers::split_patterns_modifiers;
 use buck2_core::fs::async_fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
@@ -103,6 +104,15 @@ pub struct BuildCommand {
     )]
     materializations: Option<FinalArtifactMaterializations>,
 
+    #[clap(
+        long = "output-manifest",
+        value_name = "PATH",
+        help = "Write the path, digest and size of each output to a JSON file, and do not \
+                materialize the outputs unless `--materializations` says otherwise. Outputs can \
+                then be fetched with `buck2 materialize --on-demand --output-manifest`."
+    )]
+    output_manifest: Option<String>,
+
     #[allow(unused)]
     #[clap(
         long,
@@ -160,6 +170,13 @@ pub struct BuildCommand {
 }
 
 impl BuildCommand {
+    fn final_artifact_materializations(&self) -> buck2_cli_proto::build_request::Materializations {
+        match (&self.materializations, &self.output_manifest) {
+            (None, Some(_)) => buck2_cli_proto::build_request::Materializations::Skip,
+            (materializations, _) => materializations.to_proto(),
+        }
+    }
+
     fn default_info(&self) -> build_providers::Action {
         if self.skip_default_info {
             return build_providers::Action::Skip;
@@ -224,20 +241,20 @@ impl StreamingCommand for BuildCommand {
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
         let show_default_other_outputs = false;
-        let context = ctx.client_context(
+        let mut context = ctx.client_context(
             &self.common_opts.config_opts,
             matches,
             self.sanitized_argv(),
         )?;
+        let (patterns, modifiers) = split_patterns_modifiers(&self.patterns)?;
+        context.modifiers.extend(modifiers);
 
         let result = buckd
             .with_flushing()
             .build(
                 BuildRequest {
                     context: Some(context),
-                    target_patterns: self
-                        .patterns
-                        .map(|p| buck2_data::TargetPattern { value: p.clone() }),
+                    target_patterns: patterns.into_map(|value| buck2_data::TargetPattern { value }),
                     unstable_print_providers: self.print_providers,
                     build_providers: Some(BuildProviders {
                         default_info: self.default_info() as i32,
@@ -253,8 +270,9 @@ impl StreamingCommand for BuildCommand {
                         return_default_other_outputs: show_default_other_outputs,
                     }),
                     build_opts: Some(self.build_opts.to_proto()),
-                    final_artifact_materializations: self.materializations.to_proto() as i32,
+                    final_artifact_materializations: self.final_artifact_materializations() as i32,
                     target_universe: self.target_universe,
+                    output_manifest_filename: self.output_manifest.clone().unwrap_or_default(),
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
@@ -19,6 +19,7 @@ use buck2_client_ctx::daemon::client::BuckdClientConnector;
 use buck2_client_ctx::daemon::client::NoPartialResultHandler;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::configuration::modifiers::strip_patterns_modifiers;
 use clap::ArgMatches;
 use gazebo::prelude::SliceExt;
 
@@ -45,16 +46,20 @@ impl StreamingCommand for ConfiguredTargetsCommand {
     const COMMAND_NAME: &'static str = "ctargets";
 
     async fn exec_impl(
-        self,
+        mut self,
         buckd: &mut BuckdClientConnector,
         matches: &ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
-        let context = Some(ctx.client_context(
+        let mut context = ctx.client_context(
             &self.common_opts.config_opts,
             matches,
             self.sanitized_argv(),
-        )?);
+        )?;
+        context
+            .modifiers
+            .extend(strip_patterns_modifiers(&mut self.patterns)?);
+        let context = Some(context);
         let ConfiguredTargetsResponse {
             serialized_targets_output,
         } = buckd
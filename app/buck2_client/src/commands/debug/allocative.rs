@@ -32,6 +32,13 @@ pub struct AllocativeCommand {
         default_value = "allocative-out"
     )]
     output: PathArg,
+
+    /// Previous allocative output directory (or its `flamegraph.src`) to compare against.
+    ///
+    /// Writes a report of memory growth and shrinkage by key path to `diff.txt`
+    /// and a differential flamegraph to `diff.svg` in the output directory.
+    #[clap(long, value_name = "PATH")]
+    diff: Option<PathArg>,
 }
 
 #[async_trait]
@@ -49,12 +56,17 @@ impl StreamingCommand for AllocativeCommand {
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
         let context = ctx.empty_client_context()?;
+        let diff_path = self
+            .diff
+            .map(|diff| diff.resolve(&ctx.working_dir).into_string())
+            .transpose()?;
         buckd
             .with_flushing()
             .allocative(
                 AllocativeRequest {
                     context: Some(context),
                     output_path: self.output.resolve(&ctx.working_dir).into_string()?,
+                    diff_path,
                 },
                 ctx.stdin().console_interaction_stream(self.console_opts()),
                 &mut NoPartialResultHandler,
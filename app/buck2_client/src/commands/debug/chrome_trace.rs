@@ -701,6 +701,11 @@ impl ChromeTraceWriter {
                         self.span_counters.bump_counter_while_span(event, name, 1)?;
                         Categorization::ShowIfParent { name: name.into() }
                     }
+                    buck2_data::span_start_event::Data::DownwardApiSpan(span) => {
+                        Categorization::ShowIfParent {
+                            name: span.name.clone().into(),
+                        }
+                    }
                     buck2_data::span_start_event::Data::FileWatcher(_file_watcher) => {
                         Categorization::Show {
                             category: Self::CRITICAL_PATH,
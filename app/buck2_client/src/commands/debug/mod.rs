@@ -18,7 +18,6 @@ use file_status::FileStatusCommand;
 use flush_dep_files::FlushDepFilesCommand;
 use heap_dump::HeapDumpCommand;
 use internal_version::InternalVersionCommand;
-use materialize::MaterializeCommand;
 use replay::ReplayCommand;
 
 use crate::commands::debug::allocative::AllocativeCommand;
@@ -32,6 +31,7 @@ use crate::commands::debug::trace_io::TraceIoCommand;
 use crate::commands::debug::upload_re_logs::UploadReLogsCommand;
 use crate::commands::log::debug_last_log::DebugLastLogCommand;
 use crate::commands::log::debug_what_ran::DebugWhatRanCommand;
+use crate::commands::materialize::MaterializeCommand;
 
 mod allocative;
 mod allocator_stats;
@@ -45,12 +45,11 @@ mod flush_dep_files;
 mod heap_dump;
 mod internal_version;
 mod log_perf;
-mod materialize;
 mod persist_event_logs;
 pub mod replay;
 mod segfault;
 mod set_log_filter;
-mod trace_io;
+pub(crate) mod trace_io;
 mod upload_re_logs;
 
 #[derive(Debug, clap::Parser)]
@@ -79,7 +78,7 @@ pub enum DebugCommand {
     ChromeTrace(ChromeTraceCommand),
     /// Flushes all dep files known to Buck2.
     FlushDepFiles(FlushDepFilesCommand),
-    /// Forces materialization of a path, even on the deferred materializer
+    /// Alias for `materialize`.
     Materialize(MaterializeCommand),
     // Upload RE logs given an RE session ID
     UploadReLogs(UploadReLogsCommand),
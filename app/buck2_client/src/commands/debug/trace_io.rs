@@ -102,44 +102,7 @@ impl StreamingCommand for TraceIoCommand {
                 };
                 let resp = self.send_request(req, buckd, ctx).await??;
 
-                let manifest = OfflineArchiveManifest {
-                    paths: resp
-                        .trace
-                        .into_iter()
-                        // Note: Safe because these are all ProjectRelativePath's on the daemon side.
-                        .map(ProjectRelativePathBuf::unchecked_new)
-                        .collect(),
-                    external_paths: resp
-                        .external_entries
-                        .into_iter()
-                        .map(|path| {
-                            AbsNormPathBuf::try_from(path)
-                                .expect("got unexpected non-absolute path")
-                        })
-                        .collect(),
-                    relative_symlinks: resp
-                        .relative_symlinks
-                        .into_iter()
-                        .map(|symlink| RelativeSymlink {
-                            link: ProjectRelativePathBuf::unchecked_new(symlink.link),
-                            target: ProjectRelativePathBuf::unchecked_new(symlink.target),
-                        })
-                        .collect(),
-                    external_symlinks: resp
-                        .external_symlinks
-                        .into_iter()
-                        .map(|symlink| ExternalSymlink {
-                            link: ProjectRelativePathBuf::unchecked_new(symlink.link),
-                            target: AbsPathBuf::try_from(symlink.target)
-                                .expect("got unexpected non-absolute symlink target"),
-                            remaining_path: symlink
-                                .remaining_path
-                                .map(ForwardRelativePathBuf::unchecked_new),
-                        })
-                        .collect(),
-                    repository: RepositoryMetadata::from_cwd()
-                        .context("creating repository metadata")?,
-                };
+                let manifest = manifest_from_trace_io_response(resp)?;
                 let serialized = serde_json::to_string(&manifest)
                     .context("serializing offline archive manifest to json")?;
                 if let Some(output_path) = &out {
@@ -178,3 +141,43 @@ impl StreamingCommand for TraceIoCommand {
         CommonBuildConfigurationOptions::default_ref()
     }
 }
+
+/// Convert the I/O trace returned by the daemon into an offline archive manifest.
+pub(crate) fn manifest_from_trace_io_response(
+    resp: TraceIoResponse,
+) -> anyhow::Result<OfflineArchiveManifest> {
+    Ok(OfflineArchiveManifest {
+        paths: resp
+            .trace
+            .into_iter()
+            // Note: Safe because these are all ProjectRelativePath's on the daemon side.
+            .map(ProjectRelativePathBuf::unchecked_new)
+            .collect(),
+        external_paths: resp
+            .external_entries
+            .into_iter()
+            .map(|path| AbsNormPathBuf::try_from(path).expect("got unexpected non-absolute path"))
+            .collect(),
+        relative_symlinks: resp
+            .relative_symlinks
+            .into_iter()
+            .map(|symlink| RelativeSymlink {
+                link: ProjectRelativePathBuf::unchecked_new(symlink.link),
+                target: ProjectRelativePathBuf::unchecked_new(symlink.target),
+            })
+            .collect(),
+        external_symlinks: resp
+            .external_symlinks
+            .into_iter()
+            .map(|symlink| ExternalSymlink {
+                link: ProjectRelativePathBuf::unchecked_new(symlink.link),
+                target: AbsPathBuf::try_from(symlink.target)
+                    .expect("got unexpected non-absolute symlink target"),
+                remaining_path: symlink
+                    .remaining_path
+                    .map(ForwardRelativePathBuf::unchecked_new),
+            })
+            .collect(),
+        repository: RepositoryMetadata::from_cwd().context("creating repository metadata")?,
+    })
+}
@@ -20,6 +20,7 @@ use buck2_client_ctx::daemon::client::BuckdClientConnector;
 use buck2_client_ctx::daemon::client::NoPartialResultHandler;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::configuration::modifiers::split_patterns_modifiers;
 use gazebo::prelude::*;
 
 #[derive(Debug, clap::Parser)]
@@ -58,19 +59,19 @@ impl StreamingCommand for InstallCommand {
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
-        let context = ctx.client_context(
+        let mut context = ctx.client_context(
             &self.common_opts.config_opts,
             matches,
             self.sanitized_argv(),
         )?;
+        let (patterns, modifiers) = split_patterns_modifiers(&self.patterns)?;
+        context.modifiers.extend(modifiers);
         let response = buckd
             .with_flushing()
             .install(
                 InstallRequest {
                     context: Some(context),
-                    target_patterns: self.patterns.map(|pat| buck2_data::TargetPattern {
-                        value: pat.to_owned(),
-                    }),
+                    target_patterns: patterns.into_map(|value| buck2_data::TargetPattern { value }),
                     build_opts: Some(self.build_opts.to_proto()),
                     installer_run_args: self.extra_run_args,
                     installer_debug: self.installer_debug,
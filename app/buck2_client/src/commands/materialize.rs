@@ -19,6 +19,9 @@ use buck2_client_ctx::daemon::client::NoPartialResultHandler;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::streaming::StreamingCommand;
 
+/// Materialize outputs of previous builds, even on the deferred materializer.
+///
+/// Paths within an output directory materialize the whole directory.
 #[derive(Debug, clap::Parser)]
 pub struct MaterializeCommand {
     #[clap(flatten)]
@@ -27,6 +30,17 @@ pub struct MaterializeCommand {
     /// Paths to materialize, relative to project root
     #[clap(value_name = "PATH")]
     paths: Vec<String>,
+
+    /// Fail if a path is neither on disk nor an output known to the daemon, e.g. because it was
+    /// built by a daemon which has since been restarted. Use this for paths from a
+    /// `buck2 build --output-manifest`.
+    #[clap(long)]
+    on_demand: bool,
+
+    /// With `--on-demand`, download paths which are not known to the daemon from the CAS, using
+    /// their digests in this manifest written by `buck2 build --output-manifest`.
+    #[clap(long, value_name = "PATH", requires = "on-demand")]
+    output_manifest: Option<String>,
 }
 
 #[async_trait]
@@ -54,6 +68,8 @@ impl StreamingCommand for MaterializeCommand {
                 MaterializeRequest {
                     context: Some(context),
                     paths: self.paths,
+                    on_demand: self.on_demand,
+                    output_manifest: self.output_manifest.unwrap_or_default(),
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
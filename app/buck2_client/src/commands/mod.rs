@@ -19,6 +19,8 @@ pub mod kill;
 pub mod killall;
 pub mod log;
 pub mod lsp;
+pub mod materialize;
+pub mod offline_archive;
 pub mod profile;
 pub mod query;
 pub mod rage;
@@ -0,0 +1,227 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_cli_proto::build_request::build_providers;
+use buck2_cli_proto::build_request::BuildProviders;
+use buck2_cli_proto::build_request::ResponseOptions;
+use buck2_cli_proto::trace_io_request;
+use buck2_cli_proto::BuildRequest;
+use buck2_cli_proto::MaterializeRequest;
+use buck2_cli_proto::TraceIoRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::command_outcome::CommandOutcome;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonBuildOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonDaemonCommandOptions;
+use buck2_client_ctx::daemon::client::connect::DesiredTraceIoState;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::NoPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::configuration::modifiers::strip_patterns_modifiers;
+use buck2_offline_archive::write_archive;
+use buck2_offline_archive::ARCHIVE_REPO_DIR;
+use gazebo::prelude::*;
+
+use crate::commands::build::print_build_result;
+use crate::commands::debug::trace_io::manifest_from_trace_io_response;
+
+#[derive(Debug, clap::Parser)]
+#[clap(about = "Create archives for building without network access")]
+pub enum OfflineArchiveCommand {
+    Create(CreateOfflineArchiveCommand),
+}
+
+impl OfflineArchiveCommand {
+    pub fn exec(self, matches: &clap::ArgMatches, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let matches = matches.subcommand().expect("subcommand not found").1;
+        match self {
+            Self::Create(cmd) => cmd.exec(matches, ctx),
+        }
+    }
+}
+
+/// Build the given targets and write an archive of everything that was needed to build them:
+/// sources, configuration, downloaded artifacts and files outside the project that the build
+/// accessed.
+///
+/// To build from the archive on a machine without network access, extract it and run buck2
+/// from the `repo` directory it contains: the archive includes configuration that makes
+/// downloads use the archived artifacts instead of the network.
+///
+/// This requires I/O tracing, so it restarts the daemon if tracing is not already enabled.
+#[derive(Debug, clap::Parser)]
+pub struct CreateOfflineArchiveCommand {
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+
+    #[clap(flatten)]
+    build_opts: CommonBuildOptions,
+
+    #[clap(short, long, help = "Path to write the archive (a tarball) to")]
+    output: PathArg,
+
+    #[clap(name = "TARGET_PATTERNS", help = "Patterns to build", required = true)]
+    patterns: Vec<String>,
+}
+
+#[async_trait]
+impl StreamingCommand for CreateOfflineArchiveCommand {
+    const COMMAND_NAME: &'static str = "offline-archive create";
+
+    async fn exec_impl(
+        mut self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let mut context = ctx.client_context(
+            &self.common_opts.config_opts,
+            matches,
+            self.sanitized_argv(),
+        )?;
+        context
+            .modifiers
+            .extend(strip_patterns_modifiers(&mut self.patterns)?);
+
+        let result = buckd
+            .with_flushing()
+            .build(
+                BuildRequest {
+                    context: Some(context.clone()),
+                    target_patterns: self
+                        .patterns
+                        .map(|p| buck2_data::TargetPattern { value: p.clone() }),
+                    unstable_print_providers: false,
+                    build_providers: Some(BuildProviders {
+                        default_info: build_providers::Action::Build as i32,
+                        run_info: build_providers::Action::BuildIfAvailable as i32,
+                        test_info: build_providers::Action::Skip as i32,
+                    }),
+                    response_options: Some(ResponseOptions {
+                        return_outputs: false,
+                        return_default_other_outputs: false,
+                    }),
+                    build_opts: Some(self.build_opts.to_proto()),
+                    final_artifact_materializations:
+                        buck2_cli_proto::build_request::Materializations::Default as i32,
+                    target_universe: Vec::new(),
+                    output_manifest_filename: String::new(),
+                },
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+                &mut NoPartialResultHandler,
+            )
+            .await;
+
+        let console = self.common_opts.console_opts.final_console();
+
+        let success = matches!(
+            &result,
+            Ok(CommandOutcome::Success(response)) if response.error_messages.is_empty()
+        );
+        if !success {
+            console.print_error("BUILD FAILED, not creating an offline archive")?;
+            let response = result??;
+            print_build_result(&console, &response.error_messages)?;
+            return ExitResult::failure();
+        }
+
+        let trace = buckd
+            .with_flushing()
+            .trace_io(
+                TraceIoRequest {
+                    context: Some(context.clone()),
+                    read_state: Some(trace_io_request::ReadIoTracingState { with_trace: true }),
+                },
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+                &mut NoPartialResultHandler,
+            )
+            .await??;
+
+        let manifest = manifest_from_trace_io_response(trace)?;
+
+        // With deferred materialization, outputs the build read (e.g. downloaded artifacts) might
+        // not be on disk.
+        let paths = ctx.paths()?;
+        let buck_out = paths.buck_out_dir();
+        buckd
+            .with_flushing()
+            .materialize(
+                MaterializeRequest {
+                    context: Some(context),
+                    paths: manifest
+                        .paths
+                        .iter()
+                        .filter(|path| path.starts_with(&buck_out))
+                        .map(|path| path.to_string())
+                        .collect(),
+                    on_demand: false,
+                    output_manifest: String::new(),
+                },
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+                &mut NoPartialResultHandler,
+            )
+            .await??;
+
+        let output = self.output.resolve(&ctx.working_dir);
+        let missing = write_archive(
+            &manifest,
+            paths.project_root().root(),
+            &buck_out,
+            output.as_path(),
+        )
+        .with_context(|| {
+            format!(
+                "Error writing offline archive to `{}`",
+                output.as_path().display()
+            )
+        })?;
+
+        for path in &missing {
+            console.print_warning(&format!(
+                "`{}` was accessed by the build but does not exist, it is not in the archive",
+                path
+            ))?;
+        }
+
+        console.print_success(&format!(
+            "Wrote offline archive to `{}` ({} paths). Extract it and run buck2 from its `{}` directory to build offline.",
+            output.as_path().display(),
+            manifest.paths.len(),
+            ARCHIVE_REPO_DIR,
+        ))?;
+
+        ExitResult::success()
+    }
+
+    /// Results in a daemon restart if tracing is not already enabled.
+    fn trace_io(&self) -> DesiredTraceIoState {
+        DesiredTraceIoState::Enabled
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonDaemonCommandOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn common_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+}
@@ -9,6 +9,7 @@
 
 use buck2_cli_proto::QueryOutputFormat;
 use buck2_client_ctx::query_args::CommonAttributeArgs;
+use buck2_core::configuration::modifiers::strip_query_modifiers;
 use buck2_query_parser::placeholder::QUERY_PERCENT_SS_PLACEHOLDER;
 use dupe::Dupe;
 
@@ -101,6 +102,11 @@ impl CommonQueryOptions {
         }
     }
 
+    /// Strip configuration modifiers from the query and its arguments.
+    pub fn strip_modifiers(&mut self) -> anyhow::Result<Vec<String>> {
+        strip_query_modifiers(&mut self.query, &mut self.query_args)
+    }
+
     pub fn get_query(&self) -> (String, Vec<String>) {
         if self.query.contains(QUERY_PERCENT_SS_PLACEHOLDER) {
             let replacement = Self::args_as_set(&self.query_args);
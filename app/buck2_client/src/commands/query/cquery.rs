@@ -92,19 +92,21 @@ impl StreamingCommand for CqueryCommand {
     const COMMAND_NAME: &'static str = "cquery";
 
     async fn exec_impl(
-        self,
+        mut self,
         buckd: &mut BuckdClientConnector,
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
+        let modifiers = self.query_common.strip_modifiers()?;
         let (query, query_args) = self.query_common.get_query();
         let unstable_output_format = self.query_common.output_format() as i32;
         let output_attributes = self.query_common.attributes.get()?;
-        let context = ctx.client_context(
+        let mut context = ctx.client_context(
             &self.common_opts.config_opts,
             matches,
             self.sanitized_argv(),
         )?;
+        context.modifiers.extend(modifiers);
 
         let correct_owner = match (self.correct_owner, self.deprecated_owner) {
             (true, false) => true,
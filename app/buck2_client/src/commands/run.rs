@@ -28,6 +28,7 @@ use buck2_client_ctx::daemon::client::BuckdClientConnector;
 use buck2_client_ctx::daemon::client::NoPartialResultHandler;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::configuration::modifiers::strip_patterns_modifiers;
 use buck2_wrapper_common::BUCK2_WRAPPER_ENV_VAR;
 use buck2_wrapper_common::BUCK_WRAPPER_UUID_ENV_VAR;
 use serde::Serialize;
@@ -88,16 +89,19 @@ impl StreamingCommand for RunCommand {
     const COMMAND_NAME: &'static str = "run";
 
     async fn exec_impl(
-        self,
+        mut self,
         buckd: &mut BuckdClientConnector,
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
-        let context = ctx.client_context(
+        let mut context = ctx.client_context(
             &self.common_opts.config_opts,
             matches,
             self.sanitized_argv(),
         )?;
+        context
+            .modifiers
+            .extend(strip_patterns_modifiers(std::iter::once(&mut self.target))?);
         // TODO(rafaelc): fail fast on the daemon if the target doesn't have RunInfo
         let response = buckd
             .with_flushing()
@@ -117,6 +121,7 @@ impl StreamingCommand for RunCommand {
                     build_opts: Some(self.build_opts.to_proto()),
                     final_artifact_materializations: Materializations::Materialize as i32,
                     target_universe: Vec::new(),
+                    output_manifest_filename: String::new(),
                 },
                 ctx.stdin()
                     .console_interaction_stream(&self.common_opts.console_opts),
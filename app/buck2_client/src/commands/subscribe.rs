@@ -54,6 +54,19 @@ pub struct SubscribeCommand {
     #[clap(long)]
     active_commands: bool,
 
+    /// Whether to request action, target and test notifications of running commands.
+    #[clap(long)]
+    build_events: bool,
+
+    /// Only report build events of the command with this trace id. Can be repeated.
+    #[clap(long, requires = "build-events")]
+    trace_id: Vec<String>,
+
+    /// Only report build events for targets matching this fully qualified pattern (e.g.
+    /// `cell//pkg/...`). Can be repeated.
+    #[clap(long, requires = "build-events")]
+    target_pattern: Vec<String>,
+
     /// Whether to get output as JSON. The JSON format is deemed unstable so this should only be
     /// used for debugging.
     #[clap(long)]
@@ -101,15 +114,25 @@ impl StreamingCommand for SubscribeCommand {
             ok: true,
         };
 
-        let stream = if self.active_commands {
-            futures::stream::once(futures::future::ready(SubscriptionRequest {
+        let mut initial_requests = Vec::new();
+        if self.active_commands {
+            initial_requests.push(SubscriptionRequest {
                 request: Some(buck2_subscription_proto::SubscribeToActiveCommands {}.into()),
-            }))
-            .chain(stream)
-            .left_stream()
-        } else {
-            stream.right_stream()
-        };
+            });
+        }
+        if self.build_events {
+            initial_requests.push(SubscriptionRequest {
+                request: Some(
+                    buck2_subscription_proto::SubscribeToBuildEvents {
+                        trace_ids: self.trace_id,
+                        target_patterns: self.target_pattern,
+                    }
+                    .into(),
+                ),
+            });
+        }
+
+        let stream = futures::stream::iter(initial_requests).chain(stream);
 
         let stream = stream.map(|request| buck2_cli_proto::SubscriptionRequestWrapper {
             request: Some(request),
@@ -24,6 +24,7 @@ use buck2_client_ctx::path_arg::PathArg;
 use buck2_client_ctx::query_args::CommonAttributeArgs;
 use buck2_client_ctx::stdin::Stdin;
 use buck2_client_ctx::streaming::StreamingCommand;
+use buck2_core::configuration::modifiers::strip_patterns_modifiers;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use dupe::Dupe;
 use gazebo::prelude::*;
@@ -245,11 +246,15 @@ impl StreamingCommand for TargetsCommand {
 
         let output_format = self.output_format()?;
 
-        let context = Some(ctx.client_context(
+        let mut context = ctx.client_context(
             &self.common_opts.config_opts,
             matches,
             self.sanitized_argv(),
-        )?);
+        )?;
+        context
+            .modifiers
+            .extend(strip_patterns_modifiers(&mut self.patterns)?);
+        let context = Some(context);
 
         let target_hash_modified_paths = self
             .target_hash_modified_paths
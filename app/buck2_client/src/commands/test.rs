@@ -28,6 +28,7 @@ use buck2_client_ctx::stdio::eprint_line;
 use buck2_client_ctx::streaming::StreamingCommand;
 use buck2_client_ctx::subscribers::superconsole::test::span_from_build_failure_count;
 use buck2_client_ctx::subscribers::superconsole::test::TestCounterColumn;
+use buck2_core::configuration::modifiers::split_patterns_modifiers;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::working_dir::WorkingDir;
 use gazebo::prelude::*;
@@ -168,19 +169,19 @@ impl StreamingCommand for TestCommand {
         matches: &clap::ArgMatches,
         ctx: &mut ClientCommandContext<'_>,
     ) -> ExitResult {
-        let context = ctx.client_context(
+        let mut context = ctx.client_context(
             &self.common_opts.config_opts,
             matches,
             self.sanitized_argv(),
         )?;
+        let (patterns, modifiers) = split_patterns_modifiers(&self.patterns)?;
+        context.modifiers.extend(modifiers);
         let response = buckd
             .with_flushing()
             .test(
                 TestRequest {
                     context: Some(context),
-                    target_patterns: self
-                        .patterns
-                        .map(|pat| buck2_data::TargetPattern { value: pat.clone() }),
+                    target_patterns: patterns.into_map(|value| buck2_data::TargetPattern { value }),
                     test_executor_args: self.test_executor_args,
                     excluded_labels: self.exclude,
                     included_labels: self.include,
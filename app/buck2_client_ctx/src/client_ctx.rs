@@ -95,6 +95,7 @@ impl<'a> ClientCommandContext<'a> {
         Ok(ClientContext {
             config_overrides: config_opts.config_overrides(arg_matches)?,
             target_platform: config_opts.target_platforms.clone().unwrap_or_default(),
+            modifiers: config_opts.modifiers.clone(),
             host_platform: match config_opts.host_platform_override() {
                 HostPlatformOverride::Default => GrpcHostPlatformOverride::DefaultPlatform,
                 HostPlatformOverride::Linux => GrpcHostPlatformOverride::Linux,
@@ -145,6 +146,7 @@ impl<'a> ClientCommandContext<'a> {
                 .to_owned(),
             config_overrides: Default::default(),
             target_platform: Default::default(),
+            modifiers: Vec::new(),
             host_platform: Default::default(),
             host_arch: Default::default(),
             host_xcode_version: Default::default(),
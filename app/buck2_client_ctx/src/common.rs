@@ -172,6 +172,14 @@ pub struct CommonBuildConfigurationOptions {
     )]
     pub target_platforms: Option<String>,
 
+    /// Configuration modifier to apply on top of the target platform.
+    ///
+    /// A modifier is either an alias from the `[modifier_aliases]` buckconfig section,
+    /// or a `constraint_value` or `config_setting` target. Later modifiers take precedence.
+    /// Modifiers can also be specified with a pattern suffix: `//foo:bar?opt+asan`.
+    #[clap(long = "modifier", number_of_values = 1, value_name = "MODIFIER")]
+    pub modifiers: Vec<String>,
+
     #[clap(long, ignore_case = true, value_name = "HOST", arg_enum)]
     fake_host: Option<HostPlatformOverride>,
 
@@ -311,6 +319,7 @@ impl CommonBuildConfigurationOptions {
             config_values: vec![],
             config_files: vec![],
             target_platforms: None,
+            modifiers: vec![],
             fake_host: None,
             fake_arch: None,
             fake_xcode_version: None,
@@ -409,6 +418,17 @@ pub struct CommonBuildOptions {
     /// which are skipped unconditionally.
     #[clap(long)]
     skip_incompatible_targets: bool,
+
+    /// Execute actions that run locally twice, in separate output directories, and report any
+    /// difference between their outputs. Actions that are not executed (e.g. because they were
+    /// already built or are cached) are not checked, so combine this with `--no-remote-cache`
+    /// after a `buck2 clean`.
+    #[clap(long)]
+    check_determinism: bool,
+
+    /// Only check the determinism of actions in this category. Can be repeated.
+    #[clap(long, value_name = "CATEGORY", requires("check-determinism"))]
+    check_determinism_category: Vec<String>,
 }
 
 impl CommonBuildOptions {
@@ -453,6 +473,8 @@ impl CommonBuildOptions {
             keep_going: self.keep_going,
             skip_missing_targets: self.skip_missing_targets,
             skip_incompatible_targets: self.skip_incompatible_targets,
+            check_determinism: self.check_determinism,
+            check_determinism_categories: self.check_determinism_category.clone(),
         }
     }
 }
@@ -62,7 +62,9 @@ struct TimedListBody<'c> {
 }
 
 impl<'c> TimedListBody<'c> {
-    /// Render a root  as `root [first child + remaining children]`
+    /// Render a root  as `root [first child + remaining children]`. If the first child has children
+    /// itself (e.g. progress reported by a local action), show the first of those as well, as
+    /// `root [first child > grandchild + remaining children]`.
     fn draw_root_first_child(
         &self,
         root: &BuckEventSpanHandle,
@@ -87,6 +89,18 @@ impl<'c> TimedListBody<'c> {
             )?
         );
 
+        if let Some(grandchild) = single_child.children().next() {
+            write!(
+                event_string,
+                " > {}",
+                display::display_event(
+                    &grandchild.info().event,
+                    TargetDisplayOptions::for_console(display_platform)
+                )?
+            )
+            .expect("Write to String is not fallible");
+        }
+
         let now = Instant::now();
         let child_info_elapsed = now - child_info.start;
         let info_elapsed = now - info.start;
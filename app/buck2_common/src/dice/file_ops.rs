@@ -10,6 +10,8 @@
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use allocative::Allocative;
@@ -28,6 +30,8 @@ use buck2_events::dispatch::console_message;
 use derivative::Derivative;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceData;
+use dice::DiceDataBuilder;
 use dice::DiceTransactionUpdater;
 use dice::Key;
 use dupe::Dupe;
@@ -38,6 +42,8 @@ use crate::dice::cells::HasCellResolver;
 use crate::dice::data::HasIoProvider;
 use crate::dice::file_ops::keys::FileOpsKey;
 use crate::dice::file_ops::keys::FileOpsValue;
+use crate::external_cells::ExternalCells;
+use crate::external_cells::HasExternalCells;
 use crate::file_ops::FileOps;
 use crate::file_ops::RawDirEntry;
 use crate::file_ops::RawPathMetadata;
@@ -66,12 +72,68 @@ impl<'c> HasFileOps<'c> for DiceComputations {
 // transient values.
 /// This is used as the "result" of a read_file computation so that we don't
 /// need to store the file content's in dice's cache.
+///
+/// The token carries a digest of the file content, so touching a file
+/// without changing it does not invalidate computations which read it.
 #[derive(Clone, Dupe, Allocative)]
-struct FileToken(Arc<CellPath>);
+struct FileToken {
+    path: Arc<CellPath>,
+    digest: FileContentDigest,
+    /// Shared by all the tokens of a DICE instance.
+    #[allocative(skip)]
+    stats: Option<Arc<FileInvalidationStats>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Allocative)]
+enum FileContentDigest {
+    Missing,
+    /// Blake3 of the content.
+    Present([u8; 32]),
+    /// Reading failed, the error is reported when the file is read by the consumer.
+    Unknown,
+}
+
+impl Dupe for FileContentDigest {}
 
 impl FileToken {
     async fn read_if_exists(&self, fs: &dyn FileOps) -> anyhow::Result<Option<String>> {
-        fs.read_file_if_exists((*self.0).as_ref()).await
+        fs.read_file_if_exists((*self.path).as_ref()).await
+    }
+}
+
+/// Statistics about the files invalidated in a DICE instance.
+#[derive(Default)]
+pub struct FileInvalidationStats {
+    suppressed: AtomicU64,
+}
+
+impl FileInvalidationStats {
+    /// Number of times a file was invalidated but its content was unchanged,
+    /// so computations depending on it were not invalidated.
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+}
+
+pub trait HasFileInvalidationStats {
+    fn get_file_invalidation_stats(&self) -> Option<Arc<FileInvalidationStats>>;
+}
+
+pub trait SetFileInvalidationStats {
+    fn set_file_invalidation_stats(&mut self, stats: Arc<FileInvalidationStats>);
+}
+
+impl HasFileInvalidationStats for DiceData {
+    fn get_file_invalidation_stats(&self) -> Option<Arc<FileInvalidationStats>> {
+        self.get::<Arc<FileInvalidationStats>>()
+            .ok()
+            .map(|s| s.dupe())
+    }
+}
+
+impl SetFileInvalidationStats for DiceDataBuilder {
+    fn set_file_invalidation_stats(&mut self, stats: Arc<FileInvalidationStats>) {
+        self.set(stats)
     }
 }
 
@@ -104,6 +166,9 @@ async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn F
         io: Arc<dyn IoProvider>,
         cells: CellResolver,
         ignores: Arc<AllCellIgnores>,
+        // Safe to ignore because it does not change during the lifetime of the daemon.
+        #[derivative(PartialEq = "ignore")]
+        external_cells: Option<Arc<ExternalCells>>,
     }
 
     impl DiceFileOpsDelegate {
@@ -123,6 +188,20 @@ async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn F
         fn io_provider(&self) -> &dyn IoProvider {
             self.io.as_ref()
         }
+
+        /// External cells are normally materialized when the cell configs are loaded, but make
+        /// sure they are there before they are read (e.g. with `--reuse-current-config`).
+        async fn ensure_external_cell(&self, cell: CellName) -> anyhow::Result<()> {
+            let cell = self.cells.get(cell)?;
+            if let Some(origin) = cell.external() {
+                self.external_cells
+                    .as_ref()
+                    .context("External cells are not available")?
+                    .ensure_materialized(self.io.project_root(), origin)
+                    .await?;
+            }
+            Ok(())
+        }
     }
 
     #[async_trait]
@@ -132,6 +211,7 @@ async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn F
             path: CellPathRef<'async_trait>,
         ) -> anyhow::Result<Option<String>> {
             // TODO(cjhopman): error on ignored paths, maybe.
+            self.ensure_external_cell(path.cell()).await?;
             let project_path = self.resolve(path)?;
             self.io_provider().read_file_if_exists(project_path).await
         }
@@ -143,6 +223,7 @@ async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn F
                 .into_result()
                 .with_context(|| format!("Error checking whether dir `{}` is ignored", path))?;
 
+            self.ensure_external_cell(path.cell()).await?;
             let project_path = self.resolve(path)?;
             let mut entries = self
                 .io_provider()
@@ -210,6 +291,7 @@ async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn F
             &self,
             path: CellPathRef<'async_trait>,
         ) -> anyhow::Result<Option<RawPathMetadata>> {
+            self.ensure_external_cell(path.cell()).await?;
             let project_path = self.resolve(path)?;
 
             let res = self
@@ -245,11 +327,13 @@ async fn get_default_file_ops(dice: &DiceComputations) -> SharedResult<Arc<dyn F
             let io = ctx.global_data().get_io_provider();
 
             let ignores = ctx.new_all_cell_ignores().await?;
+            let external_cells = ctx.global_data().get_external_cells();
 
             Ok(FileOpsValue(Arc::new(DiceFileOpsDelegate {
                 io,
                 cells,
                 ignores,
+                external_cells,
             })))
         }
 
@@ -358,14 +442,39 @@ impl Key for ReadFileKey {
     type Value = FileToken;
     async fn compute(
         &self,
-        _ctx: &DiceComputations,
+        ctx: &DiceComputations,
         _cancellations: &CancellationContext,
     ) -> Self::Value {
-        FileToken(self.0.dupe())
+        let content = async {
+            get_default_file_ops(ctx)
+                .await?
+                .read_file_if_exists((*self.0).as_ref())
+                .await
+        }
+        .await;
+        let digest = match content {
+            Ok(Some(content)) => {
+                FileContentDigest::Present(*blake3::hash(content.as_bytes()).as_bytes())
+            }
+            Ok(None) => FileContentDigest::Missing,
+            // The error is reported when the consumer reads the file again.
+            Err(_) => FileContentDigest::Unknown,
+        };
+        FileToken {
+            path: self.0.dupe(),
+            digest,
+            stats: ctx.global_data().get_file_invalidation_stats(),
+        }
     }
 
-    fn equality(_: &Self::Value, _: &Self::Value) -> bool {
-        false
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        let equal = x.digest != FileContentDigest::Unknown && x.digest == y.digest;
+        if equal {
+            if let Some(stats) = &x.stats {
+                stats.suppressed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        equal
     }
 }
 
@@ -488,3 +597,47 @@ impl<'c> FileOps for DiceFileOps<'c> {
 pub mod testing {
     pub use super::keys::FileOpsKey;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buck2_core::cells::cell_path::CellPath;
+    use dice::Key;
+    use dupe::Dupe;
+
+    use crate::dice::file_ops::FileContentDigest;
+    use crate::dice::file_ops::FileInvalidationStats;
+    use crate::dice::file_ops::FileToken;
+    use crate::dice::file_ops::ReadFileKey;
+
+    #[test]
+    fn test_read_file_equality() {
+        let stats = Arc::new(FileInvalidationStats::default());
+        let token = |digest| FileToken {
+            path: Arc::new(CellPath::testing_new("root//BUCK")),
+            digest,
+            stats: Some(stats.dupe()),
+        };
+        let a = FileContentDigest::Present(*blake3::hash(b"a").as_bytes());
+        let b = FileContentDigest::Present(*blake3::hash(b"b").as_bytes());
+
+        assert!(ReadFileKey::equality(&token(a), &token(a)));
+        assert!(ReadFileKey::equality(
+            &token(FileContentDigest::Missing),
+            &token(FileContentDigest::Missing)
+        ));
+        assert_eq!(2, stats.suppressed());
+
+        assert!(!ReadFileKey::equality(&token(a), &token(b)));
+        assert!(!ReadFileKey::equality(
+            &token(a),
+            &token(FileContentDigest::Missing)
+        ));
+        assert!(!ReadFileKey::equality(
+            &token(FileContentDigest::Unknown),
+            &token(FileContentDigest::Unknown)
+        ));
+        assert_eq!(2, stats.suppressed());
+    }
+}
@@ -0,0 +1,442 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Fetching of external cells.
+//!
+//! The contents of an external cell are fetched once per machine into a shared store
+//! (`~/.buck/external_cells/<store key>`), verified, and then copied (read-only) to
+//! `buck-out/external_cells/<store key>`, which is where the cell lives in the project. The copy
+//! is made the first time the cell is resolved, and since its path is derived from the origin,
+//! later commands and daemons find it there, and a cell whose origin changes moves rather than
+//! being overwritten. The source tree is never written to.
+
+use std::io::Read;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use allocative::Allocative;
+use anyhow::Context;
+use buck2_core::cells::cell_root_path::CellRootPathBuf;
+use buck2_core::cells::external::ExternalCellOrigin;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::file_name::FileName;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use dashmap::DashMap;
+use dice::DiceData;
+use dice::DiceDataBuilder;
+use dupe::Dupe;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::http::http_client;
+use crate::invocation_paths::home_buck_dir;
+use crate::invocation_paths::InvocationPaths;
+
+#[derive(Debug, thiserror::Error)]
+enum ExternalCellsError {
+    #[error("Downloading `{0}` failed with HTTP status {1}")]
+    HttpStatus(String, hyper::StatusCode),
+    #[error("Archive `{url}` has sha256 `{actual}`, but `{expected}` was expected")]
+    Sha256Mismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Unsupported archive `{0}`, expecting `.tar`, `.tar.gz` or `.tgz`")]
+    UnsupportedArchive(String),
+    #[error("`{0}` failed: {1}")]
+    Git(String, String),
+    #[error("Checked out commit `{actual}` from `{url}`, but `{expected}` was expected")]
+    CommitMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("`strip_prefix` directory `{0}` does not exist in the fetched external cell")]
+    MissingStripPrefix(String),
+}
+
+/// Fetches external cells and keeps track of the cells materialized by this daemon.
+#[derive(Default, Allocative)]
+pub struct ExternalCells {
+    /// Whether the cell is known to be in `buck-out`, keyed by store key.
+    #[allocative(skip)]
+    materialized: DashMap<String, Arc<tokio::sync::Mutex<bool>>>,
+}
+
+impl ExternalCells {
+    pub fn new() -> Self {
+        ExternalCells {
+            materialized: DashMap::new(),
+        }
+    }
+
+    /// Make sure `buck-out` holds the contents of the external cell, fetching it if needed.
+    pub async fn ensure_materialized(
+        &self,
+        project_root: &ProjectRoot,
+        origin: &ExternalCellOrigin,
+    ) -> anyhow::Result<()> {
+        let state = self
+            .materialized
+            .entry(origin.store_key())
+            .or_default()
+            .dupe();
+        let mut materialized = state.lock().await;
+        if *materialized {
+            return Ok(());
+        }
+
+        let contents_path = project_root.resolve(&contents_path(origin));
+        if !fs_util::try_exists(&contents_path)? {
+            let store_path = fetch(origin)
+                .await
+                .with_context(|| format!("Error fetching external cell {}", origin))?;
+            tokio::task::spawn_blocking(move || materialize(&store_path, &contents_path))
+                .await?
+                .with_context(|| format!("Error materializing external cell {}", origin))?;
+        }
+
+        *materialized = true;
+        Ok(())
+    }
+}
+
+/// Where the contents fetched for an external cell are copied in the project.
+pub fn contents_path(origin: &ExternalCellOrigin) -> ProjectRelativePathBuf {
+    InvocationPaths::buck_out_dir_prefix()
+        .join(ForwardRelativePath::unchecked_new("external_cells"))
+        .join(ForwardRelativePath::unchecked_new(&origin.store_key()))
+}
+
+/// The root of an external cell in the project, i.e. the contents with the `strip_prefix`
+/// applied.
+pub fn cell_root(origin: &ExternalCellOrigin) -> anyhow::Result<CellRootPathBuf> {
+    let contents_path = contents_path(origin);
+    Ok(CellRootPathBuf::new(match origin {
+        ExternalCellOrigin::Archive {
+            strip_prefix: Some(strip_prefix),
+            ..
+        } => contents_path.join(ForwardRelativePath::new(strip_prefix)?),
+        _ => contents_path,
+    }))
+}
+
+/// Shared store of fetched external cells.
+fn store_dir() -> anyhow::Result<AbsNormPathBuf> {
+    let dir = home_buck_dir()?.join(FileName::new("external_cells")?);
+    fs_util::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Fetch the external cell into the shared store (unless it is already there),
+/// and return the path to the fetched contents in the store.
+async fn fetch(origin: &ExternalCellOrigin) -> anyhow::Result<AbsNormPathBuf> {
+    let store_dir = store_dir()?;
+    let path = store_dir.join(FileName::new(&origin.store_key())?);
+
+    if !fs_util::try_exists(&path)? {
+        let archive = match origin {
+            ExternalCellOrigin::Archive { url, sha256, .. } => Some(download(url, sha256).await?),
+            ExternalCellOrigin::Git { .. } => None,
+        };
+
+        // Fetch to a temporary directory and rename, so the store never contains partial
+        // results, even if several daemons fetch the same cell concurrently.
+        let tmp = tmp_path(&path)?;
+        let origin = origin.clone();
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || {
+            fs_util::create_dir_all(&tmp)?;
+            let res = match (&origin, archive) {
+                (ExternalCellOrigin::Archive { url, .. }, Some(archive)) => {
+                    extract(url, &archive, &tmp)
+                }
+                (ExternalCellOrigin::Git { url, commit }, _) => git_checkout(url, commit, &tmp),
+                (ExternalCellOrigin::Archive { .. }, None) => unreachable!("downloaded above"),
+            };
+            finish_tmp(&tmp, &path, res)
+        })
+        .await??;
+    }
+
+    if let ExternalCellOrigin::Archive {
+        strip_prefix: Some(strip_prefix),
+        ..
+    } = origin
+    {
+        if !fs_util::try_exists(path.join(ForwardRelativePath::new(strip_prefix)?))? {
+            return Err(ExternalCellsError::MissingStripPrefix(strip_prefix.clone()).into());
+        }
+    }
+    Ok(path)
+}
+
+/// A path next to `path` to prepare its contents in before renaming them into place.
+fn tmp_path(path: &AbsNormPath) -> anyhow::Result<AbsNormPathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_nanos();
+    let name = path.file_name().context("Path has no file name")?;
+    let name = format!(
+        "{}.tmp.{}.{}",
+        name.to_string_lossy(),
+        std::process::id(),
+        nanos
+    );
+    Ok(path
+        .parent()
+        .context("Path has no parent")?
+        .join(FileName::new(&name)?))
+}
+
+/// Rename `tmp` to `path` if it was prepared successfully, and clean up.
+fn finish_tmp(
+    tmp: &AbsNormPath,
+    path: &AbsNormPath,
+    res: anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let res = res.and_then(|()| {
+        if let Err(e) = fs_util::rename(tmp, path) {
+            // Somebody else finished first.
+            if !fs_util::try_exists(path)? {
+                return Err(e);
+            }
+        }
+        Ok(())
+    });
+    if fs_util::try_exists(tmp)? {
+        remove_read_only(tmp)?;
+    }
+    res
+}
+
+async fn download(url: &str, expected_sha256: &str) -> anyhow::Result<Vec<u8>> {
+    let response = http_client()?.get(url).await?;
+    if !response.status().is_success() {
+        return Err(ExternalCellsError::HttpStatus(url.to_owned(), response.status()).into());
+    }
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .with_context(|| format!("Error downloading `{}`", url))?;
+
+    let actual = hex::encode(Sha256::digest(&body));
+    if actual != expected_sha256 {
+        return Err(ExternalCellsError::Sha256Mismatch {
+            url: url.to_owned(),
+            expected: expected_sha256.to_owned(),
+            actual,
+        }
+        .into());
+    }
+    Ok(body.to_vec())
+}
+
+fn extract(url: &str, archive: &[u8], dest: &AbsNormPath) -> anyhow::Result<()> {
+    // Ignore query strings when looking at the extension.
+    let name = url.split(['?', '#']).next().unwrap_or(url);
+    let reader: Box<dyn Read> = if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Box::new(flate2::read::GzDecoder::new(archive))
+    } else if name.ends_with(".tar") {
+        Box::new(archive)
+    } else {
+        return Err(ExternalCellsError::UnsupportedArchive(url.to_owned()).into());
+    };
+    tar::Archive::new(reader)
+        .unpack(dest)
+        .with_context(|| format!("Error extracting `{}`", url))
+}
+
+fn git_checkout(url: &str, commit: &str, dest: &AbsNormPath) -> anyhow::Result<()> {
+    fn git(dest: &AbsNormPath, args: &[&str]) -> anyhow::Result<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dest)
+            .output()
+            .context("Error running `git`")?;
+        if !output.status.success() {
+            return Err(ExternalCellsError::Git(
+                format!("git {}", args.join(" ")),
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            )
+            .into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+
+    git(dest, &["init", "--quiet"])?;
+    git(dest, &["fetch", "--quiet", "--depth", "1", url, commit])?;
+    git(dest, &["checkout", "--quiet", "FETCH_HEAD"])?;
+    let actual = git(dest, &["rev-parse", "HEAD"])?;
+    if actual != commit {
+        return Err(ExternalCellsError::CommitMismatch {
+            url: url.to_owned(),
+            expected: commit.to_owned(),
+            actual,
+        }
+        .into());
+    }
+    // Git makes its objects read-only.
+    remove_read_only(&dest.join(FileName::new(".git")?))?;
+    Ok(())
+}
+
+/// Copy the fetched contents of a cell from the store to `buck-out`. The copy only appears at
+/// `contents_path` once complete.
+fn materialize(store_path: &AbsNormPath, contents_path: &AbsNormPath) -> anyhow::Result<()> {
+    if let Some(parent) = contents_path.parent() {
+        fs_util::create_dir_all(parent)?;
+    }
+    let tmp = tmp_path(contents_path)?;
+    let res = copy_read_only(store_path, &tmp);
+    finish_tmp(&tmp, contents_path, res)
+}
+
+/// Remove a directory which may contain read-only files, e.g. made by `copy_read_only`. Windows
+/// refuses to delete read-only files, so they are made writable first.
+fn remove_read_only(path: &AbsNormPath) -> anyhow::Result<()> {
+    #[cfg(windows)]
+    make_writable(path)?;
+    fs_util::remove_all(path)
+}
+
+#[cfg(windows)]
+fn make_writable(path: &AbsNormPath) -> anyhow::Result<()> {
+    let metadata = fs_util::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        for entry in fs_util::read_dir(path)? {
+            make_writable(&entry?.path())?;
+        }
+    } else if metadata.is_file() && metadata.permissions().readonly() {
+        let mut permissions = metadata.permissions();
+        // This only clears the read-only attribute on Windows.
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs_util::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+/// Copy a directory recursively, making the copied files read-only.
+fn copy_read_only(from: &AbsNormPath, to: &AbsNormPath) -> anyhow::Result<()> {
+    fs_util::create_dir_all(to)?;
+    for entry in fs_util::read_dir(from)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let file_name = file_name
+            .to_str()
+            .with_context(|| format!("File name `{:?}` is not UTF-8", file_name))?;
+        let from = entry.path();
+        let to = to.join(FileName::new(file_name)?);
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_read_only(&from, &to)?;
+        } else if file_type.is_symlink() {
+            fs_util::symlink(fs_util::read_link(&from)?, &to)?;
+        } else {
+            fs_util::copy(&from, &to)?;
+            let mut permissions = fs_util::metadata(&to)?.permissions();
+            permissions.set_readonly(true);
+            fs_util::set_permissions(&to, permissions)?;
+        }
+    }
+    Ok(())
+}
+
+pub trait HasExternalCells {
+    fn get_external_cells(&self) -> Option<Arc<ExternalCells>>;
+}
+
+pub trait SetExternalCells {
+    fn set_external_cells(&mut self, external_cells: Arc<ExternalCells>);
+}
+
+impl HasExternalCells for DiceData {
+    fn get_external_cells(&self) -> Option<Arc<ExternalCells>> {
+        self.get::<Arc<ExternalCells>>().ok().map(|e| e.dupe())
+    }
+}
+
+impl SetExternalCells for DiceDataBuilder {
+    fn set_external_cells(&mut self, external_cells: Arc<ExternalCells>) {
+        self.set(external_cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::cells::external::ExternalCellOrigin;
+    use buck2_core::fs::fs_util;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+
+    use crate::external_cells::cell_root;
+    use crate::external_cells::contents_path;
+    use crate::external_cells::materialize;
+
+    #[test]
+    fn test_materialize() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsNormPathBuf::new(temp.path().to_owned())?;
+        let store = root.join(ForwardRelativePath::new("store")?);
+        let contents = root.join(ForwardRelativePath::new(
+            "project/buck-out/external_cells/archive-0000",
+        )?);
+
+        fs_util::create_dir_all(store.join(ForwardRelativePath::new("src")?))?;
+        fs_util::write(store.join(ForwardRelativePath::new("src/zlib.h")?), "zlib")?;
+        fs_util::write(store.join(ForwardRelativePath::new("BUCK")?), "")?;
+
+        materialize(&store, &contents)?;
+        assert_eq!(
+            "zlib",
+            fs_util::read_to_string(contents.join(ForwardRelativePath::new("src/zlib.h")?))?
+        );
+        assert!(
+            fs_util::metadata(contents.join(ForwardRelativePath::new("BUCK")?))?
+                .permissions()
+                .readonly()
+        );
+        // Nothing but the copy is left behind.
+        assert_eq!(1, fs_util::read_dir(contents.parent().unwrap())?.count());
+
+        // Materializing again (e.g. from another daemon) keeps the existing copy.
+        materialize(&store, &contents)?;
+        assert!(fs_util::try_exists(
+            contents.join(ForwardRelativePath::new("BUCK")?)
+        )?);
+        assert_eq!(1, fs_util::read_dir(contents.parent().unwrap())?.count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cell_root() -> anyhow::Result<()> {
+        let sha256 = "a".repeat(64);
+        let origin = ExternalCellOrigin::archive(
+            "https://x/zlib.tar.gz".to_owned(),
+            sha256.clone(),
+            Some("zlib-1.3".to_owned()),
+        )?;
+        assert_eq!(
+            format!("buck-out/external_cells/archive-{}", sha256),
+            contents_path(&origin).as_str()
+        );
+        assert_eq!(
+            format!("buck-out/external_cells/archive-{}/zlib-1.3", sha256),
+            cell_root(&origin)?.as_str()
+        );
+        Ok(())
+    }
+}
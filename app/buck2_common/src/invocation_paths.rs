@@ -188,6 +188,11 @@ impl InvocationPaths {
             .join(self.materializer_state_dir_name())
     }
 
+    /// Subdirectory of `cache_dir` storing historical execution times of actions
+    pub fn executor_timings_path(&self) -> AbsNormPathBuf {
+        self.cache_dir_path().join(self.executor_timings_dir_name())
+    }
+
     /// This is used by the forkserver to write the miniperf wrapper binary (if used), as well as
     /// temporary files used by miniperf. We put this in buck-out because that directory gets
     /// allowlisted for execution (because we write lots of tools there).
@@ -200,8 +205,15 @@ impl InvocationPaths {
         FileName::unchecked_new("materializer_state")
     }
 
+    pub fn executor_timings_dir_name(&self) -> &FileName {
+        FileName::unchecked_new("executor_timings")
+    }
+
     pub fn valid_cache_dirs(&self) -> Vec<&FileName> {
-        vec![self.materializer_state_dir_name()]
+        vec![
+            self.materializer_state_dir_name(),
+            self.executor_timings_dir_name(),
+        ]
     }
 
     /// When client and server versions mismatch, we restart the daemon. This file allows doing the
@@ -13,6 +13,7 @@ use std::collections::HashSet;
 use anyhow::Context;
 use buck2_core::cells::alias::NonEmptyCellAlias;
 use buck2_core::cells::cell_root_path::CellRootPathBuf;
+use buck2_core::cells::external::ExternalCellOrigin;
 use buck2_core::cells::CellResolver;
 use buck2_core::cells::CellsAggregator;
 use buck2_core::env_helper::EnvHelper;
@@ -27,6 +28,7 @@ use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use gazebo::prelude::*;
 use once_cell::unsync::OnceCell;
 
+use crate::external_cells;
 use crate::legacy_configs::path::BuckConfigFile;
 use crate::legacy_configs::path::DEFAULT_BUCK_CONFIG_FILES;
 use crate::legacy_configs::push_all_files_from_a_directory;
@@ -46,6 +48,16 @@ enum CellsError {
         like `root = .` which defines the root cell name"
     )]
     MissingRootCellName,
+    #[error(
+        "External cell `{0}` must also be declared in the root buckconfig `[repositories]` section"
+    )]
+    ExternalCellNotInRepositories(String),
+    #[error("Root cell can not be an external cell")]
+    ExternalRootCell,
+    #[error("Unknown external cell kind `{1}` for `{0}`, expecting `archive` or `git`")]
+    UnknownExternalCellKind(String, String),
+    #[error("Missing `{1}.{2}` for external cell `{0}`")]
+    MissingExternalCellField(String, String, &'static str),
 }
 
 /// Used for creating a CellResolver in a buckv1-compatible way based on values
@@ -61,6 +73,9 @@ pub struct BuckConfigBasedCells {
     pub configs_by_name: LegacyBuckConfigs,
     pub cell_resolver: CellResolver,
     pub config_paths: HashSet<AbsNormPathBuf>,
+    /// External cells whose contents are not in `buck-out` yet, so their buckconfigs could not
+    /// be loaded. See `ExternalCells::ensure_materialized`.
+    pub unmaterialized_external_cells: Vec<ExternalCellOrigin>,
 }
 
 impl BuckConfigBasedCells {
@@ -169,6 +184,10 @@ impl BuckConfigBasedCells {
         )?)];
         let mut cells_aggregator = CellsAggregator::new();
         let mut root_aliases = HashMap::new();
+        let mut external_cells = HashMap::new();
+        // Declared paths of external cells, and the paths in `buck-out` they live at instead.
+        let mut external_cell_roots = HashMap::new();
+        let mut unmaterialized_external_cells = Vec::new();
 
         // By definition, cell resolution should be happening against the cell mapping defined
         // by the .buckconfig of the project root.
@@ -200,6 +219,17 @@ impl BuckConfigBasedCells {
                 continue;
             }
 
+            // External cells are only on disk once they are materialized, see
+            // `ExternalCells::ensure_materialized`. Until then they have no config.
+            if let Some(origin) = external_cells.get(&path) {
+                let contents_path = project_fs.resolve(&external_cells::contents_path(origin));
+                if !file_ops.file_exists(&contents_path) {
+                    unmaterialized_external_cells.push(origin.clone());
+                    buckconfigs.insert(path, LegacyBuckConfig::empty());
+                    continue;
+                }
+            }
+
             let mut buckconfig_paths: Vec<MainConfigFile> = Vec::new();
 
             for buckconfig in DEFAULT_BUCK_CONFIG_FILES {
@@ -301,6 +331,12 @@ impl BuckConfigBasedCells {
 
             let is_root = path.is_repo_root();
 
+            let root_external_cells = if is_root {
+                Self::parse_external_cells(&config)?
+            } else {
+                HashMap::new()
+            };
+
             let repositories = config.get_section("repositories");
             if let Some(repositories) = repositories {
                 let mut seen_dot = false;
@@ -320,6 +356,19 @@ impl BuckConfigBasedCells {
                             )
                         })?);
                     let alias = NonEmptyCellAlias::new(alias.to_owned())?;
+                    if let Some(origin) = root_external_cells.get(&alias) {
+                        if alias_path.is_repo_root() {
+                            return Err(CellsError::ExternalRootCell.into());
+                        }
+                        let cell_root = external_cells::cell_root(origin)?;
+                        cells_aggregator.set_external(cell_root.clone(), origin.clone());
+                        external_cells.insert(cell_root.clone(), origin.clone());
+                        external_cell_roots.insert(alias_path.clone(), cell_root);
+                    }
+                    let alias_path = external_cell_roots
+                        .get(&alias_path)
+                        .cloned()
+                        .unwrap_or(alias_path);
                     if is_root {
                         root_aliases.insert(alias.clone(), alias_path.clone());
                     }
@@ -334,6 +383,12 @@ impl BuckConfigBasedCells {
                 return Err(CellsError::MissingRootCellName.into());
             }
 
+            for alias in root_external_cells.keys() {
+                if !root_aliases.contains_key(alias) {
+                    return Err(CellsError::ExternalCellNotInRepositories(alias.to_string()).into());
+                }
+            }
+
             if let Some(aliases) = config.get_section("repository_aliases") {
                 for (alias, destination) in aliases.iter() {
                     let alias = NonEmptyCellAlias::new(alias.to_owned())?;
@@ -376,9 +431,48 @@ impl BuckConfigBasedCells {
             configs_by_name: LegacyBuckConfigs::new(configs_by_name),
             cell_resolver,
             config_paths: file_ops.trace,
+            unmaterialized_external_cells,
         })
     }
 
+    /// Deal with the `[external_cells]` section, which maps cell aliases to the kind of the
+    /// external cell (`archive` or `git`), with the details in `[external_cell_<alias>]`.
+    /// External cells live in `buck-out` rather than at the path in `[repositories]`.
+    fn parse_external_cells(
+        config: &LegacyBuckConfig,
+    ) -> anyhow::Result<HashMap<NonEmptyCellAlias, ExternalCellOrigin>> {
+        let external_cells = match config.get_section("external_cells") {
+            Some(external_cells) => external_cells,
+            None => return Ok(HashMap::new()),
+        };
+        let mut res = HashMap::new();
+        for (alias, kind) in external_cells.iter() {
+            let section = format!("external_cell_{}", alias);
+            let get = |key: &'static str| {
+                config.get(&section, key).map(str::to_owned).ok_or_else(|| {
+                    CellsError::MissingExternalCellField(alias.to_owned(), section.clone(), key)
+                })
+            };
+            let origin = match kind.as_str() {
+                "archive" => ExternalCellOrigin::archive(
+                    get("url")?,
+                    get("sha256")?,
+                    config.get(&section, "strip_prefix").map(str::to_owned),
+                )?,
+                "git" => ExternalCellOrigin::git(get("git_origin")?, get("commit_hash")?)?,
+                kind => {
+                    return Err(CellsError::UnknownExternalCellKind(
+                        alias.to_owned(),
+                        kind.to_owned(),
+                    )
+                    .into());
+                }
+            };
+            res.insert(NonEmptyCellAlias::new(alias.to_owned())?, origin);
+        }
+        Ok(res)
+    }
+
     /// Deal with the `buildfile.name` key (and `name_v2`)
     fn parse_buildfile_name(config: &LegacyBuckConfig) -> anyhow::Result<Option<Vec<FileNameBuf>>> {
         // For buck2, we support a slightly different mechanism for setting the buildfile to
@@ -407,6 +501,7 @@ impl BuckConfigBasedCells {
 #[cfg(test)]
 mod tests {
 
+    use buck2_core::cells::external::ExternalCellOrigin;
     use buck2_core::cells::name::CellName;
     use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
     use buck2_core::fs::project::ProjectRoot;
@@ -835,4 +930,162 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_external_cells() -> anyhow::Result<()> {
+        let mut files = vec![(
+            "/.buckconfig",
+            indoc!(
+                r#"
+                            [repositories]
+                                root = .
+                                zlib = third-party/zlib
+                                fmt = third-party/fmt
+                            [external_cells]
+                                zlib = archive
+                                fmt = git
+                            [external_cell_zlib]
+                                url = https://example.com/zlib-1.3.tar.gz
+                                sha256 = ff0ba4c292013dbc27530b3a81e1f9a813cd39de01ca5e0f8bf355702efa593e
+                                strip_prefix = zlib-1.3
+                            [external_cell_fmt]
+                                git_origin = https://github.com/fmtlib/fmt.git
+                                commit_hash = a33701196adfad74917046096bf5a2aa0ab0bb50
+                        "#
+            ),
+        )];
+        // Only read once the cell is materialized.
+        let zlib_buckconfig = (
+            "/buck-out/external_cells/archive-ff0ba4c292013dbc27530b3a81e1f9a813cd39de01ca5e0f8bf355702efa593e/zlib-1.3/.buckconfig",
+            indoc!(
+                r#"
+                        [buildfile]
+                            name = TARGETS
+                    "#
+            ),
+        );
+
+        let project_fs = create_project_filesystem();
+        let parse = |files: &[(&str, &str)]| {
+            BuckConfigBasedCells::parse_with_file_ops(
+                &project_fs,
+                &mut TestConfigParserFileOps::new(files)?,
+                &[],
+                ProjectRelativePath::empty(),
+            )
+        };
+        let cells = parse(&files)?;
+        let resolver = &cells.cell_resolver;
+        let zlib_origin = ExternalCellOrigin::Archive {
+            url: "https://example.com/zlib-1.3.tar.gz".to_owned(),
+            sha256: "ff0ba4c292013dbc27530b3a81e1f9a813cd39de01ca5e0f8bf355702efa593e".to_owned(),
+            strip_prefix: Some("zlib-1.3".to_owned()),
+        };
+        let fmt_origin = ExternalCellOrigin::Git {
+            url: "https://github.com/fmtlib/fmt.git".to_owned(),
+            commit: "a33701196adfad74917046096bf5a2aa0ab0bb50".to_owned(),
+        };
+
+        assert_eq!(
+            None,
+            resolver.get(CellName::testing_new("root"))?.external()
+        );
+        // External cells live in `buck-out`, not at the path in `[repositories]`.
+        let zlib = resolver.get(CellName::testing_new("zlib"))?;
+        assert_eq!(Some(&zlib_origin), zlib.external());
+        assert_eq!(
+            "buck-out/external_cells/archive-ff0ba4c292013dbc27530b3a81e1f9a813cd39de01ca5e0f8bf355702efa593e/zlib-1.3",
+            zlib.path().as_str()
+        );
+        assert_eq!(
+            vec!["BUCK.v2", "BUCK"],
+            zlib.buildfiles().map(|n| n.as_str())
+        );
+        let fmt = resolver.get(CellName::testing_new("fmt"))?;
+        assert_eq!(Some(&fmt_origin), fmt.external());
+        assert_eq!(
+            "buck-out/external_cells/git-a33701196adfad74917046096bf5a2aa0ab0bb50",
+            fmt.path().as_str()
+        );
+        assert_eq!(2, cells.unmaterialized_external_cells.len());
+        assert!(cells.unmaterialized_external_cells.contains(&zlib_origin));
+        assert!(cells.unmaterialized_external_cells.contains(&fmt_origin));
+
+        // Once materialized, the buckconfig of the external cell is used.
+        files.push(zlib_buckconfig);
+        let cells = parse(&files)?;
+        assert_eq!(
+            vec!["TARGETS"],
+            cells
+                .cell_resolver
+                .get(CellName::testing_new("zlib"))?
+                .buildfiles()
+                .map(|n| n.as_str())
+        );
+        assert_eq!(vec![fmt_origin], cells.unmaterialized_external_cells);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_external_cells_errors() -> anyhow::Result<()> {
+        let parse = |config: &str| {
+            let mut file_ops = TestConfigParserFileOps::new(&[("/.buckconfig", config)])?;
+            BuckConfigBasedCells::parse_with_file_ops(
+                &create_project_filesystem(),
+                &mut file_ops,
+                &[],
+                ProjectRelativePath::empty(),
+            )
+        };
+
+        let err = parse(indoc!(
+            r#"
+                [repositories]
+                    root = .
+                [external_cells]
+                    zlib = archive
+                [external_cell_zlib]
+                    url = https://example.com/zlib.tar.gz
+                    sha256 = ff0ba4c292013dbc27530b3a81e1f9a813cd39de01ca5e0f8bf355702efa593e
+            "#
+        ))
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("`[repositories]`"), "{:#}", err);
+
+        let err = parse(indoc!(
+            r#"
+                [repositories]
+                    root = .
+                    zlib = zlib
+                [external_cells]
+                    zlib = archive
+                [external_cell_zlib]
+                    url = https://example.com/zlib.tar.gz
+            "#
+        ))
+        .err()
+        .unwrap();
+        assert!(
+            err.to_string().contains("`external_cell_zlib.sha256`"),
+            "{:#}",
+            err
+        );
+
+        let err = parse(indoc!(
+            r#"
+                [repositories]
+                    root = .
+                    zlib = zlib
+                [external_cells]
+                    zlib = svn
+            "#
+        ))
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("`svn`"), "{:#}", err);
+
+        Ok(())
+    }
 }
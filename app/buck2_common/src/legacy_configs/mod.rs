@@ -1312,7 +1312,8 @@ pub mod testing {
 
     impl ConfigParserFileOps for TestConfigParserFileOps {
         fn file_exists(&self, path: &AbsNormPath) -> bool {
-            self.data.contains_key(path)
+            // Like the real file system, directories exist if they contain files.
+            self.data.keys().any(|file| file.starts_with(path))
         }
 
         fn read_file_lines(
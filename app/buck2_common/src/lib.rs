@@ -35,6 +35,7 @@ pub mod eden;
 pub mod error_report;
 pub mod events;
 pub mod executor_config;
+pub mod external_cells;
 pub mod external_symlink;
 pub mod file_ops;
 pub mod find_buildfile;
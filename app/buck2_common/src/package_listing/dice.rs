@@ -15,6 +15,7 @@ use buck2_core::cells::cell_path::CellPathRef;
 use buck2_core::package::PackageLabel;
 use dice::DiceComputations;
 use dice::Key;
+use dice::OpaqueValue;
 use dupe::Dupe;
 use more_futures::cancellation::CancellationContext;
 
@@ -26,6 +27,45 @@ use crate::package_listing::resolver::PackageListingResolver;
 use crate::result::SharedResult;
 use crate::result::ToUnsharedResultExt;
 
+/// Listing of a package directory.
+///
+/// Fetch it with `compute_opaque` and query it with projections
+/// to record only the queried parts of the listing as dependencies.
+#[derive(
+    Clone,
+    Dupe,
+    derive_more::Display,
+    Debug,
+    Eq,
+    Hash,
+    PartialEq,
+    Allocative
+)]
+pub struct PackageListingKey(PackageLabel);
+
+#[async_trait]
+impl Key for PackageListingKey {
+    type Value = SharedResult<PackageListing>;
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        let cell_resolver = ctx.get_cell_resolver().await?;
+        let file_ops = ctx.file_ops();
+        InterpreterPackageListingResolver::new(cell_resolver, Arc::new(file_ops))
+            .resolve(self.0.dupe())
+            .await
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
 #[async_trait]
 pub trait HasPackageListingResolver<'c> {
     type PL: PackageListingResolver + 'c;
@@ -34,6 +74,12 @@ pub trait HasPackageListingResolver<'c> {
         &self,
         package: PackageLabel,
     ) -> anyhow::Result<PackageListing>;
+
+    /// Get package listing without recording it as a dependency of current computation.
+    async fn get_package_listing_opaque(
+        &self,
+        package: PackageLabel,
+    ) -> anyhow::Result<OpaqueValue<PackageListingKey>>;
 }
 
 #[async_trait]
@@ -52,6 +98,13 @@ impl<'c> HasPackageListingResolver<'c> for DiceComputations {
             .await
             .unshared_error()
     }
+
+    async fn get_package_listing_opaque(
+        &self,
+        package: PackageLabel,
+    ) -> anyhow::Result<OpaqueValue<PackageListingKey>> {
+        Ok(self.compute_opaque(&PackageListingKey(package)).await?)
+    }
 }
 
 #[derive(Clone, Dupe)]
@@ -60,41 +113,6 @@ pub struct DicePackageListingResolver<'compute>(&'compute DiceComputations);
 #[async_trait]
 impl<'c> PackageListingResolver for DicePackageListingResolver<'c> {
     async fn resolve(&self, package: PackageLabel) -> SharedResult<PackageListing> {
-        #[derive(
-            Clone,
-            Dupe,
-            derive_more::Display,
-            Debug,
-            Eq,
-            Hash,
-            PartialEq,
-            Allocative
-        )]
-        struct PackageListingKey(PackageLabel);
-
-        #[async_trait]
-        impl Key for PackageListingKey {
-            type Value = SharedResult<PackageListing>;
-            async fn compute(
-                &self,
-                ctx: &DiceComputations,
-                _cancellations: &CancellationContext,
-            ) -> Self::Value {
-                let cell_resolver = ctx.get_cell_resolver().await?;
-                let file_ops = ctx.file_ops();
-                InterpreterPackageListingResolver::new(cell_resolver, Arc::new(file_ops))
-                    .resolve(self.0.dupe())
-                    .await
-            }
-
-            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
-                match (x, y) {
-                    (Ok(x), Ok(y)) => x == y,
-                    _ => false,
-                }
-            }
-        }
-
         self.0.compute(&PackageListingKey(package.dupe())).await?
     }
 
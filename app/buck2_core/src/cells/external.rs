@@ -0,0 +1,96 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! External cells are cells whose contents are not checked into the project,
+//! but fetched by buck2 (from an archive or a git repository) and exposed
+//! read-only at the cell path.
+
+use allocative::Allocative;
+use derive_more::Display;
+
+#[derive(Debug, thiserror::Error)]
+enum ExternalCellOriginError {
+    #[error("Invalid sha256 `{0}`, expecting 64 lowercase hex characters")]
+    InvalidSha256(String),
+    #[error("Invalid commit hash `{0}`, expecting 40 lowercase hex characters")]
+    InvalidCommit(String),
+}
+
+/// Where the contents of an external cell come from.
+#[derive(Clone, Debug, Display, PartialEq, Eq, Hash, Allocative)]
+pub enum ExternalCellOrigin {
+    /// A `.tar`, `.tar.gz` or `.tgz` archive, verified against its sha256.
+    #[display(fmt = "archive `{}` (sha256 `{}`)", url, sha256)]
+    Archive {
+        url: String,
+        sha256: String,
+        /// Directory within the archive which is the root of the cell.
+        strip_prefix: Option<String>,
+    },
+    /// A git repository checked out at the given commit.
+    #[display(fmt = "git `{}` at `{}`", url, commit)]
+    Git { url: String, commit: String },
+}
+
+fn is_lower_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+impl ExternalCellOrigin {
+    pub fn archive(
+        url: String,
+        sha256: String,
+        strip_prefix: Option<String>,
+    ) -> anyhow::Result<Self> {
+        if !is_lower_hex(&sha256, 64) {
+            return Err(ExternalCellOriginError::InvalidSha256(sha256).into());
+        }
+        Ok(ExternalCellOrigin::Archive {
+            url,
+            sha256,
+            strip_prefix,
+        })
+    }
+
+    pub fn git(url: String, commit: String) -> anyhow::Result<Self> {
+        if !is_lower_hex(&commit, 40) {
+            return Err(ExternalCellOriginError::InvalidCommit(commit).into());
+        }
+        Ok(ExternalCellOrigin::Git { url, commit })
+    }
+
+    /// Name of the directory holding the fetched contents in the shared store.
+    /// The contents are fully determined by the hash, so the name does not include the URL.
+    pub fn store_key(&self) -> String {
+        match self {
+            ExternalCellOrigin::Archive { sha256, .. } => format!("archive-{}", sha256),
+            ExternalCellOrigin::Git { commit, .. } => format!("git-{}", commit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cells::external::ExternalCellOrigin;
+
+    #[test]
+    fn test_validate() {
+        let sha256 = "a".repeat(64);
+        let origin =
+            ExternalCellOrigin::archive("https://x/y.tar.gz".to_owned(), sha256.clone(), None)
+                .unwrap();
+        assert_eq!(format!("archive-{}", sha256), origin.store_key());
+        assert!(ExternalCellOrigin::archive("u".to_owned(), "A".repeat(64), None).is_err());
+        assert!(ExternalCellOrigin::archive("u".to_owned(), "a".repeat(63), None).is_err());
+
+        let origin = ExternalCellOrigin::git("u".to_owned(), "0".repeat(40)).unwrap();
+        assert_eq!(format!("git-{}", "0".repeat(40)), origin.store_key());
+        assert!(ExternalCellOrigin::git("u".to_owned(), "main".to_owned()).is_err());
+    }
+}
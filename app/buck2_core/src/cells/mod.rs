@@ -132,6 +132,7 @@ pub mod alias;
 pub mod build_file_cell;
 pub mod cell_path;
 pub mod cell_root_path;
+pub mod external;
 pub mod instance;
 pub mod name;
 pub mod nested;
@@ -160,6 +161,7 @@ use crate::cells::cell_path::CellPath;
 use crate::cells::cell_path::CellPathRef;
 use crate::cells::cell_root_path::CellRootPath;
 use crate::cells::cell_root_path::CellRootPathBuf;
+use crate::cells::external::ExternalCellOrigin;
 use crate::cells::name::CellName;
 use crate::cells::nested::NestedCells;
 use crate::fs::paths::abs_norm_path::AbsNormPath;
@@ -592,6 +594,8 @@ struct CellAggregatorInfo {
     /// The build file name in this if it's been set. If it hasn't we'll use the
     /// default `["BUCK.v2", "BUCK"]` when building the resolver.
     buildfiles: Option<Vec<FileNameBuf>>,
+    /// Set if this is an external cell.
+    external: Option<ExternalCellOrigin>,
 }
 
 impl CellAggregatorInfo {
@@ -659,6 +663,11 @@ impl CellsAggregator {
         cell_info.buildfiles = Some(buildfiles);
     }
 
+    /// Marks the cell as external, i.e. fetched from the given origin.
+    pub fn set_external(&mut self, cell_root: CellRootPathBuf, origin: ExternalCellOrigin) {
+        self.cell_info(cell_root).external = Some(origin);
+    }
+
     fn get_cell_name_from_path(&self, path: &CellRootPath) -> anyhow::Result<CellName> {
         self.cell_infos
             .get(path)
@@ -705,6 +714,7 @@ impl CellsAggregator {
                     .unwrap_or_else(default_buildfiles),
                 CellAliasResolver::new(cell_name, aliases_for_cell)?,
                 nested_cells,
+                cell_info.external.clone(),
             )?);
         }
 
@@ -26,5 +26,6 @@ pub mod config_setting;
 pub mod constraints;
 pub mod data;
 pub mod hash;
+pub mod modifiers;
 pub mod pair;
 pub mod transition;
@@ -0,0 +1,298 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Configuration modifiers are constraint values applied on top of
+//! the target platform configuration of a top-level target.
+//!
+//! Modifiers come from `PACKAGE` files and from the command line
+//! (`buck2 build //foo:bar?opt+asan` or `--modifier opt`).
+
+use dupe::Dupe;
+
+use crate::configuration::constraints::ConstraintKey;
+use crate::configuration::constraints::ConstraintValue;
+use crate::configuration::data::ConfigurationData;
+use crate::configuration::data::ConfigurationDataData;
+
+#[derive(Debug, thiserror::Error)]
+enum ModifiersError {
+    #[error("Empty modifier in pattern `{0}`")]
+    EmptyModifier(String),
+    #[error(
+        "Patterns `{0}` and `{1}` specify different modifiers, \
+        modifiers apply to the whole invocation"
+    )]
+    ConflictingModifiers(String, String),
+    #[error("Cannot apply modifiers to `{0}` configuration")]
+    CannotModify(String),
+}
+
+/// Separator between a target pattern and its modifiers.
+pub const MODIFIERS_SEPARATOR: char = '?';
+/// Separator between modifiers.
+pub const MODIFIER_SEPARATOR: char = '+';
+
+/// Split `//foo:bar?opt+asan` into `//foo:bar` and `["opt", "asan"]`.
+pub fn split_modifiers(pattern: &str) -> anyhow::Result<(&str, Vec<&str>)> {
+    match pattern.split_once(MODIFIERS_SEPARATOR) {
+        None => Ok((pattern, Vec::new())),
+        Some((pattern_without_modifiers, modifiers)) => {
+            let modifiers: Vec<&str> = modifiers.split(MODIFIER_SEPARATOR).collect();
+            if modifiers.iter().any(|m| m.is_empty()) {
+                return Err(ModifiersError::EmptyModifier(pattern.to_owned()).into());
+            }
+            Ok((pattern_without_modifiers, modifiers))
+        }
+    }
+}
+
+/// Strip modifiers from command line patterns.
+///
+/// Returns patterns without modifiers and modifiers of the invocation.
+/// Patterns without modifiers are allowed to be mixed with patterns with modifiers,
+/// but all the patterns with modifiers must specify the same modifiers.
+pub fn split_patterns_modifiers(patterns: &[String]) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let mut stripped = Vec::with_capacity(patterns.len());
+    let mut modifiers: Option<(&str, Vec<&str>)> = None;
+    for pattern in patterns {
+        let (stripped_pattern, pattern_modifiers) = split_modifiers(pattern)?;
+        stripped.push(stripped_pattern.to_owned());
+        if pattern_modifiers.is_empty() {
+            continue;
+        }
+        match &modifiers {
+            None => modifiers = Some((pattern, pattern_modifiers)),
+            Some((first, first_modifiers)) => {
+                if first_modifiers != &pattern_modifiers {
+                    return Err(ModifiersError::ConflictingModifiers(
+                        (*first).to_owned(),
+                        pattern.clone(),
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+    let modifiers = match modifiers {
+        Some((_, modifiers)) => modifiers.into_iter().map(|m| m.to_owned()).collect(),
+        None => Vec::new(),
+    };
+    Ok((stripped, modifiers))
+}
+
+/// Like [`split_patterns_modifiers`], but strips the modifiers from the patterns in place.
+pub fn strip_patterns_modifiers<'a>(
+    patterns: impl IntoIterator<Item = &'a mut String>,
+) -> anyhow::Result<Vec<String>> {
+    let mut patterns: Vec<&mut String> = patterns.into_iter().collect();
+    let (stripped, modifiers) =
+        split_patterns_modifiers(&patterns.iter().map(|p| (**p).clone()).collect::<Vec<_>>())?;
+    for (pattern, stripped) in patterns.iter_mut().zip(stripped) {
+        **pattern = stripped;
+    }
+    Ok(modifiers)
+}
+
+/// Strip modifiers from a query and its arguments.
+///
+/// `?` is not valid in the query language, so modifiers are only recognized on the query
+/// arguments, and on the query itself when it is a single target pattern
+/// (`buck2 cquery //foo:bar?opt`).
+pub fn strip_query_modifiers(
+    query: &mut String,
+    query_args: &mut [String],
+) -> anyhow::Result<Vec<String>> {
+    let query_is_pattern = !query.contains(|c: char| c.is_whitespace() || "()'\",".contains(c));
+    strip_patterns_modifiers(
+        query_is_pattern
+            .then_some(query)
+            .into_iter()
+            .chain(query_args.iter_mut()),
+    )
+}
+
+/// A modifier resolved to the constraints it sets.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ResolvedModifier {
+    /// Short name used in the label of the modified configuration.
+    pub name: String,
+    pub constraints: Vec<(ConstraintKey, ConstraintValue)>,
+}
+
+/// Apply modifiers to a configuration, later modifiers taking precedence.
+///
+/// The resulting configuration is labeled `<base>+<modifier>+...`.
+/// If modifiers do not change constraints, the base configuration is returned.
+pub fn apply_modifiers(
+    base: &ConfigurationData,
+    modifiers: &[ResolvedModifier],
+) -> anyhow::Result<ConfigurationData> {
+    if modifiers.is_empty() {
+        return Ok(base.dupe());
+    }
+    let base_data = if base == &ConfigurationData::unspecified() {
+        ConfigurationDataData::empty()
+    } else if base.is_bound() {
+        ConfigurationDataData::new(base.data()?.constraints.clone())
+    } else {
+        return Err(ModifiersError::CannotModify(base.to_string()).into());
+    };
+
+    let mut data = ConfigurationDataData::new(base_data.constraints.clone());
+    for modifier in modifiers {
+        for (key, value) in &modifier.constraints {
+            data.constraints.insert(key.dupe(), value.dupe());
+        }
+    }
+    if data == base_data {
+        return Ok(base.dupe());
+    }
+
+    let mut label = base.short_name().to_owned();
+    for modifier in modifiers {
+        label.push(MODIFIER_SEPARATOR);
+        label.push_str(&modifier.name);
+    }
+    ConfigurationData::from_platform(label, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::configuration::constraints::ConstraintKey;
+    use crate::configuration::constraints::ConstraintValue;
+    use crate::configuration::data::ConfigurationData;
+    use crate::configuration::data::ConfigurationDataData;
+    use crate::configuration::modifiers::apply_modifiers;
+    use crate::configuration::modifiers::split_modifiers;
+    use crate::configuration::modifiers::split_patterns_modifiers;
+    use crate::configuration::modifiers::strip_query_modifiers;
+    use crate::configuration::modifiers::ResolvedModifier;
+    use crate::target::label::TargetLabel;
+
+    #[test]
+    fn test_split_modifiers() {
+        assert_eq!(
+            ("//foo:bar", vec!["opt", "asan"]),
+            split_modifiers("//foo:bar?opt+asan").unwrap()
+        );
+        assert_eq!(
+            ("//foo:bar", Vec::<&str>::new()),
+            split_modifiers("//foo:bar").unwrap()
+        );
+        assert!(split_modifiers("//foo:bar?").is_err());
+        assert!(split_modifiers("//foo:bar?opt++asan").is_err());
+    }
+
+    #[test]
+    fn test_split_patterns_modifiers() {
+        let patterns = vec![
+            "//a:a?opt".to_owned(),
+            "//b:b".to_owned(),
+            "//c:c?opt".to_owned(),
+        ];
+        let (patterns, modifiers) = split_patterns_modifiers(&patterns).unwrap();
+        assert_eq!(vec!["//a:a", "//b:b", "//c:c"], patterns);
+        assert_eq!(vec!["opt"], modifiers);
+
+        assert!(
+            split_patterns_modifiers(&["//a:a?opt".to_owned(), "//b:b?dbg".to_owned()]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_strip_query_modifiers() {
+        let mut query = "//a:a?opt".to_owned();
+        let mut args = vec!["//b:b".to_owned(), "//c:c?opt".to_owned()];
+        assert_eq!(
+            vec!["opt"],
+            strip_query_modifiers(&mut query, &mut args).unwrap()
+        );
+        assert_eq!("//a:a", query);
+        assert_eq!(vec!["//b:b", "//c:c"], args);
+
+        // Only arguments are patterns in a query expression.
+        let mut query = "deps(%s)".to_owned();
+        let mut args = vec!["//a:a?dbg".to_owned()];
+        assert_eq!(
+            vec!["dbg"],
+            strip_query_modifiers(&mut query, &mut args).unwrap()
+        );
+        assert_eq!("deps(%s)", query);
+        assert_eq!(vec!["//a:a"], args);
+
+        let mut query = "//a:a?opt".to_owned();
+        let mut args = vec!["//b:b?dbg".to_owned()];
+        assert!(strip_query_modifiers(&mut query, &mut args).is_err());
+    }
+
+    #[test]
+    fn test_apply_modifiers() {
+        let key = |s: &str| ConstraintKey(TargetLabel::testing_parse(s));
+        let value = |s: &str| ConstraintValue(TargetLabel::testing_parse(s));
+        let base = ConfigurationData::from_platform(
+            "cfg//:linux".to_owned(),
+            ConfigurationDataData::new(BTreeMap::from_iter([
+                (key("cfg//:os"), value("cfg//:linux")),
+                (key("cfg//:mode"), value("cfg//:dev")),
+            ])),
+        )
+        .unwrap();
+
+        assert_eq!(base, apply_modifiers(&base, &[]).unwrap());
+
+        let modifier = |name: &str, k: &str, v: &str| ResolvedModifier {
+            name: name.to_owned(),
+            constraints: vec![(key(k), value(v))],
+        };
+        assert_eq!(
+            base,
+            apply_modifiers(&base, &[modifier("dev", "cfg//:mode", "cfg//:dev")]).unwrap()
+        );
+
+        let modified = apply_modifiers(
+            &base,
+            &[
+                modifier("dbg", "cfg//:mode", "cfg//:dbg"),
+                modifier("opt", "cfg//:mode", "cfg//:opt"),
+            ],
+        )
+        .unwrap();
+        assert_eq!("cfg//:linux+dbg+opt", modified.label().unwrap());
+        assert_eq!(
+            Some(&value("cfg//:opt")),
+            modified
+                .data()
+                .unwrap()
+                .get_constraint_value(&key("cfg//:mode"))
+        );
+        assert_eq!(
+            Some(&value("cfg//:linux")),
+            modified
+                .data()
+                .unwrap()
+                .get_constraint_value(&key("cfg//:os"))
+        );
+
+        let modified = apply_modifiers(
+            &ConfigurationData::unspecified(),
+            &[modifier("opt", "cfg//:mode", "cfg//:opt")],
+        )
+        .unwrap();
+        assert_eq!("<unspecified>+opt", modified.label().unwrap());
+        assert!(
+            apply_modifiers(
+                &ConfigurationData::unbound(),
+                &[modifier("opt", "cfg//:mode", "cfg//:opt")]
+            )
+            .is_err()
+        );
+    }
+}
@@ -11,9 +11,67 @@
 //! will need to handle as the process runner.
 
 use std::collections::HashMap;
+use std::fmt;
 
+use serde::Deserialize;
 use tracing::Level;
 
+pub mod records;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A message attached to a location in a source file, e.g. a compiler error.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Diagnostic {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    #[serde(default)]
+    pub column: Option<u32>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    /// Formatted like compilers do: `file:line:column: severity: message`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        write!(f, ": {}: {}", self.severity, self.message)
+    }
+}
+
+/// How far along the process is.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Progress {
+    /// What the process is currently doing.
+    pub step: String,
+    /// Between 0 and 100, if known.
+    #[serde(default)]
+    pub percent: Option<u32>,
+}
+
 /// The API available to processes that Buck will need to handle
 #[async_trait::async_trait]
 pub trait DownwardApi {
@@ -27,5 +85,11 @@ pub trait DownwardApi {
     /// reports an externally consumable event containing some data that will be untouched by buck
     async fn external(&self, data: HashMap<String, String>) -> anyhow::Result<()>;
 
+    /// reports how far along the process is
+    async fn progress(&self, progress: Progress) -> anyhow::Result<()>;
+
+    /// reports a diagnostic (e.g. a compiler error) without having to parse stderr
+    async fn diagnostic(&self, diagnostic: Diagnostic) -> anyhow::Result<()>;
+
     // TODO map the StepEvent and TraceEvents in buckv1 to something. Maybe just a single trace event
 }
@@ -0,0 +1,86 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The downward api for local actions. Buck points the action at a file through
+//! [`DOWNWARD_API_FILE_ENV`], and the action appends one JSON record per line to it, e.g.:
+//!
+//! ```json
+//! {"type": "progress", "step": "linking", "percent": 40}
+//! {"type": "span_start", "name": "lto"}
+//! {"type": "span_end", "name": "lto"}
+//! {"type": "diagnostic", "file": "foo.cpp", "line": 3, "severity": "error", "message": "..."}
+//! ```
+//!
+//! Buck reads the records while the action is running.
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::Diagnostic;
+use crate::Progress;
+
+/// Environment variable holding the absolute path of the file actions write records to.
+pub const DOWNWARD_API_FILE_ENV: &str = "BUCK2_DOWNWARD_API_FILE";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DownwardApiRecord {
+    Progress(Progress),
+    /// Start of a custom timing span. Spans are closed by name, and must be properly nested.
+    SpanStart {
+        name: String,
+    },
+    SpanEnd {
+        name: String,
+    },
+    Diagnostic(Diagnostic),
+}
+
+impl DownwardApiRecord {
+    pub fn parse(line: &str) -> anyhow::Result<Self> {
+        serde_json::from_str(line)
+            .with_context(|| format!("Invalid downward api record: `{}`", line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DiagnosticSeverity;
+
+    #[test]
+    fn test_parse() -> anyhow::Result<()> {
+        assert_eq!(
+            DownwardApiRecord::parse(r#"{"type": "progress", "step": "linking", "percent": 40}"#)?,
+            DownwardApiRecord::Progress(Progress {
+                step: "linking".to_owned(),
+                percent: Some(40),
+            })
+        );
+        assert_eq!(
+            DownwardApiRecord::parse(r#"{"type": "span_start", "name": "lto"}"#)?,
+            DownwardApiRecord::SpanStart {
+                name: "lto".to_owned()
+            }
+        );
+
+        let diagnostic = DownwardApiRecord::parse(
+            r#"{"type": "diagnostic", "file": "foo.cpp", "line": 3, "severity": "error", "message": "bad"}"#,
+        )?;
+        let diagnostic = match diagnostic {
+            DownwardApiRecord::Diagnostic(d) => d,
+            r => panic!("Unexpected record: {:?}", r),
+        };
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostic.to_string(), "foo.cpp:3: error: bad");
+
+        assert!(DownwardApiRecord::parse(r#"{"type": "unknown"}"#).is_err());
+        Ok(())
+    }
+}
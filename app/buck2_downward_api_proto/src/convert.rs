@@ -10,6 +10,8 @@
 use std::collections::HashMap;
 
 use anyhow::Context as _;
+use buck2_downward_api::Diagnostic;
+use buck2_downward_api::DiagnosticSeverity;
 use tracing::Level;
 
 use crate::proto;
@@ -97,6 +99,66 @@ impl TryFrom<Level> for proto::LogLevel {
     }
 }
 
+impl TryInto<Diagnostic> for proto::DiagnosticRequest {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Diagnostic, Self::Error> {
+        use proto::diagnostic_severity::Value;
+
+        let proto::DiagnosticRequest {
+            file,
+            line,
+            column,
+            severity,
+            message,
+        } = self;
+        let severity = severity.context("Missing `severity`")?;
+        let severity = Value::from_i32(severity.value).context("Invalid `severity`")?;
+
+        Ok(Diagnostic {
+            file,
+            line,
+            column,
+            severity: match severity {
+                Value::NotSet => anyhow::bail!("Missing `severity`"),
+                Value::Info => DiagnosticSeverity::Info,
+                Value::Warning => DiagnosticSeverity::Warning,
+                Value::Error => DiagnosticSeverity::Error,
+            },
+            message,
+        })
+    }
+}
+
+impl From<Diagnostic> for proto::DiagnosticRequest {
+    fn from(diagnostic: Diagnostic) -> proto::DiagnosticRequest {
+        use proto::diagnostic_severity::Value;
+
+        let Diagnostic {
+            file,
+            line,
+            column,
+            severity,
+            message,
+        } = diagnostic;
+        let value = match severity {
+            DiagnosticSeverity::Info => Value::Info,
+            DiagnosticSeverity::Warning => Value::Warning,
+            DiagnosticSeverity::Error => Value::Error,
+        };
+
+        proto::DiagnosticRequest {
+            file,
+            line,
+            column,
+            severity: Some(proto::DiagnosticSeverity {
+                value: value as i32,
+            }),
+            message,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,4 +177,18 @@ mod tests {
             assert_eq!(*v, v2);
         }
     }
+
+    #[test]
+    fn test_diagnostic_roundtrip() {
+        let diagnostic = Diagnostic {
+            file: "foo.cpp".to_owned(),
+            line: Some(3),
+            column: None,
+            severity: DiagnosticSeverity::Warning,
+            message: "unused variable".to_owned(),
+        };
+        let p = proto::DiagnosticRequest::from(diagnostic.clone());
+        let diagnostic2: Diagnostic = p.try_into().unwrap();
+        assert_eq!(diagnostic, diagnostic2);
+    }
 }
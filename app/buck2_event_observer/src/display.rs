@@ -39,6 +39,7 @@ use superconsole::Span;
 use thiserror::Error;
 
 use crate::fmt_duration;
+use crate::humanized::HumanizedBytes;
 use crate::verbosity::Verbosity;
 
 #[derive(Copy, Clone, Dupe)]
@@ -259,8 +260,12 @@ pub fn display_event(event: &BuckEvent, opts: TargetDisplayOptions) -> anyhow::R
             Data::CacheUpload(..) => Ok("upload".to_owned()),
             Data::CreateOutputSymlinks(..) => Ok("Creating output symlinks".to_owned()),
             Data::InstallEventInfo(info) => Ok(format!(
-                "Sending {} at path {}",
-                info.artifact_name, info.file_path
+                "Sending {} ({}/{}, {}) at path {}",
+                info.artifact_name,
+                info.file_index,
+                info.files_total,
+                HumanizedBytes::new(info.size),
+                info.file_path
             )),
             Data::DiceStateUpdate(..) => Ok("Syncing changes to graph".to_owned()),
             Data::Materialization(..) => Ok("materializing".to_owned()),
@@ -308,6 +313,10 @@ pub fn display_event(event: &BuckEvent, opts: TargetDisplayOptions) -> anyhow::R
             Data::Fake(fake) => Ok(format!("{} -- speak of the devil", fake.caramba)),
             Data::LocalResources(..) => Ok("Local resources setup".to_owned()),
             Data::ReleaseLocalResources(..) => Ok("Releasing local resources".to_owned()),
+            Data::DownwardApiSpan(span) => match span.percent {
+                Some(percent) => Ok(format!("{} {}%", span.name, percent)),
+                None => Ok(span.name.clone()),
+            },
         };
 
         // This shouldn't really be necessary, but that's how try blocks work :(
@@ -538,7 +538,8 @@ pub fn is_span_shown(event: &BuckEvent) -> bool {
             | Data::ReUpload(..)
             | Data::ConnectToInstaller(..)
             | Data::LocalResources(..)
-            | Data::ReleaseLocalResources(..),
+            | Data::ReleaseLocalResources(..)
+            | Data::DownwardApiSpan(..),
         ) => true,
         None => false,
     }
@@ -158,6 +158,8 @@ pub struct CommandExecutionReport {
     /// No exit_code means the command did not finish executing. Signals get mapped into this as
     /// 128 + SIGNUM, which is the convention shells follow.
     pub exit_code: Option<i32>,
+    /// Diagnostics reported by the command via the downward api.
+    pub diagnostics: Vec<buck2_data::ActionDiagnostic>,
 }
 
 /// Implement FromResidual so that it's easier to refactor functions returning a CommandExecutionResult
@@ -23,6 +23,7 @@ pub mod entry;
 pub mod execute;
 pub mod knobs;
 pub mod materialize;
+pub mod output_manifest;
 pub mod output_size;
 pub mod path;
 pub mod re;
@@ -60,6 +60,19 @@ impl Checksum {
             Self::Both { sha256, .. } => Some(sha256),
         }
     }
+
+    /// Render this checksum as a [Subresource Integrity](https://www.w3.org/TR/SRI/) string,
+    /// which is how checksums are passed to the Remote Asset API (as the `checksum.sri`
+    /// qualifier). SRI does not support SHA1, so this only works if we have a SHA256.
+    pub fn to_sri(&self) -> anyhow::Result<Option<String>> {
+        let sha256 = match self.sha256() {
+            Some(sha256) => sha256,
+            None => return Ok(None),
+        };
+        let bytes = hex::decode(sha256)
+            .with_context(|| format!("Invalid sha256 checksum: `{}`", sha256))?;
+        Ok(Some(format!("sha256-{}", base64::encode(bytes))))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -393,4 +406,25 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_checksum_to_sri() -> anyhow::Result<()> {
+        assert_eq!(
+            Checksum::Sha256(Arc::from(
+                "c3ab8ff13720e8ad9047dd39466b3c8974e592c2fa383d4a3960714caef0c4f2"
+            ))
+            .to_sri()?
+            .as_deref(),
+            Some("sha256-w6uP8Tcg6K2QR905Rms8iXTlksL6OD1KOWBxTK7wxPI=")
+        );
+
+        assert_eq!(
+            Checksum::Sha1(Arc::from("8843d7f92416211de9ebb963ff4ce28125932878")).to_sri()?,
+            None
+        );
+
+        assert!(Checksum::Sha256(Arc::from("oops")).to_sri().is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,468 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The manifest written by `buck2 build --output-manifest`. It records the path, digest and size
+//! of each output, which is enough to fetch the output from the CAS later, even if the daemon
+//! which built it has since been restarted.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use buck2_common::executor_config::RemoteExecutorUseCase;
+use buck2_common::file_ops::FileDigest;
+use buck2_common::file_ops::FileMetadata;
+use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use chrono::TimeZone;
+use chrono::Utc;
+use more_futures::cancellation::CancellationContext;
+use remote_execution as RE;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::artifact_value::ArtifactValue;
+use crate::digest::CasDigestToReExt;
+use crate::digest_config::DigestConfig;
+use crate::directory::new_symlink;
+use crate::directory::re_directory_to_re_tree;
+use crate::directory::re_tree_to_directory;
+use crate::directory::ActionDirectoryMember;
+use crate::directory::INTERNER;
+use crate::materialize::materializer::CasDownloadInfo;
+use crate::materialize::materializer::Materializer;
+use crate::output_size::OutputSize;
+use crate::re::manager::ManagedRemoteExecutionClient;
+
+#[derive(Debug, thiserror::Error)]
+enum OutputManifestError {
+    #[error("Output `{0}` has no digest in the output manifest")]
+    MissingDigest(ProjectRelativePathBuf),
+    #[error("Output `{0}` has no symlink target in the output manifest")]
+    MissingSymlinkTarget(ProjectRelativePathBuf),
+    #[error(
+        "Output `{path}` has size {size} in the output manifest, but its digest is `{digest}`"
+    )]
+    SizeMismatch {
+        path: ProjectRelativePathBuf,
+        size: u64,
+        digest: FileDigest,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutputManifestEntry {
+    pub path: ProjectRelativePathBuf,
+    #[serde(rename = "type")]
+    pub kind: OutputKind,
+    /// RE digest of the file, or of the root `Directory` of the tree.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Total size of the files.
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub executable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<String>,
+}
+
+impl OutputManifestEntry {
+    pub fn new(path: ProjectRelativePathBuf, value: &ArtifactValue) -> Self {
+        let size = value.calc_output_count_and_bytes().bytes;
+        let (kind, digest, executable, symlink_target) = match value.entry() {
+            DirectoryEntry::Dir(d) => (
+                OutputKind::Directory,
+                Some(d.fingerprint().to_string()),
+                false,
+                None,
+            ),
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => (
+                OutputKind::File,
+                Some(f.digest.to_string()),
+                f.is_executable,
+                None,
+            ),
+            DirectoryEntry::Leaf(ActionDirectoryMember::Symlink(s)) => (
+                OutputKind::Symlink,
+                None,
+                false,
+                Some(s.target().to_string()),
+            ),
+            DirectoryEntry::Leaf(ActionDirectoryMember::ExternalSymlink(s)) => (
+                OutputKind::Symlink,
+                None,
+                false,
+                Some(s.to_path_buf().to_string_lossy().into_owned()),
+            ),
+        };
+        Self {
+            path,
+            kind,
+            digest,
+            size,
+            executable,
+            symlink_target,
+        }
+    }
+
+    fn digest(&self, digest_config: DigestConfig) -> anyhow::Result<FileDigest> {
+        let digest = self
+            .digest
+            .as_ref()
+            .ok_or_else(|| OutputManifestError::MissingDigest(self.path.clone()))?;
+        let (digest, _algo) =
+            FileDigest::parse_digest(digest, digest_config.cas_digest_config())
+                .with_context(|| format!("Invalid digest for output `{}`", self.path))?;
+        Ok(digest)
+    }
+
+    /// Rebuilds the value of this output. Directories need their tree to be downloaded from the
+    /// CAS, files and symlinks are described by the manifest alone.
+    pub async fn artifact_value(
+        &self,
+        re_client: &ManagedRemoteExecutionClient,
+        re_use_case: RemoteExecutorUseCase,
+        digest_config: DigestConfig,
+    ) -> anyhow::Result<ArtifactValue> {
+        match self.kind {
+            OutputKind::File => {
+                let digest = self.digest(digest_config)?;
+                if digest.size() != self.size {
+                    return Err(OutputManifestError::SizeMismatch {
+                        path: self.path.clone(),
+                        size: self.size,
+                        digest,
+                    }
+                    .into());
+                }
+                Ok(ArtifactValue::file(FileMetadata {
+                    digest: TrackedFileDigest::new(digest, digest_config.cas_digest_config()),
+                    is_executable: self.executable,
+                }))
+            }
+            OutputKind::Symlink => {
+                let target = self
+                    .symlink_target
+                    .as_ref()
+                    .ok_or_else(|| OutputManifestError::MissingSymlinkTarget(self.path.clone()))?;
+                Ok(ArtifactValue::new(
+                    DirectoryEntry::Leaf(new_symlink(target)?),
+                    None,
+                ))
+            }
+            OutputKind::Directory => {
+                let digest = self.digest(digest_config)?;
+                let root_directory = re_client
+                    .download_typed_blobs::<RE::Directory>(vec![digest.to_re()], re_use_case)
+                    .await
+                    .and_then(|dirs| dirs.into_iter().next().context("RE response was empty"))
+                    .with_context(|| format!("Error downloading dir: {}", digest))?;
+                let tree = re_directory_to_re_tree(root_directory, re_client, re_use_case).await?;
+                // As for `cas_artifact`, the expiry of the nodes in the tree is unknown.
+                let dir =
+                    re_tree_to_directory(&tree, &Utc.timestamp_opt(0, 0).unwrap(), digest_config)
+                        .context("Invalid directory")?;
+                Ok(ArtifactValue::new(
+                    DirectoryEntry::Dir(
+                        dir.fingerprint(digest_config.as_directory_serializer())
+                            .shared(&*INTERNER),
+                    ),
+                    None,
+                ))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutputManifest {
+    pub outputs: Vec<OutputManifestEntry>,
+}
+
+impl OutputManifest {
+    /// Finds the output which is, or contains, `path`.
+    pub fn find(&self, path: &ProjectRelativePath) -> Option<&OutputManifestEntry> {
+        self.outputs.iter().find(|o| path.starts_with(&o.path))
+    }
+
+    /// Declares the outputs containing `paths` to the materializer, to be downloaded from the CAS
+    /// by their digest the next time they are materialized. Returns the paths which are not in
+    /// the manifest.
+    pub async fn declare(
+        &self,
+        paths: &[ProjectRelativePathBuf],
+        materializer: &dyn Materializer,
+        re_client: &ManagedRemoteExecutionClient,
+        re_use_case: RemoteExecutorUseCase,
+        digest_config: DigestConfig,
+    ) -> anyhow::Result<Vec<ProjectRelativePathBuf>> {
+        let mut unknown = Vec::new();
+        let mut outputs: Vec<&OutputManifestEntry> = Vec::new();
+        for path in paths {
+            match self.find(path) {
+                Some(output) => {
+                    if !outputs.iter().any(|o| o.path == output.path) {
+                        outputs.push(output);
+                    }
+                }
+                None => unknown.push(path.clone()),
+            }
+        }
+
+        let mut artifacts = Vec::with_capacity(outputs.len());
+        for output in outputs {
+            let value = output
+                .artifact_value(re_client, re_use_case, digest_config)
+                .await?;
+            artifacts.push((output.path.clone(), value));
+        }
+        if !artifacts.is_empty() {
+            materializer
+                .declare_cas_many(
+                    Arc::new(CasDownloadInfo::new_declared(re_use_case)),
+                    artifacts,
+                    CancellationContext::never_cancelled(),
+                )
+                .await?;
+        }
+        Ok(unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use allocative::Allocative;
+    use async_trait::async_trait;
+    use dupe::Dupe;
+    use futures::stream;
+    use futures::stream::BoxStream;
+    use futures::stream::StreamExt;
+    use gazebo::prelude::*;
+
+    use super::*;
+    use crate::materialize::materializer::ArtifactNotMaterializedReason;
+    use crate::materialize::materializer::CopiedArtifact;
+    use crate::materialize::materializer::DeclareMatchOutcome;
+    use crate::materialize::materializer::HttpDownloadInfo;
+    use crate::materialize::materializer::MaterializationError;
+    use crate::materialize::materializer::WriteRequest;
+
+    /// Materializes nothing, but only succeeds for paths which were declared to it.
+    #[derive(Allocative, Default)]
+    struct DeclaredOnlyMaterializer {
+        #[allocative(skip)]
+        declared: Mutex<Vec<(ProjectRelativePathBuf, ArtifactValue)>>,
+    }
+
+    #[async_trait]
+    impl Materializer for DeclaredOnlyMaterializer {
+        fn name(&self) -> &str {
+            "declared_only"
+        }
+
+        async fn declare_existing(
+            &self,
+            _artifacts: Vec<(ProjectRelativePathBuf, ArtifactValue)>,
+        ) -> anyhow::Result<()> {
+            unreachable!("the output manifest only declares CAS artifacts")
+        }
+
+        async fn declare_copy_impl(
+            &self,
+            _path: ProjectRelativePathBuf,
+            _value: ArtifactValue,
+            _srcs: Vec<CopiedArtifact>,
+            _cancellations: &CancellationContext,
+        ) -> anyhow::Result<()> {
+            unreachable!("the output manifest only declares CAS artifacts")
+        }
+
+        async fn declare_cas_many_impl<'a, 'b>(
+            &self,
+            _info: Arc<CasDownloadInfo>,
+            artifacts: Vec<(ProjectRelativePathBuf, ArtifactValue)>,
+            _cancellations: &CancellationContext,
+        ) -> anyhow::Result<()> {
+            self.declared.lock().unwrap().extend(artifacts);
+            Ok(())
+        }
+
+        async fn declare_http(
+            &self,
+            _path: ProjectRelativePathBuf,
+            _info: HttpDownloadInfo,
+            _cancellations: &CancellationContext,
+        ) -> anyhow::Result<()> {
+            unreachable!("the output manifest only declares CAS artifacts")
+        }
+
+        async fn declare_match(
+            &self,
+            _artifacts: Vec<(ProjectRelativePathBuf, ArtifactValue)>,
+        ) -> anyhow::Result<DeclareMatchOutcome> {
+            Ok(DeclareMatchOutcome::NotMatch)
+        }
+
+        async fn declare_write<'a>(
+            &self,
+            _gen: Box<dyn FnOnce() -> anyhow::Result<Vec<WriteRequest>> + Send + 'a>,
+        ) -> anyhow::Result<Vec<ArtifactValue>> {
+            unreachable!("the output manifest only declares CAS artifacts")
+        }
+
+        async fn invalidate_many(&self, _paths: Vec<ProjectRelativePathBuf>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn materialize_many(
+            &self,
+            artifact_paths: Vec<ProjectRelativePathBuf>,
+        ) -> anyhow::Result<BoxStream<'static, Result<(), MaterializationError>>> {
+            let declared = self.declared.lock().unwrap();
+            let results = artifact_paths.into_map(|path| {
+                if declared.iter().any(|(p, _)| path.starts_with(p)) {
+                    Ok(())
+                } else {
+                    Err(MaterializationError::Error {
+                        source: anyhow::anyhow!("Not declared"),
+                        path,
+                    })
+                }
+            });
+            Ok(stream::iter(results).boxed())
+        }
+
+        async fn try_materialize_final_artifact(
+            &self,
+            _artifact_path: ProjectRelativePathBuf,
+        ) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+
+        async fn get_materialized_file_paths(
+            &self,
+            paths: Vec<ProjectRelativePathBuf>,
+        ) -> anyhow::Result<Vec<Result<ProjectRelativePathBuf, ArtifactNotMaterializedReason>>>
+        {
+            Ok(paths.into_map(Ok))
+        }
+    }
+
+    #[test]
+    fn test_entry_roundtrip() {
+        let digest_config = DigestConfig::testing_default();
+        let digest = TrackedFileDigest::from_content(b"hello", digest_config.cas_digest_config());
+        let value = ArtifactValue::file(FileMetadata {
+            digest: digest.dupe(),
+            is_executable: true,
+        });
+        let entry = OutputManifestEntry::new(
+            ProjectRelativePathBuf::unchecked_new("buck-out/v2/gen/foo/out".to_owned()),
+            &value,
+        );
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "path": "buck-out/v2/gen/foo/out",
+                "type": "file",
+                "digest": digest.to_string(),
+                "size": 5,
+                "executable": true,
+            }),
+            json
+        );
+        assert_eq!(
+            entry,
+            serde_json::from_value::<OutputManifestEntry>(json).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_declare_unknown_output_from_manifest() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+        let digest = TrackedFileDigest::from_content(b"hello", digest_config.cas_digest_config());
+        let out = ProjectRelativePathBuf::unchecked_new("buck-out/v2/gen/foo/out".to_owned());
+        let manifest = OutputManifest {
+            outputs: vec![OutputManifestEntry {
+                path: out.clone(),
+                kind: OutputKind::File,
+                digest: Some(digest.to_string()),
+                size: 5,
+                executable: false,
+                symlink_target: None,
+            }],
+        };
+        let other = ProjectRelativePathBuf::unchecked_new("buck-out/v2/gen/foo/other".to_owned());
+
+        let materializer = DeclaredOnlyMaterializer::default();
+        assert!(
+            materializer
+                .ensure_materialized(vec![out.clone()])
+                .await
+                .is_err()
+        );
+
+        let unknown = manifest
+            .declare(
+                &[out.clone(), other.clone()],
+                &materializer,
+                &ManagedRemoteExecutionClient::testing_new_dummy(),
+                RemoteExecutorUseCase::buck2_default(),
+                digest_config,
+            )
+            .await?;
+        assert_eq!(vec![other], unknown);
+
+        materializer.ensure_materialized(vec![out.clone()]).await?;
+        let declared = materializer.declared.lock().unwrap();
+        assert_eq!(1, declared.len());
+        assert_eq!(out, declared[0].0);
+        match declared[0].1.entry() {
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => {
+                assert_eq!(digest, f.digest);
+                assert!(!f.is_executable);
+            }
+            _ => panic!("Expected a file"),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_size_mismatch() {
+        let digest_config = DigestConfig::testing_default();
+        let digest = TrackedFileDigest::from_content(b"hello", digest_config.cas_digest_config());
+        let entry = OutputManifestEntry {
+            path: ProjectRelativePathBuf::unchecked_new("buck-out/out".to_owned()),
+            kind: OutputKind::File,
+            digest: Some(digest.to_string()),
+            size: 6,
+            executable: false,
+            symlink_target: None,
+        };
+        let res = entry
+            .artifact_value(
+                &ManagedRemoteExecutionClient::testing_new_dummy(),
+                RemoteExecutorUseCase::buck2_default(),
+                digest_config,
+            )
+            .await;
+        assert!(res.is_err());
+    }
+}
@@ -393,6 +393,19 @@ impl RemoteExecutionClient {
             .await
     }
 
+    pub async fn fetch_blob(
+        &self,
+        uri: &str,
+        checksum_sri: &str,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<Option<TDigest>> {
+        self.data
+            .client
+            .fetch_blob(uri, checksum_sri, use_case)
+            .await
+            .map_err(|e| self.decorate_error(e))
+    }
+
     pub async fn get_digest_expirations(
         &self,
         digests: Vec<TDigest>,
@@ -432,6 +445,10 @@ impl RemoteExecutionClient {
         self.data.client.client().get_experiment_name()
     }
 
+    pub fn is_circuit_open(&self) -> bool {
+        self.data.client.client().is_circuit_open()
+    }
+
     pub fn get_network_stats(&self) -> anyhow::Result<RemoteExecutionClientStats> {
         let updated = self
             .data
@@ -1079,6 +1096,48 @@ impl RemoteExecutionClientImpl {
         Ok(())
     }
 
+    /// Ask the RE backend to fetch `uri` into the CAS via the Remote Asset API. Returns `None`
+    /// if the backend does not support (or was not configured for) the Remote Asset API.
+    async fn fetch_blob(
+        &self,
+        uri: &str,
+        checksum_sri: &str,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<Option<TDigest>> {
+        #[cfg(fbcode_build)]
+        {
+            let _unused = (uri, checksum_sri, use_case);
+            Ok(None)
+        }
+
+        #[cfg(not(fbcode_build))]
+        {
+            if !self.client().supports_fetch_blob() {
+                return Ok(None);
+            }
+
+            let response = self
+                .client()
+                .get_cas_client()
+                .fetch_blob(
+                    use_case.metadata(),
+                    remote_execution::FetchBlobRequest {
+                        uris: vec![uri.to_owned()],
+                        qualifiers: vec![remote_execution::TQualifier {
+                            name: "checksum.sri".to_owned(),
+                            value: checksum_sri.to_owned(),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    },
+                )
+                .await
+                .with_context(|| format!("FetchBlob request failed for `{}`", uri))?;
+
+            Ok(Some(response.digest))
+        }
+    }
+
     async fn get_digest_expirations(
         &self,
         digests: Vec<TDigest>,
@@ -26,6 +26,7 @@ use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_re_configuration::RemoteExecutionStaticMetadata;
+use buck2_re_configuration::RemoteExecutionStaticMetadataImpl;
 use chrono::DateTime;
 use chrono::Utc;
 use dupe::Dupe;
@@ -303,6 +304,15 @@ impl ManagedRemoteExecutionClient {
             .context("Internal error: the underlying RE connection has terminated because the corresponding guard has been dropped.")
     }
 
+    /// Whether RE failed repeatedly and should be avoided for now. This is false if we have not
+    /// connected to RE yet.
+    pub fn is_circuit_open(&self) -> bool {
+        self.lock()
+            .ok()
+            .and_then(|client| client.with_client(|client| client.is_circuit_open()))
+            .unwrap_or(false)
+    }
+
     pub async fn action_cache(
         &self,
         action_digest: ActionDigest,
@@ -433,6 +443,24 @@ impl ManagedRemoteExecutionClient {
         self.lock()?.get().await?.upload_blob(blob, use_case).await
     }
 
+    pub async fn fetch_blob(
+        &self,
+        uri: &str,
+        checksum_sri: &str,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<Option<TDigest>> {
+        let client = self.lock()?;
+        // Check the config first so that we don't connect to RE just to find out it can't do this.
+        if !client.config.static_metadata.remote_asset_enabled() {
+            return Ok(None);
+        }
+        client
+            .get()
+            .await?
+            .fetch_blob(uri, checksum_sri, use_case)
+            .await
+    }
+
     pub async fn get_digest_expirations(
         &self,
         digests: Vec<TDigest>,
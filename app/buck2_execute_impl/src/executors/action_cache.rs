@@ -26,8 +26,10 @@ use dupe::Dupe;
 use more_futures::cancellation::CancellationContext;
 use tracing::info;
 
+use crate::executors::output_validation::OutputValidationPolicy;
 use crate::re::download::download_action_results;
 use crate::re::download::DownloadResult;
+use crate::re::download::OutputValidation;
 
 pub struct ActionCacheChecker {
     pub artifact_fs: ArtifactFs,
@@ -35,6 +37,7 @@ pub struct ActionCacheChecker {
     pub re_client: ManagedRemoteExecutionClient,
     pub re_use_case: RemoteExecutorUseCase,
     pub upload_all_actions: bool,
+    pub output_validation_policy: Arc<OutputValidationPolicy>,
 }
 
 #[async_trait]
@@ -95,6 +98,7 @@ impl PreparedCommandOptionalExecutor for ActionCacheChecker {
             Ok(None) => return ControlFlow::Continue(manager),
         };
 
+        let action_name = command.target.as_proto_action_name();
         let res = download_action_results(
             request,
             &*self.materializer,
@@ -111,6 +115,11 @@ impl PreparedCommandOptionalExecutor for ActionCacheChecker {
             request.outputs(),
             action_digest,
             &response,
+            OutputValidation {
+                policy: &self.output_validation_policy,
+                category: &action_name.category,
+                fs: self.artifact_fs.fs(),
+            },
             cancellations,
         )
         .await;
@@ -0,0 +1,271 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::str::FromStr;
+
+use buck2_common::invocation_paths::InvocationPaths;
+use buck2_common::legacy_configs::LegacyBuckConfig;
+use buck2_core::collections::sorted_map::SortedMap;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use derive_more::Display;
+use dupe::Dupe;
+
+const CACHE_UPLOAD_SECTION: &str = "buck2_cache_upload";
+
+#[derive(Debug, thiserror::Error)]
+enum CacheUploadPolicyError {
+    #[error("Invalid executor `{0}`, expected `local` or `hybrid`")]
+    InvalidExecutor(String),
+    #[error("Invalid platform property `{0}`, expected `key=value`")]
+    InvalidPlatformProperty(String),
+}
+
+/// Executors whose results can be local and are therefore eligible for cache upload.
+#[derive(Debug, Display, Clone, Copy, Dupe, PartialEq, Eq)]
+pub enum CacheUploadExecutor {
+    #[display(fmt = "local")]
+    Local,
+    #[display(fmt = "hybrid")]
+    Hybrid,
+}
+
+impl FromStr for CacheUploadExecutor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "local" => Ok(Self::Local),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => Err(CacheUploadPolicyError::InvalidExecutor(s.to_owned()).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlatformProperty {
+    key: String,
+    value: String,
+}
+
+impl FromStr for PlatformProperty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.split_once('=') {
+            Some((key, value)) => Ok(Self {
+                key: key.trim().to_owned(),
+                value: value.trim().to_owned(),
+            }),
+            None => Err(CacheUploadPolicyError::InvalidPlatformProperty(s.to_owned()).into()),
+        }
+    }
+}
+
+/// Which results of local actions are uploaded to the RE action cache, in addition to those of
+/// actions which opt in with `allow_cache_upload` on an executor with cache uploads enabled.
+///
+/// Configured in the `[buck2_cache_upload]` section of the root buckconfig:
+///
+/// * `local_results`: upload results of local actions matching the rules below.
+/// * `categories`, `exclude_categories`: action categories to upload (default all).
+/// * `executors`: `local` and/or `hybrid` (default both).
+/// * `platforms`: `key=value` RE platform properties the executor must have.
+/// * `max_bytes`: do not upload results with larger outputs.
+/// * `allow_local_only`: also upload results of actions which cannot run remotely. Those often
+///   depend on the local machine, so they are not uploaded by default.
+/// * `reject_absolute_paths`: do not upload results whose outputs contain the absolute path of
+///   the project root (default true), since they are unlikely to be usable on another machine.
+/// * `reject_undeclared_inputs`: do not upload results of actions whose command line or
+///   environment refers to files of the project which are not inputs of the action (default
+///   true), since they are not part of the action digest.
+///
+/// Local actions are not sandboxed, so actions reading undeclared inputs are only found if they
+/// name them in their arguments or environment. Categories of actions known to read undeclared
+/// inputs in other ways should be excluded.
+#[derive(Debug, Default)]
+pub struct CacheUploadPolicy {
+    enabled: bool,
+    categories: Option<Vec<String>>,
+    exclude_categories: Vec<String>,
+    executors: Option<Vec<CacheUploadExecutor>>,
+    platform: Vec<PlatformProperty>,
+    pub max_bytes: Option<u64>,
+    pub allow_local_only: bool,
+    pub reject_absolute_paths: bool,
+    pub reject_undeclared_inputs: bool,
+}
+
+impl CacheUploadPolicy {
+    pub fn from_config(config: &LegacyBuckConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            enabled: config
+                .parse(CACHE_UPLOAD_SECTION, "local_results")?
+                .unwrap_or(false),
+            categories: config.parse_list(CACHE_UPLOAD_SECTION, "categories")?,
+            exclude_categories: config
+                .parse_list(CACHE_UPLOAD_SECTION, "exclude_categories")?
+                .unwrap_or_default(),
+            executors: config.parse_list(CACHE_UPLOAD_SECTION, "executors")?,
+            platform: config
+                .parse_list(CACHE_UPLOAD_SECTION, "platforms")?
+                .unwrap_or_default(),
+            max_bytes: config.parse(CACHE_UPLOAD_SECTION, "max_bytes")?,
+            allow_local_only: config
+                .parse(CACHE_UPLOAD_SECTION, "allow_local_only")?
+                .unwrap_or(false),
+            reject_absolute_paths: config
+                .parse(CACHE_UPLOAD_SECTION, "reject_absolute_paths")?
+                .unwrap_or(true),
+            reject_undeclared_inputs: config
+                .parse(CACHE_UPLOAD_SECTION, "reject_undeclared_inputs")?
+                .unwrap_or(true),
+        })
+    }
+
+    /// Whether results of an executor may be uploaded. Categories are checked separately.
+    pub fn applies_to_executor(
+        &self,
+        executor: CacheUploadExecutor,
+        platform: &SortedMap<String, String>,
+    ) -> bool {
+        self.enabled
+            && self
+                .executors
+                .as_ref()
+                .map_or(true, |executors| executors.contains(&executor))
+            && self
+                .platform
+                .iter()
+                .all(|p| platform.get(&p.key) == Some(&p.value))
+    }
+
+    pub fn applies_to_category(&self, category: &str) -> bool {
+        self.categories
+            .as_ref()
+            .map_or(true, |categories| categories.iter().any(|c| c == category))
+            && !self.exclude_categories.iter().any(|c| c == category)
+    }
+}
+
+/// Paths of the project named by the arguments or environment of an action, absolute or relative
+/// to its working directory, for which `is_declared` is false. Arguments are considered whole,
+/// except for `--flag=value` and `NAME=value`, of which the value is. Paths in `buck-out` are
+/// skipped, since those are written by buck2 itself. The paths returned might not exist.
+pub fn named_undeclared_paths<'a>(
+    args: impl IntoIterator<Item = &'a str>,
+    root: &ProjectRoot,
+    working_directory: &ProjectRelativePath,
+    is_declared: impl Fn(&ProjectRelativePath) -> bool,
+) -> Vec<ProjectRelativePathBuf> {
+    let mut paths = Vec::new();
+    for arg in args {
+        let candidate = match arg.split_once('=') {
+            Some((_, value)) => value,
+            None if arg.starts_with('-') => continue,
+            None => arg,
+        };
+        let path = if let Ok(path) = AbsNormPath::new(candidate) {
+            match root.relativize(path) {
+                Ok(path) => path.into_owned(),
+                // Outside of the project, e.g. a system tool.
+                Err(_) => continue,
+            }
+        } else if let Ok(path) = ForwardRelativePath::new(candidate) {
+            working_directory.join(path)
+        } else {
+            continue;
+        };
+        if path.is_empty()
+            || path.starts_with(InvocationPaths::buck_out_dir_prefix())
+            || is_declared(&path)
+        {
+            continue;
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::legacy_configs::testing::legacy_buck_config_from_entries;
+    use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_policy() -> anyhow::Result<()> {
+        let config = legacy_buck_config_from_entries([
+            ("buck2_cache_upload", "local_results", "true"),
+            ("buck2_cache_upload", "exclude_categories", "genrule"),
+            ("buck2_cache_upload", "executors", "hybrid"),
+            ("buck2_cache_upload", "platforms", "OSFamily=Linux"),
+        ])?;
+        let policy = CacheUploadPolicy::from_config(&config)?;
+
+        let linux = SortedMap::from_iter([("OSFamily".to_owned(), "Linux".to_owned())]);
+        let mac = SortedMap::from_iter([("OSFamily".to_owned(), "Darwin".to_owned())]);
+        assert!(policy.applies_to_executor(CacheUploadExecutor::Hybrid, &linux));
+        assert!(!policy.applies_to_executor(CacheUploadExecutor::Hybrid, &mac));
+        assert!(!policy.applies_to_executor(CacheUploadExecutor::Local, &linux));
+
+        assert!(policy.applies_to_category("cxx_compile"));
+        assert!(!policy.applies_to_category("genrule"));
+        assert!(policy.reject_absolute_paths);
+        assert!(policy.reject_undeclared_inputs);
+        assert!(!policy.allow_local_only);
+
+        let disabled = CacheUploadPolicy::default();
+        assert!(!disabled.applies_to_executor(CacheUploadExecutor::Local, &linux));
+        Ok(())
+    }
+
+    #[test]
+    fn test_named_undeclared_paths() -> anyhow::Result<()> {
+        let root = ProjectRoot::new_unchecked(AbsNormPathBuf::try_from(if cfg!(windows) {
+            "C:\\repo".to_owned()
+        } else {
+            "/repo".to_owned()
+        })?);
+        let abs = |path: &str| {
+            root.resolve(ProjectRelativePath::unchecked_new(path))
+                .to_string()
+        };
+        let args = vec![
+            "/usr/bin/cc".to_owned(),
+            "-c".to_owned(),
+            "declared.c".to_owned(),
+            format!("--include={}", abs("include/undeclared.h")),
+            "-o".to_owned(),
+            abs("buck-out/v2/gen/out.o"),
+            "../escapes.c".to_owned(),
+            "PATH=/usr/bin".to_owned(),
+            "undeclared.c".to_owned(),
+        ];
+
+        let paths = named_undeclared_paths(
+            args.iter().map(|a| a.as_str()),
+            &root,
+            ProjectRelativePath::unchecked_new("src"),
+            |path| path.as_str() == "src/declared.c",
+        );
+        assert_eq!(
+            vec![
+                ProjectRelativePathBuf::unchecked_new("include/undeclared.h".to_owned()),
+                ProjectRelativePathBuf::unchecked_new("src/undeclared.c".to_owned()),
+            ],
+            paths
+        );
+        Ok(())
+    }
+}
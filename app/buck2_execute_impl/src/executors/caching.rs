@@ -8,6 +8,8 @@
  */
 
 use std::ops::ControlFlow;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::SystemTime;
 
@@ -15,9 +17,13 @@ use anyhow::Context as _;
 use async_trait::async_trait;
 use buck2_common::executor_config::CacheUploadBehavior;
 use buck2_common::executor_config::RemoteExecutorUseCase;
+use buck2_core::directory::find;
 use buck2_core::directory::DirectoryEntry;
+use buck2_core::directory::DirectoryIterator;
+use buck2_core::directory::FingerprintedDirectory;
 use buck2_core::env_helper::EnvHelper;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
+use buck2_core::fs::fs_util;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_events::dispatch::span_async;
 use buck2_execute::digest::CasDigestToReExt;
@@ -57,12 +63,40 @@ use remote_execution::TStatus;
 use remote_execution::TTimestamp;
 use tracing::info;
 
+use crate::executors::cache_upload_policy::named_undeclared_paths;
+use crate::executors::cache_upload_policy::CacheUploadPolicy;
+use crate::executors::output_validation::find_file_containing;
+use crate::executors::output_validation::OutputValidationPolicy;
 use crate::re::download::download_action_results;
 use crate::re::download::DownloadResult;
+use crate::re::download::OutputValidation;
 
 // Whether to throw errors when cache uploads fail (primarily for tests).
 static ERROR_ON_CACHE_UPLOAD: EnvHelper<bool> = EnvHelper::new("BUCK2_TEST_ERROR_ON_CACHE_UPLOAD");
 
+static CACHE_UPLOADS_SUCCEEDED: AtomicU64 = AtomicU64::new(0);
+static CACHE_UPLOADS_REJECTED: AtomicU64 = AtomicU64::new(0);
+static CACHE_UPLOADS_FAILED: AtomicU64 = AtomicU64::new(0);
+static CACHE_UPLOAD_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Cache uploads attempted since the daemon started.
+pub struct CacheUploadStats {
+    pub succeeded: u64,
+    pub rejected: u64,
+    pub failed: u64,
+    /// Output bytes of successful uploads.
+    pub bytes: u64,
+}
+
+pub fn cache_upload_stats() -> CacheUploadStats {
+    CacheUploadStats {
+        succeeded: CACHE_UPLOADS_SUCCEEDED.load(Ordering::Relaxed),
+        rejected: CACHE_UPLOADS_REJECTED.load(Ordering::Relaxed),
+        failed: CACHE_UPLOADS_FAILED.load(Ordering::Relaxed),
+        bytes: CACHE_UPLOAD_BYTES.load(Ordering::Relaxed),
+    }
+}
+
 /// A PreparedCommandExecutor that will check the action cache before executing any actions using the underlying executor.
 pub struct CachingExecutor {
     pub inner: Arc<dyn PreparedCommandExecutor>,
@@ -73,6 +107,9 @@ pub struct CachingExecutor {
     pub upload_all_actions: bool,
     pub knobs: ExecutorGlobalKnobs,
     pub cache_upload_behavior: CacheUploadBehavior,
+    /// Set if the policy allows uploading local results of this executor.
+    pub cache_upload_policy: Option<Arc<CacheUploadPolicy>>,
+    pub output_validation_policy: Arc<OutputValidationPolicy>,
 }
 
 impl CachingExecutor {
@@ -80,6 +117,7 @@ impl CachingExecutor {
         &self,
         manager: CommandExecutionManager,
         request: &CommandExecutionRequest,
+        target: &dyn CommandExecutionTarget,
         action_digest: &ActionDigest,
         action_blobs: &ActionBlobs,
         digest_config: DigestConfig,
@@ -130,6 +168,7 @@ impl CachingExecutor {
             Ok(None) => return ControlFlow::Continue(manager),
         };
 
+        let action_name = target.as_proto_action_name();
         let res = download_action_results(
             request,
             &*self.materializer,
@@ -146,6 +185,11 @@ impl CachingExecutor {
             request.outputs(),
             action_digest,
             &response,
+            OutputValidation {
+                policy: &self.output_validation_policy,
+                category: &action_name.category,
+                fs: self.artifact_fs.fs(),
+            },
             cancellations,
         )
         .await;
@@ -157,8 +201,8 @@ impl CachingExecutor {
 
     /// Upload an action result to the RE action cache, assuming conditions for the upload are met:
     /// the action must have been successful and must have run locally (not much point in caching
-    /// something that ran on RE and is already cached), and cache uploads must be enabled, both
-    /// for this executor and this particular action.
+    /// something that ran on RE and is already cached), and cache uploads must be enabled, either
+    /// for this executor and this particular action, or by the cache upload policy.
     async fn maybe_perform_cache_upload(
         &self,
         request: &CommandExecutionRequest,
@@ -167,15 +211,19 @@ impl CachingExecutor {
         result: &CommandExecutionResult,
         digest_config: DigestConfig,
     ) -> anyhow::Result<Option<CacheUploadOutcome>> {
-        let max_bytes = match self.cache_upload_behavior {
-            CacheUploadBehavior::Enabled { max_bytes } => max_bytes,
-            CacheUploadBehavior::Disabled => return Ok(None),
+        // Actions which opt in are trusted to be hermetic, the policy applies to everything else.
+        let (max_bytes, policy) = match (self.cache_upload_behavior, &self.cache_upload_policy) {
+            (CacheUploadBehavior::Enabled { max_bytes }, _) if request.allow_cache_upload() => {
+                (max_bytes, None)
+            }
+            (_, Some(policy))
+                if policy.applies_to_category(&target.as_proto_action_name().category) =>
+            {
+                (policy.max_bytes, Some(policy))
+            }
+            _ => return Ok(None),
         };
 
-        if !request.allow_cache_upload() {
-            return Ok(None);
-        }
-
         let output_bytes = result.calc_output_size_bytes();
 
         match &result.report.status {
@@ -212,6 +260,12 @@ impl CachingExecutor {
                         }
                     }
 
+                    if let Some(policy) = policy {
+                        if let Some(reason) = self.check_hermetic(policy, request, result).await? {
+                            return Ok(CacheUploadOutcome::Rejected(reason));
+                        }
+                    }
+
                     self.perform_cache_upload(
                         digest,
                         result,
@@ -252,9 +306,100 @@ impl CachingExecutor {
                 )
             },
         )
-        .await?;
+        .await;
+
+        match &outcome {
+            Ok(CacheUploadOutcome::Success) => {
+                CACHE_UPLOADS_SUCCEEDED.fetch_add(1, Ordering::Relaxed);
+                CACHE_UPLOAD_BYTES.fetch_add(output_bytes, Ordering::Relaxed);
+            }
+            Ok(CacheUploadOutcome::Rejected(..)) => {
+                CACHE_UPLOADS_REJECTED.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(..) => {
+                CACHE_UPLOADS_FAILED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        Ok(Some(outcome?))
+    }
+
+    /// Look for signs that a local result depends on the machine it ran on. Local actions are not
+    /// sandboxed, so undeclared inputs are only found if the action names them.
+    async fn check_hermetic(
+        &self,
+        policy: &CacheUploadPolicy,
+        request: &CommandExecutionRequest,
+        result: &CommandExecutionResult,
+    ) -> anyhow::Result<Option<CacheUploadRejectionReason>> {
+        if !policy.allow_local_only && request.executor_preference().requires_local() {
+            return Ok(Some(CacheUploadRejectionReason::LocalOnly));
+        }
+
+        if policy.reject_absolute_paths {
+            let fs = self.artifact_fs.fs();
+            let mut files = Vec::new();
+            for (output, value) in result.resolve_outputs(&self.artifact_fs) {
+                let path = fs.resolve(output.path());
+                match value.entry().as_ref() {
+                    DirectoryEntry::Leaf(ActionDirectoryMember::File(..)) => files.push(path),
+                    DirectoryEntry::Dir(d) => {
+                        let mut walk = d.fingerprinted_unordered_walk();
+                        while let Some((p, entry)) = walk.next() {
+                            if let DirectoryEntry::Leaf(ActionDirectoryMember::File(..)) = entry {
+                                files.push(path.join(p.get()));
+                            }
+                        }
+                    }
+                    DirectoryEntry::Leaf(..) => {}
+                }
+            }
+
+            let root = fs.root().as_path().to_string_lossy().into_owned();
+            let found = tokio::task::spawn_blocking(move || find_file_containing(&files, &root))
+                .await
+                .context("Scanning outputs for absolute paths")??;
+            if found.is_some() {
+                return Ok(Some(CacheUploadRejectionReason::AbsolutePathInOutput));
+            }
+        }
+
+        if policy.reject_undeclared_inputs {
+            let fs = self.artifact_fs.fs();
+            let inputs = request.paths().input_directory();
+            let outputs = request.paths().output_paths();
+            let args = request
+                .all_args()
+                .chain(request.env().values())
+                .map(|arg| arg.as_str());
+            let named = named_undeclared_paths(
+                args,
+                fs,
+                request
+                    .working_directory()
+                    .unwrap_or_else(ProjectRelativePath::empty),
+                |path| {
+                    // Traversing a leaf means the path is inside a declared symlink.
+                    find(inputs, path.iter()).map_or(true, |entry| entry.is_some())
+                        || outputs.iter().any(|(output, _)| path.starts_with(output))
+                },
+            );
+            if !named.is_empty() {
+                let named: Vec<_> = named.iter().map(|path| fs.resolve(path)).collect();
+                let exists = tokio::task::spawn_blocking(move || {
+                    named
+                        .iter()
+                        .any(|path| fs_util::symlink_metadata_if_available(path).is_some())
+                })
+                .await
+                .context("Checking for undeclared inputs")?;
+                if exists {
+                    return Ok(Some(CacheUploadRejectionReason::UndeclaredInput));
+                }
+            }
+        }
 
-        Ok(Some(outcome))
+        Ok(None)
     }
 
     async fn perform_cache_upload(
@@ -441,6 +586,7 @@ impl PreparedCommandExecutor for CachingExecutor {
             .try_action_cache_fetch(
                 manager,
                 command.request,
+                command.target,
                 &command.prepared_action.action,
                 &command.prepared_action.blobs,
                 command.digest_config,
@@ -520,6 +666,12 @@ enum CacheUploadRejectionReason {
     SymlinkOutput,
     #[display(fmt = "OutputExceedsLimit({})", max_bytes)]
     OutputExceedsLimit { max_bytes: u64 },
+    #[display(fmt = "LocalOnly")]
+    LocalOnly,
+    #[display(fmt = "AbsolutePathInOutput")]
+    AbsolutePathInOutput,
+    #[display(fmt = "UndeclaredInput")]
+    UndeclaredInput,
 }
 
 fn systemtime_to_ttimestamp(time: SystemTime) -> anyhow::Result<TTimestamp> {
@@ -0,0 +1,386 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Support for `--check-determinism`: local actions are executed twice, and the outputs of both
+//! executions are compared.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+
+use anyhow::Context;
+use buck2_core::directory::unordered_entry_walk;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::directory::ActionDirectoryMember;
+
+/// How many bytes around the first difference are shown in a summary.
+const CONTEXT_BYTES: usize = 8;
+
+/// Files are compared in chunks of this size, so large outputs are not read into memory.
+const COMPARE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Which actions are executed twice to check that their outputs are identical.
+#[derive(Debug, Default)]
+pub struct DeterminismCheck {
+    /// Action categories to check, or all if empty.
+    categories: Vec<String>,
+}
+
+impl DeterminismCheck {
+    pub fn new(categories: Vec<String>) -> Self {
+        Self { categories }
+    }
+
+    pub fn applies_to_category(&self, category: &str) -> bool {
+        self.categories.is_empty() || self.categories.iter().any(|c| c == category)
+    }
+}
+
+/// An output which differs between two executions. `None` means it was not produced.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct OutputDifference {
+    pub(crate) path: ProjectRelativePathBuf,
+    pub(crate) first: Option<ActionDirectoryMember>,
+    pub(crate) second: Option<ActionDirectoryMember>,
+}
+
+/// Move `outputs` to the same paths under `dir`. If moving one of them fails, the outputs that
+/// were already moved are put back, so that they are left as they were found.
+pub(crate) fn move_outputs(
+    fs: &ProjectRoot,
+    outputs: &[ProjectRelativePathBuf],
+    dir: &ProjectRelativePath,
+) -> anyhow::Result<()> {
+    fs.remove_path_recursive(dir)?;
+
+    let mut moved = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        let src = fs.resolve(output);
+        let dest = fs.resolve(&dir.join(output.as_forward_relative_path()));
+        let res = match dest.parent() {
+            Some(parent) => fs_util::create_dir_all(parent),
+            None => Ok(()),
+        }
+        .and_then(|()| fs_util::rename(&src, &dest));
+
+        if let Err(e) = res {
+            for (src, dest) in moved.into_iter().rev() {
+                if let Err(e) = fs_util::rename(&dest, &src) {
+                    tracing::warn!("Error restoring output `{}`: {:#}", src, e);
+                }
+            }
+            return Err(e.context(format!("Error moving output `{}`", output)));
+        }
+        moved.push((src, dest));
+    }
+
+    Ok(())
+}
+
+/// All the files and symlinks in the outputs of a command, by path.
+pub(crate) fn output_members<'a>(
+    outputs: impl IntoIterator<Item = (ProjectRelativePathBuf, &'a ArtifactValue)>,
+) -> BTreeMap<ProjectRelativePathBuf, ActionDirectoryMember> {
+    let mut members = BTreeMap::new();
+    for (output, value) in outputs {
+        let mut walk = unordered_entry_walk(value.entry().as_ref());
+        while let Some((path, entry)) = walk.next() {
+            if let DirectoryEntry::Leaf(member) = entry {
+                members.insert(output.join(path.get()), member.clone());
+            }
+        }
+    }
+    members
+}
+
+pub(crate) fn diff_outputs(
+    mut first: BTreeMap<ProjectRelativePathBuf, ActionDirectoryMember>,
+    mut second: BTreeMap<ProjectRelativePathBuf, ActionDirectoryMember>,
+) -> Vec<OutputDifference> {
+    let paths = first
+        .keys()
+        .chain(second.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let first = first.remove(&path);
+            let second = second.remove(&path);
+            if first == second {
+                None
+            } else {
+                Some(OutputDifference {
+                    path,
+                    first,
+                    second,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Describe how the contents of two versions of a file differ.
+pub(crate) fn summarize_file_difference(
+    first: &AbsNormPath,
+    second: &AbsNormPath,
+) -> anyhow::Result<String> {
+    let open = |path: &AbsNormPath| {
+        std::fs::File::open(path).with_context(|| format!("Error opening `{}`", path))
+    };
+    summarize_difference(open(first)?, open(second)?, COMPARE_CHUNK_SIZE)
+}
+
+fn summarize_difference(
+    mut first: impl Read + Seek,
+    mut second: impl Read + Seek,
+    chunk_size: usize,
+) -> anyhow::Result<String> {
+    let first_len = first.seek(SeekFrom::End(0))?;
+    let second_len = second.seek(SeekFrom::End(0))?;
+    first.rewind()?;
+    second.rewind()?;
+    let common = first_len.min(second_len);
+
+    let mut differing = 0;
+    let mut first_difference = None;
+    let mut first_buf = vec![0; chunk_size.max(1)];
+    let mut second_buf = vec![0; chunk_size.max(1)];
+    let mut offset = 0;
+    while offset < common {
+        let len = (common - offset).min(first_buf.len() as u64) as usize;
+        first.read_exact(&mut first_buf[..len])?;
+        second.read_exact(&mut second_buf[..len])?;
+        for (i, (a, b)) in first_buf[..len].iter().zip(&second_buf[..len]).enumerate() {
+            if a != b {
+                differing += 1;
+                first_difference.get_or_insert(offset + i as u64);
+            }
+        }
+        offset += len as u64;
+    }
+    let first_difference = first_difference.or_else(|| (first_len != second_len).then_some(common));
+
+    let mut summary = String::new();
+    if first_len != second_len {
+        write!(summary, "{} vs {} bytes, ", first_len, second_len)?;
+    }
+    write!(summary, "{} of {} common bytes differ", differing, common)?;
+
+    if let Some(offset) = first_difference {
+        let first = read_context(&mut first, offset)?;
+        let second = read_context(&mut second, offset)?;
+        write!(
+            summary,
+            ", first at offset {} ({} vs {})",
+            offset, first, second
+        )?;
+    }
+
+    Ok(summary)
+}
+
+/// The bytes of `file` starting at `offset`, in hex.
+fn read_context(file: &mut (impl Read + Seek), offset: u64) -> anyhow::Result<String> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(CONTEXT_BYTES);
+    file.take(CONTEXT_BYTES as u64).read_to_end(&mut bytes)?;
+    Ok(hex(&bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "EOF".to_owned();
+    }
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_execute::digest_config::DigestConfig;
+    use buck2_execute::directory::extract_artifact_value;
+    use buck2_execute::directory::insert_file;
+    use buck2_execute::directory::ActionDirectoryBuilder;
+    use buck2_execute::directory::Symlink;
+
+    use super::*;
+
+    fn path(path: &str) -> ProjectRelativePathBuf {
+        ProjectRelativePathBuf::unchecked_new(path.to_owned())
+    }
+
+    fn metadata(content: &str) -> FileMetadata {
+        FileMetadata {
+            digest: TrackedFileDigest::from_content(
+                content.as_bytes(),
+                DigestConfig::testing_default().cas_digest_config(),
+            ),
+            is_executable: false,
+        }
+    }
+
+    fn file(content: &str) -> ActionDirectoryMember {
+        ActionDirectoryMember::File(metadata(content))
+    }
+
+    fn symlink(target: &str) -> ActionDirectoryMember {
+        ActionDirectoryMember::Symlink(Arc::new(Symlink::new(target.into())))
+    }
+
+    fn summarize_bytes_difference(first: &[u8], second: &[u8]) -> String {
+        summarize_difference(Cursor::new(first), Cursor::new(second), COMPARE_CHUNK_SIZE).unwrap()
+    }
+
+    #[test]
+    fn test_applies_to_category() {
+        assert!(DeterminismCheck::default().applies_to_category("cxx_compile"));
+
+        let check = DeterminismCheck::new(vec!["cxx_link".to_owned()]);
+        assert!(check.applies_to_category("cxx_link"));
+        assert!(!check.applies_to_category("cxx_compile"));
+    }
+
+    #[test]
+    fn test_summarize_bytes_difference() {
+        assert_eq!(
+            summarize_bytes_difference(b"abc", b"abc"),
+            "0 of 3 common bytes differ"
+        );
+        assert_eq!(
+            summarize_bytes_difference(b"abcd", b"abxd"),
+            "1 of 4 common bytes differ, first at offset 2 (6364 vs 7864)"
+        );
+        assert_eq!(
+            summarize_bytes_difference(b"ab", b"abc"),
+            "2 vs 3 bytes, 0 of 2 common bytes differ, first at offset 2 (EOF vs 63)"
+        );
+    }
+
+    #[test]
+    fn test_summarize_difference_across_chunks() -> anyhow::Result<()> {
+        let first = b"0123456789abcdefghij";
+        let second = b"0123456789abXdefghiXk";
+        for chunk_size in 1..25 {
+            assert_eq!(
+                summarize_difference(Cursor::new(first), Cursor::new(second), chunk_size)?,
+                "20 vs 21 bytes, 2 of 20 common bytes differ, first at offset 12 \
+                (636465666768696a vs 5864656667686958)",
+                "chunk size {}",
+                chunk_size
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_members() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+        let mut builder = ActionDirectoryBuilder::empty();
+        insert_file(&mut builder, &path("out/dir/a"), metadata("a"))?;
+        insert_file(&mut builder, &path("out/dir/nested/b"), metadata("b"))?;
+        let dir = extract_artifact_value(&builder, &path("out/dir"), digest_config)?.unwrap();
+        let single = ArtifactValue::file(metadata("c"));
+
+        let members = output_members([(path("out/dir"), &dir), (path("out/file"), &single)]);
+        assert_eq!(
+            vec![
+                (path("out/dir/a"), file("a")),
+                (path("out/dir/nested/b"), file("b")),
+                (path("out/file"), file("c")),
+            ],
+            members.into_iter().collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_outputs() {
+        let first = BTreeMap::from([
+            (path("same"), file("same")),
+            (path("changed"), file("first")),
+            (path("only_first"), file("first")),
+            (path("link"), symlink("a")),
+        ]);
+        let second = BTreeMap::from([
+            (path("same"), file("same")),
+            (path("changed"), file("second")),
+            (path("only_second"), file("second")),
+            (path("link"), symlink("b")),
+        ]);
+
+        assert_eq!(
+            vec![
+                OutputDifference {
+                    path: path("changed"),
+                    first: Some(file("first")),
+                    second: Some(file("second")),
+                },
+                OutputDifference {
+                    path: path("link"),
+                    first: Some(symlink("a")),
+                    second: Some(symlink("b")),
+                },
+                OutputDifference {
+                    path: path("only_first"),
+                    first: Some(file("first")),
+                    second: None,
+                },
+                OutputDifference {
+                    path: path("only_second"),
+                    first: None,
+                    second: Some(file("second")),
+                },
+            ],
+            diff_outputs(first.clone(), second.clone())
+        );
+        assert_eq!(
+            Vec::<OutputDifference>::new(),
+            diff_outputs(first.clone(), first)
+        );
+    }
+
+    #[test]
+    fn test_move_outputs_restores_on_failure() -> anyhow::Result<()> {
+        let temp = ProjectRootTemp::new()?;
+        let fs = temp.path();
+        temp.write_file("out/a", "a");
+        temp.write_file("out/b", "b");
+        let dir = path("determinism/digest");
+
+        move_outputs(fs, &[path("out/a"), path("out/b")], &dir)?;
+        assert!(!fs.resolve(&path("out/a")).exists());
+        assert_eq!(
+            "b",
+            fs_util::read_to_string(fs.resolve(&path("determinism/digest/out/b")))?
+        );
+
+        // `out/missing` does not exist, so `out/c` must be put back.
+        temp.write_file("out/c", "c");
+        assert!(move_outputs(fs, &[path("out/c"), path("out/missing")], &dir).is_err());
+        assert_eq!("c", fs_util::read_to_string(fs.resolve(&path("out/c")))?);
+        assert!(!fs.resolve(&path("determinism/digest/out/c")).exists());
+        Ok(())
+    }
+}
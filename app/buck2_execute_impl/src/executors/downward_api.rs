@@ -0,0 +1,264 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Handling of the records local actions write to their downward api file (see
+//! [`buck2_downward_api::records`]).
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::time::Duration;
+
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_downward_api::records::DownwardApiRecord;
+use buck2_downward_api::Diagnostic;
+use buck2_downward_api::DiagnosticSeverity;
+use buck2_events::dispatch::EventDispatcher;
+use buck2_events::dispatch::Span;
+use dupe::Dupe;
+use futures::future::select;
+use futures::future::Either;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
+use tokio::time::MissedTickBehavior;
+
+/// Name of the downward api file, in the scratch directory of the action.
+pub(crate) const DOWNWARD_API_FILE_NAME: &str = "buck2_downward_api.jsonl";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+pub(crate) struct DownwardApiFileReader {
+    path: AbsNormPathBuf,
+    dispatcher: EventDispatcher,
+    /// How much of the file we already consumed.
+    offset: u64,
+    /// Trailing bytes not yet terminated by a newline.
+    partial: Vec<u8>,
+    /// The span for the latest progress record.
+    progress: Option<Span>,
+    /// Open custom spans, innermost last.
+    spans: Vec<(String, Span)>,
+    diagnostics: Vec<buck2_data::ActionDiagnostic>,
+}
+
+impl DownwardApiFileReader {
+    pub(crate) fn new(path: AbsNormPathBuf, dispatcher: EventDispatcher) -> Self {
+        Self {
+            path,
+            dispatcher,
+            offset: 0,
+            partial: Vec::new(),
+            progress: None,
+            spans: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Run `fut`, handling records as the command writes them. Spans are created under the
+    /// current span, so this should be called within the span of the command execution.
+    pub(crate) async fn run<R>(
+        mut self,
+        fut: impl Future<Output = R>,
+    ) -> (R, Vec<buck2_data::ActionDiagnostic>) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        futures::pin_mut!(fut);
+        let res = loop {
+            let tick = interval.tick();
+            futures::pin_mut!(tick);
+            match select(fut.as_mut(), tick).await {
+                Either::Left((res, _)) => break res,
+                Either::Right(..) => self.poll().await,
+            }
+        };
+
+        (res, self.finish().await)
+    }
+
+    /// Reads what the command appended since the last poll. This uses `tokio::fs` so that the
+    /// reads happen on the blocking pool rather than on the executor.
+    async fn read_new_bytes(&self) -> std::io::Result<Vec<u8>> {
+        let mut file = tokio::fs::File::open(&self.path).await?;
+        file.seek(SeekFrom::Start(self.offset)).await?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).await?;
+        Ok(bytes)
+    }
+
+    async fn poll(&mut self) {
+        let bytes = match self.read_new_bytes().await {
+            Ok(bytes) => bytes,
+            // The command didn't write anything yet.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                tracing::debug!("Error reading `{}`: {}", self.path, e);
+                return;
+            }
+        };
+
+        self.offset += bytes.len() as u64;
+        self.partial.extend(bytes);
+        let complete = match self.partial.iter().rposition(|b| *b == b'\n') {
+            Some(i) => i + 1,
+            None => return,
+        };
+        let lines = self.partial.drain(..complete).collect::<Vec<_>>();
+        for line in String::from_utf8_lossy(&lines).lines() {
+            self.handle_line(line);
+        }
+    }
+
+    fn handle_line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let record = match DownwardApiRecord::parse(line) {
+            Ok(record) => record,
+            Err(e) => {
+                tracing::warn!("{:#}", e);
+                return;
+            }
+        };
+
+        match record {
+            DownwardApiRecord::Progress(progress) => {
+                if let Some(span) = self.progress.take() {
+                    span.end(buck2_data::DownwardApiSpanEnd {});
+                }
+                self.progress = Some(Span::start(
+                    self.dispatcher.dupe(),
+                    buck2_data::DownwardApiSpanStart {
+                        name: progress.step,
+                        percent: progress.percent.map(|p| p.min(100)),
+                    },
+                ));
+            }
+            DownwardApiRecord::SpanStart { name } => {
+                let span = Span::start(
+                    self.dispatcher.dupe(),
+                    buck2_data::DownwardApiSpanStart {
+                        name: name.clone(),
+                        percent: None,
+                    },
+                );
+                self.spans.push((name, span));
+            }
+            DownwardApiRecord::SpanEnd { name } => {
+                match self.spans.iter().rposition(|(n, _)| *n == name) {
+                    Some(i) => {
+                        // Also close any inner span that wasn't closed.
+                        for (_, span) in self.spans.drain(i..).rev() {
+                            span.end(buck2_data::DownwardApiSpanEnd {});
+                        }
+                    }
+                    None => tracing::warn!("Downward api span `{}` ended but not started", name),
+                }
+            }
+            DownwardApiRecord::Diagnostic(diagnostic) => {
+                self.dispatcher.console_message(diagnostic.to_string());
+                self.diagnostics.push(diagnostic_to_proto(diagnostic));
+            }
+        }
+    }
+
+    /// Handle what's left in the file and close all spans.
+    async fn finish(mut self) -> Vec<buck2_data::ActionDiagnostic> {
+        self.poll().await;
+        if !self.partial.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.partial)).into_owned();
+            self.handle_line(&line);
+        }
+
+        for (_, span) in self.spans.drain(..).rev() {
+            span.end(buck2_data::DownwardApiSpanEnd {});
+        }
+        if let Some(span) = self.progress.take() {
+            span.end(buck2_data::DownwardApiSpanEnd {});
+        }
+
+        self.diagnostics
+    }
+}
+
+fn diagnostic_to_proto(diagnostic: Diagnostic) -> buck2_data::ActionDiagnostic {
+    let Diagnostic {
+        file,
+        line,
+        column,
+        severity,
+        message,
+    } = diagnostic;
+
+    let severity = match severity {
+        DiagnosticSeverity::Info => buck2_data::DiagnosticSeverity::Info,
+        DiagnosticSeverity::Warning => buck2_data::DiagnosticSeverity::Warning,
+        DiagnosticSeverity::Error => buck2_data::DiagnosticSeverity::Error,
+    };
+
+    buck2_data::ActionDiagnostic {
+        file,
+        line,
+        column,
+        severity: severity as i32,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::fs::fs_util;
+    use buck2_events::dispatch::EventDispatcher;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reader() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let path = AbsNormPathBuf::new(tempdir.path().join(DOWNWARD_API_FILE_NAME))?;
+
+        let mut reader = DownwardApiFileReader::new(path.clone(), EventDispatcher::null());
+        reader.poll().await;
+
+        fs_util::write(
+            &path,
+            concat!(
+                r#"{"type": "span_start", "name": "outer"}"#,
+                "\n",
+                r#"{"type": "span_start", "name": "inner"}"#,
+                "\n",
+                r#"{"type": "diagnostic", "file": "a.c", "severity": "warning", "message": "w"}"#,
+                "\n",
+                r#"{"type": "span_end", "na"#,
+            ),
+        )?;
+        reader.poll().await;
+        assert_eq!(reader.spans.len(), 2);
+        assert_eq!(reader.diagnostics.len(), 1);
+
+        let mut contents = fs_util::read_to_string(&path)?;
+        contents.push_str("me\": \"outer\"}\n");
+        fs_util::write(&path, contents)?;
+        reader.poll().await;
+        assert!(reader.spans.is_empty());
+
+        let diagnostics = reader.finish().await;
+        assert_eq!(
+            diagnostics,
+            vec![buck2_data::ActionDiagnostic {
+                file: "a.c".to_owned(),
+                line: None,
+                column: None,
+                severity: buck2_data::DiagnosticSeverity::Warning as i32,
+                message: "w".to_owned(),
+            }]
+        );
+        Ok(())
+    }
+}
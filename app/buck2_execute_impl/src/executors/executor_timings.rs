@@ -0,0 +1,679 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Historical execution times of actions, used by the hybrid executor to send each action to the
+//! executor which is expected to finish it first instead of racing both.
+//!
+//! Durations are tracked per action category and per `(category, identifier)`, as exponentially
+//! weighted moving averages. Remote durations exclude RE queueing time, which is tracked
+//! separately across all actions since it depends on the load of RE rather than on the action.
+//!
+//! The executor which loses a race is cancelled, so its duration is unknown. For actions which
+//! always win races on the same executor, the share of races won by each executor is tracked
+//! instead.
+//!
+//! Only the most recently used keys are kept, so that the history doesn't grow without bound.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use buck2_common::legacy_configs::LegacyBuckConfig;
+use buck2_common::sqlite::KeyValueSqliteTable;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::file_name::FileName;
+use buck2_execute::execute::blocking::BlockingExecutor;
+use dupe::Dupe;
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+use parking_lot::Mutex;
+use rusqlite::Connection;
+
+/// Bump this when the schema of the timings table changes.
+const DB_SCHEMA_VERSION: u64 = 2;
+
+const TIMINGS_TABLE_NAME: &str = "executor_timings";
+
+/// Weight of a new sample once an average has enough samples.
+const EWMA_ALPHA: f64 = 0.2;
+
+/// Number of updated averages after which they are written to disk.
+const FLUSH_BATCH_SIZE: usize = 256;
+
+/// Number of keys kept, the least recently used ones are dropped beyond that.
+const MAX_KEYS: usize = 100_000;
+
+/// Configuration of the executor choice, from the `[buck2]` section of the root buckconfig.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExecutorTimingsConfig {
+    /// Number of completions on each executor before an action is routed based on history.
+    pub min_samples: u32,
+    /// How much faster, as a fraction, an executor must be predicted to be to be used alone.
+    pub margin: f64,
+    /// Race both executors once every this many predictions of an action, so that the executor
+    /// which is not predicted gets new samples if it became faster. Zero disables this.
+    pub explore_every: u32,
+}
+
+impl ExecutorTimingsConfig {
+    /// Returns `None` if the executor choice is disabled (which is the default).
+    pub fn from_config(config: &LegacyBuckConfig) -> anyhow::Result<Option<Self>> {
+        if !config
+            .parse("buck2", "hybrid_executor_learned_choice")?
+            .unwrap_or(false)
+        {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            min_samples: config
+                .parse("buck2", "hybrid_executor_learned_choice_min_samples")?
+                .unwrap_or(5),
+            margin: config
+                .parse("buck2", "hybrid_executor_learned_choice_margin")?
+                .unwrap_or(0.5),
+            explore_every: config
+                .parse("buck2", "hybrid_executor_learned_choice_explore_every")?
+                .unwrap_or(20),
+        }))
+    }
+}
+
+/// Which executor an action should be sent to.
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq)]
+pub enum ExecutorPrediction {
+    Local,
+    Remote,
+    /// Not enough history, or both executors are expected to take about as long.
+    Uncertain,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Ewma {
+    /// In milliseconds for durations.
+    mean: f64,
+    samples: u32,
+}
+
+impl Ewma {
+    fn add(&mut self, duration: Duration) {
+        self.add_value(duration.as_secs_f64() * 1000.0);
+    }
+
+    fn add_value(&mut self, value: f64) {
+        self.samples = self.samples.saturating_add(1);
+        // Plain average for the first samples, so the first one doesn't dominate.
+        let alpha = EWMA_ALPHA.max(1.0 / self.samples as f64);
+        self.mean += alpha * (value - self.mean);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ActionTimings {
+    local: Ewma,
+    /// Excludes queueing time.
+    remote: Ewma,
+    /// Share of the races won by the local executor.
+    local_wins: Ewma,
+}
+
+#[derive(Default)]
+struct ExecutorTimingsState {
+    /// Least recently used first.
+    actions: LinkedHashMap<String, ActionTimings>,
+    /// Keys updated since the last flush.
+    dirty: HashSet<String>,
+    /// Keys dropped since the last flush.
+    evicted: HashSet<String>,
+    /// Not persisted: it only reflects the current load of RE.
+    re_queue: Ewma,
+    /// Number of confident predictions per key, to decide when to explore.
+    predictions: HashMap<String, u32>,
+}
+
+fn category_key(category: &str) -> String {
+    category.to_owned()
+}
+
+fn identifier_key(category: &str, identifier: &str) -> String {
+    format!("{}\t{}", category, identifier)
+}
+
+/// Historical local and remote execution times of actions.
+pub struct ExecutorTimings {
+    config: ExecutorTimingsConfig,
+    state: Mutex<ExecutorTimingsState>,
+    table: Option<ExecutorTimingsSqliteTable>,
+}
+
+impl ExecutorTimings {
+    /// Timings which are not persisted.
+    pub fn new(config: ExecutorTimingsConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(ExecutorTimingsState::default()),
+            table: None,
+        }
+    }
+
+    /// Load timings from the db in `dir`. If the db cannot be read, or was written with a
+    /// different schema, it is recreated empty.
+    pub async fn initialize(
+        dir: AbsNormPathBuf,
+        config: ExecutorTimingsConfig,
+        io_executor: Arc<dyn BlockingExecutor>,
+    ) -> anyhow::Result<Self> {
+        io_executor
+            .execute_io_inline(|| Self::initialize_impl(&dir, config))
+            .await
+    }
+
+    fn initialize_impl(dir: &AbsNormPath, config: ExecutorTimingsConfig) -> anyhow::Result<Self> {
+        let db_path = dir.join(FileName::unchecked_new("db.sqlite"));
+        let versions =
+            HashMap::from([("schema_version".to_owned(), DB_SCHEMA_VERSION.to_string())]);
+
+        let loaded: anyhow::Result<_> = try {
+            if !db_path.exists() {
+                Err(anyhow::anyhow!("`{}` does not exist", db_path))?
+            }
+            let (versions_table, table) = ExecutorTimingsSqliteTable::open(&db_path)?;
+            if versions_table.read_all()? != versions {
+                Err(anyhow::anyhow!("Schema version mismatch"))?
+            }
+            let actions = table.read_all()?;
+            (table, actions)
+        };
+
+        let (table, actions) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                tracing::debug!("Recreating executor timings db: {:#}", e);
+                if dir.exists() {
+                    fs_util::remove_dir_all(dir)?;
+                }
+                fs_util::create_dir_all(dir)?;
+                let (versions_table, table) = ExecutorTimingsSqliteTable::open(&db_path)?;
+                versions_table.create_table()?;
+                versions_table.insert_all(versions)?;
+                table.create_table()?;
+                (table, LinkedHashMap::new())
+            }
+        };
+
+        Ok(Self {
+            config,
+            state: Mutex::new(ExecutorTimingsState {
+                actions,
+                ..Default::default()
+            }),
+            table: Some(table),
+        })
+    }
+
+    /// Predict which executor will finish an action first. History of the action's identifier
+    /// is used if there is enough of it, otherwise that of its category.
+    ///
+    /// Durations are compared if the action completed on both executors often enough. Otherwise,
+    /// if it raced often enough, an executor is predicted if it won races `1 + margin` times as
+    /// often as the other one.
+    ///
+    /// An action sent to one executor only gets samples from that executor, so a prediction
+    /// would never change if the other executor became faster. To notice that, one in
+    /// `explore_every` predictions of each action is `Uncertain`, so that both executors race.
+    pub fn predict(&self, category: &str, identifier: &str) -> ExecutorPrediction {
+        let mut state = self.state.lock();
+        let min_samples = self.config.min_samples.max(1);
+        let factor = 1.0 + self.config.margin;
+        let re_queue = state.re_queue.mean;
+
+        let mut predicted = None;
+        for key in [identifier_key(category, identifier), category_key(category)] {
+            let timings = match state.actions.get_refresh(&key) {
+                Some(timings) => *timings,
+                None => continue,
+            };
+            let (local, remote) =
+                if timings.local.samples >= min_samples && timings.remote.samples >= min_samples {
+                    // Durations, lower is better.
+                    (timings.local.mean, timings.remote.mean + re_queue)
+                } else if timings.local_wins.samples >= min_samples {
+                    // Shares of races lost, lower is better.
+                    (1.0 - timings.local_wins.mean, timings.local_wins.mean)
+                } else {
+                    continue;
+                };
+            predicted = Some((local, remote, key));
+            break;
+        }
+        let (local, remote, key) = match predicted {
+            Some(predicted) => predicted,
+            None => return ExecutorPrediction::Uncertain,
+        };
+
+        let prediction = if local * factor <= remote {
+            ExecutorPrediction::Local
+        } else if remote * factor <= local {
+            ExecutorPrediction::Remote
+        } else {
+            return ExecutorPrediction::Uncertain;
+        };
+
+        if self.config.explore_every > 0 {
+            let count = state.predictions.entry(key).or_default();
+            *count += 1;
+            if *count >= self.config.explore_every {
+                *count = 0;
+                return ExecutorPrediction::Uncertain;
+            }
+        }
+        prediction
+    }
+
+    pub fn record_local(self: &Arc<Self>, category: &str, identifier: &str, duration: Duration) {
+        self.record(category, identifier, |t| t.local.add(duration));
+    }
+
+    /// `duration` is the total duration, including `queue` time.
+    pub fn record_remote(
+        self: &Arc<Self>,
+        category: &str,
+        identifier: &str,
+        duration: Duration,
+        queue: Duration,
+    ) {
+        self.state.lock().re_queue.add(queue);
+        let execution = duration.saturating_sub(queue);
+        self.record(category, identifier, |t| t.remote.add(execution));
+    }
+
+    /// Record which executor won a race between both executors. The duration of the other one
+    /// is unknown, since it was cancelled.
+    pub fn record_race(self: &Arc<Self>, category: &str, identifier: &str, local_won: bool) {
+        let value = if local_won { 1.0 } else { 0.0 };
+        self.record(category, identifier, |t| t.local_wins.add_value(value));
+    }
+
+    fn record(self: &Arc<Self>, category: &str, identifier: &str, f: impl Fn(&mut ActionTimings)) {
+        let needs_flush = {
+            let mut state = self.state.lock();
+            for key in [identifier_key(category, identifier), category_key(category)] {
+                // Re-inserting moves the key to the most recently used end.
+                let mut timings = state.actions.remove(&key).unwrap_or_default();
+                f(&mut timings);
+                state.actions.insert(key.clone(), timings);
+                state.evicted.remove(&key);
+                state.dirty.insert(key);
+            }
+            while state.actions.len() > MAX_KEYS {
+                if let Some((key, _)) = state.actions.pop_front() {
+                    state.dirty.remove(&key);
+                    state.predictions.remove(&key);
+                    state.evicted.insert(key);
+                }
+            }
+            self.table.is_some() && state.dirty.len() + state.evicted.len() >= FLUSH_BATCH_SIZE
+        };
+
+        if needs_flush && tokio::runtime::Handle::try_current().is_ok() {
+            let this = self.dupe();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = this.flush() {
+                    tracing::warn!("Error writing executor timings: {:#}", e);
+                }
+            });
+        }
+    }
+
+    /// Write the averages updated since the last flush to disk, and delete the dropped ones.
+    pub fn flush(&self) -> anyhow::Result<()> {
+        let table = match &self.table {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+        let (updated, evicted): (Vec<_>, Vec<_>) = {
+            let mut state = self.state.lock();
+            let dirty = mem::take(&mut state.dirty);
+            let updated = dirty
+                .into_iter()
+                .filter_map(|key| {
+                    let timings = *state.actions.get(&key)?;
+                    Some((key, timings))
+                })
+                .collect();
+            (updated, mem::take(&mut state.evicted).into_iter().collect())
+        };
+        table.delete_all(&evicted)?;
+        table.insert_all(&updated)
+    }
+}
+
+impl Drop for ExecutorTimings {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!("Error writing executor timings: {:#}", e);
+        }
+    }
+}
+
+struct ExecutorTimingsSqliteTable {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl ExecutorTimingsSqliteTable {
+    /// Returns the versions table and the timings table.
+    fn open(path: &AbsNormPath) -> anyhow::Result<(KeyValueSqliteTable, Self)> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("Error opening executor timings db `{}`", path))?;
+        if cfg!(unix) {
+            connection.pragma_update(None, "journal_mode", "WAL")?;
+        }
+        // Losing the timings is harmless, so don't pay for an `fsync`.
+        connection.pragma_update(None, "synchronous", "OFF")?;
+
+        let connection = Arc::new(Mutex::new(connection));
+        Ok((
+            KeyValueSqliteTable::new("versions".to_owned(), connection.dupe()),
+            Self { connection },
+        ))
+    }
+
+    fn create_table(&self) -> anyhow::Result<()> {
+        let sql = format!(
+            "CREATE TABLE {} (
+                key             TEXT PRIMARY KEY NOT NULL,
+                local_ms        REAL NOT NULL,
+                local_samples   INTEGER NOT NULL,
+                remote_ms       REAL NOT NULL,
+                remote_samples  INTEGER NOT NULL,
+                local_wins      REAL NOT NULL,
+                races           INTEGER NOT NULL
+            )",
+            TIMINGS_TABLE_NAME
+        );
+        self.connection
+            .lock()
+            .execute(&sql, [])
+            .with_context(|| format!("creating sqlite table {}", TIMINGS_TABLE_NAME))?;
+        Ok(())
+    }
+
+    fn insert_all(&self, entries: &[(String, ActionTimings)]) -> anyhow::Result<()> {
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction()?;
+        // Insert in chunks to stay under the limit on the number of query parameters.
+        for chunk in entries.chunks(100) {
+            let sql = format!(
+                "INSERT OR REPLACE INTO {} (key, local_ms, local_samples, remote_ms, remote_samples, local_wins, races) VALUES {}",
+                TIMINGS_TABLE_NAME,
+                chunk.iter().map(|_| "(?, ?, ?, ?, ?, ?, ?)").join(", ")
+            );
+            let params: Vec<&dyn rusqlite::ToSql> = chunk
+                .iter()
+                .flat_map(|(key, t)| {
+                    [
+                        key as &dyn rusqlite::ToSql,
+                        &t.local.mean,
+                        &t.local.samples,
+                        &t.remote.mean,
+                        &t.remote.samples,
+                        &t.local_wins.mean,
+                        &t.local_wins.samples,
+                    ]
+                })
+                .collect();
+            transaction
+                .execute(&sql, params.as_slice())
+                .with_context(|| format!("inserting into sqlite table {}", TIMINGS_TABLE_NAME))?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn delete_all(&self, keys: &[String]) -> anyhow::Result<()> {
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction()?;
+        for chunk in keys.chunks(100) {
+            let sql = format!(
+                "DELETE FROM {} WHERE key IN ({})",
+                TIMINGS_TABLE_NAME,
+                chunk.iter().map(|_| "?").join(", ")
+            );
+            transaction
+                .execute(&sql, rusqlite::params_from_iter(chunk))
+                .with_context(|| format!("deleting from sqlite table {}", TIMINGS_TABLE_NAME))?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn read_all(&self) -> anyhow::Result<LinkedHashMap<String, ActionTimings>> {
+        let sql = format!(
+            "SELECT key, local_ms, local_samples, remote_ms, remote_samples, local_wins, races FROM {}",
+            TIMINGS_TABLE_NAME
+        );
+        let connection = self.connection.lock();
+        let mut stmt = connection.prepare(&sql)?;
+        let actions = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    ActionTimings {
+                        local: Ewma {
+                            mean: row.get(1)?,
+                            samples: row.get(2)?,
+                        },
+                        remote: Ewma {
+                            mean: row.get(3)?,
+                            samples: row.get(4)?,
+                        },
+                        local_wins: Ewma {
+                            mean: row.get(5)?,
+                            samples: row.get(6)?,
+                        },
+                    },
+                ))
+            })?
+            .collect::<Result<LinkedHashMap<_, _>, _>>()
+            .with_context(|| format!("reading from sqlite table {}", TIMINGS_TABLE_NAME))?;
+        Ok(actions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+
+    use super::*;
+
+    const CONFIG: ExecutorTimingsConfig = ExecutorTimingsConfig {
+        min_samples: 2,
+        margin: 0.5,
+        explore_every: 0,
+    };
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_predict() {
+        let timings = Arc::new(ExecutorTimings::new(CONFIG));
+        assert_eq!(
+            ExecutorPrediction::Uncertain,
+            timings.predict("cxx_compile", "a.cpp")
+        );
+
+        for _ in 0..2 {
+            timings.record_local("cxx_compile", "a.cpp", ms(100));
+            timings.record_remote("cxx_compile", "a.cpp", ms(1000), ms(100));
+            timings.record_local("cxx_compile", "b.cpp", ms(1000));
+            timings.record_remote("cxx_compile", "b.cpp", ms(300), ms(100));
+        }
+
+        assert_eq!(
+            ExecutorPrediction::Local,
+            timings.predict("cxx_compile", "a.cpp")
+        );
+        assert_eq!(
+            ExecutorPrediction::Remote,
+            timings.predict("cxx_compile", "b.cpp")
+        );
+        // Falls back to the category, where both executors take about as long.
+        assert_eq!(
+            ExecutorPrediction::Uncertain,
+            timings.predict("cxx_compile", "c.cpp")
+        );
+
+        // RE queueing makes remote execution slower for everything.
+        for _ in 0..20 {
+            timings.record_remote("genrule", "x", ms(5000), ms(5000));
+        }
+        assert_eq!(
+            ExecutorPrediction::Local,
+            timings.predict("cxx_compile", "c.cpp")
+        );
+    }
+
+    #[test]
+    fn test_predict_from_races() {
+        let timings = Arc::new(ExecutorTimings::new(CONFIG));
+        // Local execution always wins, so there are no remote durations.
+        for _ in 0..2 {
+            timings.record_local("cxx_compile", "a.cpp", ms(100));
+            timings.record_race("cxx_compile", "a.cpp", true);
+        }
+        assert_eq!(
+            ExecutorPrediction::Local,
+            timings.predict("cxx_compile", "a.cpp")
+        );
+
+        // Remote execution wins about as often.
+        timings.record_race("cxx_compile", "a.cpp", false);
+        timings.record_race("cxx_compile", "a.cpp", false);
+        assert_eq!(
+            ExecutorPrediction::Uncertain,
+            timings.predict("cxx_compile", "a.cpp")
+        );
+    }
+
+    #[test]
+    fn test_keys_are_bounded() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        let dir = fs
+            .path()
+            .resolve(ProjectRelativePath::unchecked_new("executor_timings"));
+
+        let timings = Arc::new(ExecutorTimings::initialize_impl(&dir, CONFIG)?);
+        // Plus one key for the category.
+        for i in 0..MAX_KEYS {
+            timings.record_local("cxx_compile", &format!("{}.cpp", i), ms(100));
+        }
+        {
+            let state = timings.state.lock();
+            assert_eq!(MAX_KEYS, state.actions.len());
+            assert!(
+                !state
+                    .actions
+                    .contains_key(&identifier_key("cxx_compile", "0.cpp"))
+            );
+            assert!(state.actions.contains_key(&category_key("cxx_compile")));
+        }
+        drop(timings);
+
+        let (_, table) =
+            ExecutorTimingsSqliteTable::open(&dir.join(FileName::unchecked_new("db.sqlite")))?;
+        assert_eq!(MAX_KEYS, table.read_all()?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_persist() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        let dir = fs
+            .path()
+            .resolve(ProjectRelativePath::unchecked_new("executor_timings"));
+
+        let timings = Arc::new(ExecutorTimings::initialize_impl(&dir, CONFIG)?);
+        for _ in 0..2 {
+            timings.record_local("cxx_compile", "a.cpp", ms(100));
+            timings.record_remote("cxx_compile", "a.cpp", ms(1000), ms(0));
+        }
+        drop(timings);
+
+        let timings = ExecutorTimings::initialize_impl(&dir, CONFIG)?;
+        assert_eq!(
+            ExecutorPrediction::Local,
+            timings.predict("cxx_compile", "a.cpp")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prediction_flips_when_predicted_executor_gets_slower() {
+        let timings = Arc::new(ExecutorTimings::new(CONFIG));
+        for _ in 0..2 {
+            timings.record_local("cxx_compile", "a.cpp", ms(100));
+            timings.record_remote("cxx_compile", "a.cpp", ms(300), ms(0));
+        }
+        assert_eq!(
+            ExecutorPrediction::Local,
+            timings.predict("cxx_compile", "a.cpp")
+        );
+
+        // Only the predicted executor runs the action, and it gets slower.
+        let mut predictions = Vec::new();
+        for _ in 0..10 {
+            timings.record_local("cxx_compile", "a.cpp", ms(1000));
+            predictions.push(timings.predict("cxx_compile", "a.cpp"));
+        }
+        assert_eq!(Some(&ExecutorPrediction::Remote), predictions.last());
+    }
+
+    #[test]
+    fn test_exploration() {
+        let timings = Arc::new(ExecutorTimings::new(ExecutorTimingsConfig {
+            explore_every: 3,
+            ..CONFIG
+        }));
+        for _ in 0..2 {
+            timings.record_local("cxx_compile", "a.cpp", ms(100));
+            timings.record_remote("cxx_compile", "a.cpp", ms(1000), ms(0));
+        }
+
+        let predictions = (0..6)
+            .map(|_| timings.predict("cxx_compile", "a.cpp"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ExecutorPrediction::Local,
+                ExecutorPrediction::Local,
+                ExecutorPrediction::Uncertain,
+                ExecutorPrediction::Local,
+                ExecutorPrediction::Local,
+                ExecutorPrediction::Uncertain,
+            ],
+            predictions
+        );
+
+        // The race was won by the remote executor, which got faster.
+        for _ in 0..10 {
+            timings.record_remote("cxx_compile", "a.cpp", ms(10), ms(0));
+        }
+        assert_eq!(
+            ExecutorPrediction::Remote,
+            timings.predict("cxx_compile", "a.cpp")
+        );
+    }
+}
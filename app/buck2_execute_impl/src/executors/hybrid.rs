@@ -20,6 +20,7 @@ use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::execute::claim::Claim;
 use buck2_execute::execute::claim::ClaimManager;
 use buck2_execute::execute::claim::MutexClaimManager;
+use buck2_execute::execute::kind::CommandExecutionKind;
 use buck2_execute::execute::manager::CommandExecutionManager;
 use buck2_execute::execute::manager::CommandExecutionManagerExt;
 use buck2_execute::execute::prepared::PreparedCommand;
@@ -36,6 +37,8 @@ use futures::FutureExt;
 use host_sharing::HostSharingRequirements;
 use more_futures::cancellation::CancellationContext;
 
+use crate::executors::executor_timings::ExecutorPrediction;
+use crate::executors::executor_timings::ExecutorTimings;
 use crate::executors::local::LocalExecutor;
 use crate::executors::re::ReExecutor;
 use crate::low_pass_filter::LowPassFilter;
@@ -46,12 +49,17 @@ use crate::low_pass_filter::LowPassFilter;
 ///
 /// If the remote executor claims the request but does not produce a successful response, we will
 /// enqueue the request again to the local executor.
+///
+/// If `executor_timings` is set, requests without a preference are sent to the executor which
+/// historically completes them first, with the other one as a fallback. They are only raced if
+/// there is no clear winner, or once in a while to notice if the other executor became faster.
 pub struct HybridExecutor {
     pub local: LocalExecutor,
     pub remote: ReExecutor,
     pub level: HybridExecutionLevel,
     pub executor_preference: ExecutorPreference,
     pub low_pass_filter: Arc<LowPassFilter>,
+    pub executor_timings: Option<Arc<ExecutorTimings>>,
 }
 
 impl HybridExecutor {
@@ -92,6 +100,68 @@ impl HybridExecutor {
         self.executor_preference
             .and(command.request.executor_preference())
     }
+
+    /// Use the executor predicted to be faster if the command has no preference.
+    fn predicted_executor_preference(
+        &self,
+        executor_preference: ExecutorPreference,
+        action_name: Option<&buck2_data::ActionName>,
+    ) -> ExecutorPreference {
+        let (timings, action_name) = match (&self.executor_timings, action_name) {
+            (Some(timings), Some(action_name)) => (timings, action_name),
+            _ => return executor_preference,
+        };
+        if !matches!(executor_preference, ExecutorPreference::Default) {
+            return executor_preference;
+        }
+        match timings.predict(&action_name.category, &action_name.identifier) {
+            ExecutorPrediction::Local => ExecutorPreference::LocalPreferred,
+            ExecutorPrediction::Remote => ExecutorPreference::RemotePreferred,
+            ExecutorPrediction::Uncertain => executor_preference,
+        }
+    }
+
+    /// `won_race` is set if both executors were racing and the other one was cancelled when
+    /// this one succeeded.
+    fn record_timing(
+        &self,
+        action_name: Option<&buck2_data::ActionName>,
+        res: &CommandExecutionResult,
+        won_race: bool,
+    ) {
+        let (timings, action_name) = match (&self.executor_timings, action_name) {
+            (Some(timings), Some(action_name)) => (timings, action_name),
+            _ => return,
+        };
+        let timing = &res.report.timing;
+        let local = match &res.report.status {
+            CommandExecutionStatus::Success {
+                execution_kind: CommandExecutionKind::Local { .. },
+            } => {
+                timings.record_local(
+                    &action_name.category,
+                    &action_name.identifier,
+                    timing.wall_time,
+                );
+                true
+            }
+            CommandExecutionStatus::Success {
+                execution_kind: CommandExecutionKind::Remote { .. },
+            } => {
+                timings.record_remote(
+                    &action_name.category,
+                    &action_name.identifier,
+                    timing.wall_time,
+                    timing.re_queue_time.unwrap_or_default(),
+                );
+                false
+            }
+            _ => return,
+        };
+        if won_race {
+            timings.record_race(&action_name.category, &action_name.identifier, local);
+        }
+    }
 }
 
 #[async_trait]
@@ -160,16 +230,15 @@ impl PreparedCommandExecutor for HybridExecutor {
             return local_result.await;
         };
 
+        // RE has been failing repeatedly, don't wait for it to fail again.
+        if !executor_preference.requires_remote() && self.remote.re_client.is_circuit_open() {
+            return local_result.await;
+        }
+
         if executor_preference.requires_remote() {
             return remote_result.await;
         }
 
-        let jobs = HybridExecutorJobs {
-            local: local_result.map(|r| (r, JobPriority(1))),
-            remote: remote_result.map(|r| (r, JobPriority(0))),
-            executor_preference,
-        };
-
         let (is_limited, fallback_only, fallback_on_failure, low_pass_filter) = match self.level {
             HybridExecutionLevel::Limited => (true, false, false, false),
             HybridExecutionLevel::Fallback {
@@ -181,6 +250,24 @@ impl PreparedCommandExecutor for HybridExecutor {
             } => (false, false, fallback_on_failure, low_pass_filter),
         };
 
+        let action_name = self
+            .executor_timings
+            .as_ref()
+            .map(|_| command.target.as_proto_action_name());
+
+        // With a predicted executor, we run that one first, and the other one only if it fails.
+        let executor_preference = if is_limited {
+            executor_preference
+        } else {
+            self.predicted_executor_preference(executor_preference, action_name.as_ref())
+        };
+
+        let jobs = HybridExecutorJobs {
+            local: local_result.map(|r| (r, JobPriority(1))),
+            remote: remote_result.map(|r| (r, JobPriority(0))),
+            executor_preference,
+        };
+
         if is_limited {
             return jobs.into_primary().await.0;
         }
@@ -217,46 +304,51 @@ impl PreparedCommandExecutor for HybridExecutor {
 
         let fallback_only = fallback_only && !command.request.force_full_hybrid_if_capable();
 
-        let ((mut first_res, first_priority), second) =
-            if executor_preference.prefers_local() || executor_preference.prefers_remote() {
-                // Don't race in this scenario, since this is typically used for
-                // actions that are too expensive to run on RE.
-                jobs.execute_sequential().await
+        // With `fallback_only`, local execution only starts once remote execution failed.
+        let sequential =
+            executor_preference.prefers_local() || executor_preference.prefers_remote();
+        let raced = !sequential && !fallback_only;
+
+        let ((mut first_res, first_priority), second) = if sequential {
+            // Don't race in this scenario, since this is typically used for
+            // actions that are too expensive to run on RE.
+            jobs.execute_sequential().await
+        } else {
+            // In the full-hybrid case, we do race both executors. If the low-pass filter is in
+            // use, then we wrap the local execution with that.
+            let jobs = if fallback_only {
+                jobs.map_local(move |local| {
+                    async move {
+                        // Block local until the remote executor aborts (that's remote_execution_liveliness_guard)
+                        // The claim actually comes back to us via the execution report so there's no race condition
+                        // where local unblocks just when RE finishes
+                        remote_execution_liveliness_observer.while_alive().await;
+                        local.await
+                    }
+                    .boxed()
+                })
+            } else if low_pass_filter {
+                jobs.map_local(move |local| {
+                    async move {
+                        // Block local until either condition is met:
+                        // - we only have a few actions (that's low_pass_filter)
+                        // - the remote executor aborts (that's remote_execution_liveliness_guard)
+                        let access = self.low_pass_filter.access(weight);
+                        let alive = remote_execution_liveliness_observer.while_alive();
+                        futures::pin_mut!(access);
+                        futures::pin_mut!(alive);
+                        let _guard = futures::future::select(access, alive).await;
+                        local.await
+                    }
+                    .boxed()
+                })
             } else {
-                // In the full-hybrid case, we do race both executors. If the low-pass filter is in
-                // use, then we wrap the local execution with that.
-                let jobs = if fallback_only {
-                    jobs.map_local(move |local| {
-                        async move {
-                            // Block local until the remote executor aborts (that's remote_execution_liveliness_guard)
-                            // The claim actually comes back to us via the execution report so there's no race condition
-                            // where local unblocks just when RE finishes
-                            remote_execution_liveliness_observer.while_alive().await;
-                            local.await
-                        }
-                        .boxed()
-                    })
-                } else if low_pass_filter {
-                    jobs.map_local(move |local| {
-                        async move {
-                            // Block local until either condition is met:
-                            // - we only have a few actions (that's low_pass_filter)
-                            // - the remote executor aborts (that's remote_execution_liveliness_guard)
-                            let access = self.low_pass_filter.access(weight);
-                            let alive = remote_execution_liveliness_observer.while_alive();
-                            futures::pin_mut!(access);
-                            futures::pin_mut!(alive);
-                            let _guard = futures::future::select(access, alive).await;
-                            local.await
-                        }
-                        .boxed()
-                    })
-                } else {
-                    jobs.map_local(|local| local.boxed())
-                };
-                jobs.execute_concurrent().await
+                jobs.map_local(|local| local.boxed())
             };
+            jobs.execute_concurrent().await
+        };
 
+        let won_race = raced && !is_retryable_status(&first_res);
         let mut res = if is_retryable_status(&first_res) {
             // If the first result had made a claim, then cancel it now to let the other result
             // proceed.
@@ -291,6 +383,8 @@ impl PreparedCommandExecutor for HybridExecutor {
             first_res
         };
 
+        self.record_timing(action_name.as_ref(), &res, won_race);
+
         res.eligible_for_full_hybrid = !fallback_only;
         res
     }
@@ -9,6 +9,7 @@
 
 use std::borrow::Cow;
 use std::ffi::OsStr;
+use std::fmt::Write;
 use std::ops::ControlFlow;
 use std::path::Path;
 use std::process::Command;
@@ -26,17 +27,23 @@ use buck2_common::local_resource_state::LocalResourceHolder;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::tag_error;
 use buck2_core::tag_result;
+use buck2_downward_api::records::DOWNWARD_API_FILE_ENV;
 use buck2_events::dispatch::get_dispatcher;
+use buck2_events::dispatch::EventDispatcher;
 use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::directory::extract_artifact_value;
 use buck2_execute::directory::insert_entry;
+use buck2_execute::directory::ActionDirectoryMember;
 use buck2_execute::entry::build_entry_from_disk;
 use buck2_execute::execute::action_digest::ActionDigest;
 use buck2_execute::execute::blocking::BlockingExecutor;
+use buck2_execute::execute::claim::MutexClaimManager;
 use buck2_execute::execute::clean_output_paths::CleanOutputPaths;
 use buck2_execute::execute::environment_inheritance::EnvironmentInheritance;
 use buck2_execute::execute::executor_stage_async;
@@ -55,6 +62,8 @@ use buck2_execute::execute::request::CommandExecutionRequest;
 use buck2_execute::execute::request::ExecutorPreference;
 use buck2_execute::execute::result::CommandExecutionMetadata;
 use buck2_execute::execute::result::CommandExecutionResult;
+use buck2_execute::execute::result::CommandExecutionStatus;
+use buck2_execute::execute::target::CommandExecutionTarget;
 use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::MaterializationError;
 use buck2_execute::materialize::materializer::Materializer;
@@ -77,6 +86,15 @@ use more_futures::cancellation::CancellationContext;
 use thiserror::Error;
 use tracing::info;
 
+use crate::executors::determinism::diff_outputs;
+use crate::executors::determinism::move_outputs;
+use crate::executors::determinism::output_members;
+use crate::executors::determinism::summarize_file_difference;
+use crate::executors::determinism::DeterminismCheck;
+use crate::executors::downward_api::DownwardApiFileReader;
+use crate::executors::downward_api::DOWNWARD_API_FILE_NAME;
+use crate::executors::output_validation::OutputValidationPolicy;
+
 #[derive(Debug, Error)]
 enum LocalExecutionError {
     #[error("Args list was empty")]
@@ -97,6 +115,8 @@ pub struct LocalExecutor {
     forkserver: Option<ForkserverClient>,
     #[allow(unused)]
     knobs: ExecutorGlobalKnobs,
+    output_validation_policy: Arc<OutputValidationPolicy>,
+    determinism_check: Option<Arc<DeterminismCheck>>,
 }
 
 impl LocalExecutor {
@@ -108,6 +128,8 @@ impl LocalExecutor {
         root: AbsNormPathBuf,
         forkserver: Option<ForkserverClient>,
         knobs: ExecutorGlobalKnobs,
+        output_validation_policy: Arc<OutputValidationPolicy>,
+        determinism_check: Option<Arc<DeterminismCheck>>,
     ) -> Self {
         Self {
             artifact_fs,
@@ -117,6 +139,8 @@ impl LocalExecutor {
             root,
             forkserver,
             knobs,
+            output_validation_policy,
+            determinism_check,
         }
     }
 
@@ -199,6 +223,7 @@ impl LocalExecutor {
         &self,
         action_digest: &ActionDigest,
         request: &CommandExecutionRequest,
+        category: &str,
         manager: CommandExecutionManager,
         cancellation: CancellationObserver,
         cancellations: &CancellationContext,
@@ -290,10 +315,14 @@ impl LocalExecutor {
         );
 
         let scratch_dir_abs;
+        let downward_api_file;
 
         let tmpdirs = if let Some(scratch_dir) = scratch_dir {
             // For the $TMPDIR - important it is absolute
             scratch_dir_abs = self.artifact_fs.fs().resolve(scratch_dir);
+            downward_api_file = Some(
+                scratch_dir_abs.join(ForwardRelativePath::unchecked_new(DOWNWARD_API_FILE_NAME)),
+            );
 
             if cfg!(windows) {
                 const MAX_PATH: usize = 260;
@@ -314,6 +343,7 @@ impl LocalExecutor {
                 vec![("TMPDIR", scratch_dir_abs.as_os_str())]
             }
         } else {
+            downward_api_file = None;
             vec![]
         };
 
@@ -351,11 +381,20 @@ impl LocalExecutor {
                     "BUCK_BUILD_ID",
                     StrOrOsStr::from(build_id),
                 )))
+                .chain(
+                    downward_api_file
+                        .iter()
+                        .map(|path| (DOWNWARD_API_FILE_ENV, StrOrOsStr::from(path.as_os_str()))),
+                )
         };
 
         let liveliness_observer = manager.liveliness_observer.dupe().and(cancellation);
 
-        let (mut timing, res) = executor_stage_async(
+        let downward_api_reader = downward_api_file
+            .clone()
+            .map(|path| DownwardApiFileReader::new(path, get_dispatcher()));
+
+        let (mut timing, res, diagnostics) = executor_stage_async(
             {
                 let env = iter_env()
                     .map(|(k, v)| buck2_data::local_command::EnvironmentEntry {
@@ -379,18 +418,20 @@ impl LocalExecutor {
                 let start_time = SystemTime::now();
 
                 let env = iter_env().map(|(k, v)| (k, v.into_os_str()));
-                let r = self
-                    .exec(
-                        &args[0],
-                        &args[1..],
-                        env,
-                        request.working_directory(),
-                        request.timeout(),
-                        request.local_environment_inheritance(),
-                        liveliness_observer,
-                        request.disable_miniperf(),
-                    )
-                    .await;
+                let exec = self.exec(
+                    &args[0],
+                    &args[1..],
+                    env,
+                    request.working_directory(),
+                    request.timeout(),
+                    request.local_environment_inheritance(),
+                    liveliness_observer,
+                    request.disable_miniperf(),
+                );
+                let (r, diagnostics) = match downward_api_reader {
+                    Some(reader) => reader.run(exec).await,
+                    None => (exec.await, Vec::new()),
+                };
 
                 let execution_time = execution_start.elapsed();
 
@@ -402,7 +443,7 @@ impl LocalExecutor {
                     execution_stats: None, // We fill this in later if available.
                 };
 
-                (timing, r)
+                (timing, r, diagnostics)
             },
         )
         .await;
@@ -420,13 +461,13 @@ impl LocalExecutor {
 
         let std_streams = CommandStdStreams::Local { stdout, stderr };
 
-        match status {
+        let mut result = match status {
             GatherOutputStatus::Finished {
                 exit_code,
                 execution_stats,
             } => {
                 let outputs = match self
-                    .calculate_and_declare_output_values(request, digest_config)
+                    .calculate_and_declare_output_values(request, category, digest_config)
                     .await
                 {
                     Ok(output_values) => output_values,
@@ -483,12 +524,16 @@ impl LocalExecutor {
                 manager.timeout(execution_kind, duration, std_streams, timing)
             }
             GatherOutputStatus::Cancelled => manager.cancel_claim(),
-        }
+        };
+
+        result.report.diagnostics = diagnostics;
+        result
     }
 
     async fn calculate_and_declare_output_values(
         &self,
         request: &CommandExecutionRequest,
+        category: &str,
         digest_config: DigestConfig,
     ) -> anyhow::Result<IndexMap<CommandExecutionOutput, ArtifactValue>> {
         let mut builder = inputs_directory(request.inputs(), &self.artifact_fs)?;
@@ -531,10 +576,147 @@ impl LocalExecutor {
             }
         }
 
+        self.output_validation_policy
+            .validate(category, self.artifact_fs.fs(), &to_declare)
+            .await?;
+
         self.materializer.declare_existing(to_declare).await?;
 
         Ok(mapped_outputs)
     }
+
+    /// Where the outputs of the first execution are kept while checking determinism.
+    fn determinism_dir(&self, action_digest: &ActionDigest) -> ProjectRelativePathBuf {
+        self.artifact_fs
+            .buck_out_path_resolver()
+            .root()
+            .join(ForwardRelativePath::unchecked_new("determinism"))
+            .join(ForwardRelativePath::unchecked_new(
+                &action_digest.raw_digest().to_string(),
+            ))
+    }
+
+    async fn move_outputs_aside(
+        &self,
+        result: &CommandExecutionResult,
+        dir: &ProjectRelativePath,
+    ) -> anyhow::Result<()> {
+        let fs = self.artifact_fs.fs();
+        let outputs: Vec<_> = result
+            .resolve_outputs(&self.artifact_fs)
+            .map(|(output, _)| output.path().to_buf())
+            .collect();
+
+        self.blocking_executor
+            .execute_io_inline(|| move_outputs(fs, &outputs, dir))
+            .await
+            .context("Error moving outputs of the first execution")
+    }
+
+    /// Compare the outputs of two executions of the same command, whose first outputs were moved
+    /// to `dir`, and report the differences.
+    async fn report_determinism(
+        &self,
+        target: &dyn CommandExecutionTarget,
+        action_digest: &ActionDigest,
+        dir: &ProjectRelativePath,
+        first: &CommandExecutionResult,
+        second: &CommandExecutionResult,
+        events: &EventDispatcher,
+    ) -> anyhow::Result<()> {
+        let name = target.as_proto_action_name();
+
+        if !matches!(second.report.status, CommandExecutionStatus::Success { .. }) {
+            events.console_message(format!(
+                "Action `{} {}` is not deterministic: it failed when executed again",
+                name.category, name.identifier
+            ));
+            events.instant_event(buck2_data::DeterminismCheck {
+                key: Some(target.as_proto_action_key()),
+                name: Some(name),
+                action_digest: action_digest.to_string(),
+                deterministic: false,
+                differences: Vec::new(),
+            });
+            return Ok(());
+        }
+
+        let members = |result: &CommandExecutionResult| {
+            output_members(
+                result
+                    .resolve_outputs(&self.artifact_fs)
+                    .map(|(output, value)| (output.into_path(), value)),
+            )
+        };
+        let differences = diff_outputs(members(first), members(second));
+
+        let fs = self.artifact_fs.fs();
+        let differences = self
+            .blocking_executor
+            .execute_io_inline(|| {
+                Ok(differences
+                    .into_iter()
+                    .map(|difference| {
+                        let summary = match (&difference.first, &difference.second) {
+                            (
+                                Some(ActionDirectoryMember::File(..)),
+                                Some(ActionDirectoryMember::File(..)),
+                            ) => Some(
+                                summarize_file_difference(
+                                    &fs.resolve(
+                                        &dir.join(difference.path.as_forward_relative_path()),
+                                    ),
+                                    &fs.resolve(&difference.path),
+                                )
+                                .unwrap_or_else(|e| format!("{:#}", e)),
+                            ),
+                            _ => None,
+                        };
+                        buck2_data::DeterminismDifference {
+                            path: difference.path.to_string(),
+                            first: difference.first.map(|m| m.to_string()).unwrap_or_default(),
+                            second: difference.second.map(|m| m.to_string()).unwrap_or_default(),
+                            summary,
+                        }
+                    })
+                    .collect::<Vec<_>>())
+            })
+            .await?;
+
+        if !differences.is_empty() {
+            let mut message = format!(
+                "Action `{} {}` is not deterministic, outputs differ between two executions:",
+                name.category, name.identifier
+            );
+            for difference in &differences {
+                let describe = |member: &str| match member {
+                    "" => "missing".to_owned(),
+                    member => member.to_owned(),
+                };
+                write!(
+                    message,
+                    "\n  {}: {} vs {}",
+                    difference.path,
+                    describe(&difference.first),
+                    describe(&difference.second)
+                )?;
+                if let Some(summary) = &difference.summary {
+                    write!(message, " ({})", summary)?;
+                }
+            }
+            events.console_message(message);
+        }
+
+        events.instant_event(buck2_data::DeterminismCheck {
+            key: Some(target.as_proto_action_key()),
+            name: Some(name),
+            action_digest: action_digest.to_string(),
+            deterministic: differences.is_empty(),
+            differences,
+        });
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -551,7 +733,7 @@ impl PreparedCommandExecutor for LocalExecutor {
 
         let PreparedCommand {
             request,
-            target: _,
+            target,
             prepared_action,
             digest_config,
         } = command;
@@ -587,20 +769,80 @@ impl PreparedCommandExecutor for LocalExecutor {
         )
         .await;
 
+        let action_name = target.as_proto_action_name();
+        let check_determinism = self.determinism_check.as_ref().map_or(false, |check| {
+            check.applies_to_category(&action_name.category)
+        });
+        let events = manager.events.dupe();
+        let liveliness_observer = manager.liveliness_observer.dupe();
+
         // If we start running something, we don't want this task to get dropped, because if we do
         // we might interfere with e.g. clean up.
         cancellations
-            .with_structured_cancellation(|cancellation| {
-                Self::exec_request(
-                    self,
-                    &prepared_action.action,
-                    request,
-                    manager,
-                    cancellation,
-                    cancellations,
-                    *digest_config,
-                    &local_resource_holders,
-                )
+            .with_structured_cancellation(|cancellation| async move {
+                let exec = |manager, cancellation| {
+                    Self::exec_request(
+                        self,
+                        &prepared_action.action,
+                        request,
+                        &action_name.category,
+                        manager,
+                        cancellation,
+                        cancellations,
+                        *digest_config,
+                        &local_resource_holders,
+                    )
+                };
+
+                let mut first = exec(manager, cancellation.clone()).await;
+                if !check_determinism
+                    || !matches!(first.report.status, CommandExecutionStatus::Success { .. })
+                {
+                    return first;
+                }
+
+                let dir = self.determinism_dir(&prepared_action.action);
+                if let Err(e) = self.move_outputs_aside(&first, &dir).await {
+                    events.console_message(format!(
+                        "Not checking determinism of `{} {}`: {:#}",
+                        action_name.category, action_name.identifier, e
+                    ));
+                    return first;
+                }
+
+                let manager = CommandExecutionManager::new(
+                    Box::new(MutexClaimManager::new()),
+                    events.dupe(),
+                    liveliness_observer,
+                );
+                let mut second = exec(manager, cancellation).await;
+                // The caller only knows about the claim of the first execution.
+                second.report.claim = first.report.claim.take();
+
+                if let Err(e) = self
+                    .report_determinism(
+                        *target,
+                        &prepared_action.action,
+                        &dir,
+                        &first,
+                        &second,
+                        &events,
+                    )
+                    .await
+                {
+                    tracing::warn!("Error checking determinism: {:#}", e);
+                }
+
+                let fs = self.artifact_fs.fs();
+                if let Err(e) = self
+                    .blocking_executor
+                    .execute_io_inline(|| fs.remove_path_recursive(&dir))
+                    .await
+                {
+                    tracing::warn!("Error removing `{}`: {:#}", dir, e);
+                }
+
+                second
             })
             .await
     }
@@ -968,11 +1210,21 @@ mod tests {
     use buck2_core::cells::CellResolver;
     use buck2_core::fs::artifact_path_resolver::ArtifactFs;
     use buck2_core::fs::buck_out_path::BuckOutPathResolver;
+    use buck2_core::fs::buck_out_path::BuckOutTestPath;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
     use buck2_core::fs::project::ProjectRoot;
     use buck2_core::fs::project::ProjectRootTemp;
     use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+    use buck2_events::create_source_sink_pair;
+    use buck2_events::Event;
+    use buck2_events::EventSource;
+    use buck2_execute::execute::blobs::ActionBlobs;
     use buck2_execute::execute::blocking::testing::DummyBlockingExecutor;
+    use buck2_execute::execute::prepared::PreparedAction;
+    use buck2_execute::execute::request::CommandExecutionPaths;
+    use buck2_execute::execute::request::OutputCreationBehavior;
     use buck2_execute::materialize::nodisk::NoDiskMaterializer;
+    use buck2_wrapper_common::invocation_id::TraceId;
     use host_sharing::HostSharingStrategy;
 
     use super::*;
@@ -1069,7 +1321,9 @@ mod tests {
         )
     }
 
-    fn test_executor() -> anyhow::Result<(LocalExecutor, AbsNormPathBuf, ProjectRootTemp)> {
+    fn test_executor(
+        determinism_check: Option<Arc<DeterminismCheck>>,
+    ) -> anyhow::Result<(LocalExecutor, AbsNormPathBuf, ProjectRootTemp)> {
         let temp = ProjectRootTemp::new().unwrap();
         let project_fs = temp.path();
         let artifact_fs = artifact_fs(project_fs.dupe());
@@ -1087,6 +1341,8 @@ mod tests {
             temp.path().root().to_buf(),
             None,
             ExecutorGlobalKnobs::default(),
+            Arc::new(OutputValidationPolicy::default()),
+            determinism_check,
         );
 
         Ok((executor, temp.path().root().to_buf(), temp))
@@ -1094,7 +1350,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_exec_cmd_environment() -> anyhow::Result<()> {
-        let (executor, root, _tmpdir) = test_executor()?;
+        let (executor, root, _tmpdir) = test_executor(None)?;
 
         let interpreter = if cfg!(windows) { "powershell" } else { "sh" };
         let (status, stdout, _) = executor
@@ -1131,7 +1387,7 @@ mod tests {
     async fn test_exec_cmd_environment_filtering() -> anyhow::Result<()> {
         use buck2_execute::execute::environment_inheritance::EnvironmentInheritance;
 
-        let (executor, _root, _tmpdir) = test_executor()?;
+        let (executor, _root, _tmpdir) = test_executor(None)?;
 
         let (status, stdout, _) = executor
             .exec(
@@ -1150,4 +1406,119 @@ mod tests {
 
         Ok(())
     }
+
+    #[derive(Debug)]
+    struct TestTarget;
+
+    impl CommandExecutionTarget for TestTarget {
+        fn re_action_key(&self) -> String {
+            "test".to_owned()
+        }
+
+        fn re_affinity_key(&self) -> String {
+            "test".to_owned()
+        }
+
+        fn as_proto_action_key(&self) -> buck2_data::ActionKey {
+            buck2_data::ActionKey::default()
+        }
+
+        fn as_proto_action_name(&self) -> buck2_data::ActionName {
+            buck2_data::ActionName {
+                category: "genrule".to_owned(),
+                identifier: "pid".to_owned(),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_exec_cmd_checks_determinism() -> anyhow::Result<()> {
+        let (executor, _root, _tmpdir) =
+            test_executor(Some(Arc::new(DeterminismCheck::default())))?;
+        let digest_config = DigestConfig::testing_default();
+
+        let output = |name: &str| CommandExecutionOutput::TestPath {
+            path: BuckOutTestPath::new(
+                ForwardRelativePathBuf::unchecked_new("base".to_owned()),
+                ForwardRelativePathBuf::unchecked_new(name.to_owned()),
+            ),
+            create: OutputCreationBehavior::Parent,
+        };
+        // The pid of the shell differs between the two executions.
+        let request = CommandExecutionRequest::new(
+            vec!["sh".to_owned()],
+            vec![
+                "-c".to_owned(),
+                "echo $$ > buck_out/v2/test/base/pid; echo same > buck_out/v2/test/base/same"
+                    .to_owned(),
+            ],
+            CommandExecutionPaths::new(
+                Vec::new(),
+                [output("pid"), output("same")].into_iter().collect(),
+                &executor.artifact_fs,
+                digest_config,
+            )?,
+            Default::default(),
+        );
+        let prepared_action = PreparedAction {
+            action: ActionDigest::from_content(b"action", digest_config.cas_digest_config()),
+            blobs: ActionBlobs::new(digest_config),
+            platform: Default::default(),
+        };
+
+        let (mut events, sink) = create_source_sink_pair();
+        let manager = CommandExecutionManager::new(
+            Box::new(MutexClaimManager::new()),
+            EventDispatcher::new(TraceId::new(), sink),
+            NoopLivelinessObserver::create(),
+        );
+        let result = executor
+            .exec_cmd(
+                &PreparedCommand {
+                    request: &request,
+                    target: &TestTarget,
+                    prepared_action: &prepared_action,
+                    digest_config,
+                },
+                manager,
+                CancellationContext::testing(),
+            )
+            .await;
+        assert!(
+            matches!(result.report.status, CommandExecutionStatus::Success { .. }),
+            "status: {:?}",
+            result.report.status
+        );
+
+        let mut checks = Vec::new();
+        while let Some(event) = events.try_receive() {
+            if let Event::Buck(event) = event {
+                if let buck2_data::buck_event::Data::Instant(instant) = event.data() {
+                    if let Some(buck2_data::instant_event::Data::DeterminismCheck(check)) =
+                        &instant.data
+                    {
+                        checks.push(check.clone());
+                    }
+                }
+            }
+        }
+        assert_eq!(1, checks.len());
+        assert!(!checks[0].deterministic);
+        assert_eq!(
+            vec!["buck_out/v2/test/base/pid"],
+            checks[0]
+                .differences
+                .iter()
+                .map(|d| d.path.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(checks[0].differences[0].summary.is_some());
+
+        // The outputs of the first execution were cleaned up.
+        let dir = executor.determinism_dir(&prepared_action.action);
+        assert!(!executor.artifact_fs.fs().resolve(&dir).exists());
+
+        Ok(())
+    }
 }
@@ -8,7 +8,12 @@
  */
 
 pub mod action_cache;
+pub mod cache_upload_policy;
 pub mod caching;
+pub mod determinism;
+pub(crate) mod downward_api;
+pub mod executor_timings;
 pub mod hybrid;
 pub mod local;
+pub mod output_validation;
 pub mod re;
@@ -0,0 +1,565 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::str::FromStr;
+
+use anyhow::Context;
+use buck2_common::legacy_configs::LegacyBuckConfig;
+use buck2_core::directory::unordered_entry_walk;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::output_size::OutputSize;
+
+const OUTPUT_VALIDATION_SECTION: &str = "buck2_output_validation";
+
+const MAX_BYTES: &str = "max_bytes";
+const MAX_FILES: &str = "max_files";
+const FORBID_ABSOLUTE_SYMLINKS: &str = "forbid_absolute_symlinks";
+const FORBID_PROJECT_ROOT_PATH: &str = "forbid_project_root_path";
+
+#[derive(Debug, thiserror::Error)]
+enum OutputValidationError {
+    #[error(
+        "Unknown output validation limit `{0}` in `[{}]`, expected `max_bytes`, `max_files`, \
+        `forbid_absolute_symlinks` or `forbid_project_root_path`, optionally prefixed with \
+        `<category>.`",
+        OUTPUT_VALIDATION_SECTION
+    )]
+    UnknownLimit(String),
+    #[error(
+        "Outputs of this `{category}` action are {actual} bytes, more than the {max} bytes \
+        allowed by `{}.{key}`. Reduce the size of the outputs or raise the limit",
+        OUTPUT_VALIDATION_SECTION
+    )]
+    TooManyBytes {
+        category: String,
+        actual: u64,
+        max: u64,
+        key: String,
+    },
+    #[error(
+        "This `{category}` action produced {actual} output files, more than the {max} files \
+        allowed by `{}.{key}`. Reduce the number of outputs (e.g. by archiving them) or raise the \
+        limit",
+        OUTPUT_VALIDATION_SECTION
+    )]
+    TooManyFiles {
+        category: String,
+        actual: u64,
+        max: u64,
+        key: String,
+    },
+    #[error(
+        "Output `{path}` is a symlink to the absolute path `{target}`, which `{}.{key}` forbids. \
+        Use a relative symlink so the output does not depend on the machine it was built on",
+        OUTPUT_VALIDATION_SECTION
+    )]
+    AbsoluteSymlink {
+        path: ProjectRelativePathBuf,
+        target: String,
+        key: String,
+    },
+    #[error(
+        "Output `{path}` contains the absolute path of the project root (`{root}`), which \
+        `{}.{key}` forbids. Write paths relative to the project root or the working directory \
+        so the output can be reused on other machines",
+        OUTPUT_VALIDATION_SECTION
+    )]
+    ProjectRootPathInOutput {
+        path: ProjectRelativePathBuf,
+        root: String,
+        key: String,
+    },
+}
+
+/// Limits set for all actions or for one category. `None` means unset.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct OutputLimits {
+    max_bytes: Option<u64>,
+    max_files: Option<u64>,
+    forbid_absolute_symlinks: Option<bool>,
+    forbid_project_root_path: Option<bool>,
+}
+
+impl OutputLimits {
+    fn set(&mut self, key: &str, limit: &str, value: &str) -> anyhow::Result<()> {
+        fn parse<T: FromStr>(key: &str, value: &str) -> anyhow::Result<Option<T>>
+        where
+            anyhow::Error: From<<T as FromStr>::Err>,
+        {
+            LegacyBuckConfig::parse_impl(OUTPUT_VALIDATION_SECTION, key, value).map(Some)
+        }
+
+        match limit {
+            MAX_BYTES => self.max_bytes = parse(key, value)?,
+            MAX_FILES => self.max_files = parse(key, value)?,
+            FORBID_ABSOLUTE_SYMLINKS => self.forbid_absolute_symlinks = parse(key, value)?,
+            FORBID_PROJECT_ROOT_PATH => self.forbid_project_root_path = parse(key, value)?,
+            _ => return Err(OutputValidationError::UnknownLimit(key.to_owned()).into()),
+        }
+        Ok(())
+    }
+}
+
+/// Checks applied to the outputs of actions after they run locally, remotely or are fetched from
+/// the action cache, before the outputs can be used by other actions or uploaded to the cache.
+///
+/// Configured in the `[buck2_output_validation]` section of the root buckconfig, either for all
+/// actions or for one category with a `<category>.` prefix, which takes precedence:
+///
+/// * `max_bytes`: maximum total size of the outputs.
+/// * `max_files`: maximum number of output files.
+/// * `forbid_absolute_symlinks`: reject outputs which are or contain symlinks to absolute paths.
+/// * `forbid_project_root_path`: reject outputs whose contents include the absolute path of the
+///   project root.
+///
+/// For example, `cxx_link.max_bytes = 1073741824`.
+#[derive(Debug, Default)]
+pub struct OutputValidationPolicy {
+    default: OutputLimits,
+    categories: HashMap<String, OutputLimits>,
+}
+
+impl OutputValidationPolicy {
+    pub fn from_config(config: &LegacyBuckConfig) -> anyhow::Result<Self> {
+        let mut policy = Self::default();
+
+        let section = match config.get_section(OUTPUT_VALIDATION_SECTION) {
+            Some(section) => section,
+            None => return Ok(policy),
+        };
+
+        for (key, value) in section.iter() {
+            let (limits, limit) = match key.rsplit_once('.') {
+                Some((category, limit)) => (
+                    policy.categories.entry(category.to_owned()).or_default(),
+                    limit,
+                ),
+                None => (&mut policy.default, key),
+            };
+            limits.set(key, limit, value.as_str())?;
+        }
+
+        Ok(policy)
+    }
+
+    /// Returns the limit for this category, and the key it was set by.
+    fn limit<T>(
+        &self,
+        category: &str,
+        limit: &str,
+        get: impl Fn(&OutputLimits) -> Option<T>,
+    ) -> Option<(T, String)> {
+        if let Some(value) = self.categories.get(category).and_then(&get) {
+            return Some((value, format!("{}.{}", category, limit)));
+        }
+        get(&self.default).map(|value| (value, limit.to_owned()))
+    }
+
+    /// Whether validating outputs of this category requires reading their contents, rather than
+    /// just their metadata.
+    pub fn checks_contents(&self, category: &str) -> bool {
+        self.limit(category, FORBID_PROJECT_ROOT_PATH, |l| {
+            l.forbid_project_root_path
+        })
+        .map_or(false, |(forbid, _)| forbid)
+    }
+
+    pub async fn validate(
+        &self,
+        category: &str,
+        fs: &ProjectRoot,
+        outputs: &[(ProjectRelativePathBuf, ArtifactValue)],
+    ) -> anyhow::Result<()> {
+        let max_bytes = self.limit(category, MAX_BYTES, |l| l.max_bytes);
+        let max_files = self.limit(category, MAX_FILES, |l| l.max_files);
+        let forbid_absolute_symlinks = self
+            .limit(category, FORBID_ABSOLUTE_SYMLINKS, |l| {
+                l.forbid_absolute_symlinks
+            })
+            .filter(|(forbid, _)| *forbid);
+        let forbid_project_root_path = self
+            .limit(category, FORBID_PROJECT_ROOT_PATH, |l| {
+                l.forbid_project_root_path
+            })
+            .filter(|(forbid, _)| *forbid);
+
+        if max_bytes.is_some() || max_files.is_some() {
+            let (mut bytes, mut count) = (0, 0);
+            for (_, value) in outputs {
+                let size = value.calc_output_count_and_bytes();
+                bytes += size.bytes;
+                count += size.count;
+            }
+
+            if let Some((max, key)) = max_bytes.filter(|(max, _)| bytes > *max) {
+                return Err(OutputValidationError::TooManyBytes {
+                    category: category.to_owned(),
+                    actual: bytes,
+                    max,
+                    key,
+                }
+                .into());
+            }
+            if let Some((max, key)) = max_files.filter(|(max, _)| count > *max) {
+                return Err(OutputValidationError::TooManyFiles {
+                    category: category.to_owned(),
+                    actual: count,
+                    max,
+                    key,
+                }
+                .into());
+            }
+        }
+
+        if forbid_absolute_symlinks.is_none() && forbid_project_root_path.is_none() {
+            return Ok(());
+        }
+
+        let mut files = Vec::new();
+        for (output, value) in outputs {
+            let mut walk = unordered_entry_walk(value.entry().as_ref());
+            while let Some((path, entry)) = walk.next() {
+                match entry {
+                    DirectoryEntry::Leaf(ActionDirectoryMember::ExternalSymlink(symlink)) => {
+                        if let Some((_, key)) = &forbid_absolute_symlinks {
+                            return Err(OutputValidationError::AbsoluteSymlink {
+                                path: output.join(path.get()),
+                                target: symlink.to_string(),
+                                key: key.clone(),
+                            }
+                            .into());
+                        }
+                    }
+                    DirectoryEntry::Leaf(ActionDirectoryMember::File(..))
+                        if forbid_project_root_path.is_some() =>
+                    {
+                        files.push(output.join(path.get()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some((_, key)) = forbid_project_root_path {
+            let root = fs.root().as_path().to_string_lossy().into_owned();
+            let resolved = files
+                .iter()
+                .map(|path| fs.resolve(path))
+                .collect::<Vec<_>>();
+            let found = {
+                let root = root.clone();
+                tokio::task::spawn_blocking(move || find_file_containing(&resolved, &root))
+                    .await
+                    .context("Scanning outputs for the project root path")??
+            };
+            if let Some(i) = found {
+                return Err(OutputValidationError::ProjectRootPathInOutput {
+                    path: files.swap_remove(i),
+                    root,
+                    key,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Files are scanned in chunks of this size, so large outputs are not read into memory.
+const SCAN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Returns the index of the first file whose contents include `needle`.
+pub(crate) fn find_file_containing(
+    files: &[AbsNormPathBuf],
+    needle: &str,
+) -> anyhow::Result<Option<usize>> {
+    find_file_containing_impl(files, needle, SCAN_CHUNK_SIZE)
+}
+
+fn find_file_containing_impl(
+    files: &[AbsNormPathBuf],
+    needle: &str,
+    chunk_size: usize,
+) -> anyhow::Result<Option<usize>> {
+    let finder = memchr::memmem::Finder::new(needle.as_bytes());
+    // A match can span two chunks, so we keep the end of the previous chunk.
+    let overlap = needle.len().saturating_sub(1);
+    let mut buf = vec![0; overlap + chunk_size.max(1)];
+    for (i, file) in files.iter().enumerate() {
+        let mut reader = std::fs::File::open(file)
+            .with_context(|| format!("Error reading output `{}`", file))?;
+        let mut filled = 0;
+        loop {
+            let read = match reader.read(&mut buf[filled..]) {
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Error reading output `{}`", file));
+                }
+            };
+            if read == 0 {
+                break;
+            }
+            filled += read;
+            if finder.find(&buf[..filled]).is_some() {
+                return Ok(Some(i));
+            }
+            if filled == buf.len() {
+                buf.copy_within(filled - overlap.., 0);
+                filled = overlap;
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+    use buck2_common::legacy_configs::testing::legacy_buck_config_from_entries;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+    use buck2_execute::digest_config::DigestConfig;
+    use buck2_execute::directory::extract_artifact_value;
+    use buck2_execute::directory::insert_file;
+    use buck2_execute::directory::new_symlink;
+    use buck2_execute::directory::ActionDirectoryBuilder;
+
+    use super::*;
+
+    fn policy(entries: &[(&str, &str)]) -> anyhow::Result<OutputValidationPolicy> {
+        let config = legacy_buck_config_from_entries(
+            entries
+                .iter()
+                .map(|(key, value)| (OUTPUT_VALIDATION_SECTION, *key, *value)),
+        )?;
+        OutputValidationPolicy::from_config(&config)
+    }
+
+    fn file(content: &str) -> FileMetadata {
+        let digest_config = DigestConfig::testing_default();
+        FileMetadata {
+            digest: TrackedFileDigest::from_content(
+                content.as_bytes(),
+                digest_config.cas_digest_config(),
+            ),
+            is_executable: false,
+        }
+    }
+
+    fn output(path: &str, value: ArtifactValue) -> (ProjectRelativePathBuf, ArtifactValue) {
+        (
+            ProjectRelativePathBuf::unchecked_new(path.to_owned()),
+            value,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_validate_max_bytes_and_files() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        let outputs = [
+            output("out/a", ArtifactValue::file(file("aaaa"))),
+            output("out/b", ArtifactValue::file(file("bbbb"))),
+        ];
+
+        let limits = policy(&[("max_bytes", "8"), ("max_files", "2")])?;
+        limits.validate("genrule", fs.path(), &outputs).await?;
+
+        let limits = policy(&[("genrule.max_bytes", "7")])?;
+        let err = limits
+            .validate("genrule", fs.path(), &outputs)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("8 bytes, more than the 7 bytes"),
+            "{:#}",
+            err
+        );
+        // Other categories are not affected.
+        limits.validate("cxx_link", fs.path(), &outputs).await?;
+
+        let limits = policy(&[("genrule.max_files", "1")])?;
+        let err = limits
+            .validate("genrule", fs.path(), &outputs)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("produced 2 output files"),
+            "{:#}",
+            err
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_absolute_symlink_in_directory() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        let digest_config = DigestConfig::testing_default();
+
+        let mut builder = ActionDirectoryBuilder::empty();
+        insert_file(
+            &mut builder,
+            ProjectRelativePath::new("out/dir/file")?,
+            file("contents"),
+        )?;
+        builder.insert(
+            ForwardRelativePath::new("out/dir/nested/link")?,
+            DirectoryEntry::Leaf(new_symlink("/usr/bin/env")?),
+        )?;
+        let dir = extract_artifact_value(
+            &builder,
+            ProjectRelativePath::new("out/dir")?,
+            digest_config,
+        )?
+        .unwrap();
+        let outputs = [output("out/dir", dir)];
+
+        policy(&[])?
+            .validate("genrule", fs.path(), &outputs)
+            .await?;
+
+        let err = policy(&[("forbid_absolute_symlinks", "true")])?
+            .validate("genrule", fs.path(), &outputs)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("`out/dir/nested/link` is a symlink to the absolute path"),
+            "{:#}",
+            err
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_project_root_path() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        let root = fs.path().root().as_path().to_string_lossy().into_owned();
+        fs.write_file("out/relative", "include/foo.h");
+        fs.write_file("out/absolute", &format!("{}/include/foo.h", root));
+
+        let limits = policy(&[("forbid_project_root_path", "true")])?;
+        assert!(limits.checks_contents("genrule"));
+
+        limits
+            .validate(
+                "genrule",
+                fs.path(),
+                &[output(
+                    "out/relative",
+                    ArtifactValue::file(file("include/foo.h")),
+                )],
+            )
+            .await?;
+
+        let err = limits
+            .validate(
+                "genrule",
+                fs.path(),
+                &[
+                    output("out/relative", ArtifactValue::file(file("include/foo.h"))),
+                    output("out/absolute", ArtifactValue::file(file("unused"))),
+                ],
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("`out/absolute` contains the absolute path of the project root"),
+            "{:#}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_from_config() -> anyhow::Result<()> {
+        let config = legacy_buck_config_from_entries([
+            ("buck2_output_validation", "max_bytes", "100"),
+            (
+                "buck2_output_validation",
+                "forbid_absolute_symlinks",
+                "true",
+            ),
+            ("buck2_output_validation", "cxx_link.max_bytes", "1000"),
+            ("buck2_output_validation", "cxx_link.max_files", "2"),
+        ])?;
+        let policy = OutputValidationPolicy::from_config(&config)?;
+
+        assert_eq!(
+            policy.limit("cxx_link", MAX_BYTES, |l| l.max_bytes),
+            Some((1000, "cxx_link.max_bytes".to_owned()))
+        );
+        assert_eq!(
+            policy.limit("genrule", MAX_BYTES, |l| l.max_bytes),
+            Some((100, "max_bytes".to_owned()))
+        );
+        assert_eq!(policy.limit("genrule", MAX_FILES, |l| l.max_files), None);
+        assert_eq!(
+            policy.limit("cxx_link", FORBID_ABSOLUTE_SYMLINKS, |l| l
+                .forbid_absolute_symlinks),
+            Some((true, "forbid_absolute_symlinks".to_owned()))
+        );
+
+        let config = legacy_buck_config_from_entries([(
+            "buck2_output_validation",
+            "cxx_link.max_size",
+            "1",
+        )])?;
+        assert!(OutputValidationPolicy::from_config(&config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_file_containing() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let a = AbsNormPathBuf::new(tempdir.path().join("a"))?;
+        let b = AbsNormPathBuf::new(tempdir.path().join("b"))?;
+        std::fs::write(&a, "relative/path")?;
+        std::fs::write(&b, "/repo/root/path")?;
+
+        assert_eq!(
+            find_file_containing(&[a.clone(), b], "/repo/root")?,
+            Some(1)
+        );
+        assert_eq!(find_file_containing(&[a], "/repo/root")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_file_containing_across_chunks() -> anyhow::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let a = AbsNormPathBuf::new(tempdir.path().join("a"))?;
+        std::fs::write(&a, "xxxxx/repo/rootxxxxx")?;
+
+        for chunk_size in 1..20 {
+            assert_eq!(
+                find_file_containing_impl(&[a.clone()], "/repo/root", chunk_size)?,
+                Some(0),
+                "chunk size {}",
+                chunk_size
+            );
+            assert_eq!(
+                find_file_containing_impl(&[a.clone()], "/repo/other", chunk_size)?,
+                None
+            );
+        }
+        Ok(())
+    }
+}
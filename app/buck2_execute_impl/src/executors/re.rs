@@ -49,8 +49,10 @@ use remote_execution::TCode;
 use thiserror::Error;
 use tracing::info;
 
+use crate::executors::output_validation::OutputValidationPolicy;
 use crate::re::download::download_action_results;
 use crate::re::download::DownloadResult;
+use crate::re::download::OutputValidation;
 
 #[derive(Debug, Error)]
 pub enum RemoteExecutorError {
@@ -70,6 +72,7 @@ pub struct ReExecutor {
     pub skip_cache_read: bool,
     pub skip_cache_write: bool,
     pub re_max_queue_time_ms: Option<u64>,
+    pub output_validation_policy: Arc<OutputValidationPolicy>,
 }
 
 impl ReExecutor {
@@ -293,6 +296,7 @@ impl PreparedCommandExecutor for ReExecutor {
             )
             .await?;
 
+        let action_name = target.as_proto_action_name();
         let res = download_action_results(
             request,
             &*self.materializer,
@@ -308,6 +312,11 @@ impl PreparedCommandExecutor for ReExecutor {
             request.outputs(),
             action_digest,
             &response,
+            OutputValidation {
+                policy: &self.output_validation_policy,
+                category: &action_name.category,
+                fs: self.artifact_fs.fs(),
+            },
             cancellations,
         )
         .boxed()
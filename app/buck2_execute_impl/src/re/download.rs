@@ -20,6 +20,7 @@ use buck2_common::file_ops::TrackedFileDigest;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::env_helper::EnvHelper;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::digest::CasDigestFromReExt;
@@ -55,6 +56,8 @@ use more_futures::cancellation::CancellationContext;
 use remote_execution as RE;
 use thiserror::Error;
 
+use crate::executors::output_validation::OutputValidationPolicy;
+
 pub async fn download_action_results<'a>(
     request: &CommandExecutionRequest,
     materializer: &dyn Materializer,
@@ -67,6 +70,7 @@ pub async fn download_action_results<'a>(
     requested_outputs: impl Iterator<Item = CommandExecutionOutputRef<'a>>,
     action_digest: &ActionDigest,
     response: &dyn RemoteActionResult,
+    output_validation: OutputValidation<'_>,
     cancellations: &CancellationContext,
 ) -> DownloadResult {
     let downloader = CasDownloader {
@@ -74,6 +78,7 @@ pub async fn download_action_results<'a>(
         re_client,
         re_use_case,
         digest_config,
+        output_validation,
     };
 
     let download = downloader.download(
@@ -106,11 +111,21 @@ pub async fn download_action_results<'a>(
     ))
 }
 
+/// The policy that the downloaded outputs of an action must satisfy, the same way the outputs
+/// of a local action do.
+#[derive(Clone, Copy)]
+pub struct OutputValidation<'a> {
+    pub policy: &'a OutputValidationPolicy,
+    pub category: &'a str,
+    pub fs: &'a ProjectRoot,
+}
+
 pub struct CasDownloader<'a> {
     pub materializer: &'a dyn Materializer,
     pub re_client: &'a ManagedRemoteExecutionClient,
     pub re_use_case: RemoteExecutorUseCase,
     pub digest_config: DigestConfig,
+    pub output_validation: OutputValidation<'a>,
 }
 
 impl CasDownloader<'_> {
@@ -254,6 +269,22 @@ impl CasDownloader<'_> {
         action_digest: &ActionDigest,
         cancellations: &CancellationContext,
     ) -> anyhow::Result<IndexMap<CommandExecutionOutput, ArtifactValue>> {
+        let OutputValidation {
+            policy,
+            category,
+            fs,
+        } = self.output_validation;
+
+        // Checking the contents of the outputs requires them to be on disk, so in that case they
+        // are validated once materialized, and invalidated if they are rejected. Otherwise, the
+        // metadata we got from RE is enough, and we avoid declaring outputs we would reject.
+        let validate_on_disk = if policy.checks_contents(category) {
+            Some(artifacts.to_declare.clone())
+        } else {
+            policy.validate(category, fs, &artifacts.to_declare).await?;
+            None
+        };
+
         // Declare the outputs to the materializer
         self.materializer
             .declare_cas_many(
@@ -274,6 +305,19 @@ impl CasDownloader<'_> {
             .await
             .context(DownloadError::Materialization)?;
 
+        if let Some(outputs) = validate_on_disk {
+            let paths = outputs.map(|(path, _)| path.clone());
+            let validated = async {
+                self.materializer.ensure_materialized(paths.clone()).await?;
+                policy.validate(category, fs, &outputs).await
+            }
+            .await;
+            if let Err(e) = validated {
+                self.materializer.invalidate_many(paths).await?;
+                return Err(e);
+            }
+        }
+
         Ok(artifacts.mapped_outputs)
     }
 }
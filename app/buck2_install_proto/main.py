@@ -7,6 +7,7 @@
 # of this source tree.
 
 import argparse
+import json
 import os
 import signal
 import subprocess
@@ -26,8 +27,14 @@ class RsyncInstallerService(install_pb2_grpc.InstallerServicer):
         self.stop_event = stop_event
         if argsparse.install_location == "":
             self.dst = argsparse.dst
+            # Digests of previously installed artifacts, so that unchanged
+            # ones can be skipped. Only tracked for local installs.
+            self.state_path = os.path.join(argsparse.dst, ".buck2_install_state.json")
         else:
             self.dst = f"{argsparse.install_location}:{argsparse.dst}"
+            self.state_path = None
+        self.state_lock = threading.Lock()
+        self.state = self.load_state()
 
     def Install(self, request, _context):
         install_id = request.install_id
@@ -41,10 +48,27 @@ class RsyncInstallerService(install_pb2_grpc.InstallerServicer):
         install_response.install_id = install_id
         return install_response
 
+    def Negotiate(self, request, _context):
+        with self.state_lock:
+            present = {
+                name: digest
+                for name, digest in self.state.items()
+                if os.path.lexists(os.path.join(self.dst, name))
+            }
+        print(f"Negotiated {len(present)} already installed files")
+        return install_pb2.NegotiateResponse(
+            install_id=request.install_id,
+            present_digests=present,
+            supports_directory_trees=False,
+        )
+
     def FileReady(self, request, _context):
         (_out, stderr, code) = self.rsync_install(
             request.path, os.path.join(self.dst, request.name)
         )
+        if code == 0:
+            with self.state_lock:
+                self.state[request.name] = request.digest
         response = {
             "install_id": request.install_id,
             "name": f"{request.name}",
@@ -60,10 +84,31 @@ class RsyncInstallerService(install_pb2_grpc.InstallerServicer):
         return file_response
 
     def ShutdownServer(self, _request, _context):
+        self.save_state()
         shutdown(self.stop_event)
         response = install_pb2.ShutdownResponse()
         return response
 
+    def load_state(self):
+        if self.state_path is None:
+            return {}
+        try:
+            with open(self.state_path) as f:
+                return json.load(f)
+        except (OSError, ValueError):
+            return {}
+
+    def save_state(self):
+        if self.state_path is None:
+            return
+        with self.state_lock:
+            state = dict(self.state)
+        try:
+            with open(self.state_path, "w") as f:
+                json.dump(state, f)
+        except OSError as e:
+            print(f"Failed to save install state: {e}")
+
     def rsync_install(self, src, dst):
         if not (dst_parent := Path(dst).parent).exists():
             dst_parent.mkdir(parents=True, exist_ok=True)
@@ -7,9 +7,11 @@
  * of this source tree.
  */
 
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use std::fmt;
 use std::fmt::Debug;
+use std::hash::Hash;
+use std::hash::Hasher;
 
 use anyhow::Context;
 use buck2_common::package_listing::file_listing::PackageFileListing;
@@ -48,6 +50,21 @@ impl Debug for GlobPattern {
     }
 }
 
+/// Patterns are compared by their source, so that equal specs can share DICE keys.
+impl PartialEq for GlobPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for GlobPattern {}
+
+impl Hash for GlobPattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_str().hash(state)
+    }
+}
+
 impl GlobPattern {
     fn new(pattern: &str) -> anyhow::Result<GlobPattern> {
         let parsed_pattern = glob::Pattern::new(pattern)
@@ -76,11 +93,16 @@ impl GlobPattern {
     }
 }
 
+/// Parsed `glob()` patterns.
+///
+/// Patterns are sorted and deduplicated, so specs which only differ in the order or repetition
+/// of patterns are equal.
 #[derive(Derivative)]
-#[derivative(Debug)]
+#[derivative(Debug, PartialEq, Eq, Hash)]
 pub struct GlobSpec {
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
     common_prefix: String,
-    exact_matches: HashSet<String>,
+    exact_matches: BTreeSet<String>,
     patterns: Vec<GlobPattern>,
     excludes: Vec<GlobPattern>,
 }
@@ -124,7 +146,7 @@ impl GlobSpec {
     ) -> anyhow::Result<Self> {
         let mut glob_patterns = Vec::new();
         let mut glob_excludes = Vec::new();
-        let mut exact_matches = HashSet::new();
+        let mut exact_matches = BTreeSet::new();
         for pattern in patterns {
             let pattern = pattern.as_ref();
             if pattern.contains('*') {
@@ -147,6 +169,10 @@ impl GlobSpec {
             let pattern = pattern.as_ref();
             glob_excludes.push(GlobPattern::new(pattern)?);
         }
+        for patterns in [&mut glob_patterns, &mut glob_excludes] {
+            patterns.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+            patterns.dedup();
+        }
         Ok(Self {
             common_prefix: longest_common_glob_prefix(patterns).to_owned(),
             exact_matches,
@@ -226,6 +252,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_glob_spec_eq() -> anyhow::Result<()> {
+        assert_eq!(
+            GlobSpec::new(&["b", "**/*.c", "*.h", "**/*.c"], &["x/**", "y/**"])?,
+            GlobSpec::new(&["*.h", "**/*.c", "b"], &["y/**", "x/**", "y/**"])?
+        );
+        assert_ne!(
+            GlobSpec::new(&["**/*.c"], &["x/**"])?,
+            GlobSpec::new::<_, &str>(&["**/*.c"], &[])?
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_glob_match_case_insensitive() -> anyhow::Result<()> {
         // NOTE: We probably should change this. But for now, let's codify the current behavior
@@ -14,14 +14,17 @@ use anyhow::Context as _;
 use buck2_core::provider::label::ProvidersLabel;
 use buck2_node::attrs::attr::Attribute;
 use buck2_node::attrs::attr_type::any::AnyAttrType;
+use buck2_node::attrs::attr_type::record::RecordField;
 use buck2_node::attrs::attr_type::AttrType;
 use buck2_node::attrs::configurable::AttrIsConfigurable;
 use buck2_node::attrs::display::AttrDisplayWithContextExt;
 use buck2_node::provider_id_set::ProviderIdSet;
+use buck2_util::arc_str::ArcStr;
 use derive_more::Display;
 use dupe::Dupe;
 use dupe::OptionDupedExt;
 use gazebo::prelude::*;
+use starlark::collections::SmallMap;
 use starlark::environment::GlobalsBuilder;
 use starlark::environment::Methods;
 use starlark::environment::MethodsBuilder;
@@ -116,7 +119,7 @@ impl AttributeExt for Attribute {
 /// when evaluating .bzl files
 pub fn get_attr_coercion_context<'v>(
     eval: &Evaluator<'v, '_>,
-) -> anyhow::Result<BuildAttrCoercionContext> {
+) -> anyhow::Result<BuildAttrCoercionContext<'static>> {
     let build_context = BuildContext::from_context(eval)?;
     Ok(BuildAttrCoercionContext::new_no_package(
         build_context.cell_info().cell_resolver().dupe(),
@@ -503,6 +506,30 @@ fn attr_module(registry: &mut MethodsBuilder) {
         Attribute::attr(eval, default, doc, coercer)
     }
 
+    /// Takes a record of named fields and gives a struct to the rule.
+    ///
+    /// Fields are specified as keyword arguments, e.g.
+    /// `attrs.record(name = attrs.string(), count = attrs.int(default = 0))`.
+    /// The value is given as a dict or a struct, each field can be a `select()`.
+    /// Fields with defaults may be omitted. Fields cannot be named `default` or `doc`.
+    fn record<'v>(
+        #[starlark(this)] _this: Value<'v>,
+        #[starlark(require = named)] default: Option<Value<'v>>,
+        #[starlark(require = named, default = "")] doc: &str,
+        #[starlark(kwargs)] fields: SmallMap<String, &AttributeAsStarlarkValue>,
+        eval: &mut Evaluator<'v, '_>,
+    ) -> anyhow::Result<AttributeAsStarlarkValue> {
+        let fields = fields.into_iter().map(|(name, attr)| {
+            Ok(RecordField {
+                name: ArcStr::from(name.as_str()),
+                ty: attr.coercer_for_inner()?,
+                default: attr.default().duped(),
+            })
+        });
+        let coercer = AttrType::record(fields.collect::<anyhow::Result<_>>()?);
+        Attribute::attr(eval, default, doc, coercer)
+    }
+
     /// Takes a tuple of values and gives a tuple to the rule.
     fn tuple<'v>(
         #[starlark(this)] _this: Value<'v>,
@@ -613,6 +640,7 @@ assert_eq(repr(attrs.list(attrs.string())), "attrs.list(attrs.string())")
 assert_eq(repr(attrs.dict(attrs.string(), attrs.string())), "attrs.dict(attrs.string(), attrs.string(), sorted=False)")
 assert_eq(repr(attrs.one_of(attrs.string())), "attrs.one_of(attrs.string())")
 assert_eq(repr(attrs.tuple(attrs.string())), "attrs.tuple(attrs.string())")
+assert_eq(repr(attrs.record(x = attrs.string(), y = attrs.int(default = 1))), "attrs.record(x=attrs.string(), y=attrs.int())")
 assert_eq(repr(attrs.option(attrs.string())), "attrs.option(attrs.string())")
 
 def test(): pass
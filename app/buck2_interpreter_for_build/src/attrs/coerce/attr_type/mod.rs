@@ -30,6 +30,7 @@ mod list;
 mod one_of;
 mod option;
 pub mod query;
+mod record;
 pub mod source;
 pub mod split_transition_dep;
 mod string;
@@ -105,6 +106,7 @@ impl AttrTypeInnerExt for AttrTypeInner {
             Self::Dict(x) => x.coerce_item(configurable, ctx, value),
             Self::List(x) => x.coerce_item(configurable, ctx, value),
             Self::Tuple(x) => x.coerce_item(configurable, ctx, value),
+            Self::Record(x) => x.coerce_item(configurable, ctx, value),
             Self::OneOf(x) => x.coerce_item(configurable, ctx, value),
             Self::Option(x) => x.coerce_item(configurable, ctx, value),
             Self::Source(x) => x.coerce_item(configurable, ctx, value),
@@ -134,6 +136,7 @@ impl AttrTypeInnerExt for AttrTypeInner {
             AttrTypeInner::Enum(x) => x.starlark_type(),
             AttrTypeInner::List(x) => x.starlark_type(),
             AttrTypeInner::Tuple(x) => x.starlark_type(),
+            AttrTypeInner::Record(x) => x.starlark_type(),
             AttrTypeInner::OneOf(x) => x.starlark_type(),
             AttrTypeInner::Option(x) => x.starlark_type(),
             AttrTypeInner::Query(x) => x.starlark_type(),
@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_node::attrs::attr_type::record::RecordAttrType;
+use buck2_node::attrs::attr_type::record::RecordLiteral;
+use buck2_node::attrs::coerced_attr::CoercedAttr;
+use buck2_node::attrs::coercion_context::AttrCoercionContext;
+use buck2_node::attrs::configurable::AttrIsConfigurable;
+use dupe::Dupe;
+use itertools::Itertools;
+use starlark::collections::SmallMap;
+use starlark::values::dict::Dict;
+use starlark::values::dict::DictRef;
+use starlark::values::structs::StructRef;
+use starlark::values::StringValueLike;
+use starlark::values::Value;
+
+use crate::attrs::coerce::attr_type::AttrTypeExt;
+use crate::attrs::coerce::error::CoercionError;
+use crate::attrs::coerce::AttrTypeCoerce;
+
+#[derive(Debug, thiserror::Error)]
+enum RecordCoercionError {
+    #[error("Record keys must be strings, got `{0}`")]
+    KeyNotString(String),
+    #[error("Unknown record field `{0}`, expecting one of: {}", .1.iter().map(|f| format!("`{}`", f)).join(", "))]
+    UnknownField(String, Vec<String>),
+    #[error("Missing record field `{0}` which has no default")]
+    MissingField(String),
+}
+
+impl AttrTypeCoerce for RecordAttrType {
+    fn coerce_item(
+        &self,
+        configurable: AttrIsConfigurable,
+        ctx: &dyn AttrCoercionContext,
+        value: Value,
+    ) -> anyhow::Result<CoercedAttr> {
+        let mut items: SmallMap<&str, Value> = SmallMap::new();
+        if let Some(dict) = DictRef::from_value(value) {
+            for (k, v) in dict.iter() {
+                let k = k
+                    .unpack_str()
+                    .ok_or_else(|| RecordCoercionError::KeyNotString(k.to_repr()))?;
+                items.insert(k, v);
+            }
+        } else if let Some(s) = StructRef::from_value(value) {
+            for (k, v) in s.iter() {
+                items.insert(k.as_str(), v);
+            }
+        } else {
+            return Err(CoercionError::type_error(Dict::TYPE, value).into());
+        }
+
+        if let Some(unknown) = items
+            .keys()
+            .find(|k| !self.fields.iter().any(|f| f.name.as_str() == **k))
+        {
+            return Err(RecordCoercionError::UnknownField(
+                (*unknown).to_owned(),
+                self.fields.iter().map(|f| f.name.to_string()).collect(),
+            )
+            .into());
+        }
+
+        let mut res = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let coerced = match items.get(field.name.as_str()) {
+                Some(v) => field.ty.coerce(configurable, ctx, *v)?,
+                None => match &field.default {
+                    Some(default) => (**default).clone(),
+                    None => {
+                        return Err(
+                            RecordCoercionError::MissingField(field.name.to_string()).into()
+                        );
+                    }
+                },
+            };
+            res.push((field.name.dupe(), coerced));
+        }
+        Ok(CoercedAttr::Record(RecordLiteral::from_iter(res)))
+    }
+
+    fn starlark_type(&self) -> String {
+        "struct.type".to_owned()
+    }
+}
@@ -10,8 +10,8 @@
 use std::cell::RefCell;
 use std::fmt;
 use std::fmt::Debug;
+use std::sync::Arc;
 
-use buck2_common::package_listing::listing::PackageListing;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::package::package_relative_path::PackageRelativePath;
@@ -36,7 +36,6 @@ use buck2_util::arc_str::ArcSlice;
 use buck2_util::arc_str::ArcStr;
 use bumpalo::Bump;
 use dupe::Dupe;
-use dupe::IterDupedExt;
 use hashbrown::raw::RawTable;
 use tracing::info;
 
@@ -44,6 +43,7 @@ use super::interner::AttrCoercionInterner;
 use crate::attrs::coerce::arc_str_interner::ArcStrInterner;
 use crate::attrs::coerce::query_functions::QUERY_FUNCTIONS;
 use crate::attrs::coerce::str_hash::str_hash;
+use crate::interpreter::package_listing_view::PackageListingView;
 
 #[derive(Debug, thiserror::Error)]
 enum BuildAttrCoercionContextError {
@@ -62,7 +62,7 @@ enum BuildAttrCoercionContextError {
 }
 
 /// An incomplete attr coercion context. Will be replaced with a real one later.
-pub struct BuildAttrCoercionContext {
+pub struct BuildAttrCoercionContext<'a> {
     /// Used to coerce targets
     cell_resolver: CellResolver,
     cell_name: CellName,
@@ -70,7 +70,7 @@ pub struct BuildAttrCoercionContext {
     /// is being evaluated, however it is absent if an extension file is being
     /// evaluated. The latter case occurs when default values for attributes
     /// are coerced when a UDR is declared.
-    enclosing_package: Option<(PackageLabel, PackageListing)>,
+    enclosing_package: Option<(PackageLabel, Arc<dyn PackageListingView + 'a>)>,
     /// Does this package (if present) have a package boundary exception on it.
     package_boundary_exception: bool,
     /// Allocator for `label_cache`.
@@ -91,18 +91,18 @@ pub struct BuildAttrCoercionContext {
     select_interner: AttrCoercionInterner<ArcSlice<(TargetLabel, CoercedAttr)>>,
 }
 
-impl Debug for BuildAttrCoercionContext {
+impl<'a> Debug for BuildAttrCoercionContext<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BuildAttrCoercionContext")
             .finish_non_exhaustive()
     }
 }
 
-impl BuildAttrCoercionContext {
+impl<'a> BuildAttrCoercionContext<'a> {
     fn new(
         cell_resolver: CellResolver,
         cell_name: CellName,
-        enclosing_package: Option<(PackageLabel, PackageListing)>,
+        enclosing_package: Option<(PackageLabel, Arc<dyn PackageListingView + 'a>)>,
         package_boundary_exception: bool,
     ) -> Self {
         Self {
@@ -125,7 +125,7 @@ impl BuildAttrCoercionContext {
 
     pub fn new_with_package(
         cell_resolver: CellResolver,
-        enclosing_package: (PackageLabel, PackageListing),
+        enclosing_package: (PackageLabel, Arc<dyn PackageListingView + 'a>),
         package_boundary_exception: bool,
     ) -> Self {
         Self::new(
@@ -158,14 +158,14 @@ impl BuildAttrCoercionContext {
     fn require_enclosing_package(
         &self,
         msg: &str,
-    ) -> anyhow::Result<&(PackageLabel, PackageListing)> {
+    ) -> anyhow::Result<&(PackageLabel, Arc<dyn PackageListingView + 'a>)> {
         self.enclosing_package.as_ref().ok_or_else(|| {
             BuildAttrCoercionContextError::NotBuildFileContext(msg.to_owned()).into()
         })
     }
 }
 
-impl AttrCoercionContext for BuildAttrCoercionContext {
+impl<'a> AttrCoercionContext for BuildAttrCoercionContext<'a> {
     fn coerce_label(&self, value: &str) -> anyhow::Result<ProvidersLabel> {
         let hash = str_hash(value);
         let mut label_cache = self.label_cache.borrow_mut();
@@ -210,23 +210,23 @@ impl AttrCoercionContext for BuildAttrCoercionContext {
         let path = <&PackageRelativePath>::try_from(value)?;
         let (package, listing) = self.require_enclosing_package(value)?;
 
-        if let Some(path) = listing.get_file(path) {
+        if let Some(path) = listing.get_file(path)? {
             return Ok(CoercedPath::File(path));
         }
 
         // TODO: Make the warnings below into errors
-        if let Some(path) = listing.get_dir(path) {
+        if let Some(dir) = listing.get_dir(path)? {
             if !allow_directory {
                 return Err(BuildAttrCoercionContextError::SourceFileIsDirectory(
                     package.dupe(),
                     value.to_owned(),
                 )
                 .into());
-            } else if let Some(subpackage) = listing.subpackages_within(&path).next() {
+            } else if let Some(subpackage) = &dir.subpackage {
                 let e = BuildAttrCoercionContextError::SourceDirectoryIncludesSubPackage(
                     package.dupe(),
                     value.to_owned(),
-                    subpackage.to_owned(),
+                    subpackage.clone(),
                 );
                 if self.package_boundary_exception {
                     info!("{} (could be due to a package boundary violation)", e);
@@ -234,10 +234,9 @@ impl AttrCoercionContext for BuildAttrCoercionContext {
                     soft_error!("source_directory_includes_subpackage", e.into())?;
                 }
             }
-            let files = listing.files_within(&path).duped().collect();
             Ok(CoercedPath::Directory(Box::new(CoercedDirectory {
-                dir: path,
-                files,
+                dir: dir.dir.dupe(),
+                files: dir.files.clone(),
             })))
         } else {
             let e =
@@ -8,6 +8,7 @@
  */
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use buck2_common::legacy_configs::LegacyBuckConfig;
 use buck2_common::package_listing::listing::testing::PackageListingExt;
@@ -59,7 +60,11 @@ pub fn coercion_ctx_listing(package_listing: PackageListing) -> impl AttrCoercio
         ),
     ]);
 
-    BuildAttrCoercionContext::new_with_package(cell_resolver, (package, package_listing), false)
+    BuildAttrCoercionContext::new_with_package(
+        cell_resolver,
+        (package, Arc::new(package_listing)),
+        false,
+    )
 }
 
 fn cell_resolver() -> CellResolver {
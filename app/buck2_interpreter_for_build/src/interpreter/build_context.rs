@@ -50,16 +50,16 @@ enum BuildContextError {
 }
 
 #[derive(Debug)]
-pub(crate) enum PerFileTypeContext {
+pub(crate) enum PerFileTypeContext<'a> {
     /// Context for evaluating `BUCK` files.
-    Build(BuildFilePath, ModuleInternals),
+    Build(BuildFilePath, ModuleInternals<'a>),
     /// Context for evaluating `PACKAGE` files.
     Package(PackageFilePath, PackageFileEvalCtx),
     Bzl(ImportPath),
     Bxl(BxlFilePath),
 }
 
-impl PerFileTypeContext {
+impl<'a> PerFileTypeContext<'a> {
     pub(crate) fn starlark_path(&self) -> StarlarkPath {
         match self {
             PerFileTypeContext::Build(path, _) => StarlarkPath::BuildFile(path),
@@ -73,7 +73,10 @@ impl PerFileTypeContext {
         self.starlark_path().file_type()
     }
 
-    pub(crate) fn require_build(&self, function_name: &str) -> anyhow::Result<&ModuleInternals> {
+    pub(crate) fn require_build(
+        &self,
+        function_name: &str,
+    ) -> anyhow::Result<&ModuleInternals<'a>> {
         match self {
             PerFileTypeContext::Build(_, internals) => Ok(internals),
             x => {
@@ -82,7 +85,7 @@ impl PerFileTypeContext {
         }
     }
 
-    pub(crate) fn into_build(self) -> anyhow::Result<ModuleInternals> {
+    pub(crate) fn into_build(self) -> anyhow::Result<ModuleInternals<'a>> {
         match self {
             PerFileTypeContext::Build(_, internals) => Ok(internals),
             x => Err(BuildContextError::NotBuildFileNoFunction(x.file_type()).into()),
@@ -108,7 +111,7 @@ impl PerFileTypeContext {
         }
     }
 
-    pub(crate) fn for_module(path: StarlarkModulePath) -> PerFileTypeContext {
+    pub(crate) fn for_module(path: StarlarkModulePath) -> PerFileTypeContext<'a> {
         match path {
             StarlarkModulePath::LoadFile(bzl) => PerFileTypeContext::Bzl(bzl.clone()),
             StarlarkModulePath::BxlFile(bxl) => PerFileTypeContext::Bxl(bxl.clone()),
@@ -145,7 +148,7 @@ pub struct BuildContext<'a> {
     pub host_info: &'a HostInfo,
 
     /// Context specific to type type.
-    pub(crate) additional: PerFileTypeContext,
+    pub(crate) additional: PerFileTypeContext<'a>,
 
     /// When true, rule function is no-op.
     pub ignore_attrs_for_profiling: bool,
@@ -159,7 +162,7 @@ impl<'a> BuildContext<'a> {
         buckconfig: &'a (dyn LegacyBuckConfigView + 'a),
         root_buckconfig: &'a (dyn LegacyBuckConfigView + 'a),
         host_info: &'a HostInfo,
-        additional: PerFileTypeContext,
+        additional: PerFileTypeContext<'a>,
         ignore_attrs_for_profiling: bool,
     ) -> BuildContext<'a> {
         let buckconfig = LegacyBuckConfigForStarlark::new(module, buckconfig);
@@ -201,9 +204,12 @@ impl<'a> BuildContext<'a> {
 
 /// Arbitrary object made available to the execution context. Converted to
 /// EvalResult at the end of interpreting
-impl ModuleInternals {
+impl<'a> ModuleInternals<'a> {
     /// Try to get this inner context from the `ctx.extra` property.
-    pub fn from_context<'a>(ctx: &'a Evaluator, function_name: &str) -> anyhow::Result<&'a Self> {
+    pub fn from_context<'v>(
+        ctx: &Evaluator<'v, 'a>,
+        function_name: &str,
+    ) -> anyhow::Result<&'a ModuleInternals<'a>> {
         BuildContext::from_context(ctx)?
             .additional
             .require_build(function_name)
@@ -9,7 +9,6 @@
 
 use buck2_interpreter::functions::dedupe::dedupe;
 use buck2_interpreter::functions::sha256::register_sha256;
-use buck2_interpreter::globspec::GlobSpec;
 use buck2_interpreter::selector::register_select;
 use starlark::environment::GlobalsBuilder;
 use starlark::environment::LibraryExtension;
@@ -58,9 +57,10 @@ pub fn native_module(builder: &mut GlobalsBuilder) {
         eval: &mut Evaluator<'v, '_>,
     ) -> anyhow::Result<Value<'v>> {
         let extra = ModuleInternals::from_context(eval, "glob")?;
-        let spec = GlobSpec::new(&include, &exclude)?;
-        let res = extra.resolve_glob(&spec).map(|path| path.as_str());
-        Ok(eval.heap().alloc(AllocList(res)))
+        let res = extra.resolve_glob(&include, &exclude)?;
+        Ok(eval
+            .heap()
+            .alloc(AllocList(res.iter().map(|path| path.as_str()))))
     }
 
     /// `package_name()` can only be called in `BUCK` files, and returns the name of the package.
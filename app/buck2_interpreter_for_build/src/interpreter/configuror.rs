@@ -12,7 +12,6 @@ use std::ptr;
 use std::sync::Arc;
 
 use allocative::Allocative;
-use buck2_common::package_listing::listing::PackageListing;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::bzl::ImportPath;
 use buck2_interpreter::extra::cell_info::InterpreterCellInfo;
@@ -32,6 +31,7 @@ use crate::interpreter::build_defs::configure_base_globals;
 use crate::interpreter::functions::host_info::HostInfo;
 use crate::interpreter::module_internals::ModuleInternals;
 use crate::interpreter::module_internals::PackageImplicits;
+use crate::interpreter::package_listing_view::PackageListingView;
 use crate::super_package::data::SuperPackage;
 
 #[derive(Clone, Allocative)]
@@ -173,16 +173,16 @@ impl BuildInterpreterConfiguror {
         &self.host_info
     }
 
-    pub(crate) fn new_extra_context(
+    pub(crate) fn new_extra_context<'a>(
         &self,
         cell_info: &InterpreterCellInfo,
         buildfile_path: BuildFilePath,
-        package_listing: PackageListing,
+        package_listing: Arc<dyn PackageListingView + 'a>,
         super_package: SuperPackage,
         package_boundary_exception: bool,
         loaded_modules: &LoadedModules,
         implicit_import: Option<&Arc<ImplicitImport>>,
-    ) -> anyhow::Result<ModuleInternals> {
+    ) -> anyhow::Result<ModuleInternals<'a>> {
         let record_target_call_stack = self.record_target_call_stack;
         let skip_targets_with_duplicate_names = self.skip_targets_with_duplicate_names;
         let package_implicits = implicit_import.map(|spec| {
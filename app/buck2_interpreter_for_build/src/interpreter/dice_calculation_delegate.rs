@@ -21,8 +21,6 @@ use buck2_common::file_ops::FileOps;
 use buck2_common::legacy_configs::dice::HasLegacyConfigs;
 use buck2_common::legacy_configs::dice::LegacyBuckConfigOnDice;
 use buck2_common::package_boundary::HasPackageBoundaryExceptions;
-use buck2_common::package_listing::dice::HasPackageListingResolver;
-use buck2_common::package_listing::listing::PackageListing;
 use buck2_common::result::SharedResult;
 use buck2_common::result::ToSharedResultExt;
 use buck2_common::result::ToUnsharedResultExt;
@@ -58,6 +56,8 @@ use crate::interpreter::dice_calculation_delegate::keys::EvalImportKey;
 use crate::interpreter::global_interpreter_state::HasGlobalInterpreterState;
 use crate::interpreter::interpreter_for_cell::InterpreterForCell;
 use crate::interpreter::interpreter_for_cell::ParseResult;
+use crate::interpreter::package_listing_view::PackageListingOnDice;
+use crate::interpreter::package_listing_view::PackageListingView;
 use crate::super_package::data::SuperPackage;
 
 #[derive(Debug, thiserror::Error)]
@@ -423,13 +423,10 @@ impl<'c> DiceCalculationDelegate<'c> {
     async fn eval_package_file_for_build_file(
         &self,
         package: PackageLabel,
-        package_listing: &PackageListing,
+        has_package_file: bool,
     ) -> anyhow::Result<SuperPackage> {
         let package_file_path = PackageFilePath::for_dir(package.as_cell_path());
-        if package_listing
-            .get_file(PackageFilePath::PACKAGE_FILE_NAME.as_ref())
-            .is_none()
-        {
+        if !has_package_file {
             // Without this optimization, `cquery <that android target>` has 6% time regression.
             // With this optimization, check for `PACKAGE` files adds 2% to time.
             self.eval_parent_package_file(&package_file_path).await
@@ -441,13 +438,13 @@ impl<'c> DiceCalculationDelegate<'c> {
     async fn resolve_package_listing(
         &self,
         package: PackageLabel,
-    ) -> anyhow::Result<PackageListing> {
+    ) -> anyhow::Result<PackageListingOnDice<'c>> {
         span_async(
             buck2_data::LoadPackageStart {
                 path: package.as_cell_path().to_string(),
             },
             async {
-                let result = self.ctx.resolve_package_listing(package.dupe()).await;
+                let result = PackageListingOnDice::new(self.ctx, package.dupe()).await;
                 let error = result.create_error_report();
                 (
                     result,
@@ -471,7 +468,10 @@ impl<'c> DiceCalculationDelegate<'c> {
         let build_file_path = BuildFilePath::new(package.dupe(), listing.buildfile().to_owned());
         let ast_deps = self.prepare_eval(StarlarkPath::BuildFile(&build_file_path));
 
-        let super_package = self.eval_package_file_for_build_file(package.dupe(), &listing);
+        let has_package_file = listing
+            .get_file(PackageFilePath::PACKAGE_FILE_NAME.as_ref())?
+            .is_some();
+        let super_package = self.eval_package_file_for_build_file(package.dupe(), has_package_file);
 
         let ((ast, deps), super_package) = future::try_join(ast_deps, super_package).await?;
 
@@ -499,7 +499,7 @@ impl<'c> DiceCalculationDelegate<'c> {
                             &build_file_path,
                             &buckconfig,
                             &root_buckconfig,
-                            listing,
+                            Arc::new(listing),
                             super_package,
                             package_boundary_exception,
                             ast,
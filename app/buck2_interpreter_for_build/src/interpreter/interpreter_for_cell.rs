@@ -13,11 +13,11 @@
 
 use std::cell::RefCell;
 use std::sync::Arc;
+use std::time::Duration;
 
 use allocative::Allocative;
 use anyhow::Context;
 use buck2_common::legacy_configs::view::LegacyBuckConfigView;
-use buck2_common::package_listing::listing::PackageListing;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::bzl::ImportPath;
 use buck2_core::cells::build_file_cell::BuildFileCell;
@@ -45,6 +45,9 @@ use gazebo::prelude::*;
 use starlark::codemap::FileSpan;
 use starlark::environment::FrozenModule;
 use starlark::environment::Module;
+use starlark::errors::Diagnostic;
+use starlark::eval::EvalLimitExceeded;
+use starlark::eval::EvalLimits;
 use starlark::syntax::AstModule;
 use starlark::values::list::ListRef;
 use starlark::values::OwnedFrozenValue;
@@ -57,6 +60,7 @@ use crate::interpreter::build_context::BuildContext;
 use crate::interpreter::build_context::PerFileTypeContext;
 use crate::interpreter::global_interpreter_state::GlobalInterpreterState;
 use crate::interpreter::module_internals::ModuleInternals;
+use crate::interpreter::package_listing_view::PackageListingView;
 use crate::super_package::data::SuperPackage;
 use crate::super_package::eval_ctx::PackageFileEvalCtx;
 use crate::super_package::package_value::PackageValues;
@@ -69,6 +73,45 @@ enum StarlarkParseError {
     Tabs(OwnedStarlarkPath),
 }
 
+#[derive(Debug, Error)]
+enum StarlarkEvalLimitsError {
+    #[error(
+        "Evaluation of `{0}` exceeded a resource limit \
+        (configured with `buck2.build_file_max_statements`, `buck2.build_file_max_eval_ms` \
+        and `buck2.build_file_max_heap_bytes`)"
+    )]
+    Exceeded(OwnedStarlarkPath),
+}
+
+/// Limits on evaluation of a single build file, so a pathological macro
+/// fails loading instead of stalling the daemon.
+fn build_file_eval_limits(
+    root_buckconfig: &dyn LegacyBuckConfigView,
+) -> anyhow::Result<Option<EvalLimits>> {
+    let limits = EvalLimits {
+        max_statements: root_buckconfig.parse("buck2", "build_file_max_statements")?,
+        max_duration: root_buckconfig
+            .parse("buck2", "build_file_max_eval_ms")?
+            .map(Duration::from_millis),
+        max_heap_bytes: root_buckconfig.parse("buck2", "build_file_max_heap_bytes")?,
+    };
+    if limits.max_statements.is_none()
+        && limits.max_duration.is_none()
+        && limits.max_heap_bytes.is_none()
+    {
+        Ok(None)
+    } else {
+        Ok(Some(limits))
+    }
+}
+
+fn is_eval_limit_exceeded(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<Diagnostic>() {
+        Some(diagnostic) => diagnostic.message.is::<EvalLimitExceeded>(),
+        None => error.is::<EvalLimitExceeded>(),
+    }
+}
+
 /// A ParseResult includes the parsed AST and a list of the imported files.
 ///
 /// The imports are under a separate Arc so that that can be shared with
@@ -306,18 +349,18 @@ impl InterpreterForCell {
     // functions can be invoked when evaluating a build file, the package (cell
     // + path) is available. It also includes the implicit root include and
     // implicit package include.
-    fn create_build_env(
+    fn create_build_env<'a>(
         &self,
         build_file: &BuildFilePath,
-        package_listing: &PackageListing,
+        package_listing: Arc<dyn PackageListingView + 'a>,
         super_package: SuperPackage,
         package_boundary_exception: bool,
         loaded_modules: &LoadedModules,
-    ) -> anyhow::Result<(Module, ModuleInternals)> {
+    ) -> anyhow::Result<(Module, ModuleInternals<'a>)> {
         let internals = self.global_state.configuror.new_extra_context(
             self.get_cell_config(build_file.build_file_cell()),
             build_file.clone(),
-            package_listing.dupe(),
+            package_listing,
             super_package,
             package_boundary_exception,
             loaded_modules,
@@ -441,20 +484,25 @@ impl InterpreterForCell {
         self.load_resolver(import).resolve_load(import_string, None)
     }
 
-    fn eval(
-        self: &Arc<Self>,
-        env: &Module,
+    fn eval<'a>(
+        self: &'a Arc<Self>,
+        env: &'a Module,
         ast: AstModule,
-        buckconfig: &dyn LegacyBuckConfigView,
-        root_buckconfig: &dyn LegacyBuckConfigView,
+        buckconfig: &'a dyn LegacyBuckConfigView,
+        root_buckconfig: &'a dyn LegacyBuckConfigView,
         loaded_modules: LoadedModules,
-        extra_context: PerFileTypeContext,
+        extra_context: PerFileTypeContext<'a>,
         eval_provider: &mut dyn StarlarkEvaluatorProvider,
-    ) -> anyhow::Result<PerFileTypeContext> {
+    ) -> anyhow::Result<PerFileTypeContext<'a>> {
         let import = extra_context.starlark_path();
         let globals = self
             .global_state
             .globals_for_file_type(extra_context.file_type());
+        let limits = match extra_context.file_type() {
+            StarlarkFileType::Buck => build_file_eval_limits(root_buckconfig)?
+                .map(|limits| (limits, OwnedStarlarkPath::new(import))),
+            _ => None,
+        };
         let file_loader =
             InterpreterFileLoader::new(loaded_modules, Arc::new(self.load_resolver(import)));
         let cell_info = self.get_cell_config(import.build_file_cell());
@@ -477,6 +525,13 @@ impl InterpreterForCell {
             if self.verbose_gc {
                 eval.verbose_gc();
             }
+            let limits_path = match limits {
+                Some((limits, path)) => {
+                    eval.set_limits(limits);
+                    Some(path)
+                }
+                None => None,
+            };
             match eval.eval_module(ast, globals) {
                 Ok(_) => {
                     eval_provider
@@ -486,7 +541,12 @@ impl InterpreterForCell {
                         .visit_frozen_module(None)
                         .context("Profiler heap visitation failed")?
                 }
-                Err(p) => return Err(p),
+                Err(p) => match limits_path {
+                    Some(path) if is_eval_limit_exceeded(&p) => {
+                        return Err(p.context(StarlarkEvalLimitsError::Exceeded(path)));
+                    }
+                    _ => return Err(p),
+                },
             }
         };
         Ok(extra.additional)
@@ -540,6 +600,7 @@ impl InterpreterForCell {
             PackageFileEvalCtx {
                 parent,
                 visibility: RefCell::new(None),
+                cfg_modifiers: RefCell::new(None),
             },
         );
 
@@ -566,10 +627,18 @@ impl InterpreterForCell {
             .keys()
             .cloned()
             .collect::<Vec<_>>();
+        let default_attr_names = package_values
+            .default_attrs
+            .borrow()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        // Package values followed by default attribute values.
         let values = package_values
             .values
             .borrow()
             .values()
+            .chain(package_values.default_attrs.borrow().values())
             .copied()
             .collect::<Vec<_>>();
         // Starlark list of strings. We only need to freeze values, not keys.
@@ -588,17 +657,25 @@ impl InterpreterForCell {
         let values = ListRef::from_frozen_value(values)
             .context("extra_value is not a list (internal error)")?;
 
-        let package_values: SmallMap<String, OwnedFrozenValue> = keys
-            .into_iter()
-            .zip(values.content().map(|v| {
+        let mut values = values
+            .iter()
+            .map(|v| {
                 let frozen_value = v.unpack_frozen().unwrap();
                 unsafe { OwnedFrozenValue::new(env.frozen_heap().dupe(), frozen_value) }
-            }))
+            })
+            .collect::<Vec<_>>();
+        let default_attr_values = values.split_off(keys.len());
+
+        let package_values: SmallMap<String, OwnedFrozenValue> =
+            keys.into_iter().zip(values).collect();
+        let default_attrs: SmallMap<String, OwnedFrozenValue> = default_attr_names
+            .into_iter()
+            .zip(default_attr_values)
             .collect();
 
         let package_file_eval_ctx = per_file_context.into_package_file()?;
 
-        Ok(package_file_eval_ctx.build_super_package(package_values))
+        Ok(package_file_eval_ctx.build_super_package(package_values, default_attrs))
     }
 
     /// Evaluates the AST for a parsed build file. Loaded modules must contain the
@@ -609,7 +686,7 @@ impl InterpreterForCell {
         build_file: &BuildFilePath,
         buckconfig: &dyn LegacyBuckConfigView,
         root_buckconfig: &dyn LegacyBuckConfigView,
-        listing: PackageListing,
+        listing: Arc<dyn PackageListingView + '_>,
         super_package: SuperPackage,
         package_boundary_exception: bool,
         ast: AstModule,
@@ -618,7 +695,7 @@ impl InterpreterForCell {
     ) -> anyhow::Result<EvaluationResult> {
         let (env, internals) = self.create_build_env(
             build_file,
-            &listing,
+            listing,
             super_package,
             package_boundary_exception,
             &loaded_modules,
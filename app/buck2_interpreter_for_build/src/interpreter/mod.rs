@@ -20,5 +20,6 @@ pub mod interpreter_for_cell;
 pub mod interpreter_setup;
 pub mod module_internals;
 pub mod natives;
+pub mod package_listing_view;
 pub mod print_handler;
 pub mod testing;
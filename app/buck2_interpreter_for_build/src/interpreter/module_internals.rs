@@ -14,13 +14,11 @@ use std::fmt::Debug;
 use std::mem;
 use std::sync::Arc;
 
-use buck2_common::package_listing::listing::PackageListing;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::bzl::ImportPath;
-use buck2_core::package::package_relative_path::PackageRelativePath;
+use buck2_core::package::package_relative_path::PackageRelativePathBuf;
 use buck2_core::target::name::TargetNameRef;
 use buck2_events::dispatch::console_message;
-use buck2_interpreter::globspec::GlobSpec;
 use buck2_interpreter::package_imports::ImplicitImport;
 use buck2_node::nodes::eval_result::EvaluationResult;
 use buck2_node::nodes::targets_map::TargetsMap;
@@ -32,11 +30,12 @@ use starlark::environment::FrozenModule;
 use starlark::values::OwnedFrozenValue;
 
 use crate::attrs::coerce::ctx::BuildAttrCoercionContext;
+use crate::interpreter::package_listing_view::PackageListingView;
 use crate::super_package::data::SuperPackage;
 
-impl From<ModuleInternals> for EvaluationResult {
+impl<'a> From<ModuleInternals<'a>> for EvaluationResult {
     // TODO(cjhopman): Let's make this an `into_evaluation_result()` on ModuleInternals instead.
-    fn from(internals: ModuleInternals) -> Self {
+    fn from(internals: ModuleInternals<'a>) -> Self {
         let ModuleInternals {
             state,
             imports,
@@ -73,8 +72,8 @@ enum State {
 /// package-specific information or objects can get them by acquiring the
 /// ModuleInternals.
 #[derive(Debug)]
-pub struct ModuleInternals {
-    attr_coercion_context: BuildAttrCoercionContext,
+pub struct ModuleInternals<'a> {
+    attr_coercion_context: BuildAttrCoercionContext<'a>,
     buildfile_path: Arc<BuildFilePath>,
     /// Have you seen an oncall annotation yet
     state: RefCell<State>,
@@ -84,8 +83,8 @@ pub struct ModuleInternals {
     default_visibility_to_public: bool,
     record_target_call_stacks: bool,
     skip_targets_with_duplicate_names: bool,
-    /// The files owned by this directory. Is `None` for .bzl files.
-    package_listing: PackageListing,
+    /// The files owned by this directory.
+    package_listing: Arc<dyn PackageListingView + 'a>,
     pub(crate) super_package: SuperPackage,
 }
 
@@ -116,16 +115,16 @@ enum OncallErrors {
     DuplicateOncall,
 }
 
-impl ModuleInternals {
+impl<'a> ModuleInternals<'a> {
     pub(crate) fn new(
-        attr_coercion_context: BuildAttrCoercionContext,
+        attr_coercion_context: BuildAttrCoercionContext<'a>,
         buildfile_path: Arc<BuildFilePath>,
         imports: Vec<ImportPath>,
         package_implicits: Option<PackageImplicits>,
         default_visibility_to_public: bool,
         record_target_call_stacks: bool,
         skip_targets_with_duplicate_names: bool,
-        package_listing: PackageListing,
+        package_listing: Arc<dyn PackageListingView + 'a>,
         super_package: SuperPackage,
     ) -> Self {
         Self {
@@ -142,7 +141,7 @@ impl ModuleInternals {
         }
     }
 
-    pub(crate) fn attr_coercion_context(&self) -> &BuildAttrCoercionContext {
+    pub(crate) fn attr_coercion_context(&self) -> &BuildAttrCoercionContext<'a> {
         &self.attr_coercion_context
     }
 
@@ -187,6 +186,7 @@ impl ModuleInternals {
                                 buildfile_path: self.buildfile_path.dupe(),
                                 oncall,
                                 default_visibility_to_public: self.default_visibility_to_public,
+                                cfg_modifiers: self.super_package.cfg_modifiers().dupe(),
                             }),
                             recorder: TargetsRecorder::new(),
                         });
@@ -223,11 +223,12 @@ impl ModuleInternals {
         self.record_target_call_stacks
     }
 
-    pub(crate) fn resolve_glob<'a>(
-        &'a self,
-        spec: &'a GlobSpec,
-    ) -> impl Iterator<Item = &'a PackageRelativePath> {
-        spec.resolve_glob(self.package_listing.files())
+    pub(crate) fn resolve_glob(
+        &self,
+        include: &[String],
+        exclude: &[String],
+    ) -> anyhow::Result<Arc<Vec<PackageRelativePathBuf>>> {
+        self.package_listing.glob(include, exclude)
     }
 }
 
@@ -0,0 +1,297 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Package listing as seen by build file evaluation.
+//!
+//! Build file evaluation does not depend on the whole package listing:
+//! it depends on results of `glob()` calls and on presence of files referenced
+//! by source attributes. Evaluation on DICE records only these as dependencies,
+//! so adding or removing a file which is not matched by any of these queries
+//! does not cause build file re-evaluation.
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use buck2_common::package_listing::dice::HasPackageListingResolver;
+use buck2_common::package_listing::dice::PackageListingKey;
+use buck2_common::package_listing::listing::PackageListing;
+use buck2_common::result::SharedResult;
+use buck2_common::result::ToUnsharedResultExt;
+use buck2_core::fs::paths::file_name::FileName;
+use buck2_core::fs::paths::file_name::FileNameBuf;
+use buck2_core::package::package_relative_path::PackageRelativePath;
+use buck2_core::package::package_relative_path::PackageRelativePathBuf;
+use buck2_core::package::PackageLabel;
+use buck2_interpreter::globspec::GlobSpec;
+use buck2_util::arc_str::ArcS;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::DiceProjectionComputations;
+use dice::OpaqueValue;
+use dice::ProjectionKey;
+use dupe::Dupe;
+use dupe::IterDupedExt;
+
+/// Directory within a package.
+#[derive(Debug, Eq, PartialEq, Allocative)]
+pub struct PackageListingDir {
+    pub dir: ArcS<PackageRelativePath>,
+    /// Files within the directory, recursively.
+    pub files: Box<[ArcS<PackageRelativePath>]>,
+    /// Any subpackage within the directory.
+    pub subpackage: Option<PackageRelativePathBuf>,
+}
+
+impl PackageListingDir {
+    fn from_listing(listing: &PackageListing, dir: &PackageRelativePath) -> Option<Arc<Self>> {
+        let dir = listing.get_dir(dir)?;
+        Some(Arc::new(PackageListingDir {
+            files: listing.files_within(&dir).duped().collect(),
+            subpackage: listing
+                .subpackages_within(&dir)
+                .next()
+                .map(|p| p.to_owned()),
+            dir,
+        }))
+    }
+}
+
+fn resolve_glob(listing: &PackageListing, spec: &GlobSpec) -> Arc<Vec<PackageRelativePathBuf>> {
+    Arc::new(
+        spec.resolve_glob(listing.files())
+            .map(|path| path.to_owned())
+            .collect(),
+    )
+}
+
+/// Queries to the package listing made during build file evaluation.
+pub trait PackageListingView: Debug {
+    fn get_file(
+        &self,
+        file: &PackageRelativePath,
+    ) -> anyhow::Result<Option<ArcS<PackageRelativePath>>>;
+
+    fn get_dir(&self, dir: &PackageRelativePath) -> anyhow::Result<Option<Arc<PackageListingDir>>>;
+
+    /// Files matching the glob, in listing order.
+    fn glob(
+        &self,
+        include: &[String],
+        exclude: &[String],
+    ) -> anyhow::Result<Arc<Vec<PackageRelativePathBuf>>>;
+}
+
+impl PackageListingView for PackageListing {
+    fn get_file(
+        &self,
+        file: &PackageRelativePath,
+    ) -> anyhow::Result<Option<ArcS<PackageRelativePath>>> {
+        Ok(PackageListing::get_file(self, file))
+    }
+
+    fn get_dir(&self, dir: &PackageRelativePath) -> anyhow::Result<Option<Arc<PackageListingDir>>> {
+        Ok(PackageListingDir::from_listing(self, dir))
+    }
+
+    fn glob(
+        &self,
+        include: &[String],
+        exclude: &[String],
+    ) -> anyhow::Result<Arc<Vec<PackageRelativePathBuf>>> {
+        Ok(resolve_glob(self, &GlobSpec::new(include, exclude)?))
+    }
+}
+
+/// Package listing view which records each query as a separate DICE dependency.
+#[derive(Debug)]
+pub(crate) struct PackageListingOnDice<'a> {
+    listing: OpaqueValue<'a, PackageListingKey>,
+    buildfile: Arc<FileNameBuf>,
+}
+
+impl<'a> PackageListingOnDice<'a> {
+    pub(crate) async fn new(
+        ctx: &'a DiceComputations,
+        package: PackageLabel,
+    ) -> anyhow::Result<PackageListingOnDice<'a>> {
+        let listing = ctx.get_package_listing_opaque(package).await?;
+        // Listing errors are reported here, other projections treat invalid listing as empty.
+        let buildfile = listing
+            .projection(&PackageListingBuildfileKey)?
+            .unshared_error()?;
+        Ok(PackageListingOnDice { listing, buildfile })
+    }
+
+    pub(crate) fn buildfile(&self) -> &FileName {
+        &self.buildfile
+    }
+}
+
+impl<'a> PackageListingView for PackageListingOnDice<'a> {
+    fn get_file(
+        &self,
+        file: &PackageRelativePath,
+    ) -> anyhow::Result<Option<ArcS<PackageRelativePath>>> {
+        Ok(self.listing.projection(&PackageListingFileKey {
+            path: file.to_owned(),
+        })?)
+    }
+
+    fn get_dir(&self, dir: &PackageRelativePath) -> anyhow::Result<Option<Arc<PackageListingDir>>> {
+        Ok(self.listing.projection(&PackageListingDirKey {
+            path: dir.to_owned(),
+        })?)
+    }
+
+    fn glob(
+        &self,
+        include: &[String],
+        exclude: &[String],
+    ) -> anyhow::Result<Arc<Vec<PackageRelativePathBuf>>> {
+        // Keyed by the parsed patterns, so equivalent globs share a key.
+        Ok(self.listing.projection(&GlobKey {
+            spec: Arc::new(GlobSpec::new(include, exclude)?),
+        })?)
+    }
+}
+
+#[derive(Debug, Display, Hash, Eq, PartialEq, Clone, Dupe, Allocative)]
+#[display(fmt = "{:?}", self)]
+struct PackageListingBuildfileKey;
+
+impl ProjectionKey for PackageListingBuildfileKey {
+    type DeriveFromKey = PackageListingKey;
+    type Value = SharedResult<Arc<FileNameBuf>>;
+
+    fn compute(
+        &self,
+        listing: &SharedResult<PackageListing>,
+        _ctx: &DiceProjectionComputations,
+    ) -> Self::Value {
+        Ok(Arc::new(listing.dupe()?.buildfile().to_owned()))
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Display, Hash, Eq, PartialEq, Clone, Allocative)]
+#[display(fmt = "file:{}", path)]
+struct PackageListingFileKey {
+    path: PackageRelativePathBuf,
+}
+
+impl ProjectionKey for PackageListingFileKey {
+    type DeriveFromKey = PackageListingKey;
+    type Value = Option<ArcS<PackageRelativePath>>;
+
+    fn compute(
+        &self,
+        listing: &SharedResult<PackageListing>,
+        _ctx: &DiceProjectionComputations,
+    ) -> Self::Value {
+        match listing {
+            Ok(listing) => listing.get_file(&self.path),
+            Err(_) => None,
+        }
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Debug, Display, Hash, Eq, PartialEq, Clone, Allocative)]
+#[display(fmt = "dir:{}", path)]
+struct PackageListingDirKey {
+    path: PackageRelativePathBuf,
+}
+
+impl ProjectionKey for PackageListingDirKey {
+    type DeriveFromKey = PackageListingKey;
+    type Value = Option<Arc<PackageListingDir>>;
+
+    fn compute(
+        &self,
+        listing: &SharedResult<PackageListing>,
+        _ctx: &DiceProjectionComputations,
+    ) -> Self::Value {
+        match listing {
+            Ok(listing) => PackageListingDir::from_listing(listing, &self.path),
+            Err(_) => None,
+        }
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Debug, Display, Hash, Eq, PartialEq, Clone, Dupe, Allocative)]
+#[display(fmt = "{:?}", spec)]
+struct GlobKey {
+    #[allocative(skip)]
+    spec: Arc<GlobSpec>,
+}
+
+impl ProjectionKey for GlobKey {
+    type DeriveFromKey = PackageListingKey;
+    type Value = Arc<Vec<PackageRelativePathBuf>>;
+
+    fn compute(
+        &self,
+        listing: &SharedResult<PackageListing>,
+        _ctx: &DiceProjectionComputations,
+    ) -> Self::Value {
+        match listing {
+            Ok(listing) => resolve_glob(listing, &self.spec),
+            Err(_) => Arc::new(Vec::new()),
+        }
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_common::package_listing::listing::testing::PackageListingExt;
+    use buck2_common::package_listing::listing::PackageListing;
+    use buck2_core::package::package_relative_path::PackageRelativePath;
+
+    use crate::interpreter::package_listing_view::PackageListingView;
+
+    #[test]
+    fn test_listing_view() -> anyhow::Result<()> {
+        let listing = PackageListing::testing_files(&["a.txt", "d/b.txt", "d/e/c.txt"]);
+        let view: &dyn PackageListingView = &listing;
+
+        assert!(view.get_file(PackageRelativePath::new("a.txt")?)?.is_some());
+        assert!(view.get_file(PackageRelativePath::new("d")?)?.is_none());
+
+        let dir = view.get_dir(PackageRelativePath::empty())?.unwrap();
+        assert_eq!(
+            vec!["a.txt", "d/b.txt", "d/e/c.txt"],
+            dir.files.iter().map(|f| f.as_str()).collect::<Vec<_>>()
+        );
+
+        let glob = view.glob(&["**/*.txt".to_owned()], &["d/e/**".to_owned()])?;
+        assert_eq!(
+            vec!["a.txt", "d/b.txt"],
+            glob.iter().map(|f| f.as_str()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
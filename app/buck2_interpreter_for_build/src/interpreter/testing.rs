@@ -310,7 +310,7 @@ impl Tester {
             path,
             buckconfig,
             root_buckconfig,
-            package_listing,
+            Arc::new(package_listing),
             SuperPackage::default(),
             false,
             ast,
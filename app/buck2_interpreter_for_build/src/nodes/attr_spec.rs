@@ -30,6 +30,12 @@ use starlark::values::Value;
 use crate::attrs::AttributeCoerceExt;
 use crate::interpreter::module_internals::ModuleInternals;
 
+#[derive(Debug, thiserror::Error)]
+enum AttributeSpecError {
+    #[error("`PACKAGE` file sets a default for attribute `{0}`, which `{1}` does not have")]
+    UnknownDefaultAttr(String, String),
+}
+
 pub trait AttributeSpecExt {
     fn parse_params<'v>(
         &self,
@@ -73,6 +79,16 @@ impl AttributeSpecExt for AttributeSpec {
             _ => panic!("First attribute is `name`, it is known"),
         };
 
+        for attr_name in internals.super_package.default_attrs().keys() {
+            if self.attribute(attr_name).is_none() {
+                return Err(AttributeSpecError::UnknownDefaultAttr(
+                    attr_name.to_owned(),
+                    format!("{}:{}", internals.buildfile_path().package(), name),
+                )
+                .into());
+            }
+        }
+
         for (attr_name, attr_idx, attribute) in indices {
             let configurable = attr_is_configurable(attr_name);
 
@@ -141,6 +157,28 @@ impl AttributeSpecExt for AttributeSpec {
                         CoercedAttr::Visibility(internals.super_package.visibility().dupe()),
                     );
                 }
+            } else if let Some(default) = internals.super_package.default_attrs().get(attr_name) {
+                let coerced = attribute
+                    .coerce(
+                        attr_name,
+                        configurable,
+                        internals.attr_coercion_context(),
+                        default.value(),
+                    )
+                    .with_context(|| {
+                        format!(
+                            "Error coercing `PACKAGE` default of attribute `{}` of `{}:{}`",
+                            attr_name,
+                            internals.buildfile_path().package(),
+                            name,
+                        )
+                    })?;
+                match coerced {
+                    CoercedValue::Custom(v) => {
+                        attr_values.push_sorted(attr_idx, v);
+                    }
+                    CoercedValue::Default => {}
+                }
             }
         }
 
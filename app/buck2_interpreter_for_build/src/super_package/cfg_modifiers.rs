@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_core::target::label::TargetLabel;
+use buck2_node::configuration::modifiers::PackageCfgModifiers;
+use starlark::environment::GlobalsBuilder;
+use starlark::eval::Evaluator;
+use starlark::starlark_module;
+use starlark::values::none::NoneType;
+
+use crate::interpreter::build_context::BuildContext;
+
+#[derive(Debug, thiserror::Error)]
+enum CfgModifiersError {
+    #[error("`set_cfg_modifiers` function can be used at most once per `PACKAGE` file")]
+    AtMostOnce,
+}
+
+#[starlark_module]
+pub(crate) fn register_set_cfg_modifiers(globals: &mut GlobalsBuilder) {
+    /// Set configuration modifiers for targets in this package and nested packages.
+    ///
+    /// Each modifier is a `constraint_value` or `config_setting` target.
+    /// Constraints of modifiers are applied on top of the target platform
+    /// when configuring top-level targets. Modifiers of nested `PACKAGE` files
+    /// take precedence over modifiers of parent files.
+    fn set_cfg_modifiers(
+        #[starlark(require = pos)] modifiers: Vec<String>,
+        eval: &mut Evaluator,
+    ) -> anyhow::Result<NoneType> {
+        let build_context = BuildContext::from_context(eval)?;
+        let package_ctx = build_context
+            .additional
+            .require_package_file("set_cfg_modifiers")?;
+        let modifiers = modifiers
+            .iter()
+            .map(|m| {
+                TargetLabel::parse(
+                    m,
+                    build_context.cell_info().name().name(),
+                    build_context.cell_info().cell_resolver(),
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        match &mut *package_ctx.cfg_modifiers.borrow_mut() {
+            Some(_) => return Err(CfgModifiersError::AtMostOnce.into()),
+            x => *x = Some(PackageCfgModifiers::new(modifiers)),
+        }
+
+        Ok(NoneType)
+    }
+}
@@ -10,6 +10,7 @@
 use std::sync::Arc;
 
 use allocative::Allocative;
+use buck2_node::configuration::modifiers::PackageCfgModifiers;
 use buck2_node::visibility::VisibilitySpecification;
 use buck2_node::visibility::WithinViewSpecification;
 use dupe::Dupe;
@@ -19,8 +20,11 @@ use starlark_map::small_map::SmallMap;
 #[derive(Default, Debug, Allocative)]
 pub(crate) struct SuperPackageData {
     package_values: SmallMap<String, OwnedFrozenValue>,
+    /// Default values of rule attributes, coerced when a rule is declared.
+    default_attrs: SmallMap<String, OwnedFrozenValue>,
     visibility: VisibilitySpecification,
     within_view: WithinViewSpecification,
+    cfg_modifiers: PackageCfgModifiers,
 }
 
 /// Contents of a `PACKAGE` file merged with contents of containing `PACKAGE` files.
@@ -31,13 +35,17 @@ pub(crate) struct SuperPackage(Arc<SuperPackageData>);
 impl SuperPackage {
     pub(crate) fn new(
         package_values: SmallMap<String, OwnedFrozenValue>,
+        default_attrs: SmallMap<String, OwnedFrozenValue>,
         visibility: VisibilitySpecification,
         within_view: WithinViewSpecification,
+        cfg_modifiers: PackageCfgModifiers,
     ) -> SuperPackage {
         SuperPackage(Arc::new(SuperPackageData {
             package_values,
+            default_attrs,
             visibility,
             within_view,
+            cfg_modifiers,
         }))
     }
 
@@ -45,6 +53,10 @@ impl SuperPackage {
         &self.0.package_values
     }
 
+    pub(crate) fn default_attrs(&self) -> &SmallMap<String, OwnedFrozenValue> {
+        &self.0.default_attrs
+    }
+
     pub(crate) fn visibility(&self) -> &VisibilitySpecification {
         &self.0.visibility
     }
@@ -52,25 +64,39 @@ impl SuperPackage {
     pub(crate) fn within_view(&self) -> &WithinViewSpecification {
         &self.0.within_view
     }
+
+    pub(crate) fn cfg_modifiers(&self) -> &PackageCfgModifiers {
+        &self.0.cfg_modifiers
+    }
 }
 
 impl PartialEq for SuperPackage {
     fn eq(&self, other: &Self) -> bool {
         let SuperPackageData {
             package_values: this_values,
+            default_attrs: this_default_attrs,
             visibility: this_visibility,
             within_view: this_within_view,
+            cfg_modifiers: this_cfg_modifiers,
         } = &*self.0;
         let SuperPackageData {
             package_values: other_values,
+            default_attrs: other_default_attrs,
             visibility: other_visibility,
             within_view: other_within_view,
+            cfg_modifiers: other_cfg_modifiers,
         } = &*other.0;
-        (this_visibility, this_within_view) == (other_visibility, other_within_view) && {
-            // If either package values are not empty, we cannot compare them
-            // because we cannot reliably compare arbitrary Starlark values.
-            // So if either package values are not empty, we consider super package not equal.
-            this_values.is_empty() && other_values.is_empty()
-        }
+        (this_visibility, this_within_view, this_cfg_modifiers)
+            == (other_visibility, other_within_view, other_cfg_modifiers)
+            && {
+                // If either package values are not empty, we cannot compare them
+                // because we cannot reliably compare arbitrary Starlark values.
+                // So if either package values are not empty, we consider super package not equal.
+                // Same for default attributes.
+                this_values.is_empty()
+                    && other_values.is_empty()
+                    && this_default_attrs.is_empty()
+                    && other_default_attrs.is_empty()
+            }
     }
 }
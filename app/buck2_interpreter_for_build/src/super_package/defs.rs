@@ -9,6 +9,7 @@
 
 use starlark::environment::GlobalsBuilder;
 
+use crate::super_package::cfg_modifiers::register_set_cfg_modifiers;
 use crate::super_package::package::register_package_function;
 use crate::super_package::package_value::register_write_package_value;
 
@@ -16,4 +17,5 @@ use crate::super_package::package_value::register_write_package_value;
 pub fn register_package_natives(globals: &mut GlobalsBuilder) {
     register_package_function(globals);
     register_write_package_value(globals);
+    register_set_cfg_modifiers(globals);
 }
@@ -9,8 +9,10 @@
 
 use std::cell::RefCell;
 
+use buck2_node::configuration::modifiers::PackageCfgModifiers;
 use buck2_node::visibility::VisibilitySpecification;
 use buck2_node::visibility::WithinViewSpecification;
+use dupe::Dupe;
 use starlark::values::OwnedFrozenValue;
 use starlark_map::small_map::SmallMap;
 
@@ -29,21 +31,34 @@ pub(crate) struct PackageFileEvalCtx {
     /// When evaluating root `PACKAGE` file, parent is still defined.
     pub(crate) parent: SuperPackage,
     pub(crate) visibility: RefCell<Option<PackageFileVisibilityFields>>,
+    /// Set by `set_cfg_modifiers`.
+    pub(crate) cfg_modifiers: RefCell<Option<PackageCfgModifiers>>,
 }
 
 impl PackageFileEvalCtx {
     pub(crate) fn build_super_package(
         self,
         package_values: SmallMap<String, OwnedFrozenValue>,
+        default_attrs: SmallMap<String, OwnedFrozenValue>,
     ) -> SuperPackage {
         let mut merged_package_values = self.parent.package_values().clone();
         merged_package_values.extend(package_values);
 
+        let visibility_fields = self.visibility.into_inner();
+
+        // Defaults of nested `PACKAGE` files override defaults of parent files. Parent defaults
+        // are dropped by `package(inherit = False)`, but kept if `package()` is not called.
+        let mut merged_default_attrs = match &visibility_fields {
+            Some(PackageFileVisibilityFields { inherit: false, .. }) => SmallMap::new(),
+            _ => self.parent.default_attrs().clone(),
+        };
+        merged_default_attrs.extend(default_attrs);
+
         let PackageFileVisibilityFields {
             visibility,
             within_view,
             inherit,
-        } = self.visibility.into_inner().unwrap_or_default();
+        } = visibility_fields.unwrap_or_default();
 
         let (visibility, within_view) = if inherit {
             (
@@ -54,6 +69,17 @@ impl PackageFileEvalCtx {
             (visibility, within_view)
         };
 
-        SuperPackage::new(merged_package_values, visibility, within_view)
+        let cfg_modifiers = match self.cfg_modifiers.into_inner() {
+            Some(cfg_modifiers) => self.parent.cfg_modifiers().extend_with(&cfg_modifiers),
+            None => self.parent.cfg_modifiers().dupe(),
+        };
+
+        SuperPackage::new(
+            merged_package_values,
+            merged_default_attrs,
+            visibility,
+            within_view,
+            cfg_modifiers,
+        )
     }
 }
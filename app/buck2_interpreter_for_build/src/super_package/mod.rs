@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+pub(crate) mod cfg_modifiers;
 pub(crate) mod data;
 pub mod defs;
 pub(crate) mod eval_ctx;
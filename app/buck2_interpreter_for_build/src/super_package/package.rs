@@ -7,9 +7,12 @@
  * of this source tree.
  */
 
+use anyhow::Context;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::pattern::ParsedPattern;
+use buck2_node::attrs::internal::NAME_ATTRIBUTE_FIELD;
+use buck2_node::attrs::internal::VISIBILITY_ATTRIBUTE_FIELD;
 use buck2_node::visibility::VisibilityPattern;
 use buck2_node::visibility::VisibilitySpecification;
 use buck2_node::visibility::WithinViewSpecification;
@@ -18,10 +21,13 @@ use starlark::environment::GlobalsBuilder;
 use starlark::eval::Evaluator;
 use starlark::starlark_module;
 use starlark::values::none::NoneType;
+use starlark::values::Value;
+use starlark_map::small_map::SmallMap;
 
 use crate::interpreter::build_context::BuildContext;
 use crate::interpreter::build_context::PerFileTypeContext;
 use crate::super_package::eval_ctx::PackageFileVisibilityFields;
+use crate::super_package::package_value::PackageValues;
 
 #[derive(Debug, thiserror::Error)]
 enum PackageFileError {
@@ -32,6 +38,8 @@ enum PackageFileError {
     NotPackage,
     #[error("`package()` function can be used at most once per `PACKAGE` file")]
     AtMostOnce,
+    #[error("Attribute `{0}` cannot be set with `package(default_attrs=...)`")]
+    DefaultAttrNotAllowed(String),
 }
 
 fn parse_visibility(
@@ -85,11 +93,23 @@ fn parse_within_view(
 /// Globals for `PACKAGE` files and `bzl` files included from `PACKAGE` files.
 #[starlark_module]
 pub(crate) fn register_package_function(globals: &mut GlobalsBuilder) {
-    fn package(
+    /// Set properties of this package and nested packages.
+    ///
+    /// `default_attrs` sets default values of rule attributes for all the targets
+    /// in this package and nested packages, unless the attribute is set explicitly.
+    /// Values are coerced to the attribute type of each rule, and declaring a rule
+    /// which does not have one of these attributes is an error.
+    /// Defaults from nested `PACKAGE` files override defaults of parent files,
+    /// which are only kept with `inherit = True`.
+    fn package<'v>(
         #[starlark(require=named, default=false)] inherit: bool,
         #[starlark(require=named, default=Vec::new())] visibility: Vec<String>,
         #[starlark(require=named, default=Vec::new())] within_view: Vec<String>,
-        eval: &mut Evaluator,
+        #[starlark(require=named, default=SmallMap::new())] default_attrs: SmallMap<
+            String,
+            Value<'v>,
+        >,
+        eval: &mut Evaluator<'v, '_>,
     ) -> anyhow::Result<NoneType> {
         let build_context = BuildContext::from_context(eval)?;
         let package_file_eval_ctx = match &build_context.additional {
@@ -107,6 +127,12 @@ pub(crate) fn register_package_function(globals: &mut GlobalsBuilder) {
             build_context.cell_info().cell_resolver(),
         )?;
 
+        for name in default_attrs.keys() {
+            if name == NAME_ATTRIBUTE_FIELD || name == VISIBILITY_ATTRIBUTE_FIELD {
+                return Err(PackageFileError::DefaultAttrNotAllowed(name.to_owned()).into());
+            }
+        }
+
         match &mut *package_file_eval_ctx.visibility.borrow_mut() {
             Some(_) => return Err(PackageFileError::AtMostOnce.into()),
             x => {
@@ -118,6 +144,17 @@ pub(crate) fn register_package_function(globals: &mut GlobalsBuilder) {
             }
         };
 
+        if !default_attrs.is_empty() {
+            let extra_value = eval
+                .module()
+                .extra_value()
+                .context("Module extra value was not set (internal error)")?;
+            let package_values = extra_value
+                .downcast_ref::<PackageValues>()
+                .context("Module extra value was not a `PackageValues` (internal error)")?;
+            *package_values.default_attrs.borrow_mut() = default_attrs;
+        }
+
         Ok(NoneType)
     }
 }
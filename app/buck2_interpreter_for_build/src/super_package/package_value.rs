@@ -56,6 +56,8 @@ fn validate_key(key: &str) -> anyhow::Result<()> {
 #[display(fmt = "{:?}", self)]
 pub(crate) struct PackageValues<'v> {
     pub(crate) values: RefCell<SmallMap<String, Value<'v>>>,
+    /// Default attribute values set by `package(default_attrs=...)`.
+    pub(crate) default_attrs: RefCell<SmallMap<String, Value<'v>>>,
 }
 
 impl<'v> StarlarkValue<'v> for PackageValues<'v> {
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use buck2_common::package_listing::listing::testing::PackageListingExt;
 use buck2_common::package_listing::listing::PackageListing;
 use buck2_common::result::SharedResult;
@@ -19,13 +21,17 @@ use buck2_interpreter_for_build::attrs::coerce::attr_type::AttrTypeExt;
 use buck2_interpreter_for_build::attrs::coerce::ctx::BuildAttrCoercionContext;
 use buck2_interpreter_for_build::interpreter::testing::cells;
 use buck2_interpreter_for_build::interpreter::testing::Tester;
+use buck2_node::attrs::attr_type::record::RecordField;
 use buck2_node::attrs::attr_type::AttrType;
 use buck2_node::attrs::coercion_context::AttrCoercionContext;
 use buck2_node::attrs::configurable::AttrIsConfigurable;
+use buck2_node::attrs::hacks::value_to_json;
 use buck2_node::attrs::hacks::value_to_string;
 use buck2_node::provider_id_set::ProviderIdSet;
+use buck2_util::arc_str::ArcStr;
 use dupe::Dupe;
 use indoc::indoc;
+use starlark::values::dict::AllocDict;
 use starlark::values::Heap;
 
 fn tester() -> Tester {
@@ -117,9 +123,11 @@ fn attr_coercer_coerces() -> anyhow::Result<()> {
         CellName::testing_new("root"),
         CellRelativePath::unchecked_new("foo"),
     );
-    let enclosing_package = (package.dupe(), PackageListing::testing_empty());
-    let coercer_ctx =
-        BuildAttrCoercionContext::new_with_package(cell_resolver, enclosing_package, false);
+    let coercer_ctx = BuildAttrCoercionContext::new_with_package(
+        cell_resolver,
+        (package.dupe(), Arc::new(PackageListing::testing_empty())),
+        false,
+    );
     let label_coercer = AttrType::dep(ProviderIdSet::EMPTY);
     let string_coercer = AttrType::string();
     let enum_coercer = AttrType::enumeration(vec![
@@ -210,6 +218,94 @@ fn attr_coercer_coerces() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn record_works() -> SharedResult<()> {
+    let mut tester = tester();
+    tester.run_starlark_bzl_test(indoc!(
+        r#"
+        frozen = attrs.record(name = attrs.string(), count = attrs.int(default = 0))
+        def test():
+            assert_eq('attrs.record(name=attrs.string(), count=attrs.int())', repr(frozen))
+        "#
+    ))
+}
+
+#[test]
+fn record_coerces() -> anyhow::Result<()> {
+    let heap = Heap::new();
+    let some_cells = cells(None)?;
+    let cell_resolver = some_cells.1;
+    let package = PackageLabel::new(
+        CellName::testing_new("root"),
+        CellRelativePath::unchecked_new("foo"),
+    );
+    let coercer_ctx = BuildAttrCoercionContext::new_with_package(
+        cell_resolver,
+        (package.dupe(), Arc::new(PackageListing::testing_empty())),
+        false,
+    );
+    let count_default =
+        AttrType::int().coerce(AttrIsConfigurable::Yes, &coercer_ctx, heap.alloc(7))?;
+    let record_coercer = AttrType::record(vec![
+        RecordField {
+            name: ArcStr::from("name"),
+            ty: AttrType::string(),
+            default: None,
+        },
+        RecordField {
+            name: ArcStr::from("count"),
+            ty: AttrType::int(),
+            default: Some(Arc::new(count_default)),
+        },
+    ]);
+
+    let value = record_coercer.coerce(
+        AttrIsConfigurable::Yes,
+        &coercer_ctx,
+        heap.alloc(AllocDict([("name", heap.alloc("foo"))])),
+    )?;
+    assert_eq!(
+        serde_json::json!({"name": "foo", "count": 7}),
+        value_to_json(&value, package.dupe())?
+    );
+
+    // Missing field without default.
+    assert!(
+        record_coercer
+            .coerce(
+                AttrIsConfigurable::Yes,
+                &coercer_ctx,
+                heap.alloc(AllocDict([("count", heap.alloc(1))])),
+            )
+            .is_err()
+    );
+    // Unknown field.
+    assert!(
+        record_coercer
+            .coerce(
+                AttrIsConfigurable::Yes,
+                &coercer_ctx,
+                heap.alloc(AllocDict([
+                    ("name", heap.alloc("foo")),
+                    ("size", heap.alloc(1))
+                ])),
+            )
+            .is_err()
+    );
+    // Wrong field type.
+    assert!(
+        record_coercer
+            .coerce(
+                AttrIsConfigurable::Yes,
+                &coercer_ctx,
+                heap.alloc(AllocDict([("name", heap.alloc(1))])),
+            )
+            .is_err()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn dep_works() -> SharedResult<()> {
     let mut t = tester();
@@ -288,7 +384,7 @@ fn coercing_src_to_path_works() -> anyhow::Result<()> {
         cell_resolver.dupe(),
         (
             package.dupe(),
-            PackageListing::testing_files(&["baz/quz.cpp"]),
+            Arc::new(PackageListing::testing_files(&["baz/quz.cpp"])),
         ),
         false,
     );
@@ -185,6 +185,7 @@ fn cells() -> CellsData {
         cell_resolver,
         configs_by_name,
         config_paths: _,
+        unmaterialized_external_cells: _,
     } = BuckConfigBasedCells::parse_with_file_ops(
         &project_fs,
         &mut TestConfigParserFileOps::new(&[(
@@ -518,3 +519,73 @@ fn test_oncall() -> anyhow::Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_build_file_eval_limits() {
+    let mut tester = Tester::with_cells(
+        buck2_interpreter_for_build::interpreter::testing::cells(Some(indoc!(
+            r#"
+            [buck2]
+                build_file_max_statements = 1000
+        "#
+        )))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let import_path = ImportPath::testing_new("root//:defs.bzl");
+    tester
+        .add_import(
+            &import_path,
+            indoc!(
+                r#"
+                    def spin(n):
+                        x = 0
+                        for i in range(n):
+                            x += i
+                        return x
+                "#
+            ),
+        )
+        .unwrap();
+
+    let build_path = BuildFilePath::testing_new("root//some/package:BUCK");
+    tester
+        .eval_build_file(
+            &build_path,
+            indoc!(
+                r#"
+                load("//:defs.bzl", "spin")
+                spin(10)
+                "#
+            ),
+            PackageListing::testing_empty(),
+        )
+        .unwrap();
+
+    let err = tester
+        .eval_build_file(
+            &build_path,
+            indoc!(
+                r#"
+                load("//:defs.bzl", "spin")
+                spin(100000)
+                "#
+            ),
+            PackageListing::testing_empty(),
+        )
+        .unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(
+        message.contains("Evaluation of `root//some/package:BUCK` exceeded a resource limit"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("executed more than 1000 statements"),
+        "{}",
+        message
+    );
+    // Call stack points into the offending function.
+    assert!(message.contains("spin(100000)"), "{}", message);
+}
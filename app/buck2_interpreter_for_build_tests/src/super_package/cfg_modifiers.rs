@@ -0,0 +1,64 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use buck2_core::cells::build_file_cell::BuildFileCell;
+use buck2_core::fs::project::ProjectRootTemp;
+use buck2_core::package::PackageLabel;
+use buck2_interpreter::starlark_profiler::StarlarkProfilerOrInstrumentation;
+use buck2_interpreter_for_build::interpreter::dice_calculation_delegate::HasCalculationDelegate;
+use indoc::indoc;
+
+use crate::tests::calculation;
+use crate::tests::root_cell;
+
+#[tokio::test]
+async fn test_cfg_modifiers_nested_package_files() {
+    let fs = ProjectRootTemp::new().unwrap();
+
+    fs.write_file(
+        "rules.bzl",
+        "rrr = rule(impl = lambda ctx: DefaultInfo(), attrs = {})",
+    );
+    fs.write_file("PACKAGE", "set_cfg_modifiers(['//constraints:opt'])");
+    fs.write_file("mouse/PACKAGE", "set_cfg_modifiers(['//mouse:asan'])");
+    fs.write_file(
+        "mouse/BUCK",
+        indoc!(
+            r#"
+                load("//:rules.bzl", "rrr")
+                rrr(name = "mouse")
+            "#
+        ),
+    );
+
+    let ctx = calculation(&fs).await;
+    let interpreter = ctx
+        .get_interpreter_calculator(root_cell(), BuildFileCell::new(root_cell()))
+        .await
+        .unwrap();
+
+    let result = interpreter
+        .eval_build_file(
+            PackageLabel::testing_parse("root//mouse"),
+            &mut StarlarkProfilerOrInstrumentation::disabled(),
+        )
+        .await
+        .unwrap();
+
+    let target_nodes: Vec<_> = result.targets().values().collect();
+    assert_eq!(1, target_nodes.len());
+    assert_eq!(
+        vec!["root//constraints:opt", "root//mouse:asan"],
+        target_nodes[0]
+            .package_cfg_modifiers()
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>()
+    );
+}
@@ -7,5 +7,6 @@
  * of this source tree.
  */
 
+mod cfg_modifiers;
 mod package_function;
 mod package_value;
@@ -9,7 +9,10 @@
 
 use buck2_core::fs::project::ProjectRootTemp;
 use buck2_core::target::label::TargetLabel;
+use buck2_node::attrs::display::AttrDisplayWithContextExt;
+use buck2_node::attrs::inspect_options::AttrInspectOptions;
 use buck2_node::nodes::frontend::TargetGraphCalculation;
+use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_node::visibility::VisibilitySpecification;
 
 use crate::tests::calculation;
@@ -141,3 +144,133 @@ simple(name = "a")
         a.visibility().unwrap(),
     );
 }
+
+#[tokio::test]
+async fn test_package_default_attrs() {
+    let fs = ProjectRootTemp::new().unwrap();
+
+    fs.write_file(
+        "rules.bzl",
+        r#"
+simple = rule(
+    impl = lambda ctx: fail(),
+    attrs = {
+        "labels": attrs.list(attrs.string(), default = []),
+        "value": attrs.string(default = ""),
+    },
+)
+"#,
+    );
+    fs.write_file(
+        "PACKAGE",
+        r#"
+package(
+    default_attrs = {
+        "labels": ["from_root"],
+        "value": "root",
+    },
+)
+"#,
+    );
+    fs.write_file(
+        "juxtaposition/PACKAGE",
+        r#"
+package(
+    default_attrs = {"value": "nested"},
+    inherit = True,
+)
+"#,
+    );
+    fs.write_file(
+        "juxtaposition/BUCK",
+        r#"
+load("//:rules.bzl", "simple")
+simple(name = "a")
+simple(name = "b", labels = ["explicit"])
+"#,
+    );
+    fs.write_file(
+        "not_inherit/PACKAGE",
+        r#"
+package(
+    default_attrs = {"value": "not_inherit"},
+)
+"#,
+    );
+    fs.write_file(
+        "not_inherit/BUCK",
+        r#"
+load("//:rules.bzl", "simple")
+simple(name = "c")
+"#,
+    );
+
+    let ctx = calculation(&fs).await;
+
+    let attr = |node: &TargetNode, name: &str| {
+        node.attr(name, AttrInspectOptions::DefinedOnly)
+            .unwrap()
+            .unwrap()
+            .as_display_no_ctx()
+            .to_string()
+    };
+
+    let a = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:a"))
+        .await
+        .unwrap();
+    assert_eq!("[\"from_root\"]", attr(&a, "labels"));
+    assert_eq!("\"nested\"", attr(&a, "value"));
+
+    let b = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:b"))
+        .await
+        .unwrap();
+    assert_eq!("[\"explicit\"]", attr(&b, "labels"));
+    assert_eq!("\"nested\"", attr(&b, "value"));
+
+    let c = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//not_inherit:c"))
+        .await
+        .unwrap();
+    assert!(
+        c.attr("labels", AttrInspectOptions::DefinedOnly)
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!("\"not_inherit\"", attr(&c, "value"));
+}
+
+#[tokio::test]
+async fn test_package_default_attrs_unknown() {
+    let fs = ProjectRootTemp::new().unwrap();
+
+    fs.write_file("rules.bzl", RULES_BZL);
+    fs.write_file(
+        "PACKAGE",
+        r#"
+package(
+    default_attrs = {"not_an_attr": 17},
+)
+"#,
+    );
+    fs.write_file(
+        "juxtaposition/BUCK",
+        r#"
+load("//:rules.bzl", "simple")
+simple(name = "a")
+"#,
+    );
+
+    let ctx = calculation(&fs).await;
+
+    let err = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:a"))
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("default for attribute `not_an_attr`"),
+        "{:?}",
+        err
+    );
+}
@@ -7,13 +7,17 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use buck2_build_api::interpreter::rule_defs::register_rule_defs;
 use buck2_common::dice::cells::SetCellResolver;
 use buck2_common::dice::data::testing::SetTestingIoProvider;
+use buck2_common::dice::file_ops::FileChangeTracker;
 use buck2_common::legacy_configs::dice::SetLegacyConfigs;
 use buck2_common::legacy_configs::LegacyBuckConfig;
 use buck2_common::legacy_configs::LegacyBuckConfigs;
 use buck2_core::bzl::ImportPath;
+use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::cell_root_path::CellRootPathBuf;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
@@ -234,3 +238,65 @@ async fn test_eval_build_file() {
 
     assert_eq!(vec!["invoke_some-exported", "java"], target_names);
 }
+
+#[tokio::test]
+async fn test_unmatched_file_change_does_not_reevaluate_build_file() {
+    let fs = ProjectRootTemp::new().unwrap();
+
+    fs.write_file(
+        "rules.bzl",
+        indoc!(
+            r#"
+                def _impl(ctx):
+                    return DefaultInfo()
+
+                java_library = rule(
+                    impl = _impl,
+                    attrs = {
+                        "srcs": attrs.list(attrs.string()),
+                    },
+                )
+        "#
+        ),
+    );
+    fs.write_file("pkg/file1.java", "");
+    fs.write_file(
+        "pkg/BUCK",
+        indoc!(
+            r#"
+                load("//rules.bzl", "java_library")
+
+                java_library(
+                    name = "java",
+                    srcs = glob(["*.java"]),
+                )
+            "#
+        ),
+    );
+
+    let ctx = calculation(&fs).await;
+    let package = PackageLabel::testing_parse("root//pkg");
+    let before = ctx.get_interpreter_results(package.dupe()).await.unwrap();
+
+    // A file that no glob matches changes the listing, but not the evaluation.
+    fs.write_file("pkg/README.md", "");
+    let mut updater = ctx.into_updater();
+    let mut changes = FileChangeTracker::new();
+    changes.file_added(CellPath::testing_new("root//pkg/README.md"));
+    changes.write_to_dice(&mut updater).unwrap();
+    let ctx = updater.commit().await;
+
+    let after = ctx.get_interpreter_results(package.dupe()).await.unwrap();
+    assert!(Arc::ptr_eq(&before, &after));
+
+    // A file matched by the glob does re-evaluate the build file.
+    fs.write_file("pkg/file2.java", "");
+    let mut updater = ctx.into_updater();
+    let mut changes = FileChangeTracker::new();
+    changes.file_added(CellPath::testing_new("root//pkg/file2.java"));
+    changes.write_to_dice(&mut updater).unwrap();
+    let ctx = updater.commit().await;
+
+    let after = ctx.get_interpreter_results(package.dupe()).await.unwrap();
+    assert!(!Arc::ptr_eq(&before, &after));
+}
@@ -31,6 +31,7 @@ impl ToJsonWithContext for ConfiguredAttr {
             ConfiguredAttr::String(v) | ConfiguredAttr::EnumVariant(v) => Ok(to_value(v)?),
             ConfiguredAttr::List(list) => list.to_json(ctx),
             ConfiguredAttr::Tuple(list) => list.to_json(ctx),
+            ConfiguredAttr::Record(record) => record.to_json(ctx),
             ConfiguredAttr::Dict(dict) => dict.to_json(ctx),
             ConfiguredAttr::None => Ok(serde_json::Value::Null),
             ConfiguredAttr::OneOf(box l, _) => l.to_json(ctx),
@@ -54,6 +55,7 @@ impl AnyMatches for ConfiguredAttr {
             ConfiguredAttr::String(v) | ConfiguredAttr::EnumVariant(v) => filter(v),
             ConfiguredAttr::List(vals) => vals.any_matches(filter),
             ConfiguredAttr::Tuple(vals) => vals.any_matches(filter),
+            ConfiguredAttr::Record(vals) => vals.any_matches(filter),
             ConfiguredAttr::Dict(d) => d.any_matches(filter),
             ConfiguredAttr::None => Ok(false),
             ConfiguredAttr::Bool(b) => b.any_matches(filter),
@@ -30,6 +30,8 @@ use crate::attrs::attr_type::list::ListAttrType;
 use crate::attrs::attr_type::one_of::OneOfAttrType;
 use crate::attrs::attr_type::option::OptionAttrType;
 use crate::attrs::attr_type::query::QueryAttrType;
+use crate::attrs::attr_type::record::RecordAttrType;
+use crate::attrs::attr_type::record::RecordField;
 use crate::attrs::attr_type::source::SourceAttrType;
 use crate::attrs::attr_type::split_transition_dep::SplitTransitionDepAttrType;
 use crate::attrs::attr_type::string::StringAttrType;
@@ -55,6 +57,7 @@ pub mod list;
 pub mod one_of;
 pub mod option;
 pub mod query;
+pub mod record;
 pub mod source;
 pub mod split_transition_dep;
 pub mod string;
@@ -76,6 +79,7 @@ pub enum AttrTypeInner {
     Dict(DictAttrType),
     List(ListAttrType),
     Tuple(TupleAttrType),
+    Record(RecordAttrType),
     OneOf(OneOfAttrType),
     Option(OptionAttrType),
     Query(QueryAttrType),
@@ -114,6 +118,7 @@ impl AttrType {
             AttrTypeInner::Dict(x) => x.fmt_with_arg(f, &arg()),
             AttrTypeInner::List(x) => x.fmt_with_arg(f, &arg()),
             AttrTypeInner::Tuple(x) => x.fmt_with_arg(f, &arg()),
+            AttrTypeInner::Record(x) => x.fmt_with_arg(f, &arg()),
             AttrTypeInner::OneOf(x) => x.fmt_with_arg(f, &arg()),
             AttrTypeInner::Option(x) => x.fmt_with_arg(f, &arg()),
             AttrTypeInner::Enum(x) => x.fmt_with_arg(f, &arg()),
@@ -235,6 +240,11 @@ impl AttrType {
         Self(Arc::new(AttrTypeInner::Tuple(TupleAttrType::new(xs))))
     }
 
+    /// A record attribute with named fields of the specified types.
+    pub fn record(fields: Vec<RecordField>) -> Self {
+        Self(Arc::new(AttrTypeInner::Record(RecordAttrType::new(fields))))
+    }
+
     pub fn one_of(xs: Vec<AttrType>) -> Self {
         Self(Arc::new(AttrTypeInner::OneOf(OneOfAttrType::new(xs))))
     }
@@ -288,6 +298,7 @@ impl AttrType {
             | AttrTypeInner::Int(_)
             | AttrTypeInner::Dep(_)
             | AttrTypeInner::Tuple(_)
+            | AttrTypeInner::Record(_)
             | AttrTypeInner::SplitTransitionDep(_)
             | AttrTypeInner::Label(_)
             | AttrTypeInner::Enum(_)
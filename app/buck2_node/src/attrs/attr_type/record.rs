@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use buck2_util::arc_str::ArcSlice;
+use buck2_util::arc_str::ArcStr;
+use serde_json::Value;
+
+use crate::attrs::attr_type::any_matches::AnyMatches;
+use crate::attrs::attr_type::AttrType;
+use crate::attrs::coerced_attr::CoercedAttr;
+use crate::attrs::display::AttrDisplayWithContext;
+use crate::attrs::display::AttrDisplayWithContextExt;
+use crate::attrs::fmt_context::AttrFmtContext;
+use crate::attrs::json::ToJsonWithContext;
+
+#[derive(Debug, Eq, PartialEq, Hash, Allocative)]
+pub struct RecordField {
+    pub name: ArcStr,
+    pub ty: AttrType,
+    /// Used when the field is not specified.
+    pub default: Option<Arc<CoercedAttr>>,
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Allocative)]
+pub struct RecordAttrType {
+    /// In declaration order.
+    pub fields: Vec<RecordField>,
+}
+
+impl RecordAttrType {
+    pub fn new(fields: Vec<RecordField>) -> Self {
+        Self { fields }
+    }
+
+    pub(crate) fn fmt_with_arg(&self, f: &mut fmt::Formatter<'_>, arg: &str) -> fmt::Result {
+        write!(f, "attrs.record(")?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", field.name, field.ty)?;
+        }
+        write!(f, "{})", arg)
+    }
+}
+
+/// Record value: field values in the order of fields of the record type.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Allocative, Default)]
+pub struct RecordLiteral<C: Eq>(pub ArcSlice<(ArcStr, C)>);
+
+impl<C: Eq> Deref for RecordLiteral<C> {
+    type Target = ArcSlice<(ArcStr, C)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<C: Eq + AttrDisplayWithContext> AttrDisplayWithContext for RecordLiteral<C> {
+    fn fmt(&self, ctx: &AttrFmtContext, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "record(")?;
+        for (i, (k, v)) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", k, v.as_display(ctx))?;
+        }
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+impl<C: Eq> FromIterator<(ArcStr, C)> for RecordLiteral<C> {
+    fn from_iter<T: IntoIterator<Item = (ArcStr, C)>>(iter: T) -> Self {
+        RecordLiteral(ArcSlice::from_iter(iter))
+    }
+}
+
+impl<C: Eq + AnyMatches> AnyMatches for RecordLiteral<C> {
+    fn any_matches(&self, filter: &dyn Fn(&str) -> anyhow::Result<bool>) -> anyhow::Result<bool> {
+        for (_, v) in self.0.iter() {
+            if v.any_matches(filter)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl<C: Eq + ToJsonWithContext> ToJsonWithContext for RecordLiteral<C> {
+    fn to_json(&self, ctx: &AttrFmtContext) -> anyhow::Result<Value> {
+        let mut res: serde_json::Map<String, serde_json::Value> =
+            serde_json::Map::with_capacity(self.len());
+        for (k, v) in self.iter() {
+            res.insert(k.as_str().to_owned(), v.to_json(ctx)?);
+        }
+        Ok(res.into())
+    }
+}
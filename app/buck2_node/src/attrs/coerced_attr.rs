@@ -43,6 +43,7 @@ use crate::attrs::attr_type::dict::DictLiteral;
 use crate::attrs::attr_type::label::LabelAttrType;
 use crate::attrs::attr_type::list::ListLiteral;
 use crate::attrs::attr_type::query::QueryAttr;
+use crate::attrs::attr_type::record::RecordLiteral;
 use crate::attrs::attr_type::string::StringLiteral;
 use crate::attrs::attr_type::tuple::TupleLiteral;
 use crate::attrs::attr_type::AttrType;
@@ -80,6 +81,8 @@ enum SelectError {
 enum CoercedAttrError {
     #[error("Inconsistent number of elements in tuple")]
     InconsistentTupleLength,
+    #[error("Inconsistent number of fields in record")]
+    InconsistentRecordLength,
 }
 
 enum CoercedSelectorKeyRef<'a> {
@@ -183,6 +186,7 @@ pub enum CoercedAttr {
     EnumVariant(StringLiteral),
     List(ListLiteral<CoercedAttr>),
     Tuple(TupleLiteral<CoercedAttr>),
+    Record(RecordLiteral<CoercedAttr>),
     Dict(DictLiteral<CoercedAttr>),
     None,
     // NOTE: unlike deps, labels are not traversed, as they are typically used in lieu of deps in
@@ -247,6 +251,7 @@ impl AttrDisplayWithContext for CoercedAttr {
             CoercedAttr::String(v) | CoercedAttr::EnumVariant(v) => Display::fmt(v, f),
             CoercedAttr::List(list) => AttrDisplayWithContext::fmt(list, ctx, f),
             CoercedAttr::Tuple(v) => AttrDisplayWithContext::fmt(v, ctx, f),
+            CoercedAttr::Record(v) => AttrDisplayWithContext::fmt(v, ctx, f),
             CoercedAttr::Dict(v) => AttrDisplayWithContext::fmt(v, ctx, f),
             CoercedAttr::None => write!(f, "None"),
             CoercedAttr::OneOf(box l, _) => AttrDisplayWithContext::fmt(l, ctx, f),
@@ -325,6 +330,7 @@ impl CoercedAttr {
             CoercedAttr::String(v) | CoercedAttr::EnumVariant(v) => Ok(to_value(v)?),
             CoercedAttr::List(list) => list.to_json(ctx),
             CoercedAttr::Tuple(list) => list.to_json(ctx),
+            CoercedAttr::Record(record) => record.to_json(ctx),
             CoercedAttr::Dict(dict) => dict.to_json(ctx),
             CoercedAttr::None => Ok(serde_json::Value::Null),
             CoercedAttr::OneOf(box l, _) => l.to_json(ctx),
@@ -422,6 +428,16 @@ impl CoercedAttr {
                 }
                 Ok(())
             }
+            CoercedAttrWithType::Record(record, t) => {
+                if record.len() != t.fields.len() {
+                    return Err(CoercedAttrError::InconsistentRecordLength.into());
+                }
+
+                for ((_, v), field) in record.iter().zip(&t.fields) {
+                    v.traverse(&field.ty, pkg.dupe(), traversal)?;
+                }
+                Ok(())
+            }
             CoercedAttrWithType::Dict(dict, t) => {
                 for (k, v) in dict.iter() {
                     k.traverse(&t.key, pkg.dupe(), traversal)?;
@@ -562,6 +578,18 @@ impl CoercedAttr {
                         .collect::<anyhow::Result<_>>()?,
                 ))
             }
+            CoercedAttrWithType::Record(record, t) => {
+                if record.len() != t.fields.len() {
+                    return Err(CoercedAttrError::InconsistentRecordLength.into());
+                }
+                ConfiguredAttr::Record(RecordLiteral(
+                    record
+                        .iter()
+                        .zip(&t.fields)
+                        .map(|((k, v), field)| Ok((k.dupe(), v.configure(&field.ty, ctx)?)))
+                        .collect::<anyhow::Result<_>>()?,
+                ))
+            }
             CoercedAttrWithType::Dict(dict, t) => ConfiguredAttr::Dict(DictLiteral(
                 dict.try_map(|(k, v)| {
                     let k2 = k.configure(&t.key, ctx)?;
@@ -625,6 +653,7 @@ impl CoercedAttr {
             CoercedAttr::String(v) | CoercedAttr::EnumVariant(v) => filter(v),
             CoercedAttr::List(vals) => vals.any_matches(filter),
             CoercedAttr::Tuple(vals) => vals.any_matches(filter),
+            CoercedAttr::Record(vals) => vals.any_matches(filter),
             CoercedAttr::Dict(d) => d.any_matches(filter),
             CoercedAttr::None => Ok(false),
             CoercedAttr::Bool(b) => b.any_matches(filter),
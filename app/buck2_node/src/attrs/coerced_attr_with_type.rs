@@ -31,6 +31,8 @@ use crate::attrs::attr_type::one_of::OneOfAttrType;
 use crate::attrs::attr_type::option::OptionAttrType;
 use crate::attrs::attr_type::query::QueryAttr;
 use crate::attrs::attr_type::query::QueryAttrType;
+use crate::attrs::attr_type::record::RecordAttrType;
+use crate::attrs::attr_type::record::RecordLiteral;
 use crate::attrs::attr_type::source::SourceAttrType;
 use crate::attrs::attr_type::split_transition_dep::SplitTransitionDepAttrType;
 use crate::attrs::attr_type::string::StringAttrType;
@@ -79,6 +81,7 @@ pub enum CoercedAttrWithType<'a, 't> {
     EnumVariant(&'a StringLiteral, &'t EnumAttrType),
     List(&'a ListLiteral<CoercedAttr>, &'t ListAttrType),
     Tuple(&'a TupleLiteral<CoercedAttr>, &'t TupleAttrType),
+    Record(&'a RecordLiteral<CoercedAttr>, &'t RecordAttrType),
     Dict(&'a DictLiteral<CoercedAttr>, &'t DictAttrType),
     OneOf(&'a CoercedAttr, u32, &'t OneOfAttrType),
     Visibility(&'a VisibilitySpecification, VisibilityAttrType),
@@ -124,6 +127,9 @@ impl<'a, 't> CoercedAttrWithType<'a, 't> {
             (CoercedAttr::Tuple(t), AttrTypeInner::Tuple(ty)) => {
                 Ok(CoercedAttrWithType::Tuple(t, ty))
             }
+            (CoercedAttr::Record(r), AttrTypeInner::Record(t)) => {
+                Ok(CoercedAttrWithType::Record(r, t))
+            }
             (CoercedAttr::Dict(d), AttrTypeInner::Dict(t)) => Ok(CoercedAttrWithType::Dict(d, t)),
             (CoercedAttr::OneOf(o, i), AttrTypeInner::OneOf(t)) => {
                 Ok(CoercedAttrWithType::OneOf(o, *i, t))
@@ -163,6 +169,7 @@ impl<'a, 't> CoercedAttrWithType<'a, 't> {
             | (CoercedAttr::EnumVariant(_), _)
             | (CoercedAttr::List(_), _)
             | (CoercedAttr::Tuple(_), _)
+            | (CoercedAttr::Record(_), _)
             | (CoercedAttr::Dict(_), _)
             | (CoercedAttr::OneOf(..), _)
             | (CoercedAttr::Visibility(_), _)
@@ -194,6 +201,7 @@ impl<'a, 't> CoercedAttrWithType<'a, 't> {
             CoercedAttr::Dict(d) => Ok(CoercedAttrWithType::AnyDict(d)),
             CoercedAttr::None => Ok(CoercedAttrWithType::None),
             CoercedAttr::OneOf(_, _)
+            | CoercedAttr::Record(_)
             | CoercedAttr::Visibility(_)
             | CoercedAttr::ExplicitConfiguredDep(_)
             | CoercedAttr::SplitTransitionDep(_)
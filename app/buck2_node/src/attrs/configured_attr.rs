@@ -30,6 +30,7 @@ use crate::attrs::attr_type::dep::DepAttr;
 use crate::attrs::attr_type::dict::DictLiteral;
 use crate::attrs::attr_type::list::ListLiteral;
 use crate::attrs::attr_type::query::QueryAttr;
+use crate::attrs::attr_type::record::RecordLiteral;
 use crate::attrs::attr_type::split_transition_dep::ConfiguredSplitTransitionDep;
 use crate::attrs::attr_type::string::StringLiteral;
 use crate::attrs::attr_type::tuple::TupleLiteral;
@@ -84,6 +85,7 @@ pub enum ConfiguredAttr {
     EnumVariant(StringLiteral),
     List(ListLiteral<ConfiguredAttr>),
     Tuple(TupleLiteral<ConfiguredAttr>),
+    Record(RecordLiteral<ConfiguredAttr>),
     Dict(DictLiteral<ConfiguredAttr>),
     None,
     // NOTE: unlike deps, labels are not traversed, as they are typically used in lieu of deps in
@@ -131,6 +133,7 @@ impl AttrDisplayWithContext for ConfiguredAttr {
             ConfiguredAttr::String(v) | ConfiguredAttr::EnumVariant(v) => Display::fmt(v, f),
             ConfiguredAttr::List(list) => AttrDisplayWithContext::fmt(list, ctx, f),
             ConfiguredAttr::Tuple(v) => AttrDisplayWithContext::fmt(v, ctx, f),
+            ConfiguredAttr::Record(v) => AttrDisplayWithContext::fmt(v, ctx, f),
             ConfiguredAttr::Dict(v) => AttrDisplayWithContext::fmt(v, ctx, f),
             ConfiguredAttr::None => write!(f, "None"),
             ConfiguredAttr::OneOf(box l, _) => AttrDisplayWithContext::fmt(l, ctx, f),
@@ -172,6 +175,12 @@ impl ConfiguredAttr {
                 }
                 Ok(())
             }
+            ConfiguredAttr::Record(record) => {
+                for (_, v) in record.iter() {
+                    v.traverse(pkg.dupe(), traversal)?;
+                }
+                Ok(())
+            }
             ConfiguredAttr::Dict(dict) => {
                 for (k, v) in dict.iter() {
                     k.traverse(pkg.dupe(), traversal)?;
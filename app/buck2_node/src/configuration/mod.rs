@@ -8,6 +8,7 @@
  */
 
 pub mod execution;
+pub mod modifiers;
 pub mod resolved;
 pub mod target_platform_detector;
 pub mod toolchain_constraints;
@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use allocative::Allocative;
+use buck2_core::target::label::TargetLabel;
+use buck2_util::arc_str::ThinArcSlice;
+use dupe::Dupe;
+
+/// Configuration modifiers set with `set_cfg_modifiers` in `PACKAGE` files.
+///
+/// Each modifier is a `constraint_value` or `config_setting` target.
+/// Modifiers from nested `PACKAGE` files follow modifiers from parent files,
+/// so they take precedence when applied.
+#[derive(Debug, Default, Clone, Dupe, Hash, Eq, PartialEq, Allocative)]
+pub struct PackageCfgModifiers(ThinArcSlice<TargetLabel>);
+
+impl PackageCfgModifiers {
+    pub fn new(modifiers: Vec<TargetLabel>) -> PackageCfgModifiers {
+        PackageCfgModifiers(modifiers.into_iter().collect())
+    }
+
+    /// Modifiers of a nested `PACKAGE` file.
+    pub fn extend_with(&self, child: &PackageCfgModifiers) -> PackageCfgModifiers {
+        if child.is_empty() {
+            return self.dupe();
+        }
+        if self.is_empty() {
+            return child.dupe();
+        }
+        PackageCfgModifiers(self.iter().chain(child.iter()).map(|m| m.dupe()).collect())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &TargetLabel> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::target::label::TargetLabel;
+
+    use crate::configuration::modifiers::PackageCfgModifiers;
+
+    #[test]
+    fn test_extend_with() {
+        let parent = PackageCfgModifiers::new(vec![TargetLabel::testing_parse("cfg//:opt")]);
+        let child = PackageCfgModifiers::new(vec![TargetLabel::testing_parse("cfg//:asan")]);
+        assert_eq!(
+            vec!["cfg//:opt", "cfg//:asan"],
+            parent
+                .extend_with(&child)
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(parent, parent.extend_with(&PackageCfgModifiers::default()));
+    }
+}
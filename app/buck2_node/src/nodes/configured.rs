@@ -197,6 +197,16 @@ struct ConfiguredNodeAttr {
 impl ConfiguredTargetNode {
     /// Creates a minimal ConfiguredTargetNode. Some operations may unexpectedly fail.
     pub fn testing_new(name: ConfiguredTargetLabel, rule_type: &str) -> Self {
+        Self::testing_new_with_deps(name, rule_type, Vec::new(), Vec::new())
+    }
+
+    /// Like `testing_new`, with the given (already configured) deps and exec deps.
+    pub fn testing_new_with_deps(
+        name: ConfiguredTargetLabel,
+        rule_type: &str,
+        deps: Vec<ConfiguredTargetNode>,
+        exec_deps: Vec<ConfiguredTargetNode>,
+    ) -> Self {
         use crate::nodes::unconfigured::testing::TargetNodeExt;
 
         let rule_type = RuleType::Starlark(Arc::new(StarlarkRuleType {
@@ -214,8 +224,8 @@ impl ConfiguredTargetNode {
             ),
             OrderedMap::new(),
             execution_platform_resolution,
-            Vec::new(),
-            Vec::new(),
+            deps,
+            exec_deps,
             OrderedMap::new(),
         )
     }
@@ -33,6 +33,7 @@ use crate::attrs::spec::AttributeSpec;
 use crate::attrs::traversal::CoercedAttrTraversal;
 use crate::attrs::values::AttrValues;
 use crate::call_stack::StarlarkCallStack;
+use crate::configuration::modifiers::PackageCfgModifiers;
 use crate::nodes::attributes::CONFIGURATION_DEPS;
 use crate::nodes::attributes::DEPS;
 use crate::nodes::attributes::ONCALL;
@@ -213,6 +214,11 @@ impl TargetNode {
         self.0.package.oncall.as_ref().map(|x| x.as_str())
     }
 
+    /// Configuration modifiers set in `PACKAGE` files.
+    pub fn package_cfg_modifiers(&self) -> &PackageCfgModifiers {
+        &self.0.package.cfg_modifiers
+    }
+
     pub fn visibility(&self) -> anyhow::Result<&VisibilitySpecification> {
         match self.0.attributes.get(AttributeSpec::visibility_attr_id()) {
             Some(CoercedAttr::Visibility(v)) => Ok(v),
@@ -494,6 +500,7 @@ pub mod testing {
                     buildfile_path,
                     oncall: None,
                     default_visibility_to_public: false,
+                    cfg_modifiers: PackageCfgModifiers::default(),
                 }),
                 label,
                 attributes,
@@ -12,6 +12,8 @@ use std::sync::Arc;
 use allocative::Allocative;
 use buck2_core::build_file_path::BuildFilePath;
 
+use crate::configuration::modifiers::PackageCfgModifiers;
+
 /// Package-specific data for `TargetNode`.
 ///
 /// (Note this has nothing to do with `PACKAGE` files which are not implemented
@@ -24,4 +26,6 @@ pub struct Package {
     pub oncall: Option<Arc<String>>,
     /// Visibility is public by default.
     pub default_visibility_to_public: bool,
+    /// Configuration modifiers from `PACKAGE` files.
+    pub cfg_modifiers: PackageCfgModifiers,
 }
@@ -0,0 +1,305 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Assembly of self-contained offline archives from an [`OfflineArchiveManifest`].
+//!
+//! The archive is a tarball with the following layout:
+//!
+//! ```text
+//! manifest.json   # The manifest the archive was created from.
+//! repo/           # Everything the build read from the project (sources, configs and the
+//!                 # offline cache of downloaded artifacts in buck-out).
+//! external/       # Files the build read from outside the project, keyed by absolute path.
+//! ```
+//!
+//! Symlinks pointing outside of the project are relocated to point into `external/`, so the
+//! archive can be extracted anywhere. `repo/` also gets a buckconfig that turns on
+//! `buck2.use_network_action_output_cache`, so running buck2 in the extracted `repo/` performs
+//! an offline build.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+
+use crate::OfflineArchiveManifest;
+
+/// Path of the manifest inside the archive.
+pub const ARCHIVE_MANIFEST_PATH: &str = "manifest.json";
+/// Directory containing project files inside the archive.
+pub const ARCHIVE_REPO_DIR: &str = "repo";
+/// Directory containing files from outside the project inside the archive.
+pub const ARCHIVE_EXTERNAL_DIR: &str = "external";
+/// Buckconfig added to the archived project so builds use the offline cache.
+const OFFLINE_BUCKCONFIG_PATH: &str = ".buckconfig.d/offline-archive";
+const OFFLINE_BUCKCONFIG: &str = "\
+# Added by `buck2 offline-archive create`: build using the artifacts downloaded when this
+# archive was created instead of accessing the network.
+[buck2]
+use_network_action_output_cache = true
+";
+
+/// Write an offline archive for `manifest` to `out`. Paths in the manifest are resolved relative
+/// to `project_root`. Directories are only archived recursively if they are under `buck_out`
+/// (i.e. they are action outputs): project directories are only recorded in the manifest because
+/// they were listed, and their relevant contents are listed in the manifest individually.
+///
+/// Outputs in `buck_out` must have been materialized already. Returns the paths of the manifest
+/// which do not exist, and so are not in the archive.
+pub fn write_archive(
+    manifest: &OfflineArchiveManifest,
+    project_root: &AbsNormPath,
+    buck_out: &ProjectRelativePath,
+    out: &Path,
+) -> anyhow::Result<Vec<ProjectRelativePathBuf>> {
+    let file = File::create(out).with_context(|| format!("creating `{}`", out.display()))?;
+    let mut builder = tar::Builder::new(BufWriter::new(file));
+    // We want to archive symlinks as symlinks: they're listed in the manifest separately.
+    builder.follow_symlinks(false);
+
+    let serialized = serde_json::to_vec_pretty(manifest)
+        .context("serializing offline archive manifest to json")?;
+    append_data(&mut builder, ARCHIVE_MANIFEST_PATH, &serialized)?;
+
+    let mut missing = Vec::new();
+    for path in &manifest.paths {
+        let abs_path = project_root.join(path);
+        let name = repo_path(path);
+        let metadata = match fs_util::symlink_metadata_if_exists(&abs_path)? {
+            Some(metadata) => metadata,
+            // The trace may reference files that were deleted since (or that never existed but
+            // were probed for). Either way, there is nothing to archive, but the caller should
+            // know since the offline build might need them.
+            None => {
+                missing.push(path.clone());
+                continue;
+            }
+        };
+
+        if metadata.is_dir() {
+            if path.starts_with(buck_out) {
+                builder
+                    .append_dir_all(&name, &abs_path)
+                    .with_context(|| format!("archiving directory `{}`", path))?;
+            } else {
+                builder
+                    .append_dir(&name, &abs_path)
+                    .with_context(|| format!("archiving directory `{}`", path))?;
+            }
+        } else if metadata.is_file() {
+            builder
+                .append_path_with_name(&abs_path, &name)
+                .with_context(|| format!("archiving file `{}`", path))?;
+        }
+        // Symlinks are handled below.
+    }
+
+    for symlink in &manifest.relative_symlinks {
+        // Those point within the project, so they are still valid in the archive as they are.
+        let abs_path = project_root.join(&symlink.link);
+        builder
+            .append_path_with_name(&abs_path, repo_path(&symlink.link))
+            .with_context(|| format!("archiving symlink `{}`", symlink.link))?;
+    }
+
+    for path in &manifest.external_paths {
+        if fs_util::symlink_metadata_if_exists(path)?.is_some() {
+            builder
+                .append_path_with_name(path, external_path(path.as_path()))
+                .with_context(|| format!("archiving external path `{}`", path))?;
+        }
+    }
+
+    for symlink in &manifest.external_symlinks {
+        // Point the link at the relocated target in `external/`.
+        let target = format!(
+            "{}{}",
+            "../".repeat(symlink.link.iter().count()),
+            external_path(symlink.target.as_path()).display(),
+        );
+        append_symlink(&mut builder, &repo_path(&symlink.link), &target)?;
+
+        for interior in symlink.interior_links()? {
+            let link = external_path(interior.link.as_path());
+            let target = format!(
+                "{}{}",
+                "../".repeat(link.components().count() - 1),
+                external_path(interior.target.as_path()).display(),
+            );
+            append_symlink(&mut builder, &link, &target)?;
+        }
+
+        // Archive what the build actually accessed through this link at its canonical location,
+        // since the path through the link may traverse the interior links we just added.
+        let full_target = symlink.full_target();
+        if let Some(metadata) = fs_util::symlink_metadata_if_exists(&full_target)? {
+            let canonical = fs_util::canonicalize(&full_target)?;
+            let name = external_path(canonical.as_path());
+            let res = if metadata.is_dir() {
+                builder.append_dir_all(&name, &canonical)
+            } else {
+                builder.append_path_with_name(&canonical, &name)
+            };
+            res.with_context(|| {
+                format!("archiving target of external symlink `{}`", symlink.link)
+            })?;
+        }
+    }
+
+    append_data(
+        &mut builder,
+        &format!("{}/{}", ARCHIVE_REPO_DIR, OFFLINE_BUCKCONFIG_PATH),
+        OFFLINE_BUCKCONFIG.as_bytes(),
+    )?;
+
+    builder
+        .into_inner()
+        .context("finishing archive")?
+        .flush()
+        .with_context(|| format!("writing `{}`", out.display()))?;
+
+    Ok(missing)
+}
+
+fn repo_path(path: &ProjectRelativePath) -> PathBuf {
+    Path::new(ARCHIVE_REPO_DIR).join(path.as_str())
+}
+
+/// Where an absolute path outside the project ends up in the archive.
+fn external_path(path: &Path) -> PathBuf {
+    let mut res = PathBuf::from(ARCHIVE_EXTERNAL_DIR);
+    // Drop the root (and prefix, on Windows) so that this stays relative to the archive root.
+    res.extend(path.components().filter(|c| {
+        matches!(
+            c,
+            std::path::Component::Normal(..) | std::path::Component::ParentDir
+        )
+    }));
+    res
+}
+
+fn append_data<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, name, data)
+        .with_context(|| format!("archiving `{}`", name))
+}
+
+fn append_symlink<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &Path,
+    target: &str,
+) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    header.set_mode(0o777);
+    builder
+        .append_link(&mut header, name, target)
+        .with_context(|| format!("archiving symlink `{}`", name.display()))
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::RepositoryMetadata;
+
+    #[test]
+    fn test_write_archive() -> anyhow::Result<()> {
+        let temp = TempDir::new()?;
+        let project_root = fs_util::canonicalize(temp.path())?;
+        let write = |path: &str| -> anyhow::Result<()> {
+            let path = project_root.join(ProjectRelativePath::new(path)?);
+            fs_util::create_dir_all(path.parent().unwrap())?;
+            fs_util::write(path, "")
+        };
+        write("project/foo/BUCK")?;
+        write("project/buck-out/v2/offline-cache/abc/file")?;
+        let project_root = project_root.join(ProjectRelativePath::new("project")?);
+
+        let manifest = OfflineArchiveManifest {
+            repository: RepositoryMetadata {
+                revision: "abc".to_owned(),
+                name: "repo".to_owned(),
+            },
+            paths: vec![
+                ProjectRelativePathBuf::unchecked_new("foo/BUCK".to_owned()),
+                ProjectRelativePathBuf::unchecked_new("foo/deleted".to_owned()),
+                ProjectRelativePathBuf::unchecked_new("buck-out/v2/offline-cache/abc".to_owned()),
+            ],
+            external_paths: Vec::new(),
+            relative_symlinks: Vec::new(),
+            external_symlinks: Vec::new(),
+        };
+        let out = temp.path().join("archive.tar");
+        let missing = write_archive(
+            &manifest,
+            &project_root,
+            ProjectRelativePath::new("buck-out/v2")?,
+            &out,
+        )?;
+        assert_eq!(
+            vec![ProjectRelativePathBuf::unchecked_new(
+                "foo/deleted".to_owned()
+            )],
+            missing
+        );
+
+        let mut archive = tar::Archive::new(File::open(&out)?);
+        let entries = archive
+            .entries()?
+            .map(|entry| Ok(entry?.path()?.display().to_string()))
+            .collect::<anyhow::Result<HashSet<_>>>()?;
+        for expected in [
+            "manifest.json",
+            "repo/foo/BUCK",
+            "repo/buck-out/v2/offline-cache/abc/file",
+            "repo/.buckconfig.d/offline-archive",
+        ] {
+            assert!(entries.contains(expected), "{} in {:?}", expected, entries);
+        }
+        assert!(!entries.iter().any(|e| e.contains("deleted")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_external_path() {
+        assert_eq!(
+            Path::new("external/usr/lib/libfoo.so"),
+            external_path(Path::new("/usr/lib/libfoo.so"))
+        );
+    }
+
+    #[test]
+    fn test_repo_path() {
+        assert_eq!(
+            Path::new("repo/foo/BUCK"),
+            repo_path(ProjectRelativePath::unchecked_new("foo/BUCK"))
+        );
+    }
+}
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+mod archive;
+
 use std::ffi::OsStr;
 use std::fmt;
 use std::path::Path;
@@ -21,6 +23,11 @@ use buck2_core::fs::paths::abs_path::AbsPathBuf;
 use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 
+pub use crate::archive::write_archive;
+pub use crate::archive::ARCHIVE_EXTERNAL_DIR;
+pub use crate::archive::ARCHIVE_MANIFEST_PATH;
+pub use crate::archive::ARCHIVE_REPO_DIR;
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct RelativeSymlink {
     pub link: ProjectRelativePathBuf,
@@ -75,6 +82,8 @@ impl ExternalSymlink {
 ///
 /// This manifest is generated by running:
 ///   `buck2 debug io-trace export-manifest`
+/// or, together with the files it references, by:
+///   `buck2 offline-archive create`
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct OfflineArchiveManifest {
     /// The repository revision this archive was generated from.
@@ -105,8 +114,26 @@ impl RepositoryMetadata {
     }
 
     pub fn from_path<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::from_hg(&path).or_else(|hg_error| {
+            // Not everyone uses Mercurial, try Git before giving up.
+            Self::from_git(&path).map_err(|_git_error| hg_error)
+        })
+    }
+
+    fn from_hg<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
         let revision = hg_in(&path, ["whereami"])?;
-        let name = hg_in(path, ["config", "remotefilelog.reponame"])?;
+        let name = hg_in(&path, ["config", "remotefilelog.reponame"])?;
+        Ok(Self { revision, name })
+    }
+
+    fn from_git<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let revision = run_in("git", &path, ["rev-parse", "HEAD"])?;
+        let toplevel = run_in("git", &path, ["rev-parse", "--show-toplevel"])?;
+        let name = Path::new(&toplevel)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .with_context(|| format!("invalid git toplevel: `{}`", toplevel))?
+            .to_owned();
         Ok(Self { revision, name })
     }
 }
@@ -131,22 +158,33 @@ where
     S: AsRef<OsStr>,
     P: AsRef<Path>,
 {
-    let result = Command::new("hg")
+    run_in("hg", path, args)
+}
+
+fn run_in<I, S, P>(program: &str, path: P, args: I) -> anyhow::Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+    P: AsRef<Path>,
+{
+    let result = Command::new(program)
         .args(args)
         .current_dir(path.as_ref())
         .env("HGPLAIN", "1")
         .output()
-        .context("failed to dispatch hg command")?;
+        .with_context(|| format!("failed to dispatch {} command", program))?;
     if result.status.success() {
-        let out = String::from_utf8(result.stdout).context("hg stdout to string")?;
+        let out = String::from_utf8(result.stdout)
+            .with_context(|| format!("{} stdout to string", program))?;
         let out = out.trim();
         if out.is_empty() {
-            anyhow::bail!("expected to be run in hg repository");
+            anyhow::bail!("expected to be run in {} repository", program);
         } else {
             Ok(out.to_owned())
         }
     } else {
-        let err = String::from_utf8(result.stderr).context("hg stderr to string")?;
+        let err = String::from_utf8(result.stderr)
+            .with_context(|| format!("{} stderr to string", program))?;
         Err(anyhow::anyhow!(err))
     }
 }
@@ -22,7 +22,7 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 enum CompatibilityErrors {
-    #[error("{0}")]
+    #[error("Target is incompatible:\n{0:#}")]
     TargetIncompatible(IncompatiblePlatformReason),
 }
 
@@ -73,10 +73,42 @@ impl<T> MaybeCompatible<T> {
 pub enum IncompatiblePlatformReasonCause {
     /// Target is incompatible because of unsatisfied config setting.
     UnsatisfiedConfig(TargetLabel),
+    /// Target is incompatible because its compatibility attribute is not satisfied.
+    UnsatisfiedCompatibility(Arc<UnsatisfiedCompatibility>),
     /// Target is incompatible because dependency is incompatible.
     Dependency(Arc<IncompatiblePlatformReason>),
 }
 
+/// Constraints of `target_compatible_with` or `compatible_with` attribute
+/// not satisfied by the target configuration.
+#[derive(Debug, Eq, PartialEq, Hash, Allocative)]
+pub struct UnsatisfiedCompatibility {
+    /// Name of the attribute which declares the constraints.
+    pub attr: String,
+    /// `true` if all the constraints must be satisfied (`target_compatible_with`),
+    /// `false` if any (`compatible_with`).
+    pub require_all: bool,
+    /// Constraints which are not satisfied. For `compatible_with` these are all the constraints.
+    pub unsatisfied: Vec<TargetLabel>,
+}
+
+impl Display for UnsatisfiedCompatibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.require_all {
+            write!(f, "`{}` unsatisfied: ", self.attr)?;
+        } else {
+            write!(f, "none of `{}` satisfied: ", self.attr)?;
+        }
+        for (i, label) in self.unsatisfied.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", label)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Allocative)]
 pub struct IncompatiblePlatformReason {
     pub target: ConfiguredTargetLabel,
@@ -88,6 +120,40 @@ impl IncompatiblePlatformReason {
         CompatibilityErrors::TargetIncompatible(self.clone()).into()
     }
 
+    /// The reason at the end of the dependency chain, i.e. the target
+    /// which is incompatible by itself rather than because of its dependencies.
+    pub fn root_cause(&self) -> &IncompatiblePlatformReason {
+        let mut reason = self;
+        while let IncompatiblePlatformReasonCause::Dependency(previous) = &reason.cause {
+            reason = previous;
+        }
+        reason
+    }
+
+    fn fmt_own_cause(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.cause {
+            IncompatiblePlatformReasonCause::UnsatisfiedConfig(unsatisfied_config) => write!(
+                f,
+                "{} is incompatible with {} ({} unsatisfied), check the target's compatibility attributes",
+                self.target.unconfigured(),
+                self.target.cfg(),
+                unsatisfied_config,
+            ),
+            IncompatiblePlatformReasonCause::UnsatisfiedCompatibility(unsatisfied) => write!(
+                f,
+                "{} is incompatible with {} ({})",
+                self.target.unconfigured(),
+                self.target.cfg(),
+                unsatisfied,
+            ),
+            IncompatiblePlatformReasonCause::Dependency(..) => write!(
+                f,
+                "{} is incompatible because of its dependency",
+                self.target
+            ),
+        }
+    }
+
     pub fn skipping_message(&self, target: &ConfiguredTargetLabel) -> String {
         format!("Skipping target incompatible node `{}`", target)
     }
@@ -123,21 +189,31 @@ impl IncompatiblePlatformReason {
 }
 
 impl Display for IncompatiblePlatformReason {
+    /// Alternate form prints the dependency chain as a tree, one target per line:
+    ///
+    /// ```text
+    /// root//:a (<cfg>)
+    /// └─ root//:b (<cfg>)
+    ///    └─ root//:c is incompatible with <cfg> (`target_compatible_with` unsatisfied: root//:linux)
+    /// ```
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match &self.cause {
-            IncompatiblePlatformReasonCause::UnsatisfiedConfig(unsatisfied_config) => write!(
-                f,
-                "{} is incompatible with {} ({} unsatisfied), check the target's compatibility attributes",
-                self.target.unconfigured(),
-                self.target.cfg(),
-                unsatisfied_config,
-            ),
-            IncompatiblePlatformReasonCause::Dependency(previous) => {
+        let mut reason = self;
+        let mut depth = 0;
+        loop {
+            if depth != 0 {
                 if f.alternate() {
-                    write!(f, "{}\n-> {:#}", self.target, previous)
+                    write!(f, "\n{}└─ ", "   ".repeat(depth - 1))?;
                 } else {
-                    write!(f, "{} -> {}", self.target, previous)
+                    write!(f, " -> ")?;
+                }
+            }
+            match &reason.cause {
+                IncompatiblePlatformReasonCause::Dependency(previous) => {
+                    write!(f, "{}", reason.target)?;
+                    reason = previous;
+                    depth += 1;
                 }
+                _ => return reason.fmt_own_cause(f),
             }
         }
     }
@@ -145,10 +221,62 @@ impl Display for IncompatiblePlatformReason {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use buck2_core::configuration::data::ConfigurationData;
     use buck2_core::target::label::TargetLabel;
+    use dupe::Dupe;
 
     use crate::query::compatibility::IncompatiblePlatformReason;
+    use crate::query::compatibility::IncompatiblePlatformReasonCause;
+    use crate::query::compatibility::UnsatisfiedCompatibility;
+
+    #[test]
+    fn test_reason_tree() {
+        let cfg = ConfigurationData::testing_new();
+        let root_cause = Arc::new(IncompatiblePlatformReason {
+            target: TargetLabel::testing_parse("cell//:c").configure(cfg.dupe()),
+            cause: IncompatiblePlatformReasonCause::UnsatisfiedCompatibility(Arc::new(
+                UnsatisfiedCompatibility {
+                    attr: "target_compatible_with".to_owned(),
+                    require_all: true,
+                    unsatisfied: vec![
+                        TargetLabel::testing_parse("cell//c:linux"),
+                        TargetLabel::testing_parse("cell//c:arm"),
+                    ],
+                },
+            )),
+        });
+        let reason = IncompatiblePlatformReason {
+            target: TargetLabel::testing_parse("cell//:a").configure(cfg.dupe()),
+            cause: IncompatiblePlatformReasonCause::Dependency(Arc::new(
+                IncompatiblePlatformReason {
+                    target: TargetLabel::testing_parse("cell//:b").configure(cfg.dupe()),
+                    cause: IncompatiblePlatformReasonCause::Dependency(root_cause.dupe()),
+                },
+            )),
+        };
+
+        assert_eq!(&*root_cause, reason.root_cause());
+        assert_eq!(
+            format!(
+                "cell//:a ({c}) -> cell//:b ({c}) -> cell//:c is incompatible with {c} \
+                (`target_compatible_with` unsatisfied: cell//c:linux, cell//c:arm)",
+                c = cfg
+            ),
+            format!("{}", reason)
+        );
+        assert_eq!(
+            format!(
+                "cell//:a ({c})\n\
+                └─ cell//:b ({c})\n   \
+                   └─ cell//:c is incompatible with {c} \
+                (`target_compatible_with` unsatisfied: cell//c:linux, cell//c:arm)",
+                c = cfg
+            ),
+            format!("{:#}", reason)
+        );
+    }
 
     #[test]
     fn test_skipping_message_for_multiple() {
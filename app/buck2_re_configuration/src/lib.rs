@@ -19,6 +19,8 @@ static BUCK2_RE_CLIENT_CFG_SECTION: &str = "buck2_re_client";
 pub trait RemoteExecutionStaticMetadataImpl: Sized {
     fn from_legacy_config(legacy_config: &LegacyBuckConfig) -> anyhow::Result<Self>;
     fn cas_semaphore_size(&self) -> usize;
+    /// Whether blobs can be resolved server-side via the Remote Asset API.
+    fn remote_asset_enabled(&self) -> bool;
 }
 
 #[allow(unused)]
@@ -103,6 +105,10 @@ mod fbcode {
         fn cas_semaphore_size(&self) -> usize {
             self.cas_connection_count as usize * 30
         }
+
+        fn remote_asset_enabled(&self) -> bool {
+            false
+        }
     }
 }
 
@@ -125,6 +131,10 @@ mod not_fbcode {
             // FIXME: make this configurable?
             1024
         }
+
+        fn remote_asset_enabled(&self) -> bool {
+            self.0.remote_asset_address.is_some()
+        }
     }
 }
 
@@ -138,6 +148,9 @@ pub struct Buck2OssReConfiguration {
     pub engine_address: Option<String>,
     /// Address for RBE Action Cache service.
     pub action_cache_address: Option<String>,
+    /// Address for the Remote Asset API (Fetch service). Unlike the other addresses, this does not
+    /// default to `address`, since many RE backends don't implement this API.
+    pub remote_asset_address: Option<String>,
     /// Whether to use TLS to interact with remote execution.
     pub tls: bool,
     /// Path to a CA certificates bundle. This must be PEM-encoded. If none is set, a default
@@ -158,6 +171,44 @@ pub struct Buck2OssReConfiguration {
     /// This can contain environment variables using shell interpolation syntax (i.e. $VAR). They
     /// will be substituted before using the value.
     pub http_headers: Vec<HttpHeader>,
+    /// Path to a credential helper executable used to obtain headers for requests to the RBE
+    /// Content Addressable Storage service (including bytestream). The helper is invoked as
+    /// `<helper> get`, receives a JSON request `{"uri": "<address>"}` on stdin and must print
+    /// `{"headers": {"Header": ["value"]}, "expires": "<RFC 3339 timestamp>"}` on stdout (the
+    /// same protocol as Bazel credential helpers). Headers are cached and refreshed before they
+    /// expire.
+    ///
+    /// This can contain environment variables using shell interpolation syntax (i.e. $VAR). They
+    /// will be substituted before using the value.
+    pub cas_credential_helper: Option<String>,
+    /// Credential helper for the RBE Engine service (including capabilities service).
+    pub engine_credential_helper: Option<String>,
+    /// Credential helper for the RBE Action Cache service.
+    pub action_cache_credential_helper: Option<String>,
+    /// Credential helper for the Remote Asset API.
+    pub remote_asset_credential_helper: Option<String>,
+    /// How long a credential helper can run before it is killed, in milliseconds. Defaults to
+    /// one minute.
+    pub credential_helper_timeout_ms: Option<u64>,
+    /// Deadline for CAS RPCs (FindMissingBlobs, BatchReadBlobs, BatchUpdateBlobs) in
+    /// milliseconds. No deadline is used if unset.
+    pub cas_rpc_timeout_ms: Option<u64>,
+    /// Deadline for Action Cache RPCs (GetActionResult) in milliseconds. No deadline is used if
+    /// unset.
+    pub action_cache_rpc_timeout_ms: Option<u64>,
+    /// How long to wait for an update on an `Execute` stream before considering the stream lost
+    /// and resuming it with `WaitExecution`, in milliseconds. Streams never time out if unset.
+    pub execute_stream_timeout_ms: Option<u64>,
+    /// How many times idempotent RPCs (FindMissingBlobs, BatchReadBlobs, GetActionResult) are
+    /// retried with exponential backoff, and how many times a lost `Execute` stream is resumed.
+    pub rpc_retries: Option<u32>,
+    /// After this many consecutive failures of RE execution or action cache calls (not counting
+    /// failures of the actions themselves), RE is considered unavailable and hybrid execution only
+    /// runs actions locally until the cooldown expires. CAS calls are not affected. Zero disables
+    /// this.
+    pub circuit_breaker_threshold: Option<u32>,
+    /// How long RE is considered unavailable once the circuit breaker trips, in milliseconds.
+    pub circuit_breaker_cooldown_ms: Option<u64>,
     /// Whether to query capabilities from the RBE backend.
     pub capabilities: Option<bool>,
     /// The instance name to use in requests.
@@ -194,6 +245,9 @@ impl Buck2OssReConfiguration {
         // them has an explicit address given as well though, use that instead
         let default_address: Option<String> =
             legacy_config.parse(BUCK2_RE_CLIENT_CFG_SECTION, "address")?;
+        // same for credential helpers
+        let default_credential_helper: Option<String> =
+            legacy_config.parse(BUCK2_RE_CLIENT_CFG_SECTION, "credential_helper")?;
 
         Ok(Self {
             cas_address: legacy_config
@@ -205,6 +259,8 @@ impl Buck2OssReConfiguration {
             action_cache_address: legacy_config
                 .parse(BUCK2_RE_CLIENT_CFG_SECTION, "action_cache_address")?
                 .or(default_address),
+            remote_asset_address: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "remote_asset_address")?,
             tls: legacy_config
                 .parse(BUCK2_RE_CLIENT_CFG_SECTION, "tls")?
                 .unwrap_or(true),
@@ -213,6 +269,37 @@ impl Buck2OssReConfiguration {
             http_headers: legacy_config
                 .parse_list(BUCK2_RE_CLIENT_CFG_SECTION, "http_headers")?
                 .unwrap_or_default(), // Empty list is as good None.
+            cas_credential_helper: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "cas_credential_helper")?
+                .or(default_credential_helper.clone()),
+            engine_credential_helper: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "engine_credential_helper")?
+                .or(default_credential_helper.clone()),
+            action_cache_credential_helper: legacy_config
+                .parse(
+                    BUCK2_RE_CLIENT_CFG_SECTION,
+                    "action_cache_credential_helper",
+                )?
+                .or(default_credential_helper.clone()),
+            remote_asset_credential_helper: legacy_config
+                .parse(
+                    BUCK2_RE_CLIENT_CFG_SECTION,
+                    "remote_asset_credential_helper",
+                )?
+                .or(default_credential_helper),
+            credential_helper_timeout_ms: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "credential_helper_timeout_ms")?,
+            cas_rpc_timeout_ms: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "cas_rpc_timeout_ms")?,
+            action_cache_rpc_timeout_ms: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "action_cache_rpc_timeout_ms")?,
+            execute_stream_timeout_ms: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "execute_stream_timeout_ms")?,
+            rpc_retries: legacy_config.parse(BUCK2_RE_CLIENT_CFG_SECTION, "rpc_retries")?,
+            circuit_breaker_threshold: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "circuit_breaker_threshold")?,
+            circuit_breaker_cooldown_ms: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "circuit_breaker_cooldown_ms")?,
             capabilities: legacy_config.parse(BUCK2_RE_CLIENT_CFG_SECTION, "capabilities")?,
             instance_name: legacy_config.parse(BUCK2_RE_CLIENT_CFG_SECTION, "instance_name")?,
         })
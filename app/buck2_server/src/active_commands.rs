@@ -9,6 +9,8 @@
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use buck2_cli_proto::ClientContext;
@@ -25,11 +27,123 @@ use dupe::Dupe;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use parking_lot::MutexGuard;
+use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 
 static ACTIVE_COMMANDS: Lazy<Mutex<HashMap<TraceId, ActiveCommandHandle>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// Subscriptions to the build events of active commands, by subscription id.
+static BUILD_EVENTS_SUBSCRIPTIONS: Lazy<Mutex<HashMap<u64, BuildEventsSender>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_BUILD_EVENTS_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// How many events a subscription can fall behind before it starts losing events.
+const BUILD_EVENTS_BUFFER: usize = 4096;
+
+struct BuildEventsSender {
+    /// Commands the subscription is interested in, or all if empty.
+    trace_ids: HashSet<TraceId>,
+    sender: mpsc::Sender<Arc<BuckEvent>>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// Receiving end of a subscription to build events, unsubscribes when dropped.
+pub struct BuildEventsReceiver {
+    id: u64,
+    receiver: mpsc::Receiver<Arc<BuckEvent>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl BuildEventsReceiver {
+    /// Next event. Never resolves when there are none, since the sender is kept in the registry
+    /// until this receiver is dropped.
+    pub async fn recv(&mut self) -> Option<Arc<BuckEvent>> {
+        self.receiver.recv().await
+    }
+
+    /// Number of events lost because this subscription fell behind, since the last call.
+    pub fn take_dropped(&self) -> u64 {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl Drop for BuildEventsReceiver {
+    fn drop(&mut self) {
+        BUILD_EVENTS_SUBSCRIPTIONS.lock().remove(&self.id);
+    }
+}
+
+/// Receive action, target and test events from the given active commands, or from all of them if
+/// `trace_ids` is empty. Each subscription has its own buffer, so a slow subscription only loses
+/// its own events, and only sees those of the commands it asked for.
+pub fn subscribe_to_build_events(trace_ids: HashSet<TraceId>) -> BuildEventsReceiver {
+    let id = NEXT_BUILD_EVENTS_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = mpsc::channel(BUILD_EVENTS_BUFFER);
+    let dropped = Arc::new(AtomicU64::new(0));
+    BUILD_EVENTS_SUBSCRIPTIONS.lock().insert(
+        id,
+        BuildEventsSender {
+            trace_ids,
+            sender,
+            dropped: dropped.dupe(),
+        },
+    );
+    BuildEventsReceiver {
+        id,
+        receiver,
+        dropped,
+    }
+}
+
+/// Send a build event to the subscriptions interested in it.
+fn publish_build_event(buck_event: &BuckEvent) {
+    let subscriptions = BUILD_EVENTS_SUBSCRIPTIONS.lock();
+    if subscriptions.is_empty() {
+        return;
+    }
+    let trace_id = buck_event.trace_id().ok();
+    let mut shared = None;
+    for subscription in subscriptions.values() {
+        let wanted = subscription.trace_ids.is_empty()
+            || trace_id
+                .as_ref()
+                .map_or(false, |id| subscription.trace_ids.contains(id));
+        if !wanted {
+            continue;
+        }
+        let event = shared
+            .get_or_insert_with(|| Arc::new(buck_event.clone()))
+            .dupe();
+        if subscription.sender.try_send(event).is_err() {
+            // Either full or the subscription is going away.
+            subscription.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn is_build_event(buck_event: &BuckEvent) -> bool {
+    use buck2_data::buck_event::Data::*;
+
+    match buck_event.data() {
+        SpanStart(start) => matches!(
+            start.data,
+            Some(buck2_data::span_start_event::Data::ActionExecution(..))
+        ),
+        SpanEnd(end) => matches!(
+            end.data,
+            Some(buck2_data::span_end_event::Data::ActionExecution(..))
+        ),
+        Instant(instant) => matches!(
+            instant.data,
+            Some(buck2_data::instant_event::Data::TargetBuilt(..))
+                | Some(buck2_data::instant_event::Data::TestResult(..))
+        ),
+        _ => false,
+    }
+}
+
 /// Return the active commands, if you can access them.
 pub fn try_active_commands() -> Option<HashMap<TraceId, ActiveCommandHandle>> {
     // Note that this function is accessed during panic, so have to be super careful
@@ -150,6 +264,10 @@ impl ActiveCommandStateWriter {
     pub fn peek_event(&mut self, buck_event: &BuckEvent) {
         use buck2_data::buck_event::Data::*;
 
+        if is_build_event(buck_event) {
+            publish_build_event(buck_event);
+        }
+
         let mut changed = false;
 
         match buck_event.data() {
@@ -18,12 +18,15 @@ use std::time::Instant;
 use allocative::Allocative;
 use anyhow::Context;
 use async_trait::async_trait;
+use buck2_build_api::actions::diagnostics::ActionDiagnostics;
+use buck2_build_api::actions::diagnostics::SetActionDiagnostics;
 use buck2_build_api::actions::impls::run_action_knobs::HasRunActionKnobs;
 use buck2_build_api::actions::impls::run_action_knobs::RunActionKnobs;
 use buck2_build_api::build::HasCreateUnhashedSymlinkLock;
 use buck2_build_api::build_signals::BuildSignalsInstaller;
 use buck2_build_api::build_signals::SetBuildSignals;
 use buck2_build_api::calculation::ConfiguredGraphCycleDescriptor;
+use buck2_build_api::configuration::modifiers::SetCliModifiers;
 use buck2_build_api::context::SetBuildContextData;
 use buck2_build_api::interpreter::context::configure_build_file_globals;
 use buck2_build_api::interpreter::context::configure_extension_file_globals;
@@ -41,7 +44,9 @@ use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::dice::cycles::CycleDetectorAdapter;
 use buck2_common::dice::cycles::PairDiceCycleDetector;
 use buck2_common::dice::data::HasIoProvider;
+use buck2_common::dice::file_ops::FileInvalidationStats;
 use buck2_common::executor_config::CommandExecutorConfig;
+use buck2_common::external_cells::HasExternalCells;
 use buck2_common::http::HttpClient;
 use buck2_common::http::SetHttpClient;
 use buck2_common::io::trace::TracingIoProvider;
@@ -79,6 +84,10 @@ use buck2_execute::re::client::RemoteExecutionClient;
 use buck2_execute::re::manager::ReConnectionHandle;
 use buck2_execute::re::manager::ReConnectionManager;
 use buck2_execute::re::manager::ReConnectionObserver;
+use buck2_execute_impl::executors::cache_upload_policy::CacheUploadPolicy;
+use buck2_execute_impl::executors::determinism::DeterminismCheck;
+use buck2_execute_impl::executors::executor_timings::ExecutorTimings;
+use buck2_execute_impl::executors::output_validation::OutputValidationPolicy;
 use buck2_execute_impl::low_pass_filter::LowPassFilter;
 use buck2_forkserver::client::ForkserverClient;
 use buck2_interpreter::dice::starlark_debug::SetStarlarkDebugger;
@@ -105,6 +114,7 @@ use dice::DiceTransactionUpdater;
 use dice::UserComputationData;
 use dice::UserCycleDetector;
 use dupe::Dupe;
+use futures::future;
 use gazebo::prelude::SliceExt;
 use host_sharing::HostSharingBroker;
 use host_sharing::HostSharingStrategy;
@@ -169,6 +179,10 @@ pub struct BaseServerCommandContext {
     pub create_unhashed_outputs_lock: Arc<Mutex<()>>,
     /// Http client used during run actions; shared with materializer.
     pub http_client: Arc<dyn HttpClient>,
+    /// Historical execution times used by the hybrid executor, if enabled.
+    pub executor_timings: Option<Arc<ExecutorTimings>>,
+    /// Statistics about the files invalidated in DICE.
+    pub file_invalidation_stats: Arc<FileInvalidationStats>,
 }
 
 /// ServerCommandContext provides access to the global daemon state and information about the calling client for
@@ -209,6 +223,9 @@ pub struct ServerCommandContext<'a> {
     skip_targets_with_duplicate_names: bool,
     disable_starlark_types: bool,
 
+    /// Configuration modifiers specified on the command line.
+    cli_modifiers: Vec<String>,
+
     pub buck_out_dir: ProjectRelativePathBuf,
 
     /// Common build options associated with this command.
@@ -330,6 +347,7 @@ impl<'a> ServerCommandContext<'a> {
             record_target_call_stacks: client_context.target_call_stacks,
             skip_targets_with_duplicate_names: client_context.skip_targets_with_duplicate_names,
             disable_starlark_types: client_context.disable_starlark_types,
+            cli_modifiers: client_context.modifiers.clone(),
             heartbeat_guard_handle: Some(heartbeat_guard_handle),
             daemon_uuid_from_client: client_context.daemon_uuid.clone(),
             sanitized_argv: client_context.sanitized_argv.clone(),
@@ -360,6 +378,16 @@ impl<'a> ServerCommandContext<'a> {
             .map(|opts| opts.skip_cache_write)
             .unwrap_or_default();
 
+        let determinism_check = self
+            .build_options
+            .as_ref()
+            .filter(|opts| opts.check_determinism)
+            .map(|opts| {
+                Arc::new(DeterminismCheck::new(
+                    opts.check_determinism_categories.clone(),
+                ))
+            });
+
         let mut run_action_knobs = RunActionKnobs {
             hash_all_commands: self.base_context.hash_all_commands,
             use_network_action_output_cache: self.base_context.use_network_action_output_cache,
@@ -407,6 +435,7 @@ impl<'a> ServerCommandContext<'a> {
             upload_all_actions,
             skip_cache_read,
             skip_cache_write,
+            determinism_check,
             create_unhashed_symlink_lock,
             starlark_debugger: self.debugger_handle.dupe(),
             keep_going: self
@@ -414,6 +443,7 @@ impl<'a> ServerCommandContext<'a> {
                 .as_ref()
                 .map_or(false, |opts| opts.keep_going),
             http_client: self.base_context.http_client.dupe(),
+            executor_timings: self.base_context.executor_timings.dupe(),
         }
     }
 
@@ -438,6 +468,7 @@ impl<'a> ServerCommandContext<'a> {
             disable_starlark_types: self.disable_starlark_types,
             skip_targets_with_duplicate_names: self.skip_targets_with_duplicate_names,
             record_target_call_stacks: self.record_target_call_stacks,
+            cli_modifiers: self.cli_modifiers.clone(),
         })
     }
 
@@ -486,8 +517,29 @@ impl CellConfigLoader {
                         );
                     }
                 }
-                parse_legacy_cells(self.config_overrides.iter(), &self.working_dir, &self.project_root)
-                    .shared_error()
+                let parse = || {
+                    parse_legacy_cells(
+                        self.config_overrides.iter(),
+                        &self.working_dir,
+                        &self.project_root,
+                    )
+                };
+                let mut cells = parse()?;
+                // External cells are materialized the first time they are resolved, so that
+                // their own buckconfigs can be loaded like those of other cells. Once in
+                // `buck-out`, they are not looked at again.
+                if !cells.unmaterialized_external_cells.is_empty() {
+                    let external_cells = dice_ctx
+                        .global_data()
+                        .get_external_cells()
+                        .context("External cells are not available")?;
+                    future::try_join_all(cells.unmaterialized_external_cells.iter().map(
+                        |origin| external_cells.ensure_materialized(&self.project_root, origin),
+                    ))
+                    .await?;
+                    cells = parse()?;
+                }
+                Ok((cells.cell_resolver, cells.configs_by_name, cells.config_paths))
             })
             .await
             .clone()
@@ -509,10 +561,12 @@ struct DiceCommandDataProvider {
     run_action_knobs: RunActionKnobs,
     skip_cache_read: bool,
     skip_cache_write: bool,
+    determinism_check: Option<Arc<DeterminismCheck>>,
     create_unhashed_symlink_lock: Arc<Mutex<()>>,
     starlark_debugger: Option<BuckStarlarkDebuggerHandle>,
     keep_going: bool,
     http_client: Arc<dyn HttpClient>,
+    executor_timings: Option<Arc<ExecutorTimings>>,
 }
 
 #[async_trait]
@@ -547,6 +601,9 @@ impl DiceDataProvider for DiceCommandDataProvider {
 
         let executor_global_knobs = ExecutorGlobalKnobs { enable_miniperf };
 
+        let cache_upload_policy = CacheUploadPolicy::from_config(root_config)?;
+        let output_validation_policy = OutputValidationPolicy::from_config(root_config)?;
+
         let host_sharing_broker =
             HostSharingBroker::new(HostSharingStrategy::SmallerTasksFirst, concurrency);
 
@@ -600,9 +657,13 @@ impl DiceDataProvider for DiceCommandDataProvider {
             self.execution_strategy,
             executor_global_knobs,
             self.upload_all_actions,
+            cache_upload_policy,
+            output_validation_policy,
+            self.executor_timings.dupe(),
             self.forkserver.dupe(),
             self.skip_cache_read,
             self.skip_cache_write,
+            self.determinism_check.dupe(),
             ctx.global_data()
                 .get_io_provider()
                 .project_root()
@@ -612,6 +673,7 @@ impl DiceDataProvider for DiceCommandDataProvider {
         data.set_http_client(self.http_client.dupe());
         data.set_materializer(self.materializer.dupe());
         data.set_build_signals(self.build_signals.build_signals.dupe());
+        data.set_action_diagnostics(Arc::new(ActionDiagnostics::default()));
         data.set_run_action_knobs(run_action_knobs);
         data.set_create_unhashed_symlink_lock(self.create_unhashed_symlink_lock.dupe());
         data.set_starlark_debugger_handle(self.starlark_debugger.clone().map(|v| Box::new(v) as _));
@@ -646,6 +708,7 @@ struct DiceCommandUpdater {
     disable_starlark_types: bool,
     record_target_call_stacks: bool,
     skip_targets_with_duplicate_names: bool,
+    cli_modifiers: Vec<String>,
 }
 
 #[async_trait]
@@ -677,6 +740,7 @@ impl DiceUpdater for DiceCommandUpdater {
         let mut ctx = self.file_watcher.sync(ctx).await?;
 
         ctx.set_buck_out_path(Some(self.buck_out_dir.clone()))?;
+        ctx.set_cli_modifiers(self.cli_modifiers.clone())?;
 
         setup_interpreter(
             &mut ctx,
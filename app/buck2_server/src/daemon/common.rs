@@ -34,9 +34,14 @@ use buck2_execute::execute::request::ExecutorPreference;
 use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::re::manager::ReConnectionHandle;
+use buck2_execute_impl::executors::cache_upload_policy::CacheUploadExecutor;
+use buck2_execute_impl::executors::cache_upload_policy::CacheUploadPolicy;
 use buck2_execute_impl::executors::caching::CachingExecutor;
+use buck2_execute_impl::executors::determinism::DeterminismCheck;
+use buck2_execute_impl::executors::executor_timings::ExecutorTimings;
 use buck2_execute_impl::executors::hybrid::HybridExecutor;
 use buck2_execute_impl::executors::local::LocalExecutor;
+use buck2_execute_impl::executors::output_validation::OutputValidationPolicy;
 use buck2_execute_impl::executors::re::ReExecutor;
 use buck2_execute_impl::low_pass_filter::LowPassFilter;
 use buck2_forkserver::client::ForkserverClient;
@@ -70,9 +75,13 @@ pub struct CommandExecutorFactory {
     pub strategy: ExecutionStrategy,
     pub executor_global_knobs: ExecutorGlobalKnobs,
     pub upload_all_actions: bool,
+    pub cache_upload_policy: Arc<CacheUploadPolicy>,
+    pub output_validation_policy: Arc<OutputValidationPolicy>,
+    pub executor_timings: Option<Arc<ExecutorTimings>>,
     pub forkserver: Option<ForkserverClient>,
     pub skip_cache_read: bool,
     pub skip_cache_write: bool,
+    pub determinism_check: Option<Arc<DeterminismCheck>>,
     project_root: ProjectRoot,
 }
 
@@ -86,9 +95,13 @@ impl CommandExecutorFactory {
         strategy: ExecutionStrategy,
         executor_global_knobs: ExecutorGlobalKnobs,
         upload_all_actions: bool,
+        cache_upload_policy: CacheUploadPolicy,
+        output_validation_policy: OutputValidationPolicy,
+        executor_timings: Option<Arc<ExecutorTimings>>,
         forkserver: Option<ForkserverClient>,
         skip_cache_read: bool,
         skip_cache_write: bool,
+        determinism_check: Option<Arc<DeterminismCheck>>,
         project_root: ProjectRoot,
     ) -> Self {
         Self {
@@ -100,9 +113,13 @@ impl CommandExecutorFactory {
             strategy,
             executor_global_knobs,
             upload_all_actions,
+            cache_upload_policy: Arc::new(cache_upload_policy),
+            output_validation_policy: Arc::new(output_validation_policy),
+            executor_timings,
             forkserver,
             skip_cache_read,
             skip_cache_write,
+            determinism_check,
             project_root,
         }
     }
@@ -123,6 +140,8 @@ impl HasCommandExecutor for CommandExecutorFactory {
                 self.project_root.root().to_owned(),
                 self.forkserver.dupe(),
                 self.executor_global_knobs.dupe(),
+                self.output_validation_policy.dupe(),
+                self.determinism_check.dupe(),
             )
         };
 
@@ -165,6 +184,7 @@ impl HasCommandExecutor for CommandExecutorFactory {
                 knobs: self.executor_global_knobs.dupe(),
                 skip_cache_read: self.skip_cache_read || !remote_cache_enabled,
                 skip_cache_write: self.skip_cache_write || !remote_cache_enabled,
+                output_validation_policy: self.output_validation_policy.dupe(),
             }
         };
 
@@ -207,6 +227,7 @@ impl HasCommandExecutor for CommandExecutorFactory {
                         level: *level,
                         executor_preference: self.strategy.hybrid_preference(),
                         low_pass_filter: self.low_pass_filter.dupe(),
+                        executor_timings: self.executor_timings.dupe(),
                     })),
                     _ => None,
                 };
@@ -221,6 +242,19 @@ impl HasCommandExecutor for CommandExecutorFactory {
                     .get_copied()?
                     .unwrap_or(self.skip_cache_read);
 
+                // Remote results are cached by RE already.
+                let cache_upload_executor = match executor {
+                    RemoteEnabledExecutor::Local(..) => Some(CacheUploadExecutor::Local),
+                    RemoteEnabledExecutor::Hybrid { .. } => Some(CacheUploadExecutor::Hybrid),
+                    RemoteEnabledExecutor::Remote(..) => None,
+                };
+                let cache_upload_policy = cache_upload_executor
+                    .filter(|executor| {
+                        self.cache_upload_policy
+                            .applies_to_executor(*executor, re_properties)
+                    })
+                    .map(|_| self.cache_upload_policy.dupe());
+
                 let executor = if disable_caching || !remote_cache_enabled {
                     inner_executor
                 } else {
@@ -234,6 +268,8 @@ impl HasCommandExecutor for CommandExecutorFactory {
                             upload_all_actions: self.upload_all_actions,
                             knobs: self.executor_global_knobs.dupe(),
                             cache_upload_behavior: *cache_upload_behavior,
+                            cache_upload_policy,
+                            output_validation_policy: self.output_validation_policy.dupe(),
                         }) as _
                     })
                 };
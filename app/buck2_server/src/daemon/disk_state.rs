@@ -22,6 +22,8 @@ use buck2_core::rollout_percentage::RolloutPercentage;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::execute::blocking::BlockingExecutor;
 use buck2_execute::materialize::materializer::MaterializationMethod;
+use buck2_execute_impl::executors::executor_timings::ExecutorTimings;
+use buck2_execute_impl::executors::executor_timings::ExecutorTimingsConfig;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializerConfigs;
 use buck2_execute_impl::materializers::sqlite::MaterializerState;
 use buck2_execute_impl::materializers::sqlite::MaterializerStateSqliteDb;
@@ -116,6 +118,30 @@ pub(crate) async fn maybe_initialize_materializer_sqlite_db(
     Ok((Some(db), materializer_state))
 }
 
+/// Load the history used by the hybrid executor to pick an executor, if enabled. Losing the
+/// history only costs some racing, so if it cannot be loaded we start over in memory.
+pub(crate) async fn maybe_initialize_executor_timings(
+    paths: &InvocationPaths,
+    io_executor: Arc<dyn BlockingExecutor>,
+    root_config: &LegacyBuckConfig,
+) -> anyhow::Result<Option<Arc<ExecutorTimings>>> {
+    let config = match ExecutorTimingsConfig::from_config(root_config)? {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+
+    let timings =
+        match ExecutorTimings::initialize(paths.executor_timings_path(), config, io_executor).await
+        {
+            Ok(timings) => timings,
+            Err(e) => {
+                tracing::warn!("Error loading executor timings: {:#}", e);
+                ExecutorTimings::new(config)
+            }
+        };
+    Ok(Some(Arc::new(timings)))
+}
+
 // Once we start storing disk state in the cache directory, we need to make sure
 // buck2 always deletes the cache directory if the cache is disabled.
 // Otherwise, buck-out state can diverge from the state of on-disk cache when
@@ -29,6 +29,7 @@ use buck2_build_api::spawner::BuckSpawner;
 use buck2_cli_proto::daemon_api_server::*;
 use buck2_cli_proto::*;
 use buck2_common::buckd_connection::BUCK_AUTH_TOKEN_HEADER;
+use buck2_common::dice::file_ops::FileInvalidationStats;
 use buck2_common::events::HasEvents;
 use buck2_common::invocation_paths::InvocationPaths;
 use buck2_common::io::trace::TracingIoProvider;
@@ -146,11 +147,13 @@ impl BuckdServerInitPreferences {
         &self,
         io: Arc<dyn IoProvider>,
         digest_config: DigestConfig,
+        file_invalidation_stats: Arc<FileInvalidationStats>,
         root_config: &LegacyBuckConfig,
     ) -> anyhow::Result<Arc<Dice>> {
         configure_dice_for_buck(
             io,
             digest_config,
+            file_invalidation_stats,
             Some(root_config),
             self.detect_cycles,
             self.which_dice,
@@ -853,6 +856,7 @@ impl DaemonApi for BuckdServer {
                         data.dice_manager.unsafe_dice().dupe(),
                         data.materializer.dupe(),
                         data.scribe_sink.dupe() as _,
+                        data.file_invalidation_stats.dupe(),
                     )
                     .create_snapshot(),
                 )
@@ -1213,6 +1217,7 @@ impl DaemonApi for BuckdServer {
                         spawn_allocative(
                             this,
                             AbsPathBuf::try_from(req.output_path)?,
+                            req.diff_path.map(AbsPathBuf::try_from).transpose()?,
                             dispatcher.dupe(),
                         )
                         .await?;
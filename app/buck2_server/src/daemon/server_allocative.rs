@@ -10,7 +10,10 @@
 use std::sync::Arc;
 
 use allocative::FlameGraphBuilder;
+use allocative::Snapshot;
+use anyhow::Context;
 use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_path::AbsPath;
 use buck2_core::fs::paths::abs_path::AbsPathBuf;
 use buck2_events::dispatch::EventDispatcher;
 
@@ -19,9 +22,12 @@ use crate::daemon::server::BuckdServerData;
 pub(crate) async fn spawn_allocative(
     buckd_server_data: Arc<BuckdServerData>,
     path: AbsPathBuf,
+    diff_path: Option<AbsPathBuf>,
     dispatcher: EventDispatcher,
 ) -> anyhow::Result<()> {
     tokio::task::spawn_blocking(move || {
+        // Read the previous snapshot first to fail before the expensive part.
+        let before = diff_path.map(|p| read_snapshot(&p)).transpose()?;
         let mut graph = FlameGraphBuilder::default();
         dispatcher.console_message(
             "Starting allocative profiling. It may take a while to finish...".to_owned(),
@@ -45,9 +51,51 @@ pub(crate) async fn spawn_allocative(
 
         fs_util::write(path.join("warnings.txt"), fg.warnings())?;
 
+        if let Some(before) = before {
+            let after = Snapshot::parse_flamegraph(&fg.flamegraph())
+                .context("Parsing current allocative flamegraph")?;
+            write_diff(&path, &before, &after)?;
+        }
+
         dispatcher.console_message("Profile written.".to_owned());
 
         anyhow::Ok(())
     })
     .await?
 }
+
+/// Read a snapshot from a previous allocative output directory or its `flamegraph.src`.
+fn read_snapshot(path: &AbsPath) -> anyhow::Result<Snapshot> {
+    let src = if fs_util::metadata(path)?.is_dir() {
+        path.join("flamegraph.src")
+    } else {
+        path.to_owned()
+    };
+    let flamegraph = fs_util::read_to_string(&src)?;
+    Snapshot::parse_flamegraph(&flamegraph)
+        .with_context(|| format!("Parsing allocative snapshot `{}`", src.display()))
+}
+
+fn write_diff(path: &AbsPath, before: &Snapshot, after: &Snapshot) -> anyhow::Result<()> {
+    const REPORT_LIMIT: usize = 1000;
+
+    let diff = before.diff(after);
+    fs_util::write(path.join("diff.txt"), diff.report(REPORT_LIMIT))?;
+    fs_util::write(path.join("grown.src"), diff.grown_flamegraph())?;
+    fs_util::write(path.join("shrunk.src"), diff.shrunk_flamegraph())?;
+
+    let mut differential = diff.differential_flamegraph();
+    fs_util::write(path.join("diff.src"), &differential)?;
+    if differential.is_empty() {
+        // inferno does not like empty flamegraphs.
+        differential = "empty 1 1\n".to_owned();
+    }
+    let mut diff_svg = Vec::new();
+    inferno::flamegraph::from_reader(
+        &mut inferno::flamegraph::Options::default(),
+        differential.as_bytes(),
+        &mut diff_svg,
+    )?;
+    fs_util::write(path.join("diff.svg"), &diff_svg)?;
+    Ok(())
+}
@@ -19,6 +19,7 @@ use buck2_build_signals_impl::CriticalPathBackendName;
 use buck2_cli_proto::unstable_dice_dump_request::DiceDumpFormat;
 use buck2_common::cas_digest::DigestAlgorithm;
 use buck2_common::cas_digest::DigestAlgorithmKind;
+use buck2_common::dice::file_ops::FileInvalidationStats;
 use buck2_common::http::http_client;
 use buck2_common::http::HttpClient;
 use buck2_common::ignores::ignore_set::IgnoreSet;
@@ -46,6 +47,7 @@ use buck2_execute::execute::blocking::BuckBlockingExecutor;
 use buck2_execute::materialize::materializer::MaterializationMethod;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::re::manager::ReConnectionManager;
+use buck2_execute_impl::executors::executor_timings::ExecutorTimings;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializer;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializerConfigs;
 use buck2_execute_impl::materializers::deferred::TtlRefreshConfiguration;
@@ -71,6 +73,7 @@ use crate::active_commands::ActiveCommandDropGuard;
 use crate::ctx::BaseServerCommandContext;
 use crate::daemon::check_working_dir;
 use crate::daemon::disk_state::delete_unknown_disk_state;
+use crate::daemon::disk_state::maybe_initialize_executor_timings;
 use crate::daemon::disk_state::maybe_initialize_materializer_sqlite_db;
 use crate::daemon::disk_state::DiskStateOptions;
 use crate::daemon::forkserver::maybe_launch_forkserver;
@@ -159,6 +162,14 @@ pub struct DaemonStateData {
     /// Http client used for materializer and RunAction implementations.
     pub http_client: Arc<dyn HttpClient>,
 
+    /// Historical execution times used by the hybrid executor to pick an executor, if enabled.
+    #[allocative(skip)]
+    pub executor_timings: Option<Arc<ExecutorTimings>>,
+
+    /// Statistics about the files invalidated in DICE.
+    #[allocative(skip)]
+    pub file_invalidation_stats: Arc<FileInvalidationStats>,
+
     /// Are we using buck-out as our cwd?
     pub cwd_buck_out: bool,
 }
@@ -351,6 +362,13 @@ impl DaemonState {
 
         let http_client = http_client()?;
 
+        let executor_timings = maybe_initialize_executor_timings(
+            paths,
+            blocking_executor.dupe() as Arc<dyn BlockingExecutor>,
+            root_config,
+        )
+        .await?;
+
         let materializer_state_identity = materializer_db.as_ref().map(|d| d.identity().clone());
 
         let re_client_manager = Arc::new(ReConnectionManager::new(
@@ -380,8 +398,14 @@ impl DaemonState {
         let forkserver =
             maybe_launch_forkserver(root_config, &paths.forkserver_state_dir()).await?;
 
+        let file_invalidation_stats = Arc::new(FileInvalidationStats::default());
         let dice = init_ctx
-            .construct_dice(io.dupe(), digest_config, root_config)
+            .construct_dice(
+                io.dupe(),
+                digest_config,
+                file_invalidation_stats.dupe(),
+                root_config,
+            )
             .await?;
 
         // TODO(cjhopman): We want to use Expr::True here, but we need to workaround
@@ -482,6 +506,8 @@ impl DaemonState {
             materializer_state_identity,
             enable_restarter,
             http_client,
+            executor_timings,
+            file_invalidation_stats,
             cwd_buck_out,
         }))
     }
@@ -674,6 +700,8 @@ impl DaemonState {
             daemon_start_time: data.start_time,
             create_unhashed_outputs_lock: data.create_unhashed_outputs_lock.dupe(),
             http_client: data.http_client.dupe(),
+            executor_timings: data.executor_timings.dupe(),
+            file_invalidation_stats: data.file_invalidation_stats.dupe(),
         })
     }
 
@@ -8,13 +8,24 @@
  */
 
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
 use std::mem;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
 use allocative::Allocative;
 use async_trait::async_trait;
+use buck2_common::dice::file_ops::DiceFileOps;
 use buck2_common::dice::file_ops::FileChangeTracker;
+use buck2_common::file_ops::FileOps;
+use buck2_common::file_ops::FileType;
 use buck2_common::ignores::ignore_set::IgnoreSet;
 use buck2_common::invocation_paths::InvocationPaths;
 use buck2_core::cells::cell_path::CellPath;
@@ -22,18 +33,26 @@ use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::collections::ordered_set::OrderedSet;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::file_name::FileName;
 use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_events::dispatch::span_async;
+use dice::DiceComputations;
 use dice::DiceTransactionUpdater;
 use dupe::Dupe;
+use futures::future;
 use notify::event::CreateKind;
 use notify::event::MetadataKind;
 use notify::event::ModifyKind;
 use notify::event::RemoveKind;
+use notify::ErrorKind;
 use notify::EventKind;
 use notify::RecommendedWatcher;
+use notify::RecursiveMode;
 use notify::Watcher;
 use tracing::info;
+use tracing::warn;
 
 use crate::file_watcher::stats::FileWatcherStats;
 use crate::file_watcher::FileWatcher;
@@ -76,12 +95,267 @@ impl ChangeType {
     }
 }
 
+/// Why the watcher could have missed events, so changes must be found by rescanning.
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq, Allocative)]
+enum RescanReason {
+    /// The kernel event queue overflowed (`IN_Q_OVERFLOW` for inotify).
+    QueueOverflow,
+    /// Watch limit was reached (`fs.inotify.max_user_watches` for inotify),
+    /// so some directories are not watched.
+    WatchLimit,
+}
+
+impl fmt::Display for RescanReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RescanReason::QueueOverflow => write!(f, "event queue overflow"),
+            RescanReason::WatchLimit => write!(f, "watch limit reached"),
+        }
+    }
+}
+
+/// Decides which paths we report changes of.
+#[derive(Allocative)]
+struct WatchedPaths {
+    cells: CellResolver,
+    ignore_specs: HashMap<CellName, IgnoreSet>,
+}
+
+impl WatchedPaths {
+    /// We ignore the buck-out prefix, as those are uninteresting events caused by us.
+    /// We also ignore other buck-out directories, as if you have two isolation dirs running at once, they are not interesting.
+    /// We do this in the notify-watcher, rather than a generic layer, as watchman users should configure
+    /// to ignore buck-out, to reduce the number of events, rather than hiding them later.
+    fn is_buck_out(path: &ProjectRelativePath) -> bool {
+        path.starts_with(InvocationPaths::buck_out_dir_prefix())
+    }
+
+    fn is_ignored(&self, cell_path: &CellPath) -> anyhow::Result<bool> {
+        // External cells are written by buck2 itself and never change under a given origin.
+        Ok(self.cells.get(cell_path.cell())?.external().is_some()
+            || self
+                .ignore_specs
+                .get(&cell_path.cell())
+                .expect("unexpected cell name mismatch")
+                .is_match(cell_path.path()))
+    }
+}
+
+/// Filesystems can store timestamps with a coarse granularity (2 seconds for FAT), so files
+/// modified slightly before the last sync are considered modified too.
+const TIMESTAMP_GRANULARITY: Duration = Duration::from_secs(2);
+
+/// Whether the file could have been modified since `since`.
+fn modified_since(metadata: &fs::Metadata, since: SystemTime) -> bool {
+    let since = since
+        .checked_sub(TIMESTAMP_GRANULARITY)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    match metadata.modified() {
+        Ok(modified) if modified < since => {}
+        _ => return true,
+    }
+    // Unlike the modification time, the change time cannot be set back (e.g. by `cp -p`).
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if let Ok(secs) = u64::try_from(metadata.ctime()) {
+            let changed =
+                SystemTime::UNIX_EPOCH + Duration::new(secs, metadata.ctime_nsec() as u32);
+            if changed >= since {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// An entry of a directory, as found on disk by a rescan.
+struct ScannedEntry {
+    path: CellPath,
+    file_type: FileType,
+    /// Set if the entry is not a directory and could have been modified since the last sync.
+    modified: bool,
+}
+
+/// Entries of a directory, as found on disk by a rescan.
+struct DirListing {
+    project_path: ProjectRelativePathBuf,
+    dir: CellPath,
+    /// Entries not ignored by the watcher.
+    entries: Vec<ScannedEntry>,
+}
+
+/// The watched directories of the repository as they are on disk now.
+///
+/// When the watcher loses events, we list the repository again and compare each directory with
+/// the listing DICE has. Added, removed and retyped paths are reported as such. Files present
+/// in both are invalidated if their timestamps show they could have been modified since the
+/// last sync.
+struct RepoScan {
+    dirs: Vec<DirListing>,
+    paths_scanned: usize,
+}
+
+impl RepoScan {
+    /// Blocking, must be run on a blocking thread.
+    fn scan(
+        root: &ProjectRoot,
+        watched: &WatchedPaths,
+        since: SystemTime,
+    ) -> anyhow::Result<RepoScan> {
+        let mut dirs = Vec::new();
+        let mut paths_scanned = 0;
+        let mut queue = vec![ProjectRelativePathBuf::unchecked_new(String::new())];
+        while let Some(dir) = queue.pop() {
+            let read_dir = match fs::read_dir(root.resolve(&dir).as_path()) {
+                Ok(read_dir) => read_dir,
+                // Directory was removed while we were scanning it.
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let mut entries = Vec::new();
+            for entry in read_dir {
+                let entry = entry?;
+                let name = match entry.file_name().to_str().map(FileName::new) {
+                    Some(Ok(name)) => dir.join(name),
+                    // The watcher cannot report these either.
+                    _ => continue,
+                };
+                if WatchedPaths::is_buck_out(&name) {
+                    continue;
+                }
+                let cell_path = watched.cells.get_cell_path(&name)?;
+                if watched.is_ignored(&cell_path)? {
+                    continue;
+                }
+                // `DirEntry::file_type` does not follow symlinks.
+                let file_type = match entry.file_type() {
+                    Ok(file_type) => FileType::from(file_type),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                let modified = if file_type == FileType::Directory {
+                    queue.push(name);
+                    false
+                } else {
+                    // `DirEntry::metadata` does not follow symlinks either.
+                    match entry.metadata() {
+                        Ok(metadata) => modified_since(&metadata, since),
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                        Err(e) => return Err(e.into()),
+                    }
+                };
+                entries.push(ScannedEntry {
+                    path: cell_path,
+                    file_type,
+                    modified,
+                });
+            }
+            paths_scanned += entries.len();
+            dirs.push(DirListing {
+                dir: watched.cells.get_cell_path(&dir)?,
+                project_path: dir,
+                entries,
+            });
+        }
+        Ok(RepoScan {
+            dirs,
+            paths_scanned,
+        })
+    }
+
+    /// Compare with the directory listings DICE has and record the differences as changes.
+    /// Returns the paths that were added, removed, changed type or could have been modified.
+    async fn diff_with_dice(
+        &self,
+        dice: &DiceComputations,
+        watched: &WatchedPaths,
+        changed: &mut FileChangeTracker,
+    ) -> anyhow::Result<Vec<CellPath>> {
+        let file_ops = DiceFileOps(dice);
+        let listings = future::join_all(
+            self.dirs
+                .iter()
+                .map(|listing| file_ops.read_dir(listing.dir.as_ref())),
+        )
+        .await;
+
+        let mut changed_paths = Vec::new();
+        for (listing, previous) in self.dirs.iter().zip(listings) {
+            let previous = match previous {
+                Ok(previous) => previous,
+                Err(_) => {
+                    // DICE could not list it before, so list it again.
+                    changed.dir_changed(listing.dir.clone());
+                    changed_paths.push(listing.dir.clone());
+                    continue;
+                }
+            };
+            let mut previous_entries = Vec::with_capacity(previous.included.len());
+            for entry in previous.included.iter() {
+                let cell_path = watched
+                    .cells
+                    .get_cell_path(&listing.project_path.join(&entry.file_name))?;
+                if !watched.is_ignored(&cell_path)? {
+                    previous_entries.push((cell_path, entry.file_type.dupe()));
+                }
+            }
+            changed_paths.extend(diff_dir(&previous_entries, &listing.entries, changed));
+        }
+        Ok(changed_paths)
+    }
+}
+
+/// Record the differences between the previous and current entries of a directory as changes.
+/// Returns the paths that were added, removed, changed type or could have been modified.
+fn diff_dir(
+    previous: &[(CellPath, FileType)],
+    current: &[ScannedEntry],
+    changed: &mut FileChangeTracker,
+) -> Vec<CellPath> {
+    let previous: HashMap<&CellPath, &FileType> = previous.iter().map(|(p, t)| (p, t)).collect();
+    let mut changed_paths = Vec::new();
+    for entry in current {
+        let path = &entry.path;
+        match (previous.get(path), &entry.file_type) {
+            (Some(previous_type), file_type) if *previous_type == file_type => {
+                if !entry.modified {
+                    continue;
+                }
+                changed.file_changed(path.clone());
+            }
+            (None, FileType::Directory) => changed.dir_added(path.clone()),
+            (None, _) => changed.file_added(path.clone()),
+            (Some(_), _) => {
+                changed.dir_added_or_removed(path.clone());
+                changed.file_added_or_removed(path.clone());
+            }
+        }
+        changed_paths.push(path.clone());
+    }
+    let current: HashSet<&CellPath> = current.iter().map(|e| &e.path).collect();
+    for (path, previous_type) in previous {
+        if current.contains(path) {
+            continue;
+        }
+        match previous_type {
+            FileType::Directory => changed.dir_removed(path.clone()),
+            _ => changed.file_removed(path.clone()),
+        }
+        changed_paths.push(path.clone());
+    }
+    changed_paths
+}
+
 /// Buffer containing the events that have happened since we last got a message.
 /// Used to dedupe events, since notify sends a notification on every change.
 #[derive(Allocative)]
 struct NotifyFileData {
     ignored: u64,
     events: OrderedSet<(CellPath, ChangeType)>,
+    /// Set if events were lost.
+    rescan: Option<RescanReason>,
 }
 
 impl NotifyFileData {
@@ -89,6 +363,7 @@ impl NotifyFileData {
         Self {
             ignored: 0,
             events: OrderedSet::new(),
+            rescan: None,
         }
     }
 
@@ -96,31 +371,35 @@ impl NotifyFileData {
         &mut self,
         event: notify::Result<notify::Event>,
         root: &ProjectRoot,
-        cells: &CellResolver,
-        ignore_specs: &HashMap<CellName, IgnoreSet>,
+        watched: &WatchedPaths,
     ) -> anyhow::Result<()> {
-        let event = event?;
+        let event = match event {
+            Ok(event) => event,
+            Err(e) if matches!(e.kind, ErrorKind::MaxFilesWatch) => {
+                warn!("FileWatcher: {}, will rescan", e);
+                self.rescan = Some(RescanReason::WatchLimit);
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if event.need_rescan() {
+            warn!("FileWatcher: events were lost, will rescan");
+            self.rescan.get_or_insert(RescanReason::QueueOverflow);
+        }
         let change_type = ChangeType::new(event.kind);
         for path in event.paths {
             // Testing shows that we get absolute paths back from the `notify` library.
             // It's not documented though.
             let path = root.relativize(AbsNormPath::new(&path)?)?;
 
-            // We ignore the buck-out prefix, as those are uninteresting events caused by us.
-            // We also ignore other buck-out directories, as if you have two isolation dirs running at once, they are not interesting.
-            // We do this in the notify-watcher, rather than a generic layer, as watchman users should configure
-            // to ignore buck-out, to reduce the number of events, rather than hiding them later.
-            if path.starts_with(InvocationPaths::buck_out_dir_prefix()) {
+            if WatchedPaths::is_buck_out(&path) {
                 // We don't want to event add them as ignored events, since they are super common
                 // and very boring
                 continue;
             }
 
-            let cell_path = cells.get_cell_path(&path)?;
-            let ignore = ignore_specs
-                .get(&cell_path.cell())
-                .expect("unexpected cell name mismatch")
-                .is_match(cell_path.path());
+            let cell_path = watched.cells.get_cell_path(&path)?;
+            let ignore = watched.is_ignored(&cell_path)?;
 
             info!(
                 "FileWatcher: {:?} {:?} (ignore = {})",
@@ -178,8 +457,17 @@ impl NotifyFileData {
 #[derive(Allocative)]
 pub struct NotifyFileWatcher {
     #[allocative(skip)]
-    watcher: RecommendedWatcher,
+    watcher: Mutex<RecommendedWatcher>,
     data: Arc<Mutex<anyhow::Result<NotifyFileData>>>,
+    root: ProjectRoot,
+    watched: Arc<WatchedPaths>,
+    /// Once the watch limit is reached, some directories are not watched, so we have to rescan
+    /// on every sync until they all are.
+    watch_limit_reached: AtomicBool,
+    /// When the events written to DICE by the last sync were taken. Files modified before are
+    /// not invalidated by a rescan.
+    #[allocative(skip)]
+    last_sync: Mutex<SystemTime>,
 }
 
 impl NotifyFileWatcher {
@@ -188,30 +476,130 @@ impl NotifyFileWatcher {
         cells: CellResolver,
         ignore_specs: HashMap<CellName, IgnoreSet>,
     ) -> anyhow::Result<Self> {
+        let watched = Arc::new(WatchedPaths {
+            cells,
+            ignore_specs,
+        });
         let data = Arc::new(Mutex::new(Ok(NotifyFileData::new())));
         let data2 = data.dupe();
         let root2 = root.dupe();
+        let watched2 = watched.dupe();
         let mut watcher = notify::recommended_watcher(move |event| {
             let mut guard = data2.lock().unwrap();
             if let Ok(state) = &mut *guard {
-                if let Err(e) = state.process(event, &root2, &cells, &ignore_specs) {
+                if let Err(e) = state.process(event, &root2, &watched2) {
                     *guard = Err(e);
                 }
             }
         })?;
-        watcher.watch(root.root().as_path(), notify::RecursiveMode::Recursive)?;
-        Ok(Self { watcher, data })
+        let watch_limit_reached = match watcher
+            .watch(root.root().as_path(), RecursiveMode::Recursive)
+        {
+            Ok(()) => false,
+            Err(e) if matches!(e.kind, ErrorKind::MaxFilesWatch) => {
+                warn!(
+                    "FileWatcher: {}, file changes will be found by rescanning the repository on every command",
+                    e
+                );
+                true
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            watcher: Mutex::new(watcher),
+            data,
+            root: root.dupe(),
+            watched,
+            watch_limit_reached: AtomicBool::new(watch_limit_reached),
+            last_sync: Mutex::new(SystemTime::now()),
+        })
+    }
+
+    /// Watch the repository again, in case watches were freed since the watch limit was
+    /// reached. Returns whether all the directories are watched now.
+    fn rewatch(&self) -> anyhow::Result<bool> {
+        let mut watcher = self.watcher.lock().unwrap();
+        let root = self.root.root().as_path();
+        // The directories watched already count towards the limit, so start over.
+        let _ignored = watcher.unwatch(root);
+        match watcher.watch(root, RecursiveMode::Recursive) {
+            Ok(()) => Ok(true),
+            Err(e) if matches!(e.kind, ErrorKind::MaxFilesWatch) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Find the changes by comparing the repository with the file state in DICE.
+    async fn rescan(
+        &self,
+        reason: RescanReason,
+        since: SystemTime,
+        mut dice: DiceTransactionUpdater,
+    ) -> anyhow::Result<(buck2_data::FileWatcherStats, DiceTransactionUpdater)> {
+        let start = Instant::now();
+        let root = self.root.dupe();
+        let watched = self.watched.dupe();
+        let scanned =
+            tokio::task::spawn_blocking(move || RepoScan::scan(&root, &watched, since)).await??;
+
+        let mut changed = FileChangeTracker::new();
+        let changed_paths = scanned
+            .diff_with_dice(&dice.existing_state().await, &self.watched, &mut changed)
+            .await?;
+        changed.write_to_dice(&mut dice)?;
+
+        let mut stats = FileWatcherStats::new(changed_paths.len(), None, None);
+        for path in &changed_paths {
+            stats.add(
+                path.to_string(),
+                buck2_data::FileWatcherEventType::Modify,
+                buck2_data::FileWatcherKind::File,
+            );
+        }
+        stats.rescan(buck2_data::FileWatcherRescan {
+            reason: reason.to_string(),
+            paths_scanned: scanned.paths_scanned as u64,
+            paths_changed: changed_paths.len() as u64,
+            duration: start.elapsed().try_into().ok(),
+        });
+        Ok((stats.finish(), dice))
     }
 
-    fn sync2(
+    async fn sync2(
         &self,
         mut dice: DiceTransactionUpdater,
     ) -> anyhow::Result<(buck2_data::FileWatcherStats, DiceTransactionUpdater)> {
-        let mut guard = self.data.lock().unwrap();
-        let old = mem::replace(&mut *guard, Ok(NotifyFileData::new()));
-        let (stats, changes) = old?.sync();
-        changes.write_to_dice(&mut dice)?;
-        Ok((stats, dice))
+        let sync_start = SystemTime::now();
+        let old = {
+            let mut guard = self.data.lock().unwrap();
+            mem::replace(&mut *guard, Ok(NotifyFileData::new()))?
+        };
+
+        if old.rescan == Some(RescanReason::WatchLimit) {
+            self.watch_limit_reached.store(true, Ordering::Relaxed);
+        }
+        let res = if self.watch_limit_reached.load(Ordering::Relaxed) {
+            // Watch again before scanning, so that changes made during the scan are not missed.
+            let all_watched = self.rewatch()?;
+            let since = *self.last_sync.lock().unwrap();
+            let res = self.rescan(RescanReason::WatchLimit, since, dice).await?;
+            if all_watched {
+                info!("FileWatcher: all directories are watched again");
+                self.watch_limit_reached.store(false, Ordering::Relaxed);
+            }
+            res
+        } else if let Some(reason) = old.rescan {
+            let since = *self.last_sync.lock().unwrap();
+            self.rescan(reason, since, dice).await?
+        } else {
+            let (stats, changes) = old.sync();
+            changes.write_to_dice(&mut dice)?;
+            (stats, dice)
+        };
+
+        *self.last_sync.lock().unwrap() = sync_start;
+        Ok(res)
     }
 }
 
@@ -223,7 +611,7 @@ impl FileWatcher for NotifyFileWatcher {
                 provider: buck2_data::FileWatcherProvider::RustNotify as i32,
             },
             async {
-                let (stats, res) = match self.sync2(dice) {
+                let (stats, res) = match self.sync2(dice).await {
                     Ok((stats, dice)) => ((Some(stats)), Ok(dice)),
                     Err(e) => (None, Err(e)),
                 };
@@ -233,3 +621,112 @@ impl FileWatcher for NotifyFileWatcher {
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use buck2_common::dice::file_ops::FileChangeTracker;
+    use buck2_common::file_ops::FileType;
+    use buck2_common::ignores::ignore_set::IgnoreSet;
+    use buck2_core::cells::cell_path::CellPath;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+    use buck2_core::cells::CellResolver;
+    use buck2_core::fs::project::ProjectRootTemp;
+
+    use crate::file_watcher::notify::diff_dir;
+    use crate::file_watcher::notify::RepoScan;
+    use crate::file_watcher::notify::ScannedEntry;
+    use crate::file_watcher::notify::WatchedPaths;
+
+    fn previous_entries(paths: &[(&str, FileType)]) -> Vec<(CellPath, FileType)> {
+        paths
+            .iter()
+            .map(|(path, file_type)| (CellPath::testing_new(path), file_type.clone()))
+            .collect()
+    }
+
+    fn current_entries(paths: &[(&str, FileType, bool)]) -> Vec<ScannedEntry> {
+        paths
+            .iter()
+            .map(|(path, file_type, modified)| ScannedEntry {
+                path: CellPath::testing_new(path),
+                file_type: file_type.clone(),
+                modified: *modified,
+            })
+            .collect()
+    }
+
+    fn sorted(paths: impl IntoIterator<Item = CellPath>) -> Vec<String> {
+        let mut paths = paths.into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_diff_dir() {
+        let previous = previous_entries(&[
+            ("root//a/dir", FileType::Directory),
+            ("root//a/unchanged", FileType::File),
+            ("root//a/modified", FileType::File),
+            ("root//a/removed", FileType::File),
+            ("root//a/retyped", FileType::File),
+        ]);
+        let current = current_entries(&[
+            ("root//a/dir", FileType::Directory, false),
+            ("root//a/unchanged", FileType::File, false),
+            ("root//a/modified", FileType::File, true),
+            ("root//a/added", FileType::Symlink, true),
+            ("root//a/retyped", FileType::Directory, false),
+        ]);
+
+        let changed_paths = diff_dir(&previous, &current, &mut FileChangeTracker::new());
+        assert_eq!(
+            vec![
+                "root//a/added",
+                "root//a/modified",
+                "root//a/removed",
+                "root//a/retyped"
+            ],
+            sorted(changed_paths)
+        );
+    }
+
+    #[test]
+    fn test_scan() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        fs.write_file("a/file", "");
+        fs.write_file("a/b/file", "");
+        fs.write_file("a/ignored/file", "");
+        fs.write_file("buck-out/v2/file", "");
+
+        let root = CellName::testing_new("root");
+        let watched = WatchedPaths {
+            cells: CellResolver::testing_with_name_and_path(root, CellRootPathBuf::testing_new("")),
+            ignore_specs: HashMap::from([(root, IgnoreSet::from_ignore_spec("a/ignored", true)?)]),
+        };
+
+        let scan = |since| -> anyhow::Result<(Vec<String>, Vec<String>)> {
+            let scanned = RepoScan::scan(fs.path(), &watched, since)?;
+            let entries = || scanned.dirs.iter().flat_map(|d| d.entries.iter());
+            Ok((
+                sorted(entries().map(|e| e.path.clone())),
+                sorted(entries().filter(|e| e.modified).map(|e| e.path.clone())),
+            ))
+        };
+
+        let (paths, modified) = scan(SystemTime::UNIX_EPOCH)?;
+        assert_eq!(
+            vec!["root//a", "root//a/b", "root//a/b/file", "root//a/file"],
+            paths
+        );
+        assert_eq!(vec!["root//a/b/file", "root//a/file"], modified);
+
+        let (_, modified) = scan(SystemTime::now() + Duration::from_secs(3600))?;
+        assert!(modified.is_empty());
+        Ok(())
+    }
+}
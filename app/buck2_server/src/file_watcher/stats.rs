@@ -72,6 +72,11 @@ impl FileWatcherStats {
         }
     }
 
+    /// Events were lost and the changes were found by rescanning the repository instead.
+    pub(crate) fn rescan(&mut self, rescan: buck2_data::FileWatcherRescan) {
+        self.stats.rescan = Some(rescan);
+    }
+
     pub(crate) fn finish(self) -> buck2_data::FileWatcherStats {
         let Self {
             mut stats,
@@ -80,7 +85,7 @@ impl FileWatcherStats {
         } = self;
 
         stats.events = changes;
-        if changes_missed {
+        if changes_missed && stats.incomplete_events_reason.is_none() {
             let reason = format!(
                 "Too many files changed ({}, max {})",
                 stats.events_processed, MAX_FILE_CHANGE_RECORDS
@@ -100,11 +100,13 @@ impl WatchmanQueryProcessor {
     ) -> anyhow::Result<()> {
         let cell_path = self.cells.get_cell_path(path)?;
 
-        let ignore = self
-            .ignore_specs
-            .get(&cell_path.cell())
-            .expect("unexpected cell name mismatch")
-            .is_match(cell_path.path());
+        // External cells are written by buck2 itself and never change under a given origin.
+        let ignore = self.cells.get(cell_path.cell())?.external().is_some()
+            || self
+                .ignore_specs
+                .get(&cell_path.cell())
+                .expect("unexpected cell name mismatch")
+                .is_match(cell_path.path());
 
         info!("Watchman: {:?} (ignore = {})", ev, ignore);
 
@@ -7,15 +7,36 @@
  * of this source tree.
  */
 
+use std::io::BufReader;
+
 use anyhow::Context;
+use buck2_common::executor_config::Executor;
+use buck2_common::executor_config::RemoteExecutorUseCase;
+use buck2_core::fs::fs_util;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_events::dispatch::span_async;
+use buck2_execute::digest_config::HasDigestConfig;
+use buck2_execute::execute::dice_data::HasFallbackExecutorConfig;
+use buck2_execute::output_manifest::OutputManifest;
 use buck2_server_ctx::command_end::command_end;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
 
 use crate::ctx::BaseServerCommandContext;
 use crate::ctx::ServerCommandContext;
 
+#[derive(Debug, thiserror::Error)]
+enum MaterializeError {
+    #[error(
+        "Paths are neither on disk nor outputs known to the daemon, they may have been built \
+        by a daemon which has since been restarted, rebuild them or pass the `--output-manifest` \
+        of the build to materialize them:\n{}",
+        .0.join("\n")
+    )]
+    NotFound(Vec<String>),
+}
+
 pub(crate) async fn materialize_command(
     context: &ServerCommandContext<'_>,
     req: buck2_cli_proto::MaterializeRequest,
@@ -26,7 +47,7 @@ pub(crate) async fn materialize_command(
         data: Some(buck2_data::MaterializeCommandStart {}.into()),
     };
     span_async(start_event, async move {
-        let result = materialize(&context.base_context, req.paths)
+        let result = materialize(context, req.paths, req.on_demand, &req.output_manifest)
             .await
             .map(|()| buck2_cli_proto::MaterializeResponse {})
             .context("Failed to materialize paths");
@@ -37,15 +58,94 @@ pub(crate) async fn materialize_command(
 }
 
 async fn materialize(
-    server_ctx: &BaseServerCommandContext,
+    context: &ServerCommandContext<'_>,
     paths: Vec<String>,
+    on_demand: bool,
+    output_manifest: &str,
 ) -> anyhow::Result<()> {
+    let server_ctx = &context.base_context;
     let mut project_paths = Vec::new();
     for path in paths {
         project_paths.push(ProjectRelativePath::new(&path)?.to_owned())
     }
     server_ctx
         .materializer
-        .ensure_materialized(project_paths)
-        .await
+        .ensure_materialized(project_paths.clone())
+        .await?;
+
+    if on_demand {
+        // Paths unknown to the materializer are silently skipped, which is not what we want when
+        // the caller needs these files.
+        let mut missing = missing_paths(server_ctx, &project_paths);
+        if !missing.is_empty() && !output_manifest.is_empty() {
+            declare_from_output_manifest(context, output_manifest, &missing).await?;
+            server_ctx
+                .materializer
+                .ensure_materialized(missing.clone())
+                .await?;
+            missing = missing_paths(server_ctx, &missing);
+        }
+        if !missing.is_empty() {
+            return Err(MaterializeError::NotFound(
+                missing.iter().map(|p| p.to_string()).collect(),
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+fn missing_paths(
+    server_ctx: &BaseServerCommandContext,
+    paths: &[ProjectRelativePathBuf],
+) -> Vec<ProjectRelativePathBuf> {
+    paths
+        .iter()
+        .filter(|path| {
+            fs_util::symlink_metadata_if_exists(server_ctx.project_root.resolve(path))
+                .map_or(true, |m| m.is_none())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Declares the outputs containing `paths` to the materializer using their digests from the
+/// output manifest, so that they are downloaded from the CAS.
+async fn declare_from_output_manifest(
+    context: &ServerCommandContext<'_>,
+    output_manifest: &str,
+    paths: &[ProjectRelativePathBuf],
+) -> anyhow::Result<()> {
+    let server_ctx = &context.base_context;
+    let output_manifest = server_ctx
+        .project_root
+        .resolve(&context.working_dir)
+        .as_abs_path()
+        .join(output_manifest);
+    let manifest: OutputManifest =
+        serde_json::from_reader(BufReader::new(fs_util::open_file(&output_manifest)?))
+            .with_context(|| format!("Error reading output manifest `{}`", output_manifest))?;
+
+    let (digest_config, re_use_case) = (context as &dyn ServerCommandContextTrait)
+        .with_dice_ctx(|_, dice| async move {
+            // Download with the use case builds run with by default, rather than the one of
+            // whichever execution platform produced each output, which the manifest doesn't record.
+            let re_use_case = match &dice.get_fallback_executor_config().executor {
+                Executor::RemoteEnabled { re_use_case, .. } => *re_use_case,
+                Executor::Local(..) => RemoteExecutorUseCase::buck2_default(),
+            };
+            Ok((dice.global_data().get_digest_config(), re_use_case))
+        })
+        .await?;
+    let re_connection = context.get_re_connection();
+    manifest
+        .declare(
+            paths,
+            server_ctx.materializer.as_ref(),
+            &re_connection.get_client(),
+            re_use_case,
+            digest_config,
+        )
+        .await?;
+    Ok(())
 }
@@ -12,11 +12,13 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use anyhow::Context as _;
+use buck2_common::dice::file_ops::FileInvalidationStats;
 use buck2_core::io_counters::IoCounterKey;
 use buck2_events::EventSink;
 use buck2_execute::execute::blocking::BlockingExecutor;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::re::manager::ReConnectionManager;
+use buck2_execute_impl::executors::caching::cache_upload_stats;
 use buck2_util::process_stats::process_stats;
 use dice::Dice;
 use dupe::Dupe;
@@ -34,6 +36,7 @@ pub struct SnapshotCollector {
     materializer: Arc<dyn Materializer>,
     event_sink: Option<Arc<dyn EventSink>>,
     net_io_collector: SystemNetworkIoCollector,
+    file_invalidation_stats: Arc<FileInvalidationStats>,
 }
 
 impl SnapshotCollector {
@@ -44,6 +47,7 @@ impl SnapshotCollector {
         dice: Arc<Dice>,
         materializer: Arc<dyn Materializer>,
         event_sink: Option<Arc<dyn EventSink>>,
+        file_invalidation_stats: Arc<FileInvalidationStats>,
     ) -> SnapshotCollector {
         SnapshotCollector {
             re_client_manager,
@@ -53,6 +57,7 @@ impl SnapshotCollector {
             materializer,
             event_sink,
             net_io_collector: SystemNetworkIoCollector::new(),
+            file_invalidation_stats,
         }
     }
 
@@ -159,6 +164,12 @@ impl SnapshotCollector {
         if let Err(e) = inner(snapshot, &self.re_client_manager) {
             tracing::debug!("Error collecting network stats: {:#}", e);
         }
+
+        let cache_uploads = cache_upload_stats();
+        snapshot.cache_uploads_succeeded = cache_uploads.succeeded;
+        snapshot.cache_uploads_rejected = cache_uploads.rejected;
+        snapshot.cache_uploads_failed = cache_uploads.failed;
+        snapshot.cache_upload_bytes = cache_uploads.bytes;
     }
 
     fn add_dice_metrics(&self, snapshot: &mut buck2_data::Snapshot) {
@@ -166,6 +177,7 @@ impl SnapshotCollector {
         snapshot.dice_key_count = metrics.key_count as u64;
         snapshot.dice_currently_active_key_count = metrics.currently_active_key_count as u64;
         snapshot.dice_active_transaction_count = metrics.active_transaction_count;
+        snapshot.dice_suppressed_file_invalidations = self.file_invalidation_stats.suppressed();
     }
 
     fn add_materializer_metrics(&self, snapshot: &mut buck2_data::Snapshot) {
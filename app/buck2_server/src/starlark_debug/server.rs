@@ -25,6 +25,7 @@ use dupe::Dupe;
 use futures::StreamExt;
 use gazebo::prelude::*;
 use itertools::Itertools;
+use starlark::debug::exception_breakpoint_filters;
 use starlark::debug::prepare_dap_adapter;
 use starlark::debug::resolve_breakpoints;
 use starlark::debug::DapAdapter;
@@ -62,17 +63,20 @@ use crate::starlark_debug::HookId;
 /// See https://microsoft.github.io/debug-adapter-protocol/specification#Types_Capabilities
 fn capabilities() -> serde_json::Value {
     // debugserver_types is out of date and missing fields on Capabilities and so we just construct
-    // a little json map explicitly ourselves.
+    // a little json map explicitly ourselves (DAP uses camelCase field names).
     serde_json::json!({
-        "supports_configuration_done_request": true,
-        "supports_evaluate_for_hovers": true,
-        "supports_set_variable": true,
-        "supports_step_in_targets_request": true,
-        "supports_conditional_breakpoints": true,
+        "supportsConfigurationDoneRequest": true,
+        "supportsEvaluateForHovers": true,
+        "supportsSetVariable": true,
+        "supportsStepInTargetsRequest": true,
+        "supportsConditionalBreakpoints": true,
+        "supportsHitConditionalBreakpoints": true,
+        "supportsLogPoints": true,
+        "exceptionBreakpointFilters": exception_breakpoint_filters(),
 
         // This is different from starlark's `dap_capabilities`. The buck starlark debugger treats
         // each ongoing starlark Evaluation as a separate thread and handles requests appropriately.
-        "supports_single_thread_execution_requests": true,
+        "supportsSingleThreadExecutionRequests": true,
     })
 }
 
@@ -204,7 +208,23 @@ impl BuckStarlarkDebuggerServer {
 
     /// Called when a starlark evaluation is paused (e.g. at a breakpoint).
     pub(crate) fn event_stopped(&self, hook_id: HookId) {
-        self.maybe_to_state(ServerMessage::EvalStopped { hook_id });
+        self.maybe_to_state(ServerMessage::EvalStopped {
+            hook_id,
+            error: None,
+        });
+    }
+
+    /// Called when a starlark evaluation is paused because it raised an error.
+    pub(crate) fn event_stopped_on_error(&self, hook_id: HookId, error: String) {
+        self.maybe_to_state(ServerMessage::EvalStopped {
+            hook_id,
+            error: Some(error),
+        });
+    }
+
+    /// Called when a starlark evaluation hits a logpoint.
+    pub(crate) fn event_output(&self, output: String) {
+        self.maybe_to_state(ServerMessage::EvalOutput { output });
     }
 
     /// Called to forward along requests from the DAP client.
@@ -245,6 +265,11 @@ enum ServerMessage {
     },
     EvalStopped {
         hook_id: HookId,
+        /// Set if the evaluation stopped because it raised an error.
+        error: Option<String>,
+    },
+    EvalOutput {
+        output: String,
     },
     Detach,
 }
@@ -263,6 +288,9 @@ struct ServerState {
     /// The currently set breakpoints. New hooks will be initialized with these.
     set_breakpoints: HashMap<String, ResolvedBreakpoints>,
 
+    /// The currently set exception breakpoints. New hooks will be initialized with these.
+    set_exception_breakpoints: Option<dap::SetExceptionBreakpointsArguments>,
+
     /// The project root is used to get the current source code to resolve breakpoints.
     project_root: ProjectRoot,
 
@@ -320,11 +348,13 @@ impl DebugServer for ServerState {
 
     fn set_exception_breakpoints(
         &mut self,
-        _x: dap::SetExceptionBreakpointsArguments,
+        x: dap::SetExceptionBreakpointsArguments,
     ) -> anyhow::Result<()> {
-        // TODO(cjhopman): This may not make sense in starlark and could be a more informative error. Or possibly we
-        // could use it to break on `fail()`.
-        Err(StarlarkDebuggerError::Unimplemented.into())
+        for hook_state in self.current_hooks.values() {
+            hook_state.adapter.set_exception_breakpoints(&x)?;
+        }
+        self.set_exception_breakpoints = Some(x);
+        Ok(())
     }
 
     fn attach(&mut self, _x: dap::AttachRequestArguments) -> anyhow::Result<()> {
@@ -483,6 +513,7 @@ impl ServerState {
             next_pseudo_thread: 0,
             next_hook_id: HookId(0),
             set_breakpoints: HashMap::new(),
+            set_exception_breakpoints: None,
         }
     }
 
@@ -583,7 +614,8 @@ impl ServerState {
                 };
                 self.to_client.send(ToClientMessage::Response(response))?;
             }
-            ServerMessage::EvalStopped { hook_id } => self.eval_stopped(hook_id)?,
+            ServerMessage::EvalStopped { hook_id, error } => self.eval_stopped(hook_id, error)?,
+            ServerMessage::EvalOutput { output } => self.eval_output(output)?,
             ServerMessage::Detach => {
                 self.detach();
                 return Ok(false);
@@ -630,6 +662,11 @@ impl ServerState {
         for (source, breakpoints) in &self.set_breakpoints {
             hook_state.adapter.set_breakpoints(source, breakpoints)?;
         }
+        if let Some(exception_breakpoints) = &self.set_exception_breakpoints {
+            hook_state
+                .adapter
+                .set_exception_breakpoints(exception_breakpoints)?;
+        }
         self.current_hooks.insert(hook_id, hook_state);
 
         self.to_client.send(ToClientMessage::Event(dap_event(
@@ -665,7 +702,7 @@ impl ServerState {
         self.current_commands.remove(&handle_id);
     }
 
-    fn eval_stopped(&mut self, hook_id: HookId) -> anyhow::Result<()> {
+    fn eval_stopped(&mut self, hook_id: HookId, error: Option<String>) -> anyhow::Result<()> {
         debug!("eval stopped {}", hook_id);
         let mut state = self.current_hooks.get_mut(&hook_id).unwrap();
         let top_frame = state.adapter.top_frame();
@@ -676,13 +713,23 @@ impl ServerState {
         state.stopped_at = Some(description);
         let thread_id = state.pseudo_thread_id;
 
-        let msg = dap::StoppedEventBody {
-            reason: "breakpoint".to_owned(),
-            thread_id: Some(thread_id as i64),
-            description: Some("Hello".to_owned()),
-            all_threads_stopped: Some(false),
-            preserve_focus_hint: None,
-            text: None,
+        let msg = match error {
+            None => dap::StoppedEventBody {
+                reason: "breakpoint".to_owned(),
+                thread_id: Some(thread_id as i64),
+                description: Some("Hello".to_owned()),
+                all_threads_stopped: Some(false),
+                preserve_focus_hint: None,
+                text: None,
+            },
+            Some(error) => dap::StoppedEventBody {
+                reason: "exception".to_owned(),
+                thread_id: Some(thread_id as i64),
+                description: Some("Paused on error".to_owned()),
+                all_threads_stopped: Some(false),
+                preserve_focus_hint: None,
+                text: Some(error),
+            },
         };
 
         self.to_client
@@ -690,6 +737,21 @@ impl ServerState {
         Ok(())
     }
 
+    fn eval_output(&mut self, output: String) -> anyhow::Result<()> {
+        let msg = dap::OutputEventBody {
+            output,
+            category: Some("console".to_owned()),
+            column: None,
+            data: None,
+            line: None,
+            source: None,
+            variables_reference: None,
+        };
+        self.to_client
+            .send(ToClientMessage::Event(dap_event("output", Some(&msg))))?;
+        Ok(())
+    }
+
     fn detach(&mut self) {
         // Dropping the DapAdapter should make any hooked Evaluator continue freely.
         self.current_hooks.clear();
@@ -739,6 +801,17 @@ impl DapAdapterClient for BuckStarlarkDapAdapterClient {
     fn event_stopped(&self) {
         self.handle.0.server.event_stopped(self.hook_id)
     }
+
+    fn event_stopped_on_error(&self, error: String) {
+        self.handle
+            .0
+            .server
+            .event_stopped_on_error(self.hook_id, error)
+    }
+
+    fn event_output(&self, output: String) {
+        self.handle.0.server.event_output(output)
+    }
 }
 
 /// Information about ongoing commands held by the debugger server.
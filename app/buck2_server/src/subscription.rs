@@ -7,18 +7,29 @@
  * of this source tree.
  */
 
+use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::Context as _;
+use buck2_common::convert::ProstDurationExt;
+use buck2_common::legacy_configs::cells::BuckConfigBasedCells;
+use buck2_core::cells::CellResolver;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_core::pattern::ParsedPattern;
+use buck2_core::target::label::TargetLabel;
 use buck2_events::dispatch::span_async;
+use buck2_events::BuckEvent;
 use buck2_server_ctx::command_end::command_end;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_wrapper_common::invocation_id::TraceId;
+use dupe::Dupe;
 use futures::future::FutureExt;
 use gazebo::prelude::*;
 use tokio::time::MissedTickBehavior;
 
 use crate::active_commands;
+use crate::active_commands::BuildEventsReceiver;
 use crate::streaming_request_handler::StreamingRequestHandler;
 
 pub(crate) async fn run_subscription_server_command(
@@ -50,6 +61,7 @@ pub(crate) async fn run_subscription_server_command(
                 .context("Error creating a materializer subscription")?;
 
             let mut wants_active_commands = false;
+            let mut build_events: Option<BuildEventsSubscription> = None;
 
             let mut ticker = tokio::time::interval(Duration::from_millis(100));
             ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
@@ -74,8 +86,33 @@ pub(crate) async fn run_subscription_server_command(
                             Request::SubscribeToActiveCommands(buck2_subscription_proto::SubscribeToActiveCommands {}) => {
                                 wants_active_commands = true;
                             }
+                            Request::SubscribeToBuildEvents(subscribe) => {
+                                let trace_ids = subscribe
+                                    .trace_ids
+                                    .iter()
+                                    .map(|id| TraceId::from_str(id).with_context(|| format!("Invalid trace id `{}`", id)))
+                                    .collect::<anyhow::Result<_>>()?;
+                                // Needed to parse the target patterns.
+                                let cell_resolver = BuckConfigBasedCells::parse(ctx.project_root())?.cell_resolver;
+                                let filter = BuildEventsFilter::new(&subscribe.target_patterns, &cell_resolver)?;
+                                // Replace any previous subscription, which may be for other commands.
+                                build_events = Some(BuildEventsSubscription {
+                                    receiver: active_commands::subscribe_to_build_events(trace_ids),
+                                    filter,
+                                });
+                            }
+                            Request::UnsubscribeFromBuildEvents(buck2_subscription_proto::UnsubscribeFromBuildEvents {}) => {
+                                build_events = None;
+                            }
                         }
                     }
+                    response = next_build_event(&mut build_events).fuse() => {
+                        partial_result_dispatcher.emit(buck2_cli_proto::SubscriptionResponseWrapper {
+                            response: Some(buck2_subscription_proto::SubscriptionResponse {
+                                response: Some(response)
+                            })
+                        });
+                    }
                     path = materializer_subscription.next_materialization().fuse() => {
                         let path = path.context("Materializer hung up")?;
                         partial_result_dispatcher.emit(buck2_cli_proto::SubscriptionResponseWrapper {
@@ -136,3 +173,384 @@ fn active_commands_snapshot() -> buck2_subscription_proto::ActiveCommandsSnapsho
 
     buck2_subscription_proto::ActiveCommandsSnapshot { active_commands }
 }
+
+struct BuildEventsSubscription {
+    receiver: BuildEventsReceiver,
+    filter: BuildEventsFilter,
+}
+
+/// Wait for the next build event passing the filter. Never resolves if there is no subscription.
+async fn next_build_event(
+    subscription: &mut Option<BuildEventsSubscription>,
+) -> buck2_subscription_proto::subscription_response::Response {
+    let subscription = match subscription {
+        Some(subscription) => subscription,
+        None => return futures::future::pending().await,
+    };
+
+    loop {
+        let event = match subscription.receiver.recv().await {
+            Some(event) => event,
+            // The sender is only removed when the receiver is dropped, so this can't happen.
+            None => return futures::future::pending().await,
+        };
+        let dropped = subscription.receiver.take_dropped();
+        if dropped > 0 {
+            tracing::warn!("Subscription fell behind, dropped {} build events", dropped);
+        }
+        if let Some(response) = subscription.filter.to_response(&event) {
+            return response;
+        }
+    }
+}
+
+/// Which build events a subscription is notified of. Filtering by command happens when the
+/// events are published, see `active_commands::subscribe_to_build_events`.
+struct BuildEventsFilter {
+    cell_resolver: CellResolver,
+    /// Absolute target patterns, e.g. `cell//pkg:target`, `cell//pkg:` or `cell//pkg/...`.
+    target_patterns: Vec<ParsedPattern<TargetPatternExtra>>,
+}
+
+impl BuildEventsFilter {
+    fn new(target_patterns: &[String], cell_resolver: &CellResolver) -> anyhow::Result<Self> {
+        Ok(Self {
+            cell_resolver: cell_resolver.dupe(),
+            target_patterns: target_patterns
+                .iter()
+                .map(|p| ParsedPattern::parse_precise(p, cell_resolver.root_cell(), cell_resolver))
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    fn matches_target(&self, label: Option<&buck2_data::TargetLabel>) -> bool {
+        if self.target_patterns.is_empty() {
+            return true;
+        }
+        let label = match label {
+            Some(label) => label,
+            None => return false,
+        };
+        match TargetLabel::parse(
+            &format!("{}:{}", label.package, label.name),
+            self.cell_resolver.root_cell(),
+            &self.cell_resolver,
+        ) {
+            Ok(label) => self.target_patterns.iter().any(|p| p.matches(&label)),
+            Err(_) => false,
+        }
+    }
+
+    /// Convert an event to a notification, if it passes the filter.
+    fn to_response(
+        &self,
+        event: &BuckEvent,
+    ) -> Option<buck2_subscription_proto::subscription_response::Response> {
+        use buck2_data::buck_event::Data;
+
+        let trace_id = event.trace_id().ok()?.to_string();
+
+        match event.data() {
+            Data::SpanStart(buck2_data::SpanStartEvent {
+                data: Some(buck2_data::span_start_event::Data::ActionExecution(start)),
+            }) => {
+                let label = action_owner_label(start.key.as_ref());
+                if !self.matches_target(label) {
+                    return None;
+                }
+                let name = start.name.clone().unwrap_or_default();
+                Some(
+                    buck2_subscription_proto::ActionStarted {
+                        trace_id,
+                        target_label: display_label(label),
+                        category: name.category,
+                        identifier: name.identifier,
+                    }
+                    .into(),
+                )
+            }
+            Data::SpanEnd(buck2_data::SpanEndEvent {
+                data: Some(buck2_data::span_end_event::Data::ActionExecution(end)),
+                duration,
+                ..
+            }) => {
+                let label = action_owner_label(end.key.as_ref());
+                if !self.matches_target(label) {
+                    return None;
+                }
+                let name = end.name.clone().unwrap_or_default();
+                Some(
+                    buck2_subscription_proto::ActionFinished {
+                        trace_id,
+                        target_label: display_label(label),
+                        category: name.category,
+                        identifier: name.identifier,
+                        failed: end.failed,
+                        duration_ms: duration_ms(duration.as_ref()),
+                    }
+                    .into(),
+                )
+            }
+            Data::Instant(buck2_data::InstantEvent {
+                data: Some(buck2_data::instant_event::Data::TargetBuilt(built)),
+            }) => {
+                let label = built.target_label.as_ref().and_then(|l| l.label.as_ref());
+                if !self.matches_target(label) {
+                    return None;
+                }
+                Some(
+                    buck2_subscription_proto::TargetBuilt {
+                        trace_id,
+                        target_label: display_label(label),
+                        failed: built.failed,
+                    }
+                    .into(),
+                )
+            }
+            Data::Instant(buck2_data::InstantEvent {
+                data: Some(buck2_data::instant_event::Data::TestResult(result)),
+            }) => {
+                let label = result.target_label.as_ref().and_then(|l| l.label.as_ref());
+                if !self.matches_target(label) {
+                    return None;
+                }
+                Some(
+                    buck2_subscription_proto::TestResult {
+                        trace_id,
+                        target_label: display_label(label),
+                        name: result.name.clone(),
+                        status: buck2_data::TestStatus::from_i32(result.status)
+                            .unwrap_or(buck2_data::TestStatus::Unknown)
+                            .as_str_name()
+                            .to_owned(),
+                        duration_ms: duration_ms(result.duration.as_ref()),
+                    }
+                    .into(),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+fn action_owner_label(key: Option<&buck2_data::ActionKey>) -> Option<&buck2_data::TargetLabel> {
+    use buck2_data::action_key::Owner;
+
+    match key?.owner.as_ref()? {
+        Owner::TargetLabel(label)
+        | Owner::TestTargetLabel(label)
+        | Owner::LocalResourceSetup(label) => label.label.as_ref(),
+        Owner::AnonTarget(anon) => anon.name.as_ref(),
+        Owner::BxlKey(..) => None,
+    }
+}
+
+fn display_label(label: Option<&buck2_data::TargetLabel>) -> String {
+    match label {
+        Some(label) => format!("{}:{}", label.package, label.name),
+        None => String::new(),
+    }
+}
+
+fn duration_ms(duration: Option<&prost_types::Duration>) -> u64 {
+    duration
+        .and_then(|d| d.try_into_duration().ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::SystemTime;
+
+    use buck2_core::cells::alias::NonEmptyCellAlias;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+
+    use super::*;
+
+    fn cell_resolver() -> CellResolver {
+        let root = CellName::testing_new("root");
+        let other = CellName::testing_new("other");
+        CellResolver::testing_with_names_and_paths_with_alias(&[
+            (
+                root,
+                CellRootPathBuf::testing_new(""),
+                HashMap::from([
+                    (NonEmptyCellAlias::testing_new("root"), root),
+                    (NonEmptyCellAlias::testing_new("other"), other),
+                ]),
+            ),
+            (other, CellRootPathBuf::testing_new("other"), HashMap::new()),
+        ])
+    }
+
+    fn filter(patterns: &[&str]) -> anyhow::Result<BuildEventsFilter> {
+        let patterns: Vec<String> = patterns.iter().map(|p| (*p).to_owned()).collect();
+        BuildEventsFilter::new(&patterns, &cell_resolver())
+    }
+
+    fn label(package: &str, name: &str) -> buck2_data::TargetLabel {
+        buck2_data::TargetLabel {
+            package: package.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+
+    fn configured_label(package: &str, name: &str) -> buck2_data::ConfiguredTargetLabel {
+        buck2_data::ConfiguredTargetLabel {
+            label: Some(label(package, name)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_target_patterns() -> anyhow::Result<()> {
+        let matches = |filter: &BuildEventsFilter, package, name| {
+            filter.matches_target(Some(&label(package, name)))
+        };
+
+        let target = filter(&["root//foo:bar"])?;
+        assert!(matches(&target, "root//foo", "bar"));
+        assert!(!matches(&target, "root//foo", "baz"));
+        assert!(!target.matches_target(None));
+
+        let package = filter(&["root//foo:"])?;
+        assert!(matches(&package, "root//foo", "baz"));
+        assert!(!matches(&package, "root//foo/bar", "baz"));
+
+        let recursive = filter(&["root//foo/..."])?;
+        assert!(matches(&recursive, "root//foo", "bar"));
+        assert!(matches(&recursive, "root//foo/bar", "baz"));
+        assert!(!matches(&recursive, "root//foobar", "baz"));
+
+        let root = filter(&["root//...", "other//foo:bar"])?;
+        assert!(matches(&root, "root//", "bar"));
+        assert!(matches(&root, "root//foo/bar", "baz"));
+        assert!(matches(&root, "other//foo", "bar"));
+        assert!(!matches(&root, "other//foo", "baz"));
+
+        let all = filter(&[])?;
+        assert!(matches(&all, "other//foo", "baz"));
+        assert!(all.matches_target(None));
+
+        assert!(filter(&["foo:bar"]).is_err());
+        assert!(filter(&["unknown//foo:bar"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_response() -> anyhow::Result<()> {
+        let trace_id = TraceId::new();
+        let event = |data: buck2_data::buck_event::Data| {
+            BuckEvent::new(SystemTime::now(), trace_id.dupe(), None, None, data)
+        };
+        let action_key = |package, name| buck2_data::ActionKey {
+            owner: Some(buck2_data::action_key::Owner::TargetLabel(
+                configured_label(package, name),
+            )),
+            ..Default::default()
+        };
+        let action_name = Some(buck2_data::ActionName {
+            category: "cxx_compile".to_owned(),
+            identifier: "foo.cpp".to_owned(),
+        });
+
+        let start = event(
+            buck2_data::SpanStartEvent {
+                data: Some(
+                    buck2_data::ActionExecutionStart {
+                        key: Some(action_key("root//foo", "bar")),
+                        name: action_name.clone(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+            }
+            .into(),
+        );
+        let end = event(
+            buck2_data::SpanEndEvent {
+                data: Some(
+                    buck2_data::ActionExecutionEnd {
+                        key: Some(action_key("root//foo", "bar")),
+                        name: action_name,
+                        failed: true,
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+                duration: Some(prost_types::Duration {
+                    seconds: 1,
+                    nanos: 500_000_000,
+                }),
+                ..Default::default()
+            }
+            .into(),
+        );
+        let built = event(
+            buck2_data::InstantEvent {
+                data: Some(
+                    buck2_data::TargetBuilt {
+                        target_label: Some(configured_label("other//baz", "qux")),
+                        failed: false,
+                    }
+                    .into(),
+                ),
+            }
+            .into(),
+        );
+        let unrelated = event(
+            buck2_data::InstantEvent {
+                data: Some(buck2_data::TagEvent { tags: Vec::new() }.into()),
+            }
+            .into(),
+        );
+
+        let all = filter(&[])?;
+        assert_eq!(
+            Some(
+                buck2_subscription_proto::ActionStarted {
+                    trace_id: trace_id.to_string(),
+                    target_label: "root//foo:bar".to_owned(),
+                    category: "cxx_compile".to_owned(),
+                    identifier: "foo.cpp".to_owned(),
+                }
+                .into()
+            ),
+            all.to_response(&start)
+        );
+        assert_eq!(
+            Some(
+                buck2_subscription_proto::ActionFinished {
+                    trace_id: trace_id.to_string(),
+                    target_label: "root//foo:bar".to_owned(),
+                    category: "cxx_compile".to_owned(),
+                    identifier: "foo.cpp".to_owned(),
+                    failed: true,
+                    duration_ms: 1500,
+                }
+                .into()
+            ),
+            all.to_response(&end)
+        );
+        assert_eq!(
+            Some(
+                buck2_subscription_proto::TargetBuilt {
+                    trace_id: trace_id.to_string(),
+                    target_label: "other//baz:qux".to_owned(),
+                    failed: false,
+                }
+                .into()
+            ),
+            all.to_response(&built)
+        );
+        assert_eq!(None, all.to_response(&unrelated));
+
+        let other = filter(&["other//..."])?;
+        assert_eq!(None, other.to_response(&start));
+        assert_eq!(None, other.to_response(&end));
+        assert!(other.to_response(&built).is_some());
+        Ok(())
+    }
+}
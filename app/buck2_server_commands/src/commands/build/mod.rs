@@ -13,6 +13,7 @@ use std::sync::Arc;
 
 use anyhow::Context as _;
 use async_trait::async_trait;
+use buck2_build_api::actions::diagnostics::HasActionDiagnostics;
 use buck2_build_api::build;
 use buck2_build_api::build::BuildEvent;
 use buck2_build_api::build::BuildTargetResult;
@@ -66,6 +67,7 @@ use futures::stream::StreamExt;
 use itertools::Itertools;
 
 use crate::commands::build::results::build_report::BuildReportCollector;
+use crate::commands::build::results::output_manifest::OutputManifestCollector;
 use crate::commands::build::results::providers::ProvidersPrinter;
 use crate::commands::build::results::result_report::ResultReporter;
 use crate::commands::build::results::result_report::ResultReporterOptions;
@@ -210,6 +212,12 @@ async fn build(
         None
     };
 
+    let mut output_manifest_collector = if request.output_manifest_filename.is_empty() {
+        None
+    } else {
+        Some(OutputManifestCollector::new(&artifact_fs))
+    };
+
     let mut result_collectors = vec![
         Some(&mut result_collector as &mut dyn BuildResultCollector),
         build_report_collector
@@ -218,6 +226,9 @@ async fn build(
         providers_printer
             .as_mut()
             .map(|v| v as &mut dyn BuildResultCollector),
+        output_manifest_collector
+            .as_mut()
+            .map(|v| v as &mut dyn BuildResultCollector),
     ]
     .into_iter()
     .flatten()
@@ -270,7 +281,12 @@ async fn build(
 
     let mut serialized_build_report = None;
     if let Some(build_report_collector) = build_report_collector {
-        let report = build_report_collector.into_report();
+        let action_diagnostics = ctx
+            .per_transaction_data()
+            .get_action_diagnostics()
+            .map(|diagnostics| diagnostics.take())
+            .unwrap_or_default();
+        let report = build_report_collector.into_report(action_diagnostics);
         if !build_opts.unstable_build_report_filename.is_empty() {
             let file = fs_util::create_file(
                 fs.resolve(cwd)
@@ -285,6 +301,17 @@ async fn build(
         };
     }
 
+    if let Some(output_manifest_collector) = output_manifest_collector {
+        let file = fs_util::create_file(
+            fs.resolve(cwd)
+                .as_abs_path()
+                .join(&request.output_manifest_filename),
+        )
+        .context("Error writing output manifest")?;
+        let mut file = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut file, &output_manifest_collector.into_manifest())?
+    }
+
     // TODO(nmj): The BuildResult / BuildResponse will eventually return all of the
     //            data back to the CLI client, and all build report generation will happen there.
     //            For now, we're going to be a little hacky to remove some stdout printing that
@@ -175,6 +175,7 @@ pub mod build_report {
     use std::collections::HashMap;
 
     use buck2_build_api::build::BuildProviderType;
+    use buck2_core::base_deferred_key::BaseDeferredKey;
     use buck2_core::configuration::data::ConfigurationData;
     use buck2_core::fs::artifact_path_resolver::ArtifactFs;
     use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
@@ -218,6 +219,40 @@ pub mod build_report {
         failures: HashMap<EntryLabel, ProjectRelativePathBuf>,
         project_root: AbsNormPathBuf,
         truncated: bool,
+        /// Diagnostics reported by actions executed during this build.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        diagnostics: Vec<BuildReportDiagnostic>,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub(crate) struct BuildReportDiagnostic {
+        /// the owner of the action that reported the diagnostic
+        owner: String,
+        file: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        column: Option<u32>,
+        severity: &'static str,
+        message: String,
+    }
+
+    impl BuildReportDiagnostic {
+        fn new(owner: &BaseDeferredKey, diagnostic: buck2_data::ActionDiagnostic) -> Self {
+            let severity = match buck2_data::DiagnosticSeverity::from_i32(diagnostic.severity) {
+                Some(buck2_data::DiagnosticSeverity::Info) => "info",
+                Some(buck2_data::DiagnosticSeverity::Warning) => "warning",
+                Some(buck2_data::DiagnosticSeverity::Error) | None => "error",
+            };
+            Self {
+                owner: owner.to_string(),
+                file: diagnostic.file,
+                line: diagnostic.line,
+                column: diagnostic.column,
+                severity,
+                message: diagnostic.message,
+            }
+        }
     }
 
     #[derive(Default, Debug, Serialize)]
@@ -280,7 +315,10 @@ pub mod build_report {
             }
         }
 
-        pub(crate) fn into_report(self) -> BuildReport {
+        pub(crate) fn into_report(
+            self,
+            action_diagnostics: Vec<(BaseDeferredKey, buck2_data::ActionDiagnostic)>,
+        ) -> BuildReport {
             BuildReport {
                 trace_id: self.trace_id.dupe(),
                 success: self.overall_success,
@@ -290,6 +328,10 @@ pub mod build_report {
                 // In buck1 we may truncate build report for a large number of targets.
                 // Setting this to false since we don't currently truncate buck2's build report.
                 truncated: false,
+                diagnostics: action_diagnostics
+                    .into_iter()
+                    .map(|(owner, diagnostic)| BuildReportDiagnostic::new(&owner, diagnostic))
+                    .collect(),
             }
         }
     }
@@ -435,3 +477,167 @@ pub mod providers {
         }
     }
 }
+
+pub mod output_manifest {
+    use std::collections::BTreeMap;
+
+    use buck2_core::fs::artifact_path_resolver::ArtifactFs;
+    use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+    use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
+    use buck2_execute::output_manifest::OutputManifest;
+    use buck2_execute::output_manifest::OutputManifestEntry;
+
+    use crate::commands::build::results::BuildOwner;
+    use crate::commands::build::results::BuildResultCollector;
+    use crate::commands::build::BuildTargetResult;
+
+    /// Collects the path, digest and size of every output, so that they can be materialized on
+    /// demand after a build which skipped materializing them.
+    pub(crate) struct OutputManifestCollector<'a> {
+        artifact_fs: &'a ArtifactFs,
+        outputs: BTreeMap<ProjectRelativePathBuf, OutputManifestEntry>,
+    }
+
+    impl<'a> OutputManifestCollector<'a> {
+        pub(crate) fn new(artifact_fs: &'a ArtifactFs) -> Self {
+            Self {
+                artifact_fs,
+                outputs: BTreeMap::new(),
+            }
+        }
+
+        pub(crate) fn into_manifest(self) -> OutputManifest {
+            OutputManifest {
+                outputs: self.outputs.into_values().collect(),
+            }
+        }
+    }
+
+    impl<'a> BuildResultCollector for OutputManifestCollector<'a> {
+        fn collect_result(&mut self, _label: &BuildOwner, result: &BuildTargetResult) {
+            for output in result.outputs.iter().flatten() {
+                for (artifact, value) in output.values.iter() {
+                    // Paths only fail to resolve for artifacts which were not built.
+                    if let Ok(path) = artifact.resolve_path(self.artifact_fs) {
+                        self.outputs
+                            .entry(path.clone())
+                            .or_insert_with(|| OutputManifestEntry::new(path, value));
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use buck2_artifact::artifact::artifact_type::testing::BuildArtifactTestingExt;
+        use buck2_artifact::artifact::artifact_type::Artifact;
+        use buck2_artifact::artifact::build_artifact::BuildArtifact;
+        use buck2_artifact::deferred::id::DeferredId;
+        use buck2_build_api::artifact_groups::ArtifactGroupValues;
+        use buck2_build_api::build::BuildProviderType;
+        use buck2_build_api::build::ProviderArtifacts;
+        use buck2_build_api::interpreter::rule_defs::provider::collection::FrozenProviderCollectionValue;
+        use buck2_build_api::interpreter::rule_defs::provider::collection::ProviderCollection;
+        use buck2_build_api::interpreter::rule_defs::provider::registration::register_builtin_providers;
+        use buck2_common::file_ops::FileMetadata;
+        use buck2_common::file_ops::TrackedFileDigest;
+        use buck2_common::result::SharedError;
+        use buck2_core::buck_path::resolver::BuckPathResolver;
+        use buck2_core::cells::cell_root_path::CellRootPathBuf;
+        use buck2_core::cells::name::CellName;
+        use buck2_core::cells::CellResolver;
+        use buck2_core::configuration::data::ConfigurationData;
+        use buck2_core::fs::buck_out_path::BuckOutPathResolver;
+        use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+        use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+        use buck2_core::fs::project::ProjectRoot;
+        use buck2_core::provider::label::ConfiguredProvidersLabel;
+        use buck2_core::provider::label::ProvidersName;
+        use buck2_core::target::label::ConfiguredTargetLabel;
+        use buck2_execute::artifact_value::ArtifactValue;
+        use buck2_execute::digest_config::DigestConfig;
+        use buck2_execute::output_manifest::OutputKind;
+        use buck2_interpreter_for_build::attrs::coerce;
+        use dupe::Dupe;
+        use starlark::environment::GlobalsBuilder;
+        use starlark::environment::Module;
+
+        use super::*;
+
+        fn default_providers() -> FrozenProviderCollectionValue {
+            let env = Module::new();
+            let globals = GlobalsBuilder::extended()
+                .with(register_builtin_providers)
+                .build();
+            let value = coerce::testing::to_value(&env, &globals, "[DefaultInfo()]");
+            let collection = ProviderCollection::try_from_value(value)
+                .map_err(|e| anyhow::anyhow!("{:?}", e))
+                .unwrap();
+            let collection = env.heap().alloc(collection);
+            env.set("", collection);
+            let frozen = env.freeze().unwrap();
+            FrozenProviderCollectionValue::try_from_value(frozen.get("").unwrap()).unwrap()
+        }
+
+        #[test]
+        fn test_collect_build_result() -> anyhow::Result<()> {
+            let digest_config = DigestConfig::testing_default();
+            let artifact_fs = ArtifactFs::new(
+                BuckPathResolver::new(CellResolver::testing_with_name_and_path(
+                    CellName::testing_new("cell"),
+                    CellRootPathBuf::new(ProjectRelativePathBuf::unchecked_new("cell".into())),
+                )),
+                BuckOutPathResolver::new(ProjectRelativePathBuf::unchecked_new(
+                    "buck-out/v2".into(),
+                )),
+                ProjectRoot::new(AbsNormPathBuf::try_from(std::env::current_dir()?)?)?,
+            );
+            let target = ConfiguredTargetLabel::testing_parse(
+                "cell//pkg:foo",
+                ConfigurationData::testing_new(),
+            );
+            let artifact = Artifact::from(BuildArtifact::testing_new(
+                target.dupe(),
+                ForwardRelativePathBuf::unchecked_new("out".into()),
+                DeferredId::testing_new(0),
+            ));
+            let digest =
+                TrackedFileDigest::from_content(b"hello", digest_config.cas_digest_config());
+            let value = ArtifactValue::file(FileMetadata {
+                digest: digest.dupe(),
+                is_executable: false,
+            });
+
+            let result = BuildTargetResult {
+                outputs: vec![
+                    Ok(ProviderArtifacts {
+                        values: ArtifactGroupValues::from_artifact(artifact.dupe(), value.dupe()),
+                        provider_type: BuildProviderType::Default,
+                    }),
+                    // The same output through another provider is only listed once.
+                    Ok(ProviderArtifacts {
+                        values: ArtifactGroupValues::from_artifact(artifact.dupe(), value),
+                        provider_type: BuildProviderType::Run,
+                    }),
+                    Err(SharedError::new(anyhow::anyhow!("Failed to build"))),
+                ],
+                providers: default_providers(),
+                run_args: None,
+            };
+            let label = ConfiguredProvidersLabel::new(target, ProvidersName::Default);
+
+            let mut collector = OutputManifestCollector::new(&artifact_fs);
+            collector.collect_result(&BuildOwner::Target(&label), &result);
+            let manifest = collector.into_manifest();
+
+            assert_eq!(1, manifest.outputs.len());
+            let entry = &manifest.outputs[0];
+            assert_eq!(artifact.resolve_path(&artifact_fs)?, entry.path);
+            assert_eq!(OutputKind::File, entry.kind);
+            assert_eq!(Some(digest.to_string()), entry.digest);
+            assert_eq!(5, entry.size);
+            Ok(())
+        }
+    }
+}
@@ -9,12 +9,15 @@
 
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::future::Future;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 use std::net::TcpListener;
 use std::process::Stdio;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -39,6 +42,7 @@ use buck2_common::dice::file_ops::HasFileOps;
 use buck2_common::executor_config::PathSeparatorKind;
 use buck2_common::file_ops::FileDigest;
 use buck2_common::pattern::resolve::resolve_target_patterns;
+use buck2_core::directory::unordered_entry_walk;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::fs_util;
@@ -52,6 +56,8 @@ use buck2_core::provider::label::ProvidersName;
 use buck2_core::target::name::TargetName;
 use buck2_data::InstallEventInfoEnd;
 use buck2_data::InstallEventInfoStart;
+use buck2_data::InstallFileSkipped;
+use buck2_events::dispatch::get_dispatcher;
 use buck2_events::dispatch::span_async;
 use buck2_execute::artifact::artifact_dyn::ArtifactDyn;
 use buck2_execute::artifact::fs::ExecutorFs;
@@ -61,7 +67,9 @@ use buck2_execute::materialize::materializer::HasMaterializer;
 use buck2_install_proto::installer_client::InstallerClient;
 use buck2_install_proto::FileReadyRequest;
 use buck2_install_proto::InstallInfoRequest;
+use buck2_install_proto::NegotiateRequest;
 use buck2_install_proto::ShutdownRequest;
+use buck2_install_proto::TreeEntry;
 use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
@@ -86,6 +94,9 @@ use starlark_map::small_map::SmallMap;
 use tokio::sync::mpsc;
 use tonic::transport::Channel;
 
+/// Maximum number of `FileReady` requests in flight per installer.
+const MAX_CONCURRENT_FILE_READY: usize = 32;
+
 #[derive(Debug, thiserror::Error)]
 pub enum InstallError {
     #[error("Target {1}:{0} cannot be installed as it does not expose an InstallInfo provider")]
@@ -324,7 +335,7 @@ async fn handle_install_request<'a>(
 ) -> anyhow::Result<()> {
     let (files_tx, files_rx) = mpsc::unbounded_channel();
     let build_files = async move {
-        build_files(ctx, materializations, install_files_slice, files_tx).await?;
+        build_files(ctx, install_files_slice, files_tx).await?;
         anyhow::Ok(())
     };
     let build_installer_and_connect = async move {
@@ -365,18 +376,40 @@ async fn handle_install_request<'a>(
         let client: InstallerClient<Channel> = connect_to_installer(tcp_port).await?;
         let artifact_fs = ctx.get_artifact_fs().await?;
 
+        let mut negotiations = HashMap::new();
         for (install_id, install_files) in install_files_slice {
             send_install_info(client.clone(), install_id, install_files, &artifact_fs).await?;
+            let negotiation = negotiate(client.clone(), install_id).await?;
+            negotiations.insert((*install_id).to_owned(), negotiation);
         }
 
+        let progress = InstallProgress {
+            files_total: install_files_slice
+                .iter()
+                .map(|(_, files)| files.len() as u64)
+                .sum(),
+            files_seen: AtomicU64::new(0),
+        };
+        let negotiations = &negotiations;
+        let progress = &progress;
+
         let send_files_result = tokio_stream::wrappers::UnboundedReceiverStream::new(files_rx)
             .map(anyhow::Ok)
-            .try_for_each_concurrent(None, |file| {
+            .try_for_each_concurrent(Some(MAX_CONCURRENT_FILE_READY), |file| {
+                let negotiation = negotiations.get(&file.install_id);
+                let artifact = ArtifactGroup::Artifact(file.artifact.dupe());
+                let materialize = async move {
+                    materialize_artifact_group(ctx, &artifact, materializations).await?;
+                    anyhow::Ok(())
+                };
                 send_file(
                     file,
                     &artifact_fs,
                     client.clone(),
                     installer_log_filename.to_owned(),
+                    negotiation,
+                    progress,
+                    materialize,
                 )
             })
             .await;
@@ -433,6 +466,58 @@ async fn send_install_info(
     Ok(())
 }
 
+/// What the installer told us it already has for an `install_id`.
+#[derive(Debug, Default)]
+struct Negotiation {
+    present_digests: HashMap<String, String>,
+    supports_directory_trees: bool,
+}
+
+impl Negotiation {
+    fn is_present(&self, name: &str, digest: &str) -> bool {
+        self.present_digests.get(name).map(String::as_str) == Some(digest)
+    }
+}
+
+async fn negotiate(
+    mut client: InstallerClient<Channel>,
+    install_id: &str,
+) -> anyhow::Result<Negotiation> {
+    let response_result = client
+        .negotiate(tonic::Request::new(NegotiateRequest {
+            install_id: install_id.to_owned(),
+        }))
+        .await;
+    let response = match response_result {
+        Ok(r) => r.into_inner(),
+        // Installers predating negotiation get every file, as before.
+        Err(status) if status.code() == tonic::Code::Unimplemented => {
+            return Ok(Negotiation::default());
+        }
+        Err(status) => {
+            return Err(InstallError::InternalInstallerFailure {
+                install_id: install_id.to_owned(),
+                err: status.message().to_owned(),
+            }
+            .into());
+        }
+    };
+
+    if response.install_id != install_id {
+        send_shutdown_command(client.clone()).await?;
+        return Err(anyhow::anyhow!(
+            "Received install id: {} doesn't match with the sent one: {}",
+            response.install_id,
+            &install_id
+        ));
+    }
+
+    Ok(Negotiation {
+        present_digests: response.present_digests,
+        supports_directory_trees: response.supports_directory_trees,
+    })
+}
+
 async fn send_shutdown_command(mut client: InstallerClient<Channel>) -> anyhow::Result<()> {
     let response_result = client
         .shutdown_server(tonic::Request::new(ShutdownRequest {}))
@@ -515,9 +600,10 @@ pub struct FileResult {
     artifact_value: ArtifactValue,
 }
 
+/// Build the files to install, without materializing them: that only happens once we know the
+/// installer does not have them already (see `send_file`).
 async fn build_files(
     ctx: &DiceComputations,
-    materializations: &MaterializationContext,
     install_files_slice: &[(&String, SmallMap<&str, Artifact>)],
     tx: mpsc::UnboundedSender<FileResult>,
 ) -> anyhow::Result<()> {
@@ -536,7 +622,7 @@ async fn build_files(
     try_join_all(file_outputs.into_iter().map(
         |(install_id, name, artifact, tx_clone)| async move {
             let artifact_values =
-                materialize_artifact_group(ctx, &artifact, materializations).await?;
+                materialize_artifact_group(ctx, &artifact, &MaterializationContext::Skip).await?;
             for (artifact, artifact_value) in artifact_values.iter() {
                 let file_result = FileResult {
                     install_id: (*install_id).to_owned(),
@@ -581,11 +667,50 @@ async fn connect_to_installer(tcp_port: u16) -> anyhow::Result<InstallerClient<C
     .await
 }
 
+struct InstallProgress {
+    files_total: u64,
+    files_seen: AtomicU64,
+}
+
+fn directory_tree(value: &ArtifactValue) -> anyhow::Result<Vec<TreeEntry>> {
+    let mut tree = Vec::new();
+    let mut walk = unordered_entry_walk(value.entry().as_ref());
+    while let Some((path, entry)) = walk.next() {
+        let path = path.get().as_str().to_owned();
+        match entry {
+            DirectoryEntry::Dir(_) => {}
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) => tree.push(TreeEntry {
+                path,
+                digest: f.digest.data().raw_digest().to_string(),
+                size: f.digest.size(),
+                is_executable: f.is_executable,
+                symlink_target: String::new(),
+            }),
+            DirectoryEntry::Leaf(ActionDirectoryMember::Symlink(symlink)) => tree.push(TreeEntry {
+                path,
+                symlink_target: symlink.target().as_str().to_owned(),
+                ..Default::default()
+            }),
+            DirectoryEntry::Leaf(ActionDirectoryMember::ExternalSymlink(symlink)) => {
+                tree.push(TreeEntry {
+                    path,
+                    symlink_target: symlink.with_full_target()?.target_str().to_owned(),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+    Ok(tree)
+}
+
 async fn send_file(
     file: FileResult,
     artifact_fs: &ArtifactFs,
     mut client: InstallerClient<Channel>,
     install_log: String,
+    negotiation: Option<&Negotiation>,
+    progress: &InstallProgress,
+    materialize: impl Future<Output = anyhow::Result<()>>,
 ) -> anyhow::Result<()> {
     let install_id = file.install_id;
     let name = file.name;
@@ -621,6 +746,36 @@ async fn send_file(
     let path = &artifact_fs
         .fs()
         .resolve(&artifact.resolve_path(artifact_fs)?);
+    let file_index = progress.files_seen.fetch_add(1, Ordering::Relaxed) + 1;
+    if negotiation.map_or(false, |n| n.is_present(&name, &digest)) {
+        tracing::debug!(
+            "Skipping `{}` for `{}`: the installer already has digest `{}`",
+            name,
+            install_id,
+            digest
+        );
+        // Still report the file, so that progress counts it.
+        get_dispatcher().instant_event(InstallFileSkipped {
+            artifact_name: name,
+            file_path: path.to_string(),
+            size,
+            file_index,
+            files_total: progress.files_total,
+        });
+        return Ok(());
+    }
+
+    materialize
+        .await
+        .with_context(|| format!("Failed to materialize `{}` for `{}`", name, install_id))?;
+
+    let tree = match file.artifact_value.entry() {
+        DirectoryEntry::Dir(_) if negotiation.map_or(false, |n| n.supports_directory_trees) => {
+            directory_tree(&file.artifact_value)?
+        }
+        _ => Vec::new(),
+    };
+
     let request = tonic::Request::new(FileReadyRequest {
         install_id: install_id.to_owned(),
         name: name.to_owned(),
@@ -628,11 +783,15 @@ async fn send_file(
         digest_algorithm,
         size,
         path: path.to_string(),
+        tree,
     });
 
     let start = InstallEventInfoStart {
         artifact_name: name.to_owned(),
         file_path: path.to_string(),
+        size,
+        file_index,
+        files_total: progress.files_total,
     };
     let end = InstallEventInfoEnd {};
     span_async(start, async {
@@ -684,3 +843,256 @@ async fn send_file(
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use buck2_artifact::artifact::artifact_type::testing::BuildArtifactTestingExt;
+    use buck2_artifact::artifact::build_artifact::BuildArtifact;
+    use buck2_artifact::deferred::id::DeferredId;
+    use buck2_common::file_ops::FileMetadata;
+    use buck2_common::file_ops::TrackedFileDigest;
+    use buck2_core::buck_path::resolver::BuckPathResolver;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+    use buck2_core::cells::CellResolver;
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::fs::buck_out_path::BuckOutPathResolver;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+    use buck2_core::fs::project::ProjectRoot;
+    use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+    use buck2_core::target::label::ConfiguredTargetLabel;
+    use buck2_events::create_source_sink_pair;
+    use buck2_events::dispatch::with_dispatcher_async;
+    use buck2_events::dispatch::EventDispatcher;
+    use buck2_events::Event;
+    use buck2_events::EventSource;
+    use buck2_execute::digest_config::DigestConfig;
+    use buck2_execute::directory::ActionDirectoryBuilder;
+    use buck2_execute::directory::INTERNER;
+    use buck2_install_proto::installer_server::Installer;
+    use buck2_install_proto::installer_server::InstallerServer;
+    use buck2_install_proto::FileResponse;
+    use buck2_install_proto::InstallResponse;
+    use buck2_install_proto::NegotiateResponse;
+    use buck2_install_proto::ShutdownResponse;
+    use buck2_wrapper_common::invocation_id::TraceId;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    use super::*;
+
+    /// An installer which already has some artifacts, and records the `FileReady` requests it
+    /// receives.
+    #[derive(Default, Clone)]
+    struct FakeInstaller {
+        present_digests: HashMap<String, String>,
+        file_ready: Arc<Mutex<Vec<FileReadyRequest>>>,
+    }
+
+    #[tonic::async_trait]
+    impl Installer for FakeInstaller {
+        async fn install(
+            &self,
+            request: tonic::Request<InstallInfoRequest>,
+        ) -> Result<tonic::Response<InstallResponse>, tonic::Status> {
+            Ok(tonic::Response::new(InstallResponse {
+                install_id: request.into_inner().install_id,
+            }))
+        }
+
+        async fn negotiate(
+            &self,
+            request: tonic::Request<NegotiateRequest>,
+        ) -> Result<tonic::Response<NegotiateResponse>, tonic::Status> {
+            Ok(tonic::Response::new(NegotiateResponse {
+                install_id: request.into_inner().install_id,
+                present_digests: self.present_digests.clone(),
+                supports_directory_trees: true,
+            }))
+        }
+
+        async fn file_ready(
+            &self,
+            request: tonic::Request<FileReadyRequest>,
+        ) -> Result<tonic::Response<FileResponse>, tonic::Status> {
+            let request = request.into_inner();
+            let response = FileResponse {
+                install_id: request.install_id.clone(),
+                name: request.name.clone(),
+                path: request.path.clone(),
+                error_detail: None,
+            };
+            self.file_ready.lock().unwrap().push(request);
+            Ok(tonic::Response::new(response))
+        }
+
+        async fn shutdown_server(
+            &self,
+            _request: tonic::Request<ShutdownRequest>,
+        ) -> Result<tonic::Response<ShutdownResponse>, tonic::Status> {
+            Ok(tonic::Response::new(ShutdownResponse {}))
+        }
+    }
+
+    async fn spawn_installer(installer: FakeInstaller) -> anyhow::Result<InstallerClient<Channel>> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let port = listener.local_addr()?.port();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(InstallerServer::new(installer))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        Ok(InstallerClient::new(
+            get_channel_tcp(Ipv4Addr::LOCALHOST, port).await?,
+        ))
+    }
+
+    fn artifact_fs() -> anyhow::Result<ArtifactFs> {
+        Ok(ArtifactFs::new(
+            BuckPathResolver::new(CellResolver::testing_with_name_and_path(
+                CellName::testing_new("cell"),
+                CellRootPathBuf::new(ProjectRelativePathBuf::unchecked_new("cell".into())),
+            )),
+            BuckOutPathResolver::new(ProjectRelativePathBuf::unchecked_new("buck-out/v2".into())),
+            ProjectRoot::new(AbsNormPathBuf::try_from(std::env::current_dir()?)?)?,
+        ))
+    }
+
+    fn file_result(name: &str, id: u32, artifact_value: ArtifactValue) -> FileResult {
+        let target =
+            ConfiguredTargetLabel::testing_parse("cell//pkg:app", ConfigurationData::testing_new());
+        FileResult {
+            install_id: "install".to_owned(),
+            name: name.to_owned(),
+            artifact: BuildArtifact::testing_new(
+                target,
+                ForwardRelativePathBuf::unchecked_new(name.to_owned()),
+                DeferredId::testing_new(id),
+            )
+            .into(),
+            artifact_value,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_only_files_the_installer_does_not_have() -> anyhow::Result<()> {
+        let digest_config = DigestConfig::testing_default();
+        let digest = |content: &[u8]| {
+            TrackedFileDigest::from_content(content, digest_config.cas_digest_config())
+        };
+        let file = |content: &[u8]| {
+            ArtifactValue::file(FileMetadata {
+                digest: digest(content),
+                is_executable: false,
+            })
+        };
+
+        let mut dir = ActionDirectoryBuilder::empty();
+        dir.insert(
+            ForwardRelativePath::new("x")?,
+            DirectoryEntry::Leaf(ActionDirectoryMember::File(FileMetadata {
+                digest: digest(b"x"),
+                is_executable: true,
+            })),
+        )?;
+        let dir = ArtifactValue::new(
+            DirectoryEntry::Dir(
+                dir.fingerprint(digest_config.as_directory_serializer())
+                    .shared(&*INTERNER),
+            ),
+            None,
+        );
+
+        let installer = FakeInstaller {
+            present_digests: HashMap::from([
+                (
+                    "present".to_owned(),
+                    digest(b"present").data().raw_digest().to_string(),
+                ),
+                (
+                    "stale".to_owned(),
+                    digest(b"old").data().raw_digest().to_string(),
+                ),
+            ]),
+            ..Default::default()
+        };
+        let client = spawn_installer(installer.clone()).await?;
+        send_install_info(client.clone(), "install", &SmallMap::new(), &artifact_fs()?).await?;
+        let negotiation = negotiate(client.clone(), "install").await?;
+        assert!(negotiation.supports_directory_trees);
+
+        let files = vec![
+            file_result("present", 0, file(b"present")),
+            file_result("stale", 1, file(b"new")),
+            file_result("new", 2, file(b"new")),
+            file_result("dir", 3, dir),
+        ];
+        let progress = InstallProgress {
+            files_total: files.len() as u64,
+            files_seen: AtomicU64::new(0),
+        };
+        let artifact_fs = artifact_fs()?;
+        let materialized = Mutex::new(Vec::new());
+        let (mut events, sink) = create_source_sink_pair();
+        with_dispatcher_async(EventDispatcher::new(TraceId::new(), sink), async {
+            for file in files {
+                let name = file.name.clone();
+                let materialized = &materialized;
+                send_file(
+                    file,
+                    &artifact_fs,
+                    client.clone(),
+                    "installer.log".to_owned(),
+                    Some(&negotiation),
+                    &progress,
+                    async move {
+                        materialized.lock().unwrap().push(name);
+                        anyhow::Ok(())
+                    },
+                )
+                .await?;
+            }
+            anyhow::Ok(())
+        })
+        .await?;
+
+        // Files the installer already has are not even materialized.
+        assert_eq!(vec!["stale", "new", "dir"], *materialized.lock().unwrap());
+
+        let file_ready = installer.file_ready.lock().unwrap();
+        assert_eq!(
+            vec!["stale", "new", "dir"],
+            file_ready
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        let tree = &file_ready[2].tree;
+        assert_eq!(1, tree.len());
+        assert_eq!("x", tree[0].path);
+        assert!(tree[0].is_executable);
+        assert!(file_ready[..2].iter().all(|r| r.tree.is_empty()));
+
+        let mut skipped = Vec::new();
+        while let Some(event) = events.try_receive() {
+            if let Event::Buck(event) = event {
+                if let buck2_data::buck_event::Data::Instant(instant) = event.data() {
+                    if let Some(buck2_data::instant_event::Data::InstallFileSkipped(skip)) =
+                        &instant.data
+                    {
+                        skipped.push((
+                            skip.artifact_name.clone(),
+                            skip.file_index,
+                            skip.files_total,
+                        ));
+                    }
+                }
+            }
+        }
+        assert_eq!(vec![("present".to_owned(), 1, 4)], skipped);
+        Ok(())
+    }
+}
@@ -0,0 +1,390 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_recursion::async_recursion;
+use async_trait::async_trait;
+use buck2_cli_proto::ClientContext;
+use buck2_client_ctx::path_arg::PathArg;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::dice::data::HasIoProvider;
+use buck2_common::dice::file_ops::HasFileOps;
+use buck2_common::io::IoProvider;
+use buck2_core::cells::name::CellName;
+use buck2_core::cells::CellResolver;
+use buck2_interpreter::file_type::StarlarkFileType;
+use buck2_interpreter::path::OwnedStarlarkModulePath;
+use buck2_interpreter::path::OwnedStarlarkPath;
+use buck2_interpreter::path::StarlarkPath;
+use buck2_interpreter_for_build::interpreter::dice_calculation_delegate::HasCalculationDelegate;
+use buck2_interpreter_for_build::interpreter::global_interpreter_state::HasGlobalInterpreterState;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dice::DiceTransaction;
+use dupe::Dupe;
+use starlark::docs::get_registered_starlark_docs;
+use starlark::stdlib::LibraryExtension;
+use starlark::syntax::AstModule;
+use starlark::typing::Interface;
+use starlark::typing::OracleDocs;
+use starlark::typing::OracleNoBuiltins;
+use starlark::typing::OracleStandard;
+use starlark::typing::Ty;
+use starlark::typing::TypingOracle;
+
+use crate::util::globals::CachedGlobals;
+use crate::util::paths::starlark_files;
+use crate::StarlarkCommandCommonOptions;
+use crate::StarlarkOpaqueSubcommand;
+
+#[derive(Debug, clap::Parser, serde::Serialize, serde::Deserialize)]
+#[clap(
+    name = "starlark-typecheck",
+    about = "Run the static Starlark typechecker against buck2's globals."
+)]
+pub struct StarlarkTypecheckCommand {
+    #[clap(flatten)]
+    common_opts: StarlarkCommandCommonOptions,
+
+    #[clap(value_name = "PATH", required = true)]
+    paths: Vec<PathArg>,
+}
+
+type BoxedOracle = Box<dyn TypingOracle + Send + Sync>;
+
+/// Symbols which we know exist (e.g. exported by the prelude) but don't have documentation for.
+struct OracleKnownNames(Arc<HashSet<String>>);
+
+impl TypingOracle for OracleKnownNames {
+    fn builtin(&self, name: &str) -> Option<Result<Ty, ()>> {
+        if self.0.contains(name) {
+            Some(Ok(Ty::Any))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum TypecheckError {
+    #[error(
+        "Load cycle: {}",
+        .0.iter().map(|p| p.borrow().path().to_string()).collect::<Vec<_>>().join(" -> ")
+    )]
+    LoadCycle(Vec<OwnedStarlarkPath>),
+}
+
+/// How the typechecker reads files, resolves loads and finds the oracle for a file.
+#[async_trait]
+trait TypecheckFiles {
+    /// The name to report errors against and the content of the file.
+    async fn read(&self, path: StarlarkPath<'_>) -> anyhow::Result<(String, String)>;
+
+    async fn resolve_load(
+        &self,
+        path: StarlarkPath<'_>,
+        load: &str,
+    ) -> anyhow::Result<OwnedStarlarkModulePath>;
+
+    async fn oracle(&mut self, path: StarlarkPath<'_>) -> anyhow::Result<Arc<Vec<BoxedOracle>>>;
+}
+
+/// Files as seen by the interpreter, caching the oracles.
+struct DiceTypecheckFiles<'a> {
+    dice: &'a DiceTransaction,
+    cell_resolver: &'a CellResolver,
+    io: &'a dyn IoProvider,
+    cached_globals: CachedGlobals<'a>,
+    oracles: HashMap<(CellName, StarlarkFileType), Arc<Vec<BoxedOracle>>>,
+}
+
+impl<'a> DiceTypecheckFiles<'a> {
+    fn new(
+        dice: &'a DiceTransaction,
+        cell_resolver: &'a CellResolver,
+        io: &'a dyn IoProvider,
+    ) -> Self {
+        Self {
+            dice,
+            cell_resolver,
+            io,
+            cached_globals: CachedGlobals::new(dice),
+            oracles: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl TypecheckFiles for DiceTypecheckFiles<'_> {
+    async fn read(&self, path: StarlarkPath<'_>) -> anyhow::Result<(String, String)> {
+        let proj_path = self
+            .cell_resolver
+            .resolve_path(path.path().as_ref().as_ref())?;
+        let path_str = proj_path.to_string();
+        let content = self
+            .io
+            .read_file_if_exists(proj_path)
+            .await?
+            .with_context(|| format!("File not found: `{}`", path_str))?;
+        Ok((path_str, content))
+    }
+
+    async fn resolve_load(
+        &self,
+        path: StarlarkPath<'_>,
+        load: &str,
+    ) -> anyhow::Result<OwnedStarlarkModulePath> {
+        let calc = self
+            .dice
+            .get_interpreter_calculator(path.cell(), path.build_file_cell())
+            .await?;
+        calc.resolve_load(path, load).await
+    }
+
+    /// The oracle knows about the buck2 globals for the file type (with their documented types),
+    /// the types of registered builtin values (e.g. `ctx`, `cmd_args` and providers) and the
+    /// names exported by the prelude and preloaded modules.
+    async fn oracle(&mut self, path: StarlarkPath<'_>) -> anyhow::Result<Arc<Vec<BoxedOracle>>> {
+        let key = (path.cell(), path.file_type());
+        if let Some(oracle) = self.oracles.get(&key) {
+            return Ok(oracle.dupe());
+        }
+
+        let global_state = self.dice.get_global_interpreter_state().await?;
+        let globals = global_state.globals_for_file_type(path.file_type());
+        let mut docs = OracleDocs::new(&get_registered_starlark_docs());
+        docs.add_object(&globals.documentation());
+        let names = self.cached_globals.get_names(&path).await?;
+
+        let oracle: Arc<Vec<BoxedOracle>> = Arc::new(vec![
+            Box::new(docs),
+            Box::new(OracleStandard::new(LibraryExtension::all())),
+            Box::new(OracleKnownNames(names)),
+            Box::new(OracleNoBuiltins),
+        ]);
+        self.oracles.insert(key, oracle.dupe());
+        Ok(oracle)
+    }
+}
+
+/// Typechecks files, caching the interfaces of loaded modules.
+struct Typechecker<F> {
+    files: F,
+    interfaces: HashMap<OwnedStarlarkModulePath, Interface>,
+    /// Files being typechecked, outermost first, to detect load cycles.
+    in_progress: Vec<OwnedStarlarkPath>,
+}
+
+impl<F: TypecheckFiles + Send> Typechecker<F> {
+    fn new(files: F) -> Self {
+        Self {
+            files,
+            interfaces: HashMap::new(),
+            in_progress: Vec::new(),
+        }
+    }
+
+    /// Interface of a loaded module, typechecking it (and its loads) if we haven't yet.
+    /// Type errors in loaded modules are not reported, unless they were also requested.
+    #[async_recursion]
+    async fn interface(&mut self, path: OwnedStarlarkModulePath) -> anyhow::Result<Interface> {
+        if let Some(interface) = self.interfaces.get(&path) {
+            return Ok(interface.dupe());
+        }
+        let (_errors, interface) = self.typecheck(path.borrow().into()).await?;
+        self.interfaces.insert(path, interface.dupe());
+        Ok(interface)
+    }
+
+    async fn typecheck(
+        &mut self,
+        path: StarlarkPath<'_>,
+    ) -> anyhow::Result<(Vec<anyhow::Error>, Interface)> {
+        let owned = OwnedStarlarkPath::new(path);
+        if let Some(i) = self.in_progress.iter().position(|p| *p == owned) {
+            let mut cycle = self.in_progress[i..].to_vec();
+            cycle.push(owned);
+            return Err(TypecheckError::LoadCycle(cycle).into());
+        }
+
+        self.in_progress.push(owned);
+        let res = self.typecheck_uncached(path).await;
+        self.in_progress.pop();
+        res
+    }
+
+    async fn typecheck_uncached(
+        &mut self,
+        path: StarlarkPath<'_>,
+    ) -> anyhow::Result<(Vec<anyhow::Error>, Interface)> {
+        let dialect = path.file_type().dialect(false);
+        let (path_str, content) = self.files.read(path).await?;
+        let ast = match AstModule::parse(&path_str, content, &dialect) {
+            Ok(ast) => ast,
+            Err(e) => return Ok((vec![e], Interface::empty())),
+        };
+
+        let mut loads = HashMap::new();
+        for load in ast.loads() {
+            let module = self.files.resolve_load(path, load.module_id).await?;
+            let interface = self
+                .interface(module)
+                .await
+                .with_context(|| format!("From `load` at {}", load.span))?;
+            loads.insert(load.module_id.to_owned(), interface);
+        }
+
+        let oracle = self.files.oracle(path).await?;
+        let (errors, _typemap, interface, _approximations) = ast.typecheck(&*oracle, &loads);
+        Ok((errors, interface))
+    }
+}
+
+#[async_trait]
+impl StarlarkOpaqueSubcommand for StarlarkTypecheckCommand {
+    async fn server_execute(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        _client_ctx: ClientContext,
+    ) -> anyhow::Result<()> {
+        server_ctx
+            .with_dice_ctx(async move |server_ctx, ctx| {
+                let cell_resolver = ctx.get_cell_resolver().await?;
+                let fs = ctx.file_ops();
+                let io = ctx.global_data().get_io_provider();
+                let mut typechecker =
+                    Typechecker::new(DiceTypecheckFiles::new(&ctx, &cell_resolver, &*io));
+
+                let mut stdout = stdout.as_writer();
+                let mut error_count = 0;
+                let files =
+                    starlark_files(&self.paths, server_ctx, &cell_resolver, &fs, &*io).await?;
+                for file in &files {
+                    let (errors, _) = typechecker.typecheck(file.borrow()).await?;
+                    error_count += errors.len();
+                    for error in errors {
+                        writeln!(stdout, "{:#}", error)?;
+                    }
+                }
+                if error_count > 0 {
+                    Err(anyhow::anyhow!("Found {} type errors", error_count))
+                } else {
+                    writeln!(
+                        server_ctx.stderr()?,
+                        "Found no type errors in {} files",
+                        files.len()
+                    )?;
+                    Ok(())
+                }
+            })
+            .await
+    }
+
+    fn common_opts(&self) -> &StarlarkCommandCommonOptions {
+        &self.common_opts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::bzl::ImportPath;
+
+    use super::*;
+
+    /// Files of the `root` cell, by cell relative path.
+    struct TestFiles(HashMap<&'static str, &'static str>);
+
+    #[async_trait]
+    impl TypecheckFiles for TestFiles {
+        async fn read(&self, path: StarlarkPath<'_>) -> anyhow::Result<(String, String)> {
+            let path = path.path().path().to_string();
+            let content = self
+                .0
+                .get(path.as_str())
+                .with_context(|| format!("File not found: `{}`", path))?;
+            Ok((path, (*content).to_owned()))
+        }
+
+        async fn resolve_load(
+            &self,
+            _path: StarlarkPath<'_>,
+            load: &str,
+        ) -> anyhow::Result<OwnedStarlarkModulePath> {
+            Ok(OwnedStarlarkModulePath::LoadFile(ImportPath::testing_new(
+                &format!("root//{}", load),
+            )))
+        }
+
+        async fn oracle(
+            &mut self,
+            _path: StarlarkPath<'_>,
+        ) -> anyhow::Result<Arc<Vec<BoxedOracle>>> {
+            Ok(Arc::new(vec![
+                Box::new(OracleStandard::new(LibraryExtension::all())),
+                Box::new(OracleNoBuiltins),
+            ]))
+        }
+    }
+
+    fn typecheck(
+        files: &[(&'static str, &'static str)],
+        path: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut typechecker = Typechecker::new(TestFiles(files.iter().copied().collect()));
+        let path = ImportPath::testing_new(path);
+        let (errors, _) =
+            futures::executor::block_on(typechecker.typecheck(StarlarkPath::LoadFile(&path)))?;
+        Ok(errors.iter().map(|e| format!("{:#}", e)).collect())
+    }
+
+    #[test]
+    fn test_type_error_in_loaded_file() -> anyhow::Result<()> {
+        let files = [
+            ("a.bzl", "load(\":b.bzl\", \"foo\")\nres = foo(1)\n"),
+            (
+                "b.bzl",
+                "def foo(x: str.type) -> int.type:\n    return hash(x)\nbad = hash(1)\n",
+            ),
+        ];
+
+        // Errors in `b.bzl` are only reported when it is checked itself, but its interface is
+        // used to check `a.bzl`.
+        assert_eq!(
+            typecheck(&files, "root//:a.bzl")?,
+            vec![r#"Expected type `"string"` but got `"int"`, at a.bzl:2:7-13"#]
+        );
+        assert_eq!(
+            typecheck(&files, "root//:b.bzl")?,
+            vec![r#"Expected type `"string"` but got `"int"`, at b.bzl:3:7-14"#]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_cycle() {
+        let files = [
+            ("a.bzl", "load(\":b.bzl\", \"b\")\na = 1\n"),
+            ("b.bzl", "load(\":a.bzl\", \"a\")\nb = 1\n"),
+        ];
+
+        let err = typecheck(&files, "root//:a.bzl").unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("Load cycle: root//a.bzl -> root//b.bzl -> root//a.bzl"),
+            "{:#}",
+            err
+        );
+    }
+}
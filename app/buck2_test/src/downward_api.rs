@@ -10,7 +10,10 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use buck2_downward_api::Diagnostic;
 use buck2_downward_api::DownwardApi;
+use buck2_downward_api::Progress;
+use buck2_events::dispatch::console_message;
 use tracing::Level;
 
 pub struct BuckTestDownwardApi;
@@ -30,4 +33,15 @@ impl DownwardApi for BuckTestDownwardApi {
     async fn external(&self, _data: HashMap<String, String>) -> anyhow::Result<()> {
         unimplemented!("need buck event stream to implement")
     }
+
+    async fn progress(&self, progress: Progress) -> anyhow::Result<()> {
+        // Test executors aren't attached to an action span, so there is nowhere to display this.
+        tracing::debug!("Test executor progress: {:?}", progress);
+        Ok(())
+    }
+
+    async fn diagnostic(&self, diagnostic: Diagnostic) -> anyhow::Result<()> {
+        console_message(diagnostic.to_string());
+        Ok(())
+    }
 }
@@ -13,12 +13,16 @@ use std::task::Poll;
 use std::time::Duration;
 
 use anyhow::Context as _;
+use buck2_downward_api::Diagnostic;
 use buck2_downward_api::DownwardApi;
+use buck2_downward_api::Progress;
 use buck2_downward_api_proto::downward_api_client;
 use buck2_downward_api_proto::downward_api_server;
 use buck2_downward_api_proto::ConsoleRequest;
+use buck2_downward_api_proto::DiagnosticRequest;
 use buck2_downward_api_proto::ExternalEventRequest;
 use buck2_downward_api_proto::LogRequest;
+use buck2_downward_api_proto::ProgressRequest;
 use buck2_events::dispatch::with_dispatcher_async;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_grpc::make_channel;
@@ -121,6 +125,26 @@ impl DownwardApi for TestOrchestratorClient {
 
         Ok(())
     }
+
+    async fn progress(&self, progress: Progress) -> anyhow::Result<()> {
+        let Progress { step, percent } = progress;
+
+        self.downward_api_client
+            .clone()
+            .progress(ProgressRequest { step, percent })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn diagnostic(&self, diagnostic: Diagnostic) -> anyhow::Result<()> {
+        self.downward_api_client
+            .clone()
+            .diagnostic(DiagnosticRequest::from(diagnostic))
+            .await?;
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -532,6 +556,43 @@ where
         })
         .await
     }
+
+    async fn progress(
+        &self,
+        request: tonic::Request<ProgressRequest>,
+    ) -> Result<tonic::Response<buck2_downward_api_proto::Empty>, tonic::Status> {
+        to_tonic(async move {
+            let ProgressRequest { step, percent } = request.into_inner();
+
+            self.inner
+                .progress(Progress { step, percent })
+                .await
+                .context("Failed to report progress")?;
+
+            Ok(buck2_downward_api_proto::Empty {})
+        })
+        .await
+    }
+
+    async fn diagnostic(
+        &self,
+        request: tonic::Request<DiagnosticRequest>,
+    ) -> Result<tonic::Response<buck2_downward_api_proto::Empty>, tonic::Status> {
+        to_tonic(async move {
+            let diagnostic = request
+                .into_inner()
+                .try_into()
+                .context("Invalid `diagnostic`")?;
+
+            self.inner
+                .diagnostic(diagnostic)
+                .await
+                .context("Failed to report diagnostic")?;
+
+            Ok(buck2_downward_api_proto::Empty {})
+        })
+        .await
+    }
 }
 
 pub fn spawn_orchestrator_server<I, O, D>(
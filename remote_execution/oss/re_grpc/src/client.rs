@@ -12,6 +12,7 @@ use std::env::VarError;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use anyhow::Context;
 use buck2_re_configuration::Buck2OssReConfiguration;
@@ -26,6 +27,9 @@ use futures::Stream;
 use gazebo::prelude::*;
 use once_cell::sync::Lazy;
 use prost::Message;
+use re_grpc_proto::build::bazel::remote::asset::v1::fetch_client::FetchClient;
+use re_grpc_proto::build::bazel::remote::asset::v1::FetchBlobRequest as GFetchBlobRequest;
+use re_grpc_proto::build::bazel::remote::asset::v1::Qualifier;
 use re_grpc_proto::build::bazel::remote::execution::v2::action_cache_client::ActionCacheClient;
 use re_grpc_proto::build::bazel::remote::execution::v2::batch_update_blobs_request::Request;
 use re_grpc_proto::build::bazel::remote::execution::v2::capabilities_client::CapabilitiesClient;
@@ -47,19 +51,25 @@ use re_grpc_proto::build::bazel::remote::execution::v2::FindMissingBlobsResponse
 use re_grpc_proto::build::bazel::remote::execution::v2::GetActionResultRequest;
 use re_grpc_proto::build::bazel::remote::execution::v2::GetCapabilitiesRequest;
 use re_grpc_proto::build::bazel::remote::execution::v2::ResultsCachePolicy;
+use re_grpc_proto::build::bazel::remote::execution::v2::WaitExecutionRequest;
 use re_grpc_proto::google::bytestream::byte_stream_client::ByteStreamClient;
 use re_grpc_proto::google::bytestream::ReadRequest;
 use re_grpc_proto::google::bytestream::ReadResponse;
 use re_grpc_proto::google::bytestream::WriteRequest;
 use re_grpc_proto::google::bytestream::WriteResponse;
 use re_grpc_proto::google::longrunning::operation::Result as OpResult;
+use re_grpc_proto::google::longrunning::Operation;
 use re_grpc_proto::google::rpc::Code;
 use re_grpc_proto::google::rpc::Status;
 use regex::Regex;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
+use tonic::body::BoxBody;
+use tonic::codec::Streaming;
 use tonic::codegen::InterceptedService;
+use tonic::codegen::Service;
+use tonic::codegen::StdError;
 use tonic::metadata;
 use tonic::metadata::MetadataKey;
 use tonic::metadata::MetadataValue;
@@ -70,10 +80,17 @@ use tonic::transport::Channel;
 use tonic::transport::Identity;
 use tonic::transport::Uri;
 
+use crate::credential_helper;
+use crate::credential_helper::CredentialHelper;
 use crate::error::*;
 use crate::metadata::*;
 use crate::request::*;
 use crate::response::*;
+use crate::rpc_policy::backoff;
+use crate::rpc_policy::is_transient;
+use crate::rpc_policy::status_to_error;
+use crate::rpc_policy::Rpc;
+use crate::rpc_policy::RpcPolicy;
 
 // RBE Services (e.g. Buildbarn) may not be robust against having too many files open at
 // once. Limit to an arbitrary reasonable number since this information is not expressed
@@ -264,29 +281,88 @@ impl REClientBuilder {
         )
         .await;
 
+        // The Remote Asset API is optional (most RE backends don't implement it), so we only
+        // connect to it if it was explicitly configured.
+        let fetch = match opts.remote_asset_address.clone() {
+            Some(address) => Some(
+                create_channel(Some(address))
+                    .await
+                    .context("Error creating Remote Asset client")?,
+            ),
+            None => None,
+        };
+
         let interceptor = InjectHeadersInterceptor::new(&opts.http_headers)?;
+        let helper_timeout = Duration::from_millis(
+            opts.credential_helper_timeout_ms
+                .unwrap_or(credential_helper::DEFAULT_TIMEOUT_MS),
+        );
+
+        // Each endpoint can use its own credential helper, the URI passed to the helper is the
+        // address of the endpoint.
+        let with_helper = |helper: &Option<String>, address: &Option<String>| async move {
+            match (helper, address) {
+                (Some(helper), Some(address)) => {
+                    let helper =
+                        substitute_env_vars(helper).context("Invalid credential helper")?;
+                    let address = substitute_env_vars(address).context("Invalid address")?;
+                    let helper = CredentialHelper::new(helper, address, helper_timeout);
+                    // Fail early rather than on the first request if the helper does not work.
+                    helper.refresh().await?;
+                    anyhow::Ok(Some(helper))
+                }
+                _ => anyhow::Ok(None),
+            }
+        };
+
+        let (cas_helper, execution_helper, action_cache_helper, remote_asset_helper) =
+            futures::future::try_join4(
+                with_helper(&opts.cas_credential_helper, &opts.cas_address),
+                with_helper(&opts.engine_credential_helper, &opts.engine_address),
+                with_helper(
+                    &opts.action_cache_credential_helper,
+                    &opts.action_cache_address,
+                ),
+                with_helper(
+                    &opts.remote_asset_credential_helper,
+                    &opts.remote_asset_address,
+                ),
+            )
+            .await
+            .context("Error obtaining credentials")?;
+
+        let service = |channel: Channel, helper: &Option<Arc<CredentialHelper>>| {
+            InterceptedService::new(
+                CredentialChannel {
+                    channel,
+                    credential_helper: helper.dupe(),
+                },
+                interceptor.dupe(),
+            )
+        };
 
         let mut grpc_clients = GRPCClients {
-            cas_client: ContentAddressableStorageClient::with_interceptor(
+            cas_client: ContentAddressableStorageClient::new(service(
                 cas.context("Error creating CAS client")?,
-                interceptor.dupe(),
-            ),
-            execution_client: ExecutionClient::with_interceptor(
+                &cas_helper,
+            )),
+            execution_client: ExecutionClient::new(service(
                 execution.context("Error creating Execution client")?,
-                interceptor.dupe(),
-            ),
-            action_cache_client: ActionCacheClient::with_interceptor(
+                &execution_helper,
+            )),
+            action_cache_client: ActionCacheClient::new(service(
                 action_cache.context("Error creating ActionCache client")?,
-                interceptor.dupe(),
-            ),
-            bytestream_client: ByteStreamClient::with_interceptor(
+                &action_cache_helper,
+            )),
+            bytestream_client: ByteStreamClient::new(service(
                 bytestream.context("Error creating Bytestream client")?,
-                interceptor.dupe(),
-            ),
-            capabilities_client: CapabilitiesClient::with_interceptor(
+                &cas_helper,
+            )),
+            capabilities_client: CapabilitiesClient::new(service(
                 capabilities.context("Error creating Capabilities client")?,
-                interceptor.dupe(),
-            ),
+                &execution_helper,
+            )),
+            fetch_client: fetch.map(|fetch| FetchClient::new(service(fetch, &remote_asset_helper))),
         };
 
         let instance_name = InstanceName(opts.instance_name.clone());
@@ -304,7 +380,12 @@ impl REClientBuilder {
             return Err(anyhow::anyhow!("Server has remote execution disabled."));
         }
 
-        Ok(REClient::new(grpc_clients, capabilities, instance_name))
+        Ok(REClient::new(
+            grpc_clients,
+            capabilities,
+            instance_name,
+            RpcPolicy::new(opts),
+        ))
     }
 
     async fn fetch_rbe_capabilities(
@@ -345,6 +426,9 @@ impl REClientBuilder {
     }
 }
 
+/// The service the gRPC clients use.
+type GrpcService = InterceptedService<CredentialChannel, InjectHeadersInterceptor>;
+
 #[derive(Clone, Dupe)]
 struct InjectHeadersInterceptor {
     headers: Arc<Vec<(MetadataKey<metadata::Ascii>, MetadataValue<metadata::Ascii>)>>,
@@ -390,13 +474,158 @@ impl Interceptor for InjectHeadersInterceptor {
     }
 }
 
+/// Adds the headers obtained from a credential helper to requests. Unlike an interceptor, this
+/// can wait for the helper without blocking the runtime.
+#[derive(Clone)]
+struct CredentialChannel {
+    channel: Channel,
+    credential_helper: Option<Arc<CredentialHelper>>,
+}
+
+impl Service<http::Request<BoxBody>> for CredentialChannel {
+    type Response = http::Response<tonic::transport::Body>;
+    type Error = StdError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.channel.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: http::Request<BoxBody>) -> Self::Future {
+        // The channel `poll_ready` was called on is the one that must handle the request.
+        let clone = self.channel.clone();
+        let mut channel = std::mem::replace(&mut self.channel, clone);
+        let credential_helper = self.credential_helper.dupe();
+        Box::pin(async move {
+            if let Some(credential_helper) = credential_helper {
+                let headers = credential_helper.headers().await.map_err(|e| {
+                    StdError::from(tonic::Status::unauthenticated(format!(
+                        "Error obtaining credentials: {:#}",
+                        e
+                    )))
+                })?;
+                // Headers from the helper replace static headers with the same name.
+                for (k, _) in headers.iter() {
+                    request.headers_mut().remove(k);
+                }
+                for (k, v) in headers.iter() {
+                    request.headers_mut().append(k.clone(), v.clone());
+                }
+            }
+            channel.call(request).await.map_err(StdError::from)
+        })
+    }
+}
+
+/// Operation updates for an `Execute` request. If the stream breaks or stalls before the
+/// operation is done, it is resumed with `WaitExecution`.
+struct ExecuteOperationStream {
+    stream: Streaming<Operation>,
+    client: ExecutionClient<GrpcService>,
+    metadata: RemoteExecutionMetadata,
+    policy: Arc<RpcPolicy>,
+    /// Name of the operation, known once we received the first update.
+    operation: Option<String>,
+    done: bool,
+    resumptions: u32,
+}
+
+impl ExecuteOperationStream {
+    async fn next(&mut self) -> anyhow::Result<Option<Operation>> {
+        loop {
+            let res = match self.policy.execute_stream_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, self.stream.message())
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(tonic::Status::deadline_exceeded(format!(
+                            "No update on the execution stream within {:?}",
+                            timeout
+                        )))
+                    }),
+                None => self.stream.message().await,
+            };
+
+            let error = match res {
+                Ok(Some(op)) => {
+                    if self.operation.is_none() && !op.name.is_empty() {
+                        self.operation = Some(op.name.clone());
+                    }
+                    if op.done {
+                        self.done = true;
+                        self.policy.circuit_breaker.record_success();
+                    }
+                    return Ok(Some(op));
+                }
+                Ok(None) if self.done => return Ok(None),
+                // The server is allowed to close the stream before the operation is done, in
+                // which case we are supposed to wait for it.
+                Ok(None) => None,
+                Err(status) if is_transient(status.code()) => Some(status),
+                Err(status) => return Err(status_to_error(status).into()),
+            };
+
+            self.resume(error).await?;
+        }
+    }
+
+    async fn resume(&mut self, mut error: Option<tonic::Status>) -> anyhow::Result<()> {
+        loop {
+            let operation = match &self.operation {
+                Some(operation) if self.resumptions < self.policy.retries => operation.clone(),
+                _ => {
+                    self.policy
+                        .circuit_breaker
+                        .record_failure(std::time::Instant::now());
+                    let lost = RemoteExecutionError::ExecuteStreamLost {
+                        operation: self.operation.clone().unwrap_or_default(),
+                    };
+                    return Err(match error {
+                        Some(status) => anyhow::Error::from(status_to_error(status)).context(lost),
+                        None => lost.into(),
+                    });
+                }
+            };
+
+            self.resumptions += 1;
+            let delay = backoff(self.resumptions);
+            tracing::debug!(
+                "Execution stream for `{}` was lost, resuming in {:?}: {:?}",
+                operation,
+                delay,
+                error
+            );
+            tokio::time::sleep(delay).await;
+
+            let mut client = self.client.clone();
+            match client
+                .wait_execution(with_internal_metadata(
+                    WaitExecutionRequest { name: operation },
+                    self.metadata.clone(),
+                ))
+                .await
+            {
+                Ok(stream) => {
+                    self.stream = stream.into_inner();
+                    return Ok(());
+                }
+                Err(status) if is_transient(status.code()) => error = Some(status),
+                Err(status) => return Err(status_to_error(status).into()),
+            }
+        }
+    }
+}
+
 pub struct GRPCClients {
-    cas_client:
-        ContentAddressableStorageClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
-    execution_client: ExecutionClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
-    action_cache_client: ActionCacheClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
-    bytestream_client: ByteStreamClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
-    capabilities_client: CapabilitiesClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
+    cas_client: ContentAddressableStorageClient<GrpcService>,
+    execution_client: ExecutionClient<GrpcService>,
+    action_cache_client: ActionCacheClient<GrpcService>,
+    bytestream_client: ByteStreamClient<GrpcService>,
+    capabilities_client: CapabilitiesClient<GrpcService>,
+    /// Only present if a Remote Asset API endpoint was configured.
+    fetch_client: Option<FetchClient<GrpcService>>,
 }
 
 #[derive(Default)]
@@ -410,6 +639,7 @@ pub struct REClient {
     grpc_clients: GRPCClients,
     capabilities: RECapabilities,
     instance_name: InstanceName,
+    policy: Arc<RpcPolicy>,
     state: Mutex<REState>,
 }
 
@@ -473,35 +703,47 @@ impl REClient {
         grpc_clients: GRPCClients,
         capabilities: RECapabilities,
         instance_name: InstanceName,
+        policy: RpcPolicy,
     ) -> Self {
         REClient {
             grpc_clients,
             capabilities,
             instance_name,
+            policy: Arc::new(policy),
             state: Mutex::new(REState::default()),
         }
     }
 
+    /// Whether RE failed too many times in a row recently, in which case requests fail
+    /// immediately and callers should avoid RE if they can.
+    pub fn is_circuit_open(&self) -> bool {
+        self.policy
+            .circuit_breaker
+            .is_open(std::time::Instant::now())
+    }
+
     pub async fn get_action_result(
         &self,
         metadata: RemoteExecutionMetadata,
         request: ActionResultRequest,
     ) -> anyhow::Result<ActionResultResponse> {
-        let mut client = self.grpc_clients.action_cache_client.clone();
+        let request = GetActionResultRequest {
+            instance_name: self.instance_name.as_str().to_owned(),
+            action_digest: Some(tdigest_to(request.digest)),
+            ..Default::default()
+        };
 
-        let res = client
-            .get_action_result(with_internal_metadata(
-                GetActionResultRequest {
-                    instance_name: self.instance_name.as_str().to_owned(),
-                    action_digest: Some(tdigest_to(request.digest)),
-                    ..Default::default()
-                },
-                metadata,
-            ))
+        let res = self
+            .policy
+            .call(Rpc::GetActionResult, || {
+                let mut client = self.grpc_clients.action_cache_client.clone();
+                let request = with_internal_metadata(request.clone(), metadata.clone());
+                async move { client.get_action_result(request).await }
+            })
             .await?;
 
         Ok(ActionResultResponse {
-            action_result: convert_action_result(res.into_inner())?,
+            action_result: convert_action_result(res)?,
             ttl: 0,
         })
     }
@@ -522,8 +764,6 @@ impl REClient {
         // TODO(aloiscochard): Map those properly in the request
         // use crate::proto::build::bazel::remote::execution::v2::ExecutionPolicy;
 
-        let mut client = self.grpc_clients.execution_client.clone();
-
         let action_digest = tdigest_to(execute_request.action_digest.clone());
 
         let request = GExecuteRequest {
@@ -534,13 +774,27 @@ impl REClient {
             action_digest: Some(action_digest.clone()),
         };
 
-        let stream = client
-            .execute(with_internal_metadata(request, metadata))
-            .await?
-            .into_inner();
+        let stream = self
+            .policy
+            .call(Rpc::Execute, || {
+                let mut client = self.grpc_clients.execution_client.clone();
+                let request = with_internal_metadata(request.clone(), metadata.clone());
+                async move { client.execute(request).await }
+            })
+            .await?;
+
+        let stream = ExecuteOperationStream {
+            stream,
+            client: self.grpc_clients.execution_client.clone(),
+            metadata,
+            policy: self.policy.dupe(),
+            operation: None,
+            done: false,
+            resumptions: 0,
+        };
 
         let stream = futures::stream::try_unfold(stream, move |mut stream| async {
-            let msg = match stream.try_next().await.context("RE channel error")? {
+            let msg = match stream.next().await.context("RE channel error")? {
                 Some(msg) => msg,
                 None => return Ok(None),
             };
@@ -640,13 +894,20 @@ impl REClient {
             &self.instance_name,
             request,
             self.capabilities.max_msg_size,
-            |re_request| async {
+            |re_request| {
                 let metadata = metadata.clone();
-                let mut cas_client = self.grpc_clients.cas_client.clone();
-                let resp = cas_client
-                    .batch_update_blobs(with_internal_metadata(re_request, metadata))
-                    .await?;
-                Ok(resp.into_inner())
+                async move {
+                    // Not retried (see `Rpc::is_idempotent`), but each attempt sends its own copy
+                    // of the request, so this doesn't rely on that.
+                    self.policy
+                        .call(Rpc::BatchUpdateBlobs, || {
+                            let mut cas_client = self.grpc_clients.cas_client.clone();
+                            let request =
+                                with_internal_metadata(re_request.clone(), metadata.clone());
+                            async move { cas_client.batch_update_blobs(request).await }
+                        })
+                        .await
+                }
             },
             |segments| async {
                 let metadata = metadata.clone();
@@ -680,13 +941,18 @@ impl REClient {
             &self.instance_name,
             request,
             self.capabilities.max_msg_size,
-            |re_request| async {
+            |re_request| {
                 let metadata = metadata.clone();
-                let mut client = self.grpc_clients.cas_client.clone();
-                Ok(client
-                    .batch_read_blobs(with_internal_metadata(re_request, metadata))
-                    .await?
-                    .into_inner())
+                async move {
+                    self.policy
+                        .call(Rpc::BatchReadBlobs, || {
+                            let mut client = self.grpc_clients.cas_client.clone();
+                            let request =
+                                with_internal_metadata(re_request.clone(), metadata.clone());
+                            async move { client.batch_read_blobs(request).await }
+                        })
+                        .await
+                }
             },
             |read_request| {
                 let metadata = metadata.clone();
@@ -703,12 +969,68 @@ impl REClient {
         .await
     }
 
+    /// Whether this client can resolve blobs via the Remote Asset API (i.e. whether `fetch_blob`
+    /// can be used).
+    pub fn supports_fetch_blob(&self) -> bool {
+        self.grpc_clients.fetch_client.is_some()
+    }
+
+    /// Ask the Remote Asset API to fetch a blob from one of the given URIs into the CAS. This
+    /// does not download anything locally: the returned digest can be used to download the blob
+    /// from the CAS later (or to use it as an input to remote actions).
+    pub async fn fetch_blob(
+        &self,
+        metadata: RemoteExecutionMetadata,
+        request: FetchBlobRequest,
+    ) -> anyhow::Result<FetchBlobResponse> {
+        let mut client = self
+            .grpc_clients
+            .fetch_client
+            .clone()
+            .context("Remote Asset API is not configured (set `remote_asset_address`)")?;
+
+        let timeout = request
+            .timeout
+            .map(|timeout| {
+                prost_types::Duration::try_from(timeout)
+                    .map_err(|e| anyhow::anyhow!("Invalid timeout `{:?}`: {:?}", timeout, e))
+            })
+            .transpose()?;
+
+        let res = client
+            .fetch_blob(with_internal_metadata(
+                GFetchBlobRequest {
+                    instance_name: self.instance_name.as_str().to_owned(),
+                    timeout,
+                    oldest_content_accepted: None,
+                    uris: request.uris,
+                    qualifiers: request.qualifiers.into_map(|q| Qualifier {
+                        name: q.name,
+                        value: q.value,
+                    }),
+                },
+                metadata,
+            ))
+            .await?
+            .into_inner();
+
+        check_status(res.status.unwrap_or_default())?;
+
+        let digest = res
+            .blob_digest
+            .context("Missing `blob_digest` in successful FetchBlob response")?;
+
+        Ok(FetchBlobResponse {
+            uri: res.uri,
+            digest: tdigest_from(digest),
+        })
+    }
+
     pub async fn get_digests_ttl(
         &self,
         metadata: RemoteExecutionMetadata,
         request: GetDigestsTtlRequest,
     ) -> anyhow::Result<GetDigestsTtlResponse> {
-        let mut cas_client = self.grpc_clients.cas_client.clone();
         let mut remote_ttl: HashMap<TDigest, DigestWithTtl> = HashMap::new();
 
         for digest_chunk in request.digests.chunks(100) {
@@ -725,17 +1047,19 @@ impl REClient {
                     },
                 );
             }
-            let missing_blobs = cas_client
-                .find_missing_blobs(with_internal_metadata(
-                    FindMissingBlobsRequest {
-                        instance_name: self.instance_name.as_str().to_owned(),
-                        blob_digests: digest_chunk.map(|b| tdigest_to(b.clone())),
-                    },
-                    metadata.clone(),
-                ))
+            let request = FindMissingBlobsRequest {
+                instance_name: self.instance_name.as_str().to_owned(),
+                blob_digests: digest_chunk.map(|b| tdigest_to(b.clone())),
+            };
+            let resp: FindMissingBlobsResponse = self
+                .policy
+                .call(Rpc::FindMissingBlobs, || {
+                    let mut cas_client = self.grpc_clients.cas_client.clone();
+                    let request = with_internal_metadata(request.clone(), metadata.clone());
+                    async move { cas_client.find_missing_blobs(request).await }
+                })
                 .await
                 .context("Failed to request what blobs are not present on remote")?;
-            let resp: FindMissingBlobsResponse = missing_blobs.into_inner();
             for digest in &resp.missing_blob_digests.map(|d| tdigest_from(d.clone())) {
                 // If it's present in the MissingBlobsResponse, it's expired on the remote and
                 // needs to be refetched.
@@ -0,0 +1,327 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Credential helpers, following the protocol used by Bazel.
+//!
+//! The helper is invoked as `<helper> get`, receives `{"uri": "..."}` on stdin and prints
+//! `{"headers": {"Name": ["value", ...]}, "expires": "<RFC 3339>"}` on stdout. Both fields of
+//! the response are optional.
+
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration as StdDuration;
+
+use anyhow::Context;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use http::HeaderName;
+use http::HeaderValue;
+use tokio::io::AsyncWriteExt;
+
+/// Headers are refreshed in the background once they are this close to expiring.
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+/// How long to cache the headers for if the helper does not say when they expire.
+const DEFAULT_CACHE_DURATION_SECS: i64 = 30 * 60;
+
+/// How long the helper can run for if no timeout is configured.
+pub(crate) const DEFAULT_TIMEOUT_MS: u64 = 60 * 1000;
+
+pub(crate) type Headers = Arc<Vec<(HeaderName, HeaderValue)>>;
+
+#[derive(Debug, thiserror::Error)]
+enum CredentialHelperError {
+    #[error("Credential helper `{0}` exited with {1}: {2}")]
+    Failed(String, std::process::ExitStatus, String),
+    #[error("Credential helper `{0}` returned invalid response")]
+    InvalidResponse(String),
+    #[error("Credential helper `{0}` did not exit within {1:?}, killed it")]
+    Timeout(String, StdDuration),
+}
+
+#[derive(serde::Serialize)]
+struct CredentialHelperRequest<'a> {
+    uri: &'a str,
+}
+
+#[derive(serde::Deserialize)]
+struct CredentialHelperResponse {
+    #[serde(default)]
+    headers: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    expires: Option<String>,
+}
+
+#[derive(Clone)]
+struct Credentials {
+    headers: Headers,
+    expires: DateTime<Utc>,
+}
+
+impl Credentials {
+    fn parse(response: &[u8], now: DateTime<Utc>) -> anyhow::Result<Self> {
+        let response: CredentialHelperResponse = serde_json::from_slice(response)?;
+
+        let mut headers = Vec::new();
+        for (key, values) in response.headers {
+            let key = HeaderName::from_bytes(key.as_bytes())
+                .with_context(|| format!("Invalid header name: `{}`", key))?;
+            for value in values {
+                let value = HeaderValue::from_str(&value)
+                    .with_context(|| format!("Invalid value for header `{}`", key))?;
+                headers.push((key.clone(), value));
+            }
+        }
+
+        let expires = match response.expires {
+            Some(expires) => DateTime::parse_from_rfc3339(&expires)
+                .with_context(|| format!("Invalid expiry time: `{}`", expires))?
+                .with_timezone(&Utc),
+            None => now + Duration::seconds(DEFAULT_CACHE_DURATION_SECS),
+        };
+
+        Ok(Self {
+            headers: Arc::new(headers),
+            expires,
+        })
+    }
+
+    fn needs_refresh(&self, now: DateTime<Utc>) -> bool {
+        self.expires - Duration::seconds(REFRESH_MARGIN_SECS) <= now
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires <= now
+    }
+}
+
+/// Runs a credential helper for a single endpoint and caches its output until it expires.
+pub(crate) struct CredentialHelper {
+    program: String,
+    uri: String,
+    timeout: StdDuration,
+    credentials: Mutex<Option<Credentials>>,
+    /// Held while the helper runs, so that it only runs once at a time.
+    refresh_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl CredentialHelper {
+    pub(crate) fn new(program: String, uri: String, timeout: StdDuration) -> Arc<Self> {
+        Arc::new(Self {
+            program,
+            uri,
+            timeout,
+            credentials: Mutex::new(None),
+            refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+        })
+    }
+
+    /// Run the helper. It is killed if it does not exit within the timeout.
+    async fn fetch(&self) -> anyhow::Result<Credentials> {
+        let request = serde_json::to_vec(&CredentialHelperRequest { uri: &self.uri })?;
+
+        let mut child = tokio::process::Command::new(&self.program)
+            .arg("get")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Error spawning credential helper `{}`", self.program))?;
+        let mut stdin = child.stdin.take().context("stdin is piped")?;
+
+        let output = tokio::time::timeout(self.timeout, async move {
+            stdin.write_all(&request).await.with_context(|| {
+                format!("Error writing to credential helper `{}`", self.program)
+            })?;
+            drop(stdin);
+            child
+                .wait_with_output()
+                .await
+                .with_context(|| format!("Error waiting for credential helper `{}`", self.program))
+        })
+        .await
+        .map_err(|_| CredentialHelperError::Timeout(self.program.clone(), self.timeout))??;
+
+        if !output.status.success() {
+            return Err(CredentialHelperError::Failed(
+                self.program.clone(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            )
+            .into());
+        }
+
+        Credentials::parse(&output.stdout, Utc::now())
+            .context(CredentialHelperError::InvalidResponse(self.program.clone()))
+    }
+
+    /// Obtain fresh headers from the helper, replacing the cached ones.
+    pub(crate) async fn refresh(&self) -> anyhow::Result<()> {
+        let _guard = self.refresh_lock.lock().await;
+        self.refresh_locked().await
+    }
+
+    async fn refresh_locked(&self) -> anyhow::Result<()> {
+        let credentials = self.fetch().await?;
+        *self.credentials.lock().unwrap() = Some(credentials);
+        Ok(())
+    }
+
+    /// Cached headers, if they have not expired.
+    fn cached_headers(self: &Arc<Self>) -> Option<Headers> {
+        let now = Utc::now();
+        let credentials = self.credentials.lock().unwrap();
+        let c = credentials.as_ref()?;
+        if c.is_expired(now) {
+            return None;
+        }
+        if c.needs_refresh(now) {
+            self.refresh_in_background();
+        }
+        Some(c.headers.clone())
+    }
+
+    /// Headers to attach to a request.
+    ///
+    /// Headers close to expiry are returned as is while a refresh runs in the background. If
+    /// there are no valid headers, this waits for the helper, which is only run once for all
+    /// the concurrent requests.
+    pub(crate) async fn headers(self: &Arc<Self>) -> anyhow::Result<Headers> {
+        if let Some(headers) = self.cached_headers() {
+            return Ok(headers);
+        }
+
+        let _guard = self.refresh_lock.lock().await;
+        // Another request could have refreshed the headers while we were waiting.
+        if let Some(headers) = self.cached_headers() {
+            return Ok(headers);
+        }
+        self.refresh_locked().await?;
+        self.cached_headers()
+            .context("Credential helper returned expired headers")
+    }
+
+    fn refresh_in_background(self: &Arc<Self>) {
+        if tokio::runtime::Handle::try_current().is_err() {
+            return;
+        }
+        // If the lock is taken, a refresh is already in progress.
+        let guard = match self.refresh_lock.clone().try_lock_owned() {
+            Ok(guard) => guard,
+            Err(_) => return,
+        };
+        let this = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = this.refresh_locked().await {
+                // The current headers are still valid, we will retry on the next request.
+                tracing::warn!("Error refreshing credentials: {:#}", e);
+            }
+            drop(guard);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() -> anyhow::Result<()> {
+        let now = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")?.with_timezone(&Utc);
+
+        let creds = Credentials::parse(
+            br#"{"headers": {"authorization": ["Bearer a"], "x-extra": ["b", "c"]}, "expires": "2023-01-01T00:30:00Z"}"#,
+            now,
+        )?;
+        assert_eq!(
+            vec![
+                ("authorization", "Bearer a"),
+                ("x-extra", "b"),
+                ("x-extra", "c"),
+            ],
+            creds
+                .headers
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.to_str().unwrap()))
+                .collect::<Vec<_>>()
+        );
+        assert!(!creds.needs_refresh(now));
+        assert!(creds.needs_refresh(now + Duration::minutes(29)));
+        assert!(!creds.is_expired(now + Duration::minutes(29)));
+        assert!(creds.is_expired(now + Duration::minutes(30)));
+
+        let creds = Credentials::parse(br#"{}"#, now)?;
+        assert!(creds.headers.is_empty());
+        assert_eq!(now + Duration::minutes(30), creds.expires);
+
+        assert!(Credentials::parse(br#"{"headers": {"bad key": ["a"]}}"#, now).is_err());
+        assert!(Credentials::parse(br#"{"expires": "tomorrow"}"#, now).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn write_helper(dir: &std::path::Path, script: &str) -> anyhow::Result<String> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("helper");
+        std::fs::write(&path, format!("#!/bin/sh\n{}", script))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(path.to_str().context("temp path is utf-8")?.to_owned())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_helper_runs_once_for_concurrent_requests() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let runs = dir.path().join("runs");
+        let helper = write_helper(
+            dir.path(),
+            &format!(
+                "echo run >> {}\nsleep 0.1\necho '{{\"headers\": {{\"authorization\": [\"Bearer a\"]}}}}'",
+                runs.display()
+            ),
+        )?;
+        let helper = CredentialHelper::new(
+            helper,
+            "grpc://localhost".to_owned(),
+            StdDuration::from_millis(DEFAULT_TIMEOUT_MS),
+        );
+
+        let headers = futures::future::try_join_all((0..8).map(|_| helper.headers())).await?;
+        for headers in headers {
+            assert_eq!("authorization", headers[0].0.as_str());
+        }
+        assert_eq!("run\n", std::fs::read_to_string(&runs)?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_helper_timeout() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let helper = write_helper(dir.path(), "sleep 60")?;
+        let helper = CredentialHelper::new(
+            helper,
+            "grpc://localhost".to_owned(),
+            StdDuration::from_millis(100),
+        );
+
+        let err = helper.headers().await.unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("did not exit within"),
+            "{:#}",
+            err
+        );
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@
 
 use std::fmt;
 use std::fmt::Display;
+use std::time::Duration;
 
 use dupe::Dupe;
 use thiserror::Error;
@@ -20,6 +21,25 @@ pub struct REClientError {
     pub code: TCode,
 }
 
+/// Failures of RE itself (as opposed to failures of the actions it runs).
+#[derive(Error, Debug, Clone)]
+pub enum RemoteExecutionError {
+    #[error("`{rpc}` did not complete within {timeout:?}")]
+    DeadlineExceeded {
+        rpc: &'static str,
+        timeout: Duration,
+    },
+    #[error("`{rpc}` failed after {attempts} attempt(s)")]
+    RetriesExhausted { rpc: &'static str, attempts: u32 },
+    #[error("Lost the `Execute` stream for operation `{operation}` and could not resume it")]
+    ExecuteStreamLost { operation: String },
+    #[error(
+        "Remote execution is unavailable after {failures} consecutive failures, \
+        will try again in {retry_in:?}"
+    )]
+    CircuitOpen { failures: u32, retry_in: Duration },
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct REError {
     pub code: TCode,
@@ -42,6 +62,7 @@ pub struct TCode(pub i32);
 impl TCode {
     pub const OK: Self = TCode(0i32);
     pub const INVALID_ARGUMENT: Self = TCode(3i32);
+    pub const DEADLINE_EXCEEDED: Self = TCode(4i32);
     pub const NOT_FOUND: Self = TCode(5i32);
 }
 
@@ -51,6 +72,10 @@ impl Display for TCode {
             write!(f, "OK")
         } else if self == &TCode::INVALID_ARGUMENT {
             write!(f, "INVALID_ARGUMENT")
+        } else if self == &TCode::DEADLINE_EXCEEDED {
+            write!(f, "DEADLINE_EXCEEDED")
+        } else if self == &TCode::NOT_FOUND {
+            write!(f, "NOT_FOUND")
         } else {
             write!(f, "UNKNOWN")
         }
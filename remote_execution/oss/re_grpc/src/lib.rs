@@ -13,12 +13,14 @@
 #![cfg_attr(feature = "gazebo_lint", plugin(gazebo_lint))]
 
 mod client;
+mod credential_helper;
 mod digest;
 mod error;
 mod grpc;
 mod metadata;
 mod request;
 mod response;
+mod rpc_policy;
 pub use client::*;
 pub use digest::*;
 pub use error::*;
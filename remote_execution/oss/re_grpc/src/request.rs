@@ -100,3 +100,18 @@ pub struct WriteActionResultRequest {
     pub action_result: TActionResult2,
     pub _dot_dot: (),
 }
+
+#[derive(Clone, Default)]
+pub struct TQualifier {
+    pub name: String,
+    pub value: String,
+    pub _dot_dot: (),
+}
+
+#[derive(Clone, Default)]
+pub struct FetchBlobRequest {
+    pub uris: Vec<String>,
+    pub qualifiers: Vec<TQualifier>,
+    pub timeout: Option<std::time::Duration>,
+    pub _dot_dot: (),
+}
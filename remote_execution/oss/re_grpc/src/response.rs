@@ -154,6 +154,13 @@ pub struct GetDigestsTtlResponse {
     pub digests_with_ttl: Vec<DigestWithTtl>,
 }
 
+#[derive(Clone, Default)]
+pub struct FetchBlobResponse {
+    /// The URI from the request that the blob was fetched from.
+    pub uri: String,
+    pub digest: TDigest,
+}
+
 #[derive(Clone, Default)]
 pub struct ExecuteResponse {
     pub action_result: TActionResult2,
@@ -0,0 +1,435 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Deadlines, retries and circuit breaking for RPCs to RE.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use buck2_re_configuration::Buck2OssReConfiguration;
+use dupe::Dupe;
+use futures::future::Future;
+use tonic::Code;
+
+use crate::error::REClientError;
+use crate::error::RemoteExecutionError;
+use crate::error::TCode;
+
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_CIRCUIT_BREAKER_THRESHOLD: u32 = 5;
+const DEFAULT_CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Dupe)]
+pub(crate) enum Rpc {
+    GetActionResult,
+    FindMissingBlobs,
+    BatchReadBlobs,
+    BatchUpdateBlobs,
+    Execute,
+    WaitExecution,
+}
+
+impl Rpc {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Rpc::GetActionResult => "GetActionResult",
+            Rpc::FindMissingBlobs => "FindMissingBlobs",
+            Rpc::BatchReadBlobs => "BatchReadBlobs",
+            Rpc::BatchUpdateBlobs => "BatchUpdateBlobs",
+            Rpc::Execute => "Execute",
+            Rpc::WaitExecution => "WaitExecution",
+        }
+    }
+
+    /// Whether the RPC can be retried without side effects.
+    fn is_idempotent(self) -> bool {
+        match self {
+            Rpc::GetActionResult | Rpc::FindMissingBlobs | Rpc::BatchReadBlobs => true,
+            Rpc::BatchUpdateBlobs | Rpc::Execute | Rpc::WaitExecution => false,
+        }
+    }
+
+    /// Whether failures of the RPC count towards opening the circuit breaker, and whether it is
+    /// rejected when the circuit is open. CAS RPCs are not: the materializer needs them to
+    /// download outputs RE already produced, and has no other way to get them.
+    fn uses_circuit_breaker(self) -> bool {
+        match self {
+            Rpc::GetActionResult | Rpc::Execute | Rpc::WaitExecution => true,
+            Rpc::FindMissingBlobs | Rpc::BatchReadBlobs | Rpc::BatchUpdateBlobs => false,
+        }
+    }
+}
+
+/// Whether the status indicates a problem with RE (which might go away if we try again), rather
+/// than a problem with the request. `Internal` and `Unknown` are not included: they might be
+/// caused by the request, so retrying it blindly is not safe.
+pub(crate) fn is_transient(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted | Code::Aborted
+    )
+}
+
+pub(crate) fn status_to_error(status: tonic::Status) -> REClientError {
+    REClientError {
+        code: TCode(status.code() as i32),
+        message: status.message().to_owned(),
+    }
+}
+
+pub(crate) fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+/// Stops sending execution and action cache requests to RE once they failed too many times in a
+/// row, until the cooldown expires. After that, requests are let through again: a success closes
+/// the circuit, a failure opens it again.
+pub(crate) struct CircuitBreaker {
+    /// Zero means the circuit never opens.
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<CircuitBreakerState>,
+}
+
+#[derive(Default)]
+struct CircuitBreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::new(CircuitBreakerState::default()),
+        }
+    }
+
+    pub(crate) fn check(&self, now: Instant) -> Result<(), RemoteExecutionError> {
+        let state = self.state.lock().unwrap();
+        match state.opened_at {
+            Some(opened_at) if now < opened_at + self.cooldown => {
+                Err(RemoteExecutionError::CircuitOpen {
+                    failures: state.consecutive_failures,
+                    retry_in: opened_at + self.cooldown - now,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn is_open(&self, now: Instant) -> bool {
+        self.check(now).is_err()
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.opened_at = None;
+    }
+
+    pub(crate) fn record_failure(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if self.threshold != 0 && state.consecutive_failures >= self.threshold {
+            if state.opened_at.is_none() {
+                tracing::warn!(
+                    "Remote execution failed {} times in a row, not using it for {:?}",
+                    state.consecutive_failures,
+                    self.cooldown
+                );
+            }
+            state.opened_at = Some(now);
+        }
+    }
+}
+
+/// Outcome of a single attempt of an RPC.
+enum AttemptError {
+    Status(tonic::Status),
+    Timeout(Duration),
+}
+
+pub(crate) struct RpcPolicy {
+    cas_timeout: Option<Duration>,
+    action_cache_timeout: Option<Duration>,
+    pub(crate) execute_stream_timeout: Option<Duration>,
+    pub(crate) retries: u32,
+    pub(crate) circuit_breaker: CircuitBreaker,
+}
+
+impl RpcPolicy {
+    pub(crate) fn new(opts: &Buck2OssReConfiguration) -> Self {
+        Self {
+            cas_timeout: opts.cas_rpc_timeout_ms.map(Duration::from_millis),
+            action_cache_timeout: opts.action_cache_rpc_timeout_ms.map(Duration::from_millis),
+            execute_stream_timeout: opts.execute_stream_timeout_ms.map(Duration::from_millis),
+            retries: opts.rpc_retries.unwrap_or(DEFAULT_RETRIES),
+            circuit_breaker: CircuitBreaker::new(
+                opts.circuit_breaker_threshold
+                    .unwrap_or(DEFAULT_CIRCUIT_BREAKER_THRESHOLD),
+                opts.circuit_breaker_cooldown_ms
+                    .map_or(DEFAULT_CIRCUIT_BREAKER_COOLDOWN, Duration::from_millis),
+            ),
+        }
+    }
+
+    fn timeout(&self, rpc: Rpc) -> Option<Duration> {
+        match rpc {
+            Rpc::GetActionResult => self.action_cache_timeout,
+            Rpc::FindMissingBlobs | Rpc::BatchReadBlobs | Rpc::BatchUpdateBlobs => self.cas_timeout,
+            // Those return streams, see `execute_stream_timeout`.
+            Rpc::Execute | Rpc::WaitExecution => None,
+        }
+    }
+
+    /// Run an RPC, applying its deadline and retrying it if it is idempotent.
+    ///
+    /// Errors returned by RE are reported as `REClientError`, failures of RE itself are also
+    /// reported as `RemoteExecutionError` (in the error context).
+    pub(crate) async fn call<T, F, Fut>(&self, rpc: Rpc, mut f: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+    {
+        let circuit_breaker = if rpc.uses_circuit_breaker() {
+            self.circuit_breaker.check(Instant::now())?;
+            Some(&self.circuit_breaker)
+        } else {
+            None
+        };
+
+        let attempts = if rpc.is_idempotent() {
+            self.retries + 1
+        } else {
+            1
+        };
+        let timeout = self.timeout(rpc);
+
+        let mut attempt = 0;
+        let error = loop {
+            attempt += 1;
+
+            let res = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, f()).await {
+                    Ok(res) => res.map_err(AttemptError::Status),
+                    Err(_) => Err(AttemptError::Timeout(timeout)),
+                },
+                None => f().await.map_err(AttemptError::Status),
+            };
+
+            let error = match res {
+                Ok(res) => {
+                    if let Some(circuit_breaker) = circuit_breaker {
+                        circuit_breaker.record_success();
+                    }
+                    return Ok(res.into_inner());
+                }
+                Err(AttemptError::Status(status)) if !is_transient(status.code()) => {
+                    // RE is up, it just didn't like the request.
+                    if let Some(circuit_breaker) = circuit_breaker {
+                        circuit_breaker.record_success();
+                    }
+                    return Err(status_to_error(status).into());
+                }
+                Err(AttemptError::Status(status)) => anyhow::Error::from(status_to_error(status)),
+                Err(AttemptError::Timeout(timeout)) => {
+                    anyhow::Error::from(RemoteExecutionError::DeadlineExceeded {
+                        rpc: rpc.name(),
+                        timeout,
+                    })
+                }
+            };
+
+            if attempt >= attempts {
+                break error;
+            }
+
+            let delay = backoff(attempt);
+            tracing::debug!(
+                "`{}` failed, retrying in {:?}: {:#}",
+                rpc.name(),
+                delay,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        };
+
+        if let Some(circuit_breaker) = circuit_breaker {
+            circuit_breaker.record_failure(Instant::now());
+        }
+        Err(error.context(RemoteExecutionError::RetriesExhausted {
+            rpc: rpc.name(),
+            attempts,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    fn policy(retries: u32, threshold: u32) -> RpcPolicy {
+        RpcPolicy::new(&Buck2OssReConfiguration {
+            rpc_retries: Some(retries),
+            circuit_breaker_threshold: Some(threshold),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(Duration::from_millis(100), backoff(1));
+        assert_eq!(Duration::from_millis(200), backoff(2));
+        assert_eq!(Duration::from_millis(400), backoff(3));
+        assert_eq!(MAX_BACKOFF, backoff(100));
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(10));
+        let now = Instant::now();
+
+        breaker.record_failure(now);
+        assert!(!breaker.is_open(now));
+        breaker.record_failure(now);
+        assert!(breaker.is_open(now));
+        assert!(breaker.is_open(now + Duration::from_secs(9)));
+
+        // Half open: requests go through, another failure opens the circuit again.
+        let later = now + Duration::from_secs(10);
+        assert!(!breaker.is_open(later));
+        breaker.record_failure(later);
+        assert!(breaker.is_open(later));
+
+        breaker.record_success();
+        assert!(!breaker.is_open(later));
+
+        let disabled = CircuitBreaker::new(0, Duration::from_secs(10));
+        for _ in 0..10 {
+            disabled.record_failure(now);
+        }
+        assert!(!disabled.is_open(now));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_idempotent() -> anyhow::Result<()> {
+        let policy = policy(3, 0);
+        let calls = AtomicU32::new(0);
+
+        let res = policy
+            .call(Rpc::FindMissingBlobs, || {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if call < 2 {
+                        Err(tonic::Status::unavailable("flaky"))
+                    } else {
+                        Ok(tonic::Response::new(call))
+                    }
+                }
+            })
+            .await?;
+        assert_eq!(2, res);
+
+        calls.store(0, Ordering::SeqCst);
+        let err = policy
+            .call(Rpc::Execute, || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err::<tonic::Response<()>, _>(tonic::Status::unavailable("down")) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert!(matches!(
+            err.downcast_ref::<RemoteExecutionError>(),
+            Some(RemoteExecutionError::RetriesExhausted { attempts: 1, .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_not_found_is_not_retried() {
+        let policy = policy(3, 1);
+        let calls = AtomicU32::new(0);
+
+        let err = policy
+            .call(Rpc::GetActionResult, || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err::<tonic::Response<()>, _>(tonic::Status::not_found("miss")) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert_eq!(
+            Some(TCode::NOT_FOUND),
+            err.downcast_ref::<REClientError>().map(|e| e.code.dupe())
+        );
+        assert!(!policy.circuit_breaker.is_open(Instant::now()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_opens_after_failures() {
+        let policy = policy(0, 2);
+        let fail = || async { Err::<tonic::Response<()>, _>(tonic::Status::unavailable("down")) };
+        let succeed = || async { Ok(tonic::Response::new(())) };
+
+        assert!(policy.call(Rpc::GetActionResult, fail).await.is_err());
+        assert!(policy.call(Rpc::Execute, fail).await.is_err());
+        let err = policy
+            .call(Rpc::GetActionResult, succeed)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RemoteExecutionError>(),
+            Some(RemoteExecutionError::CircuitOpen { failures: 2, .. })
+        ));
+
+        // Outputs can still be downloaded from the CAS.
+        assert!(policy.call(Rpc::BatchReadBlobs, succeed).await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cas_failures_do_not_open_circuit() {
+        let policy = policy(0, 1);
+        let fail = || async { Err::<tonic::Response<()>, _>(tonic::Status::unavailable("down")) };
+
+        assert!(policy.call(Rpc::BatchReadBlobs, fail).await.is_err());
+        assert!(policy.call(Rpc::BatchUpdateBlobs, fail).await.is_err());
+        assert!(!policy.circuit_breaker.is_open(Instant::now()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_internal_is_not_retried() {
+        let policy = policy(3, 0);
+        let calls = AtomicU32::new(0);
+
+        let err = policy
+            .call(Rpc::BatchReadBlobs, || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err::<tonic::Response<()>, _>(tonic::Status::internal("bad request")) }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert_eq!(
+            Some(TCode(Code::Internal as i32)),
+            err.downcast_ref::<REClientError>().map(|e| e.code.dupe())
+        );
+    }
+}
@@ -60,6 +60,29 @@ impl DapAdapterClient for Client {
             text: None,
         });
     }
+
+    fn event_stopped_on_error(&self, error: String) {
+        self.event_stopped(StoppedEventBody {
+            reason: "exception".to_owned(),
+            thread_id: Some(0),
+            description: Some("Error".to_owned()),
+            all_threads_stopped: Some(true),
+            preserve_focus_hint: None,
+            text: Some(error),
+        });
+    }
+
+    fn event_output(&self, output: String) {
+        self.event_output(OutputEventBody {
+            output,
+            category: Some("console".to_owned()),
+            column: None,
+            data: None,
+            line: None,
+            source: None,
+            variables_reference: None,
+        });
+    }
 }
 
 fn get_ast(source: &str) -> anyhow::Result<Arc<AstModule>> {
@@ -131,9 +154,8 @@ impl DebugServer for Backend {
         Ok(resolved.to_response())
     }
 
-    fn set_exception_breakpoints(&self, _: SetExceptionBreakpointsArguments) -> anyhow::Result<()> {
-        // We just assume that break on error is always useful
-        Ok(())
+    fn set_exception_breakpoints(&self, x: SetExceptionBreakpointsArguments) -> anyhow::Result<()> {
+        self.adapter.set_exception_breakpoints(&x)
     }
 
     fn launch(&self, _: LaunchRequestArguments, args: Map<String, Value>) -> anyhow::Result<()> {
@@ -17,6 +17,8 @@
 
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::mem;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::mpsc::channel;
@@ -40,6 +42,8 @@ use crate::debug::ScopesInfo;
 use crate::debug::StepKind;
 use crate::debug::Variable;
 use crate::debug::VariablesInfo;
+use crate::debug::ERROR_EXCEPTION_FILTER;
+use crate::errors::Diagnostic;
 use crate::eval::BeforeStmtFuncDyn;
 use crate::eval::Evaluator;
 use crate::slice_vec_ext::SliceExt;
@@ -54,6 +58,7 @@ pub(crate) fn prepare_dap_adapter(
     let state = Arc::new(SharedAdapterState {
         client,
         breakpoints: Arc::new(Mutex::new(BreakpointConfig::new())),
+        break_on_error: AtomicBool::new(false),
         disable_breakpoints: Arc::new(0usize.into()),
     });
 
@@ -101,17 +106,10 @@ impl<'a> BeforeStmtFuncDyn<'a> for DapAdapterEvalHookImpl {
         let stop = if self.state.disable_breakpoints.load(Ordering::SeqCst) > 0 {
             false
         } else {
-            let breaks = self.state.breakpoints.lock().unwrap();
-            let breakpoint = breaks.at(span_loc);
+            // Clone the breakpoint so that we don't hold the lock while evaluating its condition.
+            let breakpoint = self.state.breakpoints.lock().unwrap().at(span_loc).cloned();
             match breakpoint {
-                Some(Breakpoint {
-                    condition: Some(condition),
-                    ..
-                }) => match evaluate_expr(&self.state, eval, condition.to_owned()) {
-                    Ok(v) => v.to_bool(),
-                    _ => true,
-                },
-                Some(..) => true,
+                Some(breakpoint) => self.hit_breakpoint(&breakpoint, eval),
                 None => false,
             }
         };
@@ -127,25 +125,30 @@ impl<'a> BeforeStmtFuncDyn<'a> for DapAdapterEvalHookImpl {
         };
 
         if stop || step_stop {
-            self.step = None;
             self.state.client.event_stopped();
-            loop {
-                let msg = self.receiver.recv();
-                match msg.map(|msg| msg(span_loc, eval)) {
-                    Ok(Next::Continue) => break,
-                    Ok(Next::Step(kind)) => {
-                        self.step = Some((kind, eval.call_stack_count()));
-                        break;
-                    }
-                    Ok(Next::RemainPaused) => continue,
-                    Err(..) => {
-                        // DapAdapter has been dropped so we'll continue.
-                        break;
-                    }
-                }
-            }
+            self.pause(span_loc, eval);
         }
     }
+
+    fn on_error<'v>(
+        &mut self,
+        span_loc: FileSpanRef,
+        error: &anyhow::Error,
+        eval: &mut Evaluator<'v, 'a>,
+    ) {
+        if self.state.disable_breakpoints.load(Ordering::SeqCst) > 0
+            || !self.state.break_on_error.load(Ordering::SeqCst)
+        {
+            return;
+        }
+        // Don't include the call stack of a Diagnostic, the debugger shows it already.
+        let message = match error.downcast_ref::<Diagnostic>() {
+            Some(diagnostic) => format!("{:#}", diagnostic.message),
+            None => format!("{:#}", error),
+        };
+        self.state.client.event_stopped_on_error(message);
+        self.pause(span_loc, eval);
+    }
 }
 
 impl Debug for DapAdapterEvalHookImpl {
@@ -162,6 +165,59 @@ impl DapAdapterEvalHookImpl {
             step: None,
         }
     }
+
+    /// Called when execution reaches a breakpoint, returns whether we should stop there.
+    fn hit_breakpoint(&self, breakpoint: &Breakpoint, eval: &mut Evaluator) -> bool {
+        if let Some(condition) = &breakpoint.condition {
+            match evaluate_expr(&self.state, eval, condition.to_owned()) {
+                Ok(v) if !v.to_bool() => return false,
+                // If the condition fails to evaluate we stop so that the user can see why.
+                _ => {}
+            }
+        }
+
+        let hits = breakpoint.hits.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(hit_condition) = breakpoint.hit_condition {
+            if !hit_condition.matches(hits) {
+                return false;
+            }
+        }
+
+        match &breakpoint.log_message {
+            Some(log_message) => {
+                let output = log_message.interpolate(|expr| {
+                    match evaluate_expr(&self.state, eval, expr.to_owned()) {
+                        Ok(v) => v.to_str(),
+                        Err(e) => format!("<{:#}>", e),
+                    }
+                });
+                self.state.client.event_output(output);
+                // Logpoints never stop.
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Waits for and handles messages from the DapAdapter until it lets the evaluation resume.
+    fn pause(&mut self, span_loc: FileSpanRef, eval: &mut Evaluator) {
+        self.step = None;
+        loop {
+            let msg = self.receiver.recv();
+            match msg.map(|msg| msg(span_loc, eval)) {
+                Ok(Next::Continue) => break,
+                Ok(Next::Step(kind)) => {
+                    self.step = Some((kind, eval.call_stack_count()));
+                    break;
+                }
+                Ok(Next::RemainPaused) => continue,
+                Err(..) => {
+                    // DapAdapter has been dropped so we'll continue.
+                    break;
+                }
+            }
+        }
+    }
 }
 
 impl DapAdapterEvalHook for DapAdapterEvalHookImpl {
@@ -217,6 +273,8 @@ struct SharedAdapterState {
     // These breakpoints must all match statements as per before_stmt.
     // Those values for which we abort the execution.
     breakpoints: Arc<Mutex<BreakpointConfig>>,
+    // Whether to stop when an error is raised.
+    break_on_error: AtomicBool,
     // Set while we are doing evaluate calls (>= 1 means disable)
     disable_breakpoints: Arc<AtomicUsize>,
 }
@@ -267,6 +325,17 @@ impl DapAdapter for DapAdapterImpl {
             .set_breakpoints(source, breakpoints)
     }
 
+    fn set_exception_breakpoints(
+        &self,
+        args: &SetExceptionBreakpointsArguments,
+    ) -> anyhow::Result<()> {
+        let break_on_error = args.filters.iter().any(|f| f == ERROR_EXCEPTION_FILTER);
+        self.state
+            .break_on_error
+            .store(break_on_error, Ordering::SeqCst);
+        Ok(())
+    }
+
     fn top_frame(&self) -> anyhow::Result<Option<StackFrame>> {
         self.with_ctx(Box::new(|span, eval| {
             let frame = eval.call_stack_top_frame();
@@ -406,9 +475,22 @@ pub(crate) fn resolve_breakpoints(
         Vec::new(),
         |v| {
             v.map(|x| {
-                poss.get(&(x.line as usize - 1)).map(|span| Breakpoint {
+                let span = poss.get(&(x.line as usize - 1))?;
+                // Breakpoints with an invalid hit condition or log message are reported as unverified.
+                let hit_condition = match &x.hit_condition {
+                    Some(hit_condition) => Some(HitCondition::parse(hit_condition)?),
+                    None => None,
+                };
+                let log_message = match &x.log_message {
+                    Some(log_message) => Some(LogMessage::parse(log_message)?),
+                    None => None,
+                };
+                Some(Breakpoint {
                     span: span.clone(),
                     condition: x.condition.clone(),
+                    hit_condition,
+                    log_message,
+                    hits: Arc::new(AtomicUsize::new(0)),
                 })
             })
         },
@@ -422,3 +504,115 @@ pub(crate) fn resolved_breakpoints_to_dap(
         breakpoints: breakpoints.0.map(|x| breakpoint(x.is_some())),
     }
 }
+
+/// A condition on the number of times a breakpoint was hit for it to stop.
+///
+/// Accepts `N` or `== N` (stop on the Nth hit only), `> N`, `>= N`, `< N`, `<= N`
+/// and `% N` (stop on every Nth hit).
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq)]
+pub(crate) enum HitCondition {
+    Eq(usize),
+    Gt(usize),
+    Ge(usize),
+    Lt(usize),
+    Le(usize),
+    Multiple(usize),
+}
+
+impl HitCondition {
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        // Longer operators first, so that `>=` is not parsed as `>`.
+        let ops: [(&str, fn(usize) -> HitCondition); 6] = [
+            ("==", HitCondition::Eq),
+            (">=", HitCondition::Ge),
+            ("<=", HitCondition::Le),
+            (">", HitCondition::Gt),
+            ("<", HitCondition::Lt),
+            ("%", HitCondition::Multiple),
+        ];
+        let (op, n): (fn(usize) -> HitCondition, &str) = ops
+            .iter()
+            .find_map(|(prefix, op)| Some((*op, s.strip_prefix(prefix)?)))
+            .unwrap_or((HitCondition::Eq, s));
+        let n = n.trim().parse().ok()?;
+        match op(n) {
+            HitCondition::Multiple(0) => None,
+            c => Some(c),
+        }
+    }
+
+    pub(crate) fn matches(self, hits: usize) -> bool {
+        match self {
+            HitCondition::Eq(n) => hits == n,
+            HitCondition::Gt(n) => hits > n,
+            HitCondition::Ge(n) => hits >= n,
+            HitCondition::Lt(n) => hits < n,
+            HitCondition::Le(n) => hits <= n,
+            HitCondition::Multiple(n) => hits % n == 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LogMessagePart {
+    Text(String),
+    Expr(String),
+}
+
+/// The message of a logpoint. Expressions within `{}` are evaluated and interpolated into the
+/// message, `{{` and `}}` are literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LogMessage(Vec<LogMessagePart>);
+
+impl LogMessage {
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut expr = String::new();
+                    loop {
+                        match chars.next()? {
+                            '}' => break,
+                            c => expr.push(c),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(LogMessagePart::Text(mem::take(&mut text)));
+                    }
+                    parts.push(LogMessagePart::Expr(expr));
+                }
+                // Unmatched closing brace.
+                '}' => return None,
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(LogMessagePart::Text(text));
+        }
+        Some(LogMessage(parts))
+    }
+
+    pub(crate) fn interpolate(&self, mut eval: impl FnMut(&str) -> String) -> String {
+        let mut res = String::new();
+        for part in &self.0 {
+            match part {
+                LogMessagePart::Text(text) => res.push_str(text),
+                LogMessagePart::Expr(expr) => res.push_str(&eval(expr)),
+            }
+        }
+        res.push('\n');
+        res
+    }
+}
@@ -20,6 +20,8 @@
 //! that provide for debugging a starlark Evaluation.
 
 use std::fmt::Debug;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 
 use debugserver_types::*;
 use dupe::Dupe;
@@ -35,6 +37,28 @@ mod tests;
 pub trait DapAdapterClient: Debug + Send + Sync + 'static {
     /// Indicates that the evaluation stopped at a breakpoint.
     fn event_stopped(&self);
+
+    /// Indicates that the evaluation stopped because an error was raised while exception
+    /// breakpoints are enabled (see [`DapAdapter::set_exception_breakpoints`]).
+    fn event_stopped_on_error(&self, _error: String) {
+        self.event_stopped()
+    }
+
+    /// Output of a logpoint, to be shown in the debug console.
+    fn event_output(&self, _output: String) {}
+}
+
+/// The exception breakpoints filter that makes the evaluation stop when an error is raised
+/// (either by `fail()` or by the evaluation itself).
+pub const ERROR_EXCEPTION_FILTER: &str = "error";
+
+/// The exception breakpoints filters that the adapter supports.
+pub fn exception_breakpoint_filters() -> Vec<ExceptionBreakpointsFilter> {
+    vec![ExceptionBreakpointsFilter {
+        filter: ERROR_EXCEPTION_FILTER.to_owned(),
+        label: "Errors (including fail())".to_owned(),
+        default: Some(false),
+    }]
 }
 
 /// Information about the variables scopes
@@ -100,6 +124,14 @@ pub trait DapAdapter: Debug + Send + 'static {
         breakpoints: &ResolvedBreakpoints,
     ) -> anyhow::Result<()>;
 
+    /// Sets the exception breakpoints filters (and clears existing ones).
+    ///
+    /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_SetExceptionBreakpoints>
+    fn set_exception_breakpoints(
+        &self,
+        args: &SetExceptionBreakpointsArguments,
+    ) -> anyhow::Result<()>;
+
     /// Gets the top stack frame, may be None if entered from native.
     fn top_frame(&self) -> anyhow::Result<Option<StackFrame>>;
 
@@ -135,10 +167,16 @@ pub trait DapAdapter: Debug + Send + 'static {
     fn evaluate(&self, expr: &str) -> anyhow::Result<EvaluateResponseBody>;
 }
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+/// A breakpoint resolved to a statement.
+#[derive(Debug, Clone)]
 pub struct Breakpoint {
     span: FileSpan,
     condition: Option<String>,
+    hit_condition: Option<implementation::HitCondition>,
+    log_message: Option<implementation::LogMessage>,
+    /// Number of times the breakpoint was hit (and its condition was true). This is shared by
+    /// all the clones of the breakpoint, so it counts hits across evaluations.
+    hits: Arc<AtomicUsize>,
 }
 
 /// Breakpoints resolved to their spans.
@@ -176,6 +214,9 @@ pub fn dap_capabilities() -> Capabilities {
         supports_set_variable: Some(true),
         supports_step_in_targets_request: Some(true),
         supports_conditional_breakpoints: Some(true),
+        supports_hit_conditional_breakpoints: Some(true),
+        supports_log_points: Some(true),
+        exception_breakpoint_filters: Some(exception_breakpoint_filters()),
         ..Capabilities::default()
     }
 }
@@ -21,6 +21,7 @@ mod t {
     use std::sync::atomic::AtomicUsize;
     use std::sync::atomic::Ordering;
     use std::sync::Arc;
+    use std::sync::Mutex;
     use std::thread::ScopedJoinHandle;
     use std::time::Duration;
     use std::time::Instant;
@@ -31,10 +32,13 @@ mod t {
     use crate::assert::test_functions;
     use crate::debug::adapter::implementation::prepare_dap_adapter;
     use crate::debug::adapter::implementation::resolve_breakpoints;
+    use crate::debug::adapter::implementation::HitCondition;
+    use crate::debug::adapter::implementation::LogMessage;
     use crate::debug::DapAdapter;
     use crate::debug::DapAdapterClient;
     use crate::debug::DapAdapterEvalHook;
     use crate::debug::StepKind;
+    use crate::debug::ERROR_EXCEPTION_FILTER;
     use crate::environment::GlobalsBuilder;
     use crate::environment::Module;
     use crate::eval::Evaluator;
@@ -46,12 +50,8 @@ mod t {
     #[derive(Debug)]
     struct Client {
         breakpoints_hit: Arc<AtomicUsize>,
-    }
-
-    impl Client {
-        pub fn new(breakpoints_hit: Arc<AtomicUsize>) -> Self {
-            Self { breakpoints_hit }
-        }
+        errors: Arc<Mutex<Vec<String>>>,
+        output: Arc<Mutex<String>>,
     }
 
     impl DapAdapterClient for Client {
@@ -59,21 +59,39 @@ mod t {
             println!("stopped!");
             self.breakpoints_hit.fetch_add(1, Ordering::SeqCst);
         }
+
+        fn event_stopped_on_error(&self, error: String) {
+            println!("stopped on error: {}", error);
+            self.errors.lock().unwrap().push(error);
+            self.breakpoints_hit.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn event_output(&self, output: String) {
+            self.output.lock().unwrap().push_str(&output);
+        }
     }
 
     struct BreakpointController {
         breakpoints_hit: Arc<AtomicUsize>,
+        errors: Arc<Mutex<Vec<String>>>,
+        output: Arc<Mutex<String>>,
     }
 
     impl BreakpointController {
         fn new() -> Self {
             Self {
                 breakpoints_hit: Arc::new(AtomicUsize::new(0)),
+                errors: Arc::new(Mutex::new(Vec::new())),
+                output: Arc::new(Mutex::new(String::new())),
             }
         }
 
         fn get_client(&self) -> Box<dyn DapAdapterClient> {
-            Box::new(Client::new(self.breakpoints_hit.dupe()))
+            Box::new(Client {
+                breakpoints_hit: self.breakpoints_hit.dupe(),
+                errors: self.errors.dupe(),
+                output: self.output.dupe(),
+            })
         }
 
         fn wait_for_eval_stopped(&self, breakpoint_count: usize, timeout: Duration) {
@@ -120,6 +138,19 @@ mod t {
         }
     }
 
+    fn breakpoint_with_options(
+        path: &str,
+        line: i64,
+        hit_condition: Option<&str>,
+        log_message: Option<&str>,
+    ) -> SetBreakpointsArguments {
+        let mut args = breakpoints_args(path, &[(line, None)]);
+        let breakpoint = &mut args.breakpoints.as_mut().unwrap()[0];
+        breakpoint.hit_condition = hit_condition.map(|v| v.to_owned());
+        breakpoint.log_message = log_message.map(|v| v.to_owned());
+        args
+    }
+
     fn eval_with_hook(
         ast: AstModule,
         hook: impl DapAdapterEvalHook,
@@ -377,4 +408,132 @@ print(x)
             Ok(())
         })
     }
+
+    #[test]
+    fn test_hit_condition_breakpoint() -> anyhow::Result<()> {
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+def adjust(y):
+    y[0] += 1 # line 3
+x = [0]
+for _ in range(5):
+    adjust(x)
+print(x)
+        ";
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            let breakpoints = resolve_breakpoints(
+                &breakpoint_with_options("test.bzl", 3, Some(">= 4"), None),
+                &ast,
+            )?;
+            adapter.set_breakpoints("test.bzl", &breakpoints)?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            // should only break on the 4th and 5th hits
+            controller.wait_for_eval_stopped(1, TIMEOUT);
+            assert_eq!("[3]", adapter.evaluate("y")?.result);
+            adapter.continue_()?;
+            controller.wait_for_eval_stopped(2, TIMEOUT);
+            assert_eq!("[4]", adapter.evaluate("y")?.result);
+            adapter.continue_()?;
+            join_timeout(eval_result, TIMEOUT)?;
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_logpoint() -> anyhow::Result<()> {
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+def adjust(y):
+    y[0] += 1 # line 3
+x = [0]
+for _ in range(3):
+    adjust(x)
+print(x)
+        ";
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            let breakpoints = resolve_breakpoints(
+                &breakpoint_with_options("test.bzl", 3, None, Some("y[0] is {y[0]} {{ok}}")),
+                &ast,
+            )?;
+            adapter.set_breakpoints("test.bzl", &breakpoints)?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            // logpoints never stop
+            join_timeout(eval_result, TIMEOUT)?;
+            assert_eq!(0, controller.breakpoints_hit.load(Ordering::SeqCst));
+            assert_eq!(
+                "y[0] is 0 {ok}\ny[0] is 1 {ok}\ny[0] is 2 {ok}\n",
+                &*controller.output.lock().unwrap()
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_break_on_error() -> anyhow::Result<()> {
+        let controller = BreakpointController::new();
+        let (adapter, eval_hook) = prepare_dap_adapter(controller.get_client());
+        let file_contents = "
+def check(y):
+    if y > 1:
+        fail('too big:', y) # line 4
+def go():
+    for i in range(3):
+        check(i)
+go()
+        ";
+        std::thread::scope(|s| {
+            let ast = AstModule::parse("test.bzl", file_contents.to_owned(), &Dialect::Extended)?;
+            adapter.set_exception_breakpoints(&SetExceptionBreakpointsArguments {
+                filters: vec![ERROR_EXCEPTION_FILTER.to_owned()],
+                exception_options: None,
+            })?;
+            let eval_result =
+                s.spawn(move || -> anyhow::Result<_> { eval_with_hook(ast, eval_hook) });
+            // should only stop once, in the frame calling fail()
+            controller.wait_for_eval_stopped(1, TIMEOUT);
+            assert_eq!("2", adapter.evaluate("y")?.result);
+            assert_eq!(Some(4), adapter.top_frame()?.map(|frame| frame.line),);
+            assert_eq!(
+                vec!["fail: too big: 2".to_owned()],
+                *controller.errors.lock().unwrap()
+            );
+            adapter.continue_()?;
+            assert!(join_timeout(eval_result, TIMEOUT).is_err());
+            assert_eq!(1, controller.breakpoints_hit.load(Ordering::SeqCst));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_hit_condition_parse() {
+        assert_eq!(Some(HitCondition::Eq(3)), HitCondition::parse("3"));
+        assert_eq!(Some(HitCondition::Eq(3)), HitCondition::parse("== 3"));
+        assert_eq!(Some(HitCondition::Ge(10)), HitCondition::parse(" >=10 "));
+        assert_eq!(Some(HitCondition::Gt(1)), HitCondition::parse(">1"));
+        assert_eq!(Some(HitCondition::Multiple(5)), HitCondition::parse("% 5"));
+        assert_eq!(None, HitCondition::parse("% 0"));
+        assert_eq!(None, HitCondition::parse("x > 1"));
+
+        assert!(HitCondition::Multiple(2).matches(4));
+        assert!(!HitCondition::Multiple(2).matches(3));
+        assert!(HitCondition::Le(2).matches(2));
+        assert!(!HitCondition::Lt(2).matches(2));
+    }
+
+    #[test]
+    fn test_log_message_parse() {
+        let message = LogMessage::parse("{{x}} = {x}, {y}!").unwrap();
+        assert_eq!(
+            "{x} = <x>, <y>!\n",
+            message.interpolate(|expr| format!("<{}>", expr))
+        );
+        assert_eq!(None, LogMessage::parse("{x"));
+        assert_eq!(None, LogMessage::parse("x}"));
+    }
 }
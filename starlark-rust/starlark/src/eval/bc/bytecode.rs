@@ -139,7 +139,9 @@ fn step<'v, 'b, EC: EvaluationCallbacks>(
         }
     }
 
-    ec.before_instr(eval, ip, opcode);
+    if let Err(e) = ec.before_instr(eval, ip, opcode) {
+        return InstrControl::Err(e);
+    }
     opcode.dispatch(HandlerImpl { eval, frame, ip })
 }
 
@@ -162,7 +164,10 @@ pub(crate) fn run_block<'v, EC: EvaluationCallbacks>(
         ip = match step(eval, ec, frame, ip) {
             InstrControl::Next(ip) => ip,
             InstrControl::Return(v) => return Ok(v),
-            InstrControl::Err(e) => return Err(Bc::wrap_error_for_instr_ptr(ip, e, eval)),
+            InstrControl::Err(e) => {
+                ec.on_error(eval, ip, &e);
+                return Err(Bc::wrap_error_for_instr_ptr(ip, e, eval));
+            }
         }
     }
 }
@@ -18,6 +18,7 @@
 //! Configuration of `BeforeStmt` instrumentation of bytecode.
 
 use crate::codemap::FileSpanRef;
+use crate::eval::runtime::limits::EvalLimitsState;
 use crate::eval::Evaluator;
 
 /// Configuration of `BeforeStmt` instrumentation of bytecode.
@@ -29,6 +30,8 @@ pub(crate) struct BeforeStmt<'a> {
     /// even if no `before_stmt` functions are registered.
     /// This is needed when compiling dependencies of a file to be profiled.
     pub(crate) instrument: bool,
+    /// Resource limits checked before each statement.
+    pub(crate) limits: Option<EvalLimitsState>,
 }
 
 /// This is used by DAP, and it is not public API.
@@ -46,6 +49,18 @@ impl<'a> BeforeStmtFunc<'a> {
             BeforeStmtFunc::Dyn(d) => d.call(span, eval),
         }
     }
+
+    pub(crate) fn on_error<'v>(
+        &mut self,
+        span: FileSpanRef,
+        error: &anyhow::Error,
+        eval: &mut Evaluator<'v, 'a>,
+    ) {
+        match self {
+            BeforeStmtFunc::Fn(_) => {}
+            BeforeStmtFunc::Dyn(d) => d.on_error(span, error, eval),
+        }
+    }
 }
 
 /// This is used by DAP, and it is not public API.
@@ -56,11 +71,25 @@ pub trait BeforeStmtFuncDyn<'a> {
     // TODO(cjhopman): pull DAP into the crate, and hide this function.
     #[doc(hidden)]
     fn call<'v>(&mut self, span: FileSpanRef, eval: &mut Evaluator<'v, 'a>);
+
+    /// This is used by DAP, and it is not public API.
+    ///
+    /// Called when an error is raised at `span`, before it propagates to the caller.
+    /// Errors propagating from callees are only reported in the frame where they originated.
+    // TODO(cjhopman): pull DAP into the crate, and hide this function.
+    #[doc(hidden)]
+    fn on_error<'v>(
+        &mut self,
+        _span: FileSpanRef,
+        _error: &anyhow::Error,
+        _eval: &mut Evaluator<'v, 'a>,
+    ) {
+    }
 }
 
 impl<'a> BeforeStmt<'a> {
     pub(crate) fn enabled(&self) -> bool {
-        self.instrument || !self.before_stmt.is_empty()
+        self.instrument || !self.before_stmt.is_empty() || self.limits.is_some()
     }
 }
 
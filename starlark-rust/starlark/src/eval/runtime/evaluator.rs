@@ -51,6 +51,8 @@ use crate::eval::runtime::before_stmt::BeforeStmtFunc;
 use crate::eval::runtime::call_stack::CheapCallStack;
 use crate::eval::runtime::frame_span::FrameSpan;
 use crate::eval::runtime::inlined_frame::InlinedFrames;
+use crate::eval::runtime::limits::EvalLimits;
+use crate::eval::runtime::limits::EvalLimitsState;
 use crate::eval::runtime::profile::bc::BcProfile;
 use crate::eval::runtime::profile::data::ProfileData;
 use crate::eval::runtime::profile::heap::HeapProfile;
@@ -429,6 +431,15 @@ impl<'v, 'a> Evaluator<'v, 'a> {
             .change(|v| v.before_stmt.before_stmt.push(f))
     }
 
+    /// Limit resources used by evaluation from now on.
+    ///
+    /// Limits are checked before each statement, including statements of functions
+    /// defined in loaded modules, so setting limits makes evaluation slower.
+    pub fn set_limits(&mut self, limits: EvalLimits) {
+        self.eval_instrumentation
+            .change(|v| v.before_stmt.limits = Some(EvalLimitsState::new(limits)))
+    }
+
     /// This function is used by DAP, and it is not public API.
     // TODO(nga): pull DAP into the crate, and hide this function.
     #[doc(hidden)]
@@ -798,14 +809,30 @@ impl<'v, 'a> Evaluator<'v, 'a> {
 }
 
 pub(crate) trait EvaluationCallbacks {
-    fn before_instr(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _opcode: BcOpcode);
+    fn before_instr(
+        &mut self,
+        _eval: &mut Evaluator,
+        _ip: BcPtrAddr,
+        _opcode: BcOpcode,
+    ) -> anyhow::Result<()>;
+    fn on_error(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _error: &anyhow::Error);
 }
 
 pub(crate) struct EvalCallbacksDisabled;
 
 impl EvaluationCallbacks for EvalCallbacksDisabled {
     #[inline(always)]
-    fn before_instr(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _opcode: BcOpcode) {}
+    fn before_instr(
+        &mut self,
+        _eval: &mut Evaluator,
+        _ip: BcPtrAddr,
+        _opcode: BcOpcode,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn on_error(&mut self, _eval: &mut Evaluator, _ip: BcPtrAddr, _error: &anyhow::Error) {}
 }
 
 pub(crate) struct EvalCallbacksEnabled<'a> {
@@ -816,35 +843,55 @@ pub(crate) struct EvalCallbacksEnabled<'a> {
 }
 
 impl<'a> EvalCallbacksEnabled<'a> {
-    fn before_stmt(&mut self, eval: &mut Evaluator, ip: BcPtrAddr) {
+    fn before_stmt(&mut self, eval: &mut Evaluator, ip: BcPtrAddr) -> anyhow::Result<()> {
         let offset = ip.offset_from(self.bc_start_ptr);
         if let Some(loc) = self.stmt_locs.stmt_at(offset) {
-            before_stmt(loc.span, eval);
+            before_stmt(loc.span, eval)?;
         }
+        Ok(())
     }
 }
 
 impl<'a> EvaluationCallbacks for EvalCallbacksEnabled<'a> {
     #[inline(always)]
-    fn before_instr(&mut self, eval: &mut Evaluator, ip: BcPtrAddr, opcode: BcOpcode) {
+    fn before_instr(
+        &mut self,
+        eval: &mut Evaluator,
+        ip: BcPtrAddr,
+        opcode: BcOpcode,
+    ) -> anyhow::Result<()> {
         if self.bc_profile {
             eval.eval_instrumentation.bc_profile.before_instr(opcode)
         }
         if self.before_stmt {
-            self.before_stmt(eval, ip);
+            self.before_stmt(eval, ip)?;
+        }
+        Ok(())
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn on_error(&mut self, eval: &mut Evaluator, ip: BcPtrAddr, error: &anyhow::Error) {
+        if self.before_stmt {
+            let span = Bc::slow_arg_at_ptr(ip).span;
+            before_stmt_on_error(span, error, eval);
         }
     }
 }
 
 // This function should be called before every meaningful statement.
-// The purposes are GC, profiling and debugging.
+// The purposes are GC, profiling, debugging and resource limits.
 //
 // This function is called only if `before_stmt` is set before compilation start.
-pub(crate) fn before_stmt(span: FrameSpan, eval: &mut Evaluator) {
+pub(crate) fn before_stmt(span: FrameSpan, eval: &mut Evaluator) -> anyhow::Result<()> {
     assert!(
         eval.eval_instrumentation.before_stmt.enabled(),
         "this code should only be called if `before_stmt` is set"
     );
+    let heap = eval.heap();
+    if let Some(limits) = &mut eval.eval_instrumentation.before_stmt.limits {
+        limits.before_stmt(heap)?;
+    }
     let mut fs = mem::take(&mut eval.eval_instrumentation.before_stmt.before_stmt);
     for f in &mut fs {
         f.call(span.span.file_span_ref(), eval)
@@ -854,4 +901,26 @@ pub(crate) fn before_stmt(span: FrameSpan, eval: &mut Evaluator) {
         added.is_empty(),
         "`before_stmt` cannot be modified during evaluation"
     );
+    Ok(())
+}
+
+// This function should be called when an instruction fails, before the error is annotated
+// with the span of the instruction. The purpose is debugging.
+//
+// This function is called only if `before_stmt` is set before compilation start.
+pub(crate) fn before_stmt_on_error(span: FrameSpan, error: &anyhow::Error, eval: &mut Evaluator) {
+    // Errors which already have a span were raised (and reported) in a callee,
+    // we only report them in the frame where they originated.
+    if let Some(Diagnostic { span: Some(_), .. }) = error.downcast_ref::<Diagnostic>() {
+        return;
+    }
+    let mut fs = mem::take(&mut eval.eval_instrumentation.before_stmt.before_stmt);
+    for f in &mut fs {
+        f.on_error(span.span.file_span_ref(), error, eval)
+    }
+    let added = mem::replace(&mut eval.eval_instrumentation.before_stmt.before_stmt, fs);
+    assert!(
+        added.is_empty(),
+        "`before_stmt` cannot be modified during evaluation"
+    );
 }
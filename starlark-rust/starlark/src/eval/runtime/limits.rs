@@ -0,0 +1,101 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Limits on resources used by evaluation.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::values::Heap;
+
+/// Checking the clock before every statement is too expensive.
+const STATEMENTS_BETWEEN_TIME_CHECKS: u64 = 1000;
+
+/// Limits on resources used by evaluation, checked before each statement.
+///
+/// When a limit is exceeded, evaluation fails with [`EvalLimitExceeded`] error,
+/// annotated with the call stack at the point of failure.
+#[derive(Debug, Clone, Default)]
+pub struct EvalLimits {
+    /// Maximum number of statements executed, including statements in called functions.
+    pub max_statements: Option<u64>,
+    /// Maximum wall time of evaluation.
+    pub max_duration: Option<Duration>,
+    /// Maximum size of the heap in bytes, including garbage not yet collected.
+    pub max_heap_bytes: Option<usize>,
+}
+
+/// Error when evaluation exceeded [`EvalLimits`].
+#[derive(Debug, thiserror::Error)]
+pub enum EvalLimitExceeded {
+    /// Too many statements executed.
+    #[error("Evaluation executed more than {0} statements")]
+    Statements(u64),
+    /// Evaluation took too long.
+    #[error("Evaluation took more than {0:.3?}")]
+    Duration(Duration),
+    /// Heap is too large.
+    #[error("Evaluation heap size {actual} bytes exceeds the limit of {limit} bytes")]
+    HeapBytes {
+        /// Configured limit.
+        limit: usize,
+        /// Heap size when the limit was checked.
+        actual: usize,
+    },
+}
+
+pub(crate) struct EvalLimitsState {
+    limits: EvalLimits,
+    statements: u64,
+    start: Instant,
+}
+
+impl EvalLimitsState {
+    pub(crate) fn new(limits: EvalLimits) -> EvalLimitsState {
+        EvalLimitsState {
+            limits,
+            statements: 0,
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn before_stmt(&mut self, heap: &Heap) -> Result<(), EvalLimitExceeded> {
+        self.statements += 1;
+        if let Some(max_statements) = self.limits.max_statements {
+            if self.statements > max_statements {
+                return Err(EvalLimitExceeded::Statements(max_statements));
+            }
+        }
+        if let Some(max_heap_bytes) = self.limits.max_heap_bytes {
+            let actual = heap.allocated_bytes();
+            if actual > max_heap_bytes {
+                return Err(EvalLimitExceeded::HeapBytes {
+                    limit: max_heap_bytes,
+                    actual,
+                });
+            }
+        }
+        if let Some(max_duration) = self.limits.max_duration {
+            if self.statements % STATEMENTS_BETWEEN_TIME_CHECKS == 0
+                && self.start.elapsed() > max_duration
+            {
+                return Err(EvalLimitExceeded::Duration(max_duration));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::environment::Globals;
+use crate::environment::Module;
+use crate::errors::Diagnostic;
+use crate::eval::EvalLimitExceeded;
+use crate::eval::EvalLimits;
+use crate::eval::Evaluator;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+
+fn limit_exceeded(err: &anyhow::Error) -> Option<&EvalLimitExceeded> {
+    err.downcast_ref::<Diagnostic>()?
+        .message
+        .downcast_ref::<EvalLimitExceeded>()
+}
+
+fn eval_with_limits(program: &str, limits: EvalLimits) -> anyhow::Result<()> {
+    let module = Module::new();
+    let globals = Globals::standard();
+    let mut evaluator = Evaluator::new(&module);
+    evaluator.set_limits(limits);
+    let ast = AstModule::parse("a.star", program.to_owned(), &Dialect::Extended)?;
+    evaluator.eval_module(ast, &globals)?;
+    Ok(())
+}
+
+#[test]
+fn test_max_statements() {
+    let program = "\
+def f(n):
+  x = 0
+  for i in range(n):
+    x += i
+  return x
+f(10)
+";
+    eval_with_limits(
+        program,
+        EvalLimits {
+            max_statements: Some(100),
+            ..EvalLimits::default()
+        },
+    )
+    .unwrap();
+
+    let program = program.replace("f(10)", "f(1000)");
+    let err = eval_with_limits(
+        &program,
+        EvalLimits {
+            max_statements: Some(100),
+            ..EvalLimits::default()
+        },
+    )
+    .unwrap_err();
+    assert!(
+        matches!(
+            limit_exceeded(&err),
+            Some(EvalLimitExceeded::Statements(100))
+        ),
+        "{:?}",
+        err
+    );
+    // Error includes the call stack where the limit was exceeded.
+    let message = format!("{}", err);
+    assert!(message.contains("Traceback"), "{}", message);
+    assert!(message.contains("f(1000)"), "{}", message);
+}
+
+#[test]
+fn test_max_heap_bytes() {
+    let err = eval_with_limits(
+        "x = [[i] for i in range(100000)]\ny = len(x)",
+        EvalLimits {
+            max_heap_bytes: Some(1000),
+            ..EvalLimits::default()
+        },
+    )
+    .unwrap_err();
+    assert!(
+        matches!(
+            limit_exceeded(&err),
+            Some(EvalLimitExceeded::HeapBytes { limit: 1000, .. })
+        ),
+        "{:?}",
+        err
+    );
+}